@@ -0,0 +1,62 @@
+/* C interface to the fft crate, built with `--features capi`.
+ *
+ * Maintained by hand alongside src/capi.rs, whose tests compile
+ * resources/test/capi.c against it and the library, and check what every
+ * function gives. */
+
+#ifndef FFT_H
+#define FFT_H
+
+#include <stddef.h>
+#include <stdint.h>
+
+/* Fourier decomposition of a closed path, owned by the caller until given to
+ * fft_epicycle_free. */
+typedef struct Epicycle Epicycle;
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/* Decomposition of the closed path of `count` points in `points`, which holds
+ * `2 * count` doubles as interleaved x, y values, or NULL when `points` is NULL
+ * or `count` too large for them to fit in memory. Free it with
+ * fft_epicycle_free. */
+Epicycle *fft_epicycle_new(const double *points, size_t count);
+
+/* Frees `epicycle`, doing nothing when it is NULL. */
+void fft_epicycle_free(Epicycle *epicycle);
+
+/* Number of terms of `epicycle`, 0 when it is NULL. */
+size_t fft_epicycle_len(const Epicycle *epicycle);
+
+/* Writes the coefficient and signed frequency of the term at `index`, terms
+ * being sorted by decreasing amplitude. Returns 0 on success, -1 otherwise. */
+int32_t fft_epicycle_term(const Epicycle *epicycle,
+                          size_t index,
+                          double *re,
+                          double *im,
+                          int64_t *frequency);
+
+/* Writes the position of the pen at time `t`, one full turn being 2π, using
+ * the `precision` largest terms. Returns 0 on success, -1 otherwise. */
+int32_t fft_epicycle_coordinate(const Epicycle *epicycle,
+                                size_t precision,
+                                double t,
+                                double *x,
+                                double *y);
+
+/* Writes the positions of the pen at `count` evenly spaced times over one full
+ * turn, using the `precision` largest terms, into `points` as `2 * count`
+ * doubles. Returns 0 on success, -1 otherwise, such as when `count` is too
+ * large for them to fit in memory. */
+int32_t fft_epicycle_sample(const Epicycle *epicycle,
+                            size_t precision,
+                            size_t count,
+                            double *points);
+
+#ifdef __cplusplus
+}  /* extern "C" */
+#endif
+
+#endif /* FFT_H */
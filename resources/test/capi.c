@@ -0,0 +1,64 @@
+/* Calls every function declared in include/fft.h on the unit square, printing
+ * what they give for the tests of src/capi.rs to compare with the
+ * decomposition computed in Rust.
+ *
+ * Doubles are printed as the hexadecimal of their bits to be compared exactly.
+ * Sizes and indices only have their highest bit set where they can, and
+ * outputs start with all their bits set, so that a narrower type on either
+ * side of the interface changes what is printed. */
+
+#include <inttypes.h>
+#include <stdint.h>
+#include <stdio.h>
+#include <string.h>
+
+#include "fft.h"
+
+static void print_double(double value) {
+    uint64_t bits;
+    memcpy(&bits, &value, sizeof bits);
+    printf(" %016" PRIx64, bits);
+}
+
+int main(void) {
+    const double points[] = {0.0, 0.0, 1.0, 0.0, 1.0, 1.0, 0.0, 1.0};
+    /* More terms than there are, all of them being used. */
+    const size_t all = SIZE_MAX / 2 + 1;
+    double re = -1.0, im = -1.0, x = -1.0, y = -1.0;
+    double samples[6];
+    int64_t frequency = INT64_MIN;
+    size_t i;
+    Epicycle *epicycle = fft_epicycle_new(points, 4);
+
+    printf("len %zu\n", fft_epicycle_len(epicycle));
+
+    printf("term %" PRId32, fft_epicycle_term(epicycle, 0, &re, &im, &frequency));
+    print_double(re);
+    print_double(im);
+    printf(" %" PRId64 "\n", frequency);
+    printf("missing %" PRId32 "\n",
+           fft_epicycle_term(epicycle, all, &re, &im, &frequency));
+
+    printf("coordinate %" PRId32,
+           fft_epicycle_coordinate(epicycle, all, 1.0, &x, &y));
+    print_double(x);
+    print_double(y);
+    printf("\n");
+
+    for (i = 0; i < 6; i++) {
+        samples[i] = -1.0;
+    }
+    printf("sample %" PRId32, fft_epicycle_sample(epicycle, all, 3, samples));
+    for (i = 0; i < 6; i++) {
+        print_double(samples[i]);
+    }
+    printf("\n");
+    fft_epicycle_free(epicycle);
+
+    printf("null %d %zu %" PRId32 "\n",
+           fft_epicycle_new(NULL, 4) == NULL,
+           fft_epicycle_len(NULL),
+           fft_epicycle_sample(NULL, 4, 3, samples));
+    fft_epicycle_free(NULL);
+    return 0;
+}
@@ -0,0 +1,335 @@
+//! C interface to the decompositions, declared in `include/fft.h`, for programs in other
+//! languages such as game engines and creative coding environments.
+//!
+//! Epicycles are handed out as opaque pointers owned by the caller until given back to
+//! `fft_epicycle_free`. Functions taking pointers check them for null, and those that can fail
+//! return 0 on success and -1 otherwise. Points are passed as interleaved `x, y` doubles.
+
+use crate::complex;
+use crate::epicycle::Epicycle;
+use crate::path;
+use std::mem;
+use std::ptr;
+use std::slice;
+
+/// Decomposition of the closed path of `count` points in `points`, which holds `2 * count`
+/// doubles, or null when `points` is null or `count` too large for them to fit in memory. Free it
+/// with `fft_epicycle_free`.
+///
+/// # Safety
+///
+/// `points` must be null or point to `2 * count` readable doubles.
+#[no_mangle]
+pub unsafe extern "C" fn fft_epicycle_new(points: *const f64, count: usize) -> *mut Epicycle {
+    let length = match doubles(count) {
+        Some(length) if !points.is_null() => length,
+        _ => return ptr::null_mut(),
+    };
+    let values = slice::from_raw_parts(points, length);
+    let path = path::Path {
+        data: values
+            .chunks_exact(2)
+            .map(|point| complex::Complex::new(point[0], point[1]))
+            .collect(),
+    };
+    Box::into_raw(Box::new(Epicycle::new(&path)))
+}
+
+/// Frees `epicycle`, doing nothing when it is null.
+///
+/// # Safety
+///
+/// `epicycle` must be null or a pointer returned by `fft_epicycle_new` not freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn fft_epicycle_free(epicycle: *mut Epicycle) {
+    if !epicycle.is_null() {
+        drop(Box::from_raw(epicycle));
+    }
+}
+
+/// Number of terms of `epicycle`, 0 when it is null.
+///
+/// # Safety
+///
+/// `epicycle` must be null or a live pointer returned by `fft_epicycle_new`.
+#[no_mangle]
+pub unsafe extern "C" fn fft_epicycle_len(epicycle: *const Epicycle) -> usize {
+    epicycle.as_ref().map_or(0, Epicycle::len)
+}
+
+/// Writes the coefficient and signed frequency of the term at `index`, terms being sorted by
+/// decreasing amplitude.
+///
+/// # Safety
+///
+/// `epicycle` must be null or a live pointer returned by `fft_epicycle_new`, and the others
+/// null or writable.
+#[no_mangle]
+pub unsafe extern "C" fn fft_epicycle_term(
+    epicycle: *const Epicycle,
+    index: usize,
+    re: *mut f64,
+    im: *mut f64,
+    frequency: *mut i64,
+) -> i32 {
+    let term = match epicycle
+        .as_ref()
+        .and_then(|epicycle| epicycle.terms().get(index))
+    {
+        Some(term) => term,
+        None => return -1,
+    };
+    if re.is_null() || im.is_null() || frequency.is_null() {
+        return -1;
+    }
+    *re = term.0.re;
+    *im = term.0.im;
+    *frequency = term.1;
+    0
+}
+
+/// Writes the position of the pen at time `t`, one full turn being 2π, using the `precision`
+/// largest terms.
+///
+/// # Safety
+///
+/// `epicycle` must be null or a live pointer returned by `fft_epicycle_new`, and `x` and `y`
+/// null or writable.
+#[no_mangle]
+pub unsafe extern "C" fn fft_epicycle_coordinate(
+    epicycle: *const Epicycle,
+    precision: usize,
+    t: f64,
+    x: *mut f64,
+    y: *mut f64,
+) -> i32 {
+    let epicycle = match epicycle.as_ref() {
+        Some(epicycle) => epicycle,
+        None => return -1,
+    };
+    if x.is_null() || y.is_null() {
+        return -1;
+    }
+    let position = epicycle.get_coordinate_for(precision, t);
+    *x = position.re;
+    *y = position.im;
+    0
+}
+
+/// Writes the positions of the pen at `count` evenly spaced times over one full turn, using the
+/// `precision` largest terms, into `points` as `2 * count` doubles. Fails when `count` is too large
+/// for them to fit in memory.
+///
+/// # Safety
+///
+/// `epicycle` must be null or a live pointer returned by `fft_epicycle_new`, and `points` null
+/// or point to `2 * count` writable doubles.
+#[no_mangle]
+pub unsafe extern "C" fn fft_epicycle_sample(
+    epicycle: *const Epicycle,
+    precision: usize,
+    count: usize,
+    points: *mut f64,
+) -> i32 {
+    let epicycle = match epicycle.as_ref() {
+        Some(epicycle) => epicycle,
+        None => return -1,
+    };
+    let length = match doubles(count) {
+        Some(length) if !points.is_null() => length,
+        _ => return -1,
+    };
+    let output = slice::from_raw_parts_mut(points, length);
+    for (point, sample) in output
+        .chunks_exact_mut(2)
+        .zip(epicycle.samples(precision, count))
+    {
+        point[0] = sample.re;
+        point[1] = sample.im;
+    }
+    0
+}
+
+/// Number of doubles of `count` points, `None` when they are more than a slice can hold.
+fn doubles(count: usize) -> Option<usize> {
+    count
+        .checked_mul(2)
+        .filter(|length| *length <= isize::MAX as usize / mem::size_of::<f64>())
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::epicycle;
+    use std::env;
+    use std::fs;
+    use std::io;
+    use std::path::Path;
+    use std::process::Command;
+
+    /// C program calling every function declared in the header, see its comment.
+    const PROGRAM: &str = include_str!("../resources/test/capi.c");
+
+    const SOURCE: &str = include_str!("capi.rs");
+
+    /// Doubles as printed by the C program.
+    fn bits(values: &[f64]) -> String {
+        values
+            .iter()
+            .map(|value| format!(" {:016x}", value.to_bits()))
+            .collect()
+    }
+
+    /// Runs `command` from the root of the crate, panicking unless it succeeds, `None` when it
+    /// cannot be found.
+    fn run(command: &mut Command) -> Option<Vec<u8>> {
+        let output = match command.current_dir(env!("CARGO_MANIFEST_DIR")).output() {
+            Ok(output) => output,
+            Err(failure) if failure.kind() == io::ErrorKind::NotFound => return None,
+            Err(failure) => panic!("{:?} failed: {}", command, failure),
+        };
+        assert!(
+            output.status.success(),
+            "{:?} failed: {}",
+            command,
+            String::from_utf8_lossy(&output.stderr)
+        );
+        Some(output.stdout)
+    }
+
+    /// Builds the library as C programs link it, compiles the C program against the header and
+    /// runs it, so that a declaration drifting from the function it declares is caught.
+    #[test]
+    fn header_test() {
+        let functions: Vec<&str> = SOURCE
+            .split("extern \"C\" fn ")
+            .skip(1)
+            .map(|rest| &rest[..=rest.find('(').unwrap()])
+            .collect();
+        assert_eq!(6, functions.len());
+        for function in functions {
+            assert!(PROGRAM.contains(function), "{} is not called", function);
+        }
+
+        let target = Path::new(env!("CARGO_MANIFEST_DIR")).join("target/capi");
+        fs::create_dir_all(&target).unwrap();
+        let compiler = env::var("CC").unwrap_or_else(|_| "cc".to_string());
+        let program = target.join("capi");
+        run(Command::new(env!("CARGO"))
+            .args([
+                "rustc",
+                "--lib",
+                "--features",
+                "capi",
+                "--crate-type",
+                "cdylib",
+            ])
+            .arg("--target-dir")
+            .arg(&target))
+        .unwrap();
+        let library = target.join("debug");
+        let compiled = run(Command::new(&compiler)
+            .args([
+                "-std=c99",
+                "-Wall",
+                "-Wextra",
+                "-pedantic",
+                "-Werror",
+                "-Iinclude",
+            ])
+            .arg("resources/test/capi.c")
+            .arg("-L")
+            .arg(&library)
+            .arg(format!("-Wl,-rpath,{}", library.display()))
+            .args(["-lfft", "-o"])
+            .arg(&program));
+        if compiled.is_none() {
+            eprintln!("No C compiler {}, the header is not checked", compiler);
+            return;
+        }
+        let output = String::from_utf8(run(&mut Command::new(&program)).unwrap()).unwrap();
+
+        let expected = epicycle::unit_square();
+        let (coefficient, frequency) = expected.terms()[0];
+        let position = expected.get_coordinate_for(4, 1.0);
+        let samples: Vec<f64> = expected
+            .sample(4, 3)
+            .iter()
+            .flat_map(|sample| vec![sample.re, sample.im])
+            .collect();
+        assert_eq!(
+            format!(
+                "len 4\nterm 0{} {}\nmissing -1\ncoordinate 0{}\nsample 0{}\nnull 1 0 -1\n",
+                bits(&[coefficient.re, coefficient.im]),
+                frequency,
+                bits(&[position.re, position.im]),
+                bits(&samples)
+            ),
+            output
+        );
+    }
+
+    #[test]
+    fn epicycle_test() {
+        let points = [0.0, 0.0, 1.0, 0.0, 1.0, 1.0, 0.0, 1.0];
+        let expected = Epicycle::new(&path::Path {
+            data: points
+                .chunks(2)
+                .map(|point| complex::Complex::new(point[0], point[1]))
+                .collect(),
+        });
+        unsafe {
+            let epicycle = fft_epicycle_new(points.as_ptr(), 4);
+            assert_eq!(4, fft_epicycle_len(epicycle));
+
+            let (mut re, mut im, mut frequency) = (0.0, 0.0, 0);
+            assert_eq!(
+                0,
+                fft_epicycle_term(epicycle, 0, &mut re, &mut im, &mut frequency)
+            );
+            assert_eq!(expected.terms()[0].0, complex::Complex::new(re, im));
+            assert_eq!(expected.terms()[0].1, frequency);
+            assert_eq!(
+                -1,
+                fft_epicycle_term(epicycle, 4, &mut re, &mut im, &mut frequency)
+            );
+
+            let (mut x, mut y) = (0.0, 0.0);
+            assert_eq!(0, fft_epicycle_coordinate(epicycle, 4, 1.0, &mut x, &mut y));
+            assert_eq!(
+                expected.get_coordinate_for(4, 1.0),
+                complex::Complex::new(x, y)
+            );
+
+            let mut samples = [0.0; 6];
+            assert_eq!(0, fft_epicycle_sample(epicycle, 4, 3, samples.as_mut_ptr()));
+            for (point, sample) in samples.chunks(2).zip(expected.sample(4, 3)) {
+                assert_eq!(sample, complex::Complex::new(point[0], point[1]));
+            }
+            fft_epicycle_free(epicycle);
+
+            assert!(fft_epicycle_new(ptr::null(), 4).is_null());
+            assert_eq!(0, fft_epicycle_len(ptr::null()));
+            assert_eq!(
+                -1,
+                fft_epicycle_coordinate(ptr::null(), 4, 1.0, &mut x, &mut y)
+            );
+            assert_eq!(
+                -1,
+                fft_epicycle_sample(ptr::null(), 4, 3, samples.as_mut_ptr())
+            );
+            fft_epicycle_free(ptr::null_mut());
+
+            assert!(fft_epicycle_new(points.as_ptr(), usize::MAX).is_null());
+            assert!(fft_epicycle_new(points.as_ptr(), usize::MAX / 2 + 1).is_null());
+            assert!(fft_epicycle_new(points.as_ptr(), usize::MAX / 4).is_null());
+            let epicycle = fft_epicycle_new(points.as_ptr(), 4);
+            assert_eq!(
+                -1,
+                fft_epicycle_sample(epicycle, 4, usize::MAX / 2 + 1, samples.as_mut_ptr())
+            );
+            fft_epicycle_free(epicycle);
+        }
+    }
+}
@@ -0,0 +1,139 @@
+use crate::cli::arguments::{self, Argument, Arguments};
+use crate::cli::config::Config;
+use crate::cli::error;
+use fft::epicycle;
+use std::error::Error;
+use std::f64::consts::PI;
+
+pub const USAGE: &str = "\
+Prints the largest terms of the decomposition of a path.
+
+Usage: fft analyze [INPUT] [OPTIONS]
+
+Arguments:
+  [INPUT]               Path to analyze, one `x, y` point per line, `-` for the standard
+                        input, from the configuration file by default
+
+Options:
+  -n, --terms <TERMS>   Number of terms listed [default: 10]
+      --config <FILE>   Configuration file [default: fftdraw.toml when present]
+  -h, --help            Prints this help
+";
+
+/// Settings of the analyze command.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Options {
+    pub input: String,
+    pub terms: usize,
+}
+
+/// Options given by `arguments` on top of `config`, `None` when help is asked for.
+pub fn parse(arguments: &[String], config: &Config) -> Result<Option<Options>, Box<dyn Error>> {
+    let mut input = None;
+    let mut terms = 10;
+
+    let mut arguments = Arguments::new(arguments);
+    while let Some(argument) = arguments.next() {
+        match &argument {
+            Argument::Positional(value) if input.is_none() => input = Some(value.clone()),
+            Argument::Flag(flag) => match flag.as_str() {
+                "-h" | "--help" => return Ok(None),
+                "-n" | "--terms" => terms = arguments.parsed()?,
+                _ => return Err(arguments::unexpected(&argument)),
+            },
+            _ => return Err(arguments::unexpected(&argument)),
+        }
+    }
+
+    Ok(Some(Options {
+        input: input
+            .or_else(|| config.input.clone())
+            .ok_or("Missing input file")?,
+        terms,
+    }))
+}
+
+pub fn run(arguments: &[String], config: &Config) -> Result<(), Box<dyn Error>> {
+    let options = match parse(arguments, config).map_err(error::usage)? {
+        Some(options) => options,
+        None => {
+            print!("{}", USAGE);
+            return Ok(());
+        }
+    };
+    let path = super::read_path(&options.input)?;
+    let epicycle = super::decompose(&path)?;
+    print!("{}", report(&epicycle, options.terms));
+    Ok(())
+}
+
+/// Table of the `terms` largest terms of `epicycle` with the share of the energy of the path
+/// reached by using all the terms up to each of them, and the reconstruction error left.
+pub fn report(epicycle: &epicycle::Epicycle, terms: usize) -> String {
+    let energy = |coefficient: &fft::complex::Complex| {
+        coefficient.re * coefficient.re + coefficient.im * coefficient.im
+    };
+    let total: f64 = epicycle.terms().iter().map(|(c, _)| energy(c)).sum();
+    let mut report = format!("terms  {}\n\n", epicycle.len());
+    report.push_str(" rank  frequency   amplitude    phase   energy   RMS error\n");
+    let mut cumulated = 0.0;
+    for (index, (coefficient, frequency)) in epicycle.terms().iter().take(terms).enumerate() {
+        cumulated += energy(coefficient);
+        let share = if total > 0.0 { cumulated / total } else { 1.0 };
+        report.push_str(&format!(
+            "{:>5}  {:>9}  {:>10.4}  {:>7.4}  {:>6.2}%  {:>10.4}\n",
+            index + 1,
+            frequency,
+            coefficient.amplitude(),
+            phase(coefficient),
+            share * 100.0,
+            epicycle.truncation_error(index + 1)
+        ));
+    }
+    report
+}
+
+/// Phase of `coefficient` in radians, between -π and π.
+pub fn phase(coefficient: &fft::complex::Complex) -> f64 {
+    let phase = coefficient.phase().in_radians();
+    if phase > PI {
+        phase - 2.0 * PI
+    } else {
+        phase
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use fft::complex;
+    use fft::path;
+
+    #[test]
+    fn report_test() {
+        // Constant term 1 and the frequency 1 with amplitude 1, half of the energy each.
+        let epicycle = epicycle::Epicycle::new(&path::Path {
+            data: (0..4)
+                .map(|k| {
+                    let angle = 2.0 * PI * f64::from(k) / 4.0;
+                    complex::Complex::new(1.0 + angle.cos(), angle.sin())
+                })
+                .collect(),
+        });
+        let report = report(&epicycle, 2);
+        let lines: Vec<_> = report.lines().collect();
+        assert_eq!("terms  4", lines[0]);
+        assert_eq!(5, lines.len());
+        assert!(lines[3].contains(" 50.00%"));
+        assert!(lines[4].contains("100.00%"));
+    }
+
+    #[test]
+    fn parse_test() {
+        let arguments: Vec<String> = vec!["in.txt".to_string(), "--terms=3".to_string()];
+        let options = parse(&arguments, &Config::default()).unwrap().unwrap();
+        assert_eq!(3, options.terms);
+        assert!(parse(&[], &Config::default()).is_err());
+    }
+}
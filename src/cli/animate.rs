@@ -0,0 +1,274 @@
+use crate::cli::arguments::{self, Argument, Arguments};
+use crate::cli::config::Config;
+use crate::cli::error;
+use fft::render;
+use fft::render::dashboard::Dashboard;
+use fft::render::gif::{self, GifConfig};
+use fft::render::progress::{self, Progress};
+use std::error::Error;
+use std::f64::consts::PI;
+use std::io::{self, IsTerminal};
+
+pub const USAGE: &str = "\
+Draws the reconstruction of a path being traced as an animated GIF, or as numbered PNG frames.
+
+Usage: fft animate [INPUT] [OPTIONS]
+
+Arguments:
+  [INPUT]                   Path to draw, one `x, y` point per line, `-` for the standard
+                            input, from the configuration file by default
+
+Options:
+  -o, --output <FILE>       GIF file to write, `-` for the standard output
+  -p, --precision <TERMS>   Number of terms used, all of them by default
+  -s, --size <WxH>          Image size in pixels, a single number for a square [default: 640]
+      --fps <FRAMES>        Frames per second, at most 50 [default: 25]
+      --duration <TIME>     Time taken to trace the whole path, such as 12s or 500ms [default: 4s]
+      --trail <TRAIL>       full, or fade for a trace fading over a quarter of a turn, fade:0.5
+                            over half a turn [default: full]
+      --frames <DIR>        Writes the frames as frame_0001.png, frame_0002.png... in DIR
+      --resume              Keeps the frames of --frames written by an interrupted run with the
+                            same settings and only draws the missing ones
+      --dashboard           Shows the stages, the largest terms and a preview of the current
+                            frame on the standard error while drawing
+      --config <FILE>       Configuration file [default: fftdraw.toml when present]
+  -h, --help                Prints this help
+";
+
+/// Settings of the animate command.
+#[derive(Debug, Clone)]
+pub struct Options {
+    pub input: String,
+    pub outputs: Vec<String>,
+    /// Directory the frames are written to as PNG files.
+    pub frames: Option<String>,
+    pub resume: bool,
+    /// Whether progress is shown as a dashboard rather than a line.
+    pub dashboard: bool,
+    pub precision: Option<usize>,
+    pub config: render::RenderConfig,
+    pub animation: GifConfig,
+}
+
+/// Options given by `arguments` on top of `config`, `None` when help is asked for. Without
+/// `--output` or `--frames`, the GIF files listed in the configuration are written.
+pub fn parse(arguments: &[String], config: &Config) -> Result<Option<Options>, Box<dyn Error>> {
+    let mut input = None;
+    let mut outputs = Vec::new();
+    let mut frames = None;
+    let mut resume = false;
+    let mut dashboard = false;
+    let mut precision = config.precision;
+    let mut render = config.render.clone();
+    let mut animation = config.animation.clone();
+
+    let mut arguments = Arguments::new(arguments);
+    while let Some(argument) = arguments.next() {
+        match &argument {
+            Argument::Positional(value) if input.is_none() => input = Some(value.clone()),
+            Argument::Flag(flag) => match flag.as_str() {
+                "-h" | "--help" => return Ok(None),
+                "-o" | "--output" => outputs.push(arguments.value()?),
+                "-p" | "--precision" => precision = Some(arguments.parsed()?),
+                "-s" | "--size" => {
+                    let (width, height) = arguments::parse_size(&arguments.value()?)?;
+                    render.width = width;
+                    render.height = height;
+                }
+                "--fps" => animation.fps = arguments.parsed()?,
+                "--duration" => {
+                    animation.duration = arguments::parse_duration(&arguments.value()?)?
+                }
+                "--trail" => render.trail = arguments::parse_trail(&arguments.value()?)?,
+                "--frames" => frames = Some(arguments.value()?),
+                "--resume" => resume = true,
+                "--dashboard" => dashboard = true,
+                _ => return Err(arguments::unexpected(&argument)),
+            },
+            _ => return Err(arguments::unexpected(&argument)),
+        }
+    }
+
+    if animation.fps == 0 {
+        return Err("Invalid --fps 0, it must be positive".into());
+    }
+    if resume && frames.is_none() {
+        return Err("--resume needs the directory of the frames given by --frames".into());
+    }
+    if outputs.is_empty() && frames.is_none() {
+        outputs = config
+            .outputs
+            .iter()
+            .filter(|output| output.to_ascii_lowercase().ends_with(".gif"))
+            .cloned()
+            .collect();
+    }
+    if outputs.is_empty() && frames.is_none() {
+        return Err("Missing --output".into());
+    }
+    if !outputs.is_empty() {
+        gif::check_size(render.width, render.height)?;
+    }
+    Ok(Some(Options {
+        input: input
+            .or_else(|| config.input.clone())
+            .ok_or("Missing input file")?,
+        outputs,
+        frames,
+        resume,
+        dashboard,
+        precision,
+        config: render,
+        animation,
+    }))
+}
+
+pub fn run(arguments: &[String], config: &Config) -> Result<(), Box<dyn Error>> {
+    let options = match parse(arguments, config).map_err(error::usage)? {
+        Some(options) => options,
+        None => {
+            print!("{}", USAGE);
+            return Ok(());
+        }
+    };
+    let mut dashboard = if options.dashboard {
+        let title = format!("fft animate {}", options.input);
+        Some(super::dashboard(&title, &options.config))
+    } else {
+        None
+    };
+    let path = super::in_stage(&mut dashboard, "read", || super::read_path(&options.input))?;
+    let epicycle = super::in_stage(&mut dashboard, "decompose", || super::decompose(&path))?;
+    let precision = options.precision.unwrap_or_else(|| epicycle.len());
+    let config = render::RenderConfig {
+        source: super::source_name(&options.input),
+        ..options.config.clone()
+    };
+    super::log::debug(format!(
+        "{} frames of {} ms",
+        options.animation.frame_count(),
+        u32::from(options.animation.frame_delay()) * 10
+    ));
+    // Shows each frame drawn in the stage `name` on the dashboard, if any.
+    let on_frame = |dashboard: &mut Option<Dashboard>, name: &str, progress: Progress| {
+        if let Some(dashboard) = dashboard {
+            dashboard.advance(name, progress.fraction());
+            dashboard.show(&epicycle, precision, 2.0 * PI * progress.fraction());
+            dashboard.print(false);
+        }
+    };
+    if let Some(directory) = &options.frames {
+        let export = render::FrameExport {
+            frame_count: options.animation.frame_count(),
+            resume: options.resume,
+            ..render::FrameExport::default()
+        };
+        let line = io::stderr().is_terminal() && dashboard.is_none();
+        let mut progress = |progress: Progress| {
+            if line {
+                progress::print(progress);
+            }
+            on_frame(&mut dashboard, "draw frames", progress);
+        };
+        let _span = super::log::span(format!("write frames to {}", directory));
+        let result = render::export_frames(
+            &epicycle,
+            precision,
+            directory,
+            &export,
+            &config,
+            &mut progress,
+        );
+        super::end_stage(&mut dashboard, "draw frames", result.is_ok());
+        result.map_err(|failure| {
+            let message = format!("Cannot write the frames to {}: {}", directory, failure);
+            error::tag(error::Kind::Output, message.into())
+        })?;
+    }
+    if options.outputs.is_empty() {
+        return Ok(());
+    }
+    let result = {
+        let _span = super::log::span("draw animation");
+        let mut progress = |progress| on_frame(&mut dashboard, "draw animation", progress);
+        super::write_streamed(&options.outputs, |output| {
+            gif::encode_to(
+                output,
+                &epicycle,
+                precision,
+                &config,
+                &options.animation,
+                &mut progress,
+            )
+        })
+    };
+    super::end_stage(&mut dashboard, "draw animation", result.is_ok());
+    result
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    fn arguments(line: &str) -> Vec<String> {
+        line.split_whitespace().map(String::from).collect()
+    }
+
+    #[test]
+    fn parse_test() {
+        let config = Config::default();
+        let options = parse(&arguments("in.txt -o out.gif -s 320"), &config)
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            ("in.txt", vec!["out.gif".to_string()]),
+            (&options.input[..], options.outputs)
+        );
+        assert_eq!((320, 320), (options.config.width, options.config.height));
+        assert!(parse(&arguments("-h"), &config).unwrap().is_none());
+        assert!(parse(&arguments("in.txt"), &config).is_err());
+        assert!(parse(&arguments("in.txt -o out.gif -f svg"), &config).is_err());
+
+        let config =
+            Config::parse("output = [\"a.png\", \"a.gif\"]\n[animation]\nfps = 5").unwrap();
+        let options = parse(&arguments("in.txt"), &config).unwrap().unwrap();
+        assert_eq!(vec!["a.gif".to_string()], options.outputs);
+        assert_eq!(5, options.animation.fps);
+    }
+
+    #[test]
+    fn parse_timing_test() {
+        let config = Config::default();
+        let options = parse(
+            &arguments("in.txt -o out.gif --fps 30 --duration 12s --trail fade"),
+            &config,
+        )
+        .unwrap()
+        .unwrap();
+        assert_eq!(30, options.animation.fps);
+        assert_eq!(12.0, options.animation.duration);
+        assert_eq!(render::Trail::Fade(0.25), options.config.trail);
+        assert_eq!(400, options.animation.frame_count());
+        assert!(parse(&arguments("in.txt -o out.gif --fps 0"), &config).is_err());
+        assert!(parse(&arguments("in.txt -o out.gif --duration -1s"), &config).is_err());
+        assert!(parse(&arguments("in.txt -o out.gif --trail dots"), &config).is_err());
+    }
+
+    #[test]
+    fn parse_frames_test() {
+        let config = Config::default();
+        let options = parse(&arguments("in.txt --frames out --resume"), &config)
+            .unwrap()
+            .unwrap();
+        assert_eq!(Some("out".to_string()), options.frames);
+        assert!(options.resume);
+        assert!(!options.dashboard);
+        assert!(options.outputs.is_empty());
+        assert!(parse(&arguments("in.txt -o out.gif --resume"), &config).is_err());
+        let options = parse(&arguments("in.txt -o out.gif --dashboard"), &config)
+            .unwrap()
+            .unwrap();
+        assert!(options.dashboard);
+    }
+}
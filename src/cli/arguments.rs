@@ -0,0 +1,239 @@
+use fft::render::Trail;
+use std::error::Error;
+use std::str::FromStr;
+use std::thread;
+
+/// One command-line argument.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Argument {
+    /// A flag such as `-o` or `--output`, without its value.
+    Flag(String),
+    /// Anything else, including a lone `-`.
+    Positional(String),
+}
+
+/// Walks through command-line arguments, a flag taking its value either from the next argument
+/// or after an `=` as in `--output=out.png`.
+#[derive(Debug)]
+pub struct Arguments<'a> {
+    remaining: std::slice::Iter<'a, String>,
+    flag: String,
+    inline: Option<String>,
+}
+
+impl<'a> Arguments<'a> {
+    pub fn new(arguments: &'a [String]) -> Arguments<'a> {
+        Arguments {
+            remaining: arguments.iter(),
+            flag: String::new(),
+            inline: None,
+        }
+    }
+
+    pub fn next(&mut self) -> Option<Argument> {
+        let argument = self.remaining.next()?;
+        if !argument.starts_with('-') || argument == "-" {
+            return Some(Argument::Positional(argument.clone()));
+        }
+        let (flag, inline) = match argument.split_once('=') {
+            Some((flag, value)) if flag.starts_with("--") => (flag, Some(value.to_string())),
+            _ => (argument.as_str(), None),
+        };
+        self.flag = flag.to_string();
+        self.inline = inline;
+        Some(Argument::Flag(self.flag.clone()))
+    }
+
+    /// Value of the flag just returned by `next`.
+    pub fn value(&mut self) -> Result<String, Box<dyn Error>> {
+        if let Some(value) = self.inline.take() {
+            return Ok(value);
+        }
+        self.remaining
+            .next()
+            .cloned()
+            .ok_or_else(|| format!("Missing value for {}", self.flag).into())
+    }
+
+    /// Value of the flag just returned by `next`, parsed.
+    pub fn parsed<T>(&mut self) -> Result<T, Box<dyn Error>>
+    where
+        T: FromStr,
+        T::Err: std::fmt::Display,
+    {
+        let value = self.value()?;
+        value
+            .parse()
+            .map_err(|error| format!("Invalid value {} for {}: {}", value, self.flag, error).into())
+    }
+}
+
+/// Error for an argument a command does not accept.
+pub fn unexpected(argument: &Argument) -> Box<dyn Error> {
+    let text = match argument {
+        Argument::Flag(flag) => flag,
+        Argument::Positional(value) => value,
+    };
+    format!("Unexpected argument {}, see --help", text).into()
+}
+
+/// Parses `640x480`, or `640` for a square image.
+pub fn parse_size(size: &str) -> Result<(u32, u32), Box<dyn Error>> {
+    let (width, height) = match size.split_once(['x', 'X']) {
+        Some((width, height)) => (width.trim().parse()?, height.trim().parse()?),
+        None => {
+            let side = size.trim().parse()?;
+            (side, side)
+        }
+    };
+    if width == 0 || height == 0 {
+        return Err(format!("Invalid size {}, both sides must be positive", size).into());
+    }
+    Ok((width, height))
+}
+
+/// Parses a comma separated list of positive counts, such as `1,2,4,8`.
+pub fn parse_counts(list: &str) -> Result<Vec<usize>, Box<dyn Error>> {
+    let counts = list
+        .split(',')
+        .map(|item| {
+            item.trim()
+                .parse()
+                .map_err(|error| format!("Invalid number {}: {}", item.trim(), error))
+        })
+        .collect::<Result<Vec<usize>, _>>()?;
+    if counts.contains(&0) {
+        return Err(format!("Invalid list {}, numbers must be positive", list).into());
+    }
+    Ok(counts)
+}
+
+/// Parses a number of threads, `0` standing for one per processor.
+pub fn parse_jobs(jobs: &str) -> Result<usize, Box<dyn Error>> {
+    match jobs.trim().parse::<usize>() {
+        Ok(0) => Ok(thread::available_parallelism().map_or(1, |count| count.get())),
+        Ok(count) => Ok(count),
+        Err(error) => Err(format!("Invalid number of jobs {}: {}", jobs, error).into()),
+    }
+}
+
+/// Parses a positive duration in seconds such as `12s`, `12` or `500ms`.
+pub fn parse_duration(duration: &str) -> Result<f64, Box<dyn Error>> {
+    let text = duration.trim();
+    let (number, scale) = if let Some(number) = text.strip_suffix("ms") {
+        (number, 1e-3)
+    } else {
+        (text.strip_suffix('s').unwrap_or(text), 1.0)
+    };
+    let seconds = number
+        .trim()
+        .parse::<f64>()
+        .map_err(|error| format!("Invalid duration {}: {}", duration, error))?
+        * scale;
+    if !(seconds.is_finite() && seconds > 0.0) {
+        return Err(format!("Invalid duration {}, it must be positive", duration).into());
+    }
+    Ok(seconds)
+}
+
+/// Parses a trail: `full`, `fade` for segments fading over a quarter of a turn, or `fade:0.5` for
+/// another fraction of a turn.
+pub fn parse_trail(trail: &str) -> Result<Trail, Box<dyn Error>> {
+    let (kind, length) = match trail.split_once(':') {
+        Some((kind, length)) => (kind, Some(length)),
+        None => (trail, None),
+    };
+    match (kind.to_ascii_lowercase().as_str(), length) {
+        ("full", None) => Ok(Trail::Full),
+        ("fade", None) => Ok(Trail::Fade(0.25)),
+        ("fade", Some(length)) => {
+            let length: f64 = length
+                .parse()
+                .map_err(|error| format!("Invalid trail length {}: {}", length, error))?;
+            if !(length > 0.0 && length <= 1.0) {
+                return Err(format!(
+                    "Invalid trail {}, the length is a fraction of a turn",
+                    trail
+                )
+                .into());
+            }
+            Ok(Trail::Fade(length))
+        }
+        _ => Err(format!(
+            "Unknown trail {}, expected full, fade or fade:<fraction>",
+            trail
+        )
+        .into()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn arguments_test() {
+        let line: Vec<String> = ["in.txt", "-o", "a", "--size=3x4", "-", "-p"]
+            .iter()
+            .map(|a| a.to_string())
+            .collect();
+        let mut arguments = Arguments::new(&line);
+        assert_eq!(
+            Some(Argument::Positional("in.txt".to_string())),
+            arguments.next()
+        );
+        assert_eq!(Some(Argument::Flag("-o".to_string())), arguments.next());
+        assert_eq!("a", arguments.value().unwrap());
+        assert_eq!(Some(Argument::Flag("--size".to_string())), arguments.next());
+        assert_eq!("3x4", arguments.value().unwrap());
+        assert_eq!(
+            Some(Argument::Positional("-".to_string())),
+            arguments.next()
+        );
+        assert_eq!(Some(Argument::Flag("-p".to_string())), arguments.next());
+        assert!(arguments.parsed::<usize>().is_err());
+        assert_eq!(None, arguments.next());
+    }
+
+    #[test]
+    fn parse_size_test() {
+        assert_eq!((800, 600), parse_size("800x600").unwrap());
+        assert_eq!((300, 300), parse_size("300").unwrap());
+        assert!(parse_size("0x10").is_err());
+        assert!(parse_size("tall").is_err());
+    }
+
+    #[test]
+    fn parse_counts_test() {
+        assert_eq!(vec![1, 2, 16], parse_counts("1, 2,16").unwrap());
+        assert!(parse_counts("1,,2").is_err());
+        assert!(parse_counts("0,1").is_err());
+    }
+
+    #[test]
+    fn parse_jobs_test() {
+        assert_eq!(3, parse_jobs("3").unwrap());
+        assert!(parse_jobs("0").unwrap() >= 1);
+        assert!(parse_jobs("-1").is_err());
+    }
+
+    #[test]
+    fn parse_duration_test() {
+        assert_eq!(12.0, parse_duration("12s").unwrap());
+        assert_eq!(1.5, parse_duration("1.5").unwrap());
+        assert_eq!(0.5, parse_duration("500ms").unwrap());
+        assert!(parse_duration("0s").is_err());
+        assert!(parse_duration("12m").is_err());
+    }
+
+    #[test]
+    fn parse_trail_test() {
+        assert_eq!(Trail::Full, parse_trail("full").unwrap());
+        assert_eq!(Trail::Fade(0.25), parse_trail("fade").unwrap());
+        assert_eq!(Trail::Fade(0.5), parse_trail("fade:0.5").unwrap());
+        assert!(parse_trail("fade:2").is_err());
+        assert!(parse_trail("full:0.5").is_err());
+        assert!(parse_trail("dots").is_err());
+    }
+}
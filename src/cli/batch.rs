@@ -0,0 +1,325 @@
+use crate::cli::arguments::{self, Argument, Arguments};
+use crate::cli::config::Config;
+use crate::cli::error;
+use crate::cli::render::{self, Format};
+use crate::cli::template;
+use fft::render::dashboard::Dashboard;
+use std::error::Error;
+use std::f64::consts::PI;
+use std::fs;
+use std::path::Path;
+use std::sync::Mutex;
+
+pub const USAGE: &str = "\
+Draws the reconstruction of every path matching a pattern, one image per path.
+
+Usage: fft batch <PATTERN> --output <TEMPLATE> [OPTIONS]
+
+Arguments:
+  <PATTERN>                 Directory, whose .txt files are drawn, or files such as
+                            `frames/*.txt`, `*` and `?` being allowed in the file name
+
+Options:
+  -o, --output <TEMPLATE>   Image to write for each path, where {stem} is replaced by the name
+                            of the path file without extension, {name} by its full name,
+                            {index} by its position among the matching files, from 1, and
+                            {precision}, {size}, {width} and {height} by the image settings
+  -p, --precision <TERMS>   Number of terms used, all of them by default
+  -s, --size <WxH>          Image size in pixels, a single number for a square [default: 640]
+  -f, --format <FORMAT>     png, svg or ppm, guessed from the output extension by default
+  -j, --jobs <COUNT>        Number of files drawn at once, 0 for one per processor [default: 1]
+      --dashboard           Shows the state of each file, the largest terms and a preview of
+                            the last path drawn on the standard error while drawing
+      --config <FILE>       Configuration file [default: fftdraw.toml when present]
+  -h, --help                Prints this help
+";
+
+/// Settings of the batch command.
+#[derive(Debug, Clone)]
+pub struct Options {
+    pub pattern: String,
+    pub template: String,
+    pub format: Option<Format>,
+    /// Number of threads drawing the files.
+    pub jobs: usize,
+    /// Whether progress is shown as a dashboard.
+    pub dashboard: bool,
+    /// Settings shared by every render, without input and outputs.
+    pub render: render::Options,
+}
+
+/// Options given by `arguments` on top of `config`, `None` when help is asked for.
+pub fn parse(arguments: &[String], config: &Config) -> Result<Option<Options>, Box<dyn Error>> {
+    let mut pattern = None;
+    let mut template = None;
+    let mut format = None;
+    let mut precision = config.precision;
+    let mut render = config.render.clone();
+    let mut jobs = 1;
+    let mut dashboard = false;
+
+    let mut arguments = Arguments::new(arguments);
+    while let Some(argument) = arguments.next() {
+        match &argument {
+            Argument::Positional(value) if pattern.is_none() => pattern = Some(value.clone()),
+            Argument::Flag(flag) => match flag.as_str() {
+                "-h" | "--help" => return Ok(None),
+                "-o" | "--output" => template = Some(arguments.value()?),
+                "-p" | "--precision" => precision = Some(arguments.parsed()?),
+                "-s" | "--size" => {
+                    let (width, height) = arguments::parse_size(&arguments.value()?)?;
+                    render.width = width;
+                    render.height = height;
+                }
+                "-f" | "--format" => format = Some(Format::parse(&arguments.value()?)?),
+                "-j" | "--jobs" => jobs = arguments::parse_jobs(&arguments.value()?)?,
+                "--dashboard" => dashboard = true,
+                _ => return Err(arguments::unexpected(&argument)),
+            },
+            _ => return Err(arguments::unexpected(&argument)),
+        }
+    }
+
+    let template = template.ok_or("Missing --output")?;
+    template::check(&template, &template::PLACEHOLDERS)?;
+    Ok(Some(Options {
+        pattern: pattern.ok_or("Missing pattern of the files to draw")?,
+        template,
+        format,
+        jobs,
+        dashboard,
+        render: render::Options {
+            input: String::new(),
+            outputs: Vec::new(),
+            precision,
+            sweep: Vec::new(),
+            sheet: false,
+            jobs: 1,
+            matched: false,
+            config: render,
+        },
+    }))
+}
+
+pub fn run(arguments: &[String], config: &Config) -> Result<(), Box<dyn Error>> {
+    let options = match parse(arguments, config).map_err(error::usage)? {
+        Some(options) => options,
+        None => {
+            print!("{}", USAGE);
+            return Ok(());
+        }
+    };
+    let inputs = matching_files(&options.pattern).map_err(error::input)?;
+    if inputs.is_empty() {
+        let message = format!("No file matches {}", options.pattern);
+        return Err(error::input(message.into()));
+    }
+    let dashboard = if options.dashboard {
+        let title = format!("fft batch {}", options.pattern);
+        let mut dashboard = super::dashboard(&title, &options.render.config);
+        for input in &inputs {
+            dashboard.add(input);
+        }
+        dashboard.print(true);
+        Some(dashboard)
+    } else {
+        None
+    };
+    let dashboard = Mutex::new(dashboard);
+    let results = super::run_jobs(options.jobs, inputs.len(), |index| {
+        let input = &inputs[index];
+        let output = output_name(&options.template, input, index + 1);
+        super::log::debug(format!("{} -> {}", input, output));
+        let update = |change: &dyn Fn(&mut Dashboard)| {
+            if let Some(dashboard) = dashboard.lock().unwrap().as_mut() {
+                change(dashboard);
+                dashboard.print(false);
+            }
+        };
+        update(&|dashboard| dashboard.start(input));
+        let result = options
+            .format
+            .map_or_else(|| Format::of(&output).map_err(error::usage), Ok)
+            .and_then(|format| {
+                let epicycle = super::decompose(&super::read_path(input)?)?;
+                let precision = options.render.precision.unwrap_or_else(|| epicycle.len());
+                update(&|dashboard| dashboard.show(&epicycle, precision, 2.0 * PI));
+                render::draw_decomposed(
+                    &epicycle,
+                    &render::Options {
+                        input: input.clone(),
+                        outputs: vec![(output.clone(), format)],
+                        ..options.render.clone()
+                    },
+                )
+            });
+        match &result {
+            Ok(()) => update(&|dashboard| dashboard.finish(input)),
+            Err(_) => update(&|dashboard| dashboard.fail(input)),
+        }
+        result.map_err(|failure| {
+            let mut detached = error::detach(failure.as_ref());
+            if options.dashboard {
+                // Listed after the dashboard, which would hide them.
+                detached.message = format!("{}: {}", input, detached.message);
+            } else {
+                eprintln!("{}: {}", input, failure);
+            }
+            detached
+        })
+    });
+    if let Some(dashboard) = dashboard.lock().unwrap().as_mut() {
+        dashboard.print(true);
+        for failure in results.iter().filter_map(|result| result.as_ref().err()) {
+            eprintln!("{}", failure);
+        }
+    }
+    let mut failures = 0;
+    // Class shared by all the failures, if any.
+    let mut kind = None;
+    for result in results {
+        if let Err(failure) = result {
+            failures += 1;
+            let failure_kind = failure.kind;
+            kind = match kind {
+                Some(kind) if kind != failure_kind => Some(error::Kind::Other),
+                _ => Some(failure_kind),
+            };
+        }
+    }
+    if let Some(kind) = kind {
+        let message = format!("{} of {} files could not be drawn", failures, inputs.len());
+        return Err(error::tag(kind, message.into()));
+    }
+    Ok(())
+}
+
+/// Files matching `pattern`, sorted by name. A directory matches the `.txt` files it contains,
+/// otherwise `*` (any text) and `?` (any character) may be used in the file name.
+pub fn matching_files(pattern: &str) -> Result<Vec<String>, Box<dyn Error>> {
+    let path = Path::new(pattern);
+    // Directory given in the pattern, if any, which the matching files are prefixed with.
+    let (directory, name) = if path.is_dir() {
+        (Some(path), "*.txt".to_string())
+    } else {
+        let name = path
+            .file_name()
+            .ok_or_else(|| format!("Invalid pattern {}", pattern))?
+            .to_string_lossy()
+            .into_owned();
+        (path.parent().filter(|d| !d.as_os_str().is_empty()), name)
+    };
+    let listed = directory.unwrap_or_else(|| Path::new("."));
+    if listed.to_string_lossy().contains(['*', '?']) {
+        return Err(format!("Wildcards are only allowed in file names: {}", pattern).into());
+    }
+    let mut files = Vec::new();
+    for entry in fs::read_dir(listed)
+        .map_err(|error| format!("Cannot list {}: {}", listed.display(), error))?
+    {
+        let entry = entry?;
+        let file_name = entry.file_name().to_string_lossy().into_owned();
+        if entry.path().is_file() && wildcard(&name, &file_name) {
+            files.push(match directory {
+                Some(directory) => directory.join(&file_name).to_string_lossy().into_owned(),
+                None => file_name,
+            });
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+/// Whether `name` matches `pattern`, where `*` stands for any text and `?` for any character.
+fn wildcard(pattern: &str, name: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let name: Vec<char> = name.chars().collect();
+    // Position in the pattern after the last `*` and the position in the name it was tried at.
+    let mut star: Option<(usize, usize)> = None;
+    let (mut p, mut n) = (0, 0);
+    while n < name.len() {
+        if p < pattern.len() && (pattern[p] == '?' || pattern[p] == name[n]) {
+            p += 1;
+            n += 1;
+        } else if p < pattern.len() && pattern[p] == '*' {
+            star = Some((p + 1, n));
+            p += 1;
+        } else if let Some((after, tried)) = star {
+            p = after;
+            n = tried + 1;
+            star = Some((after, tried + 1));
+        } else {
+            return false;
+        }
+    }
+    pattern[p..].iter().all(|c| *c == '*')
+}
+
+/// Output file for `input`, the `index`-th matching file, from `template`. The placeholders of
+/// the image settings are left for the render to fill.
+pub fn output_name(template: &str, input: &str, index: usize) -> String {
+    let mut values = template::input_values(input).to_vec();
+    values.push(("index", index.to_string()));
+    template::fill(template, &values)
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use std::env;
+
+    #[test]
+    fn wildcard_test() {
+        assert!(wildcard("*.txt", "frame_001.txt"));
+        assert!(wildcard("frame_??1.txt", "frame_001.txt"));
+        assert!(wildcard("*_*.txt", "a_b_c.txt"));
+        assert!(wildcard("*", ""));
+        assert!(!wildcard("*.txt", "frame.txt.bak"));
+        assert!(!wildcard("frame_?.txt", "frame_01.txt"));
+    }
+
+    #[test]
+    fn output_name_test() {
+        assert_eq!(
+            "out/frame_7.png and frame.txt",
+            output_name("out/{stem}_{index}.png and {name}", "in/frame.txt", 7)
+        );
+        assert_eq!(
+            "frame_{precision}p.png",
+            output_name("{stem}_{precision}p.png", "frame.txt", 1)
+        );
+        let config = Config::default();
+        let arguments =
+            |line: &str| -> Vec<String> { line.split_whitespace().map(String::from).collect() };
+        assert!(parse(&arguments("in -o {stem}_{size}.png"), &config).is_ok());
+        assert!(parse(&arguments("in -o {colour}.png"), &config).is_err());
+        let options = parse(&arguments("in -o {stem}.png --dashboard -j 2"), &config)
+            .unwrap()
+            .unwrap();
+        assert!(options.dashboard);
+        assert_eq!(2, options.jobs);
+    }
+
+    #[test]
+    fn matching_files_test() {
+        let mut directory = env::temp_dir();
+        directory.push(format!("fft_batch_test_{}", std::process::id()));
+        fs::create_dir_all(&directory).unwrap();
+        for name in &["b.txt", "a.txt", "c.csv"] {
+            fs::write(directory.join(name), "0, 0\n").unwrap();
+        }
+        let root = directory.to_string_lossy().into_owned();
+        let expected = vec![
+            directory.join("a.txt").to_string_lossy().into_owned(),
+            directory.join("b.txt").to_string_lossy().into_owned(),
+        ];
+        assert_eq!(expected, matching_files(&root).unwrap());
+        let pattern = directory.join("*.txt").to_string_lossy().into_owned();
+        assert_eq!(expected, matching_files(&pattern).unwrap());
+        let pattern = directory.join("?.csv").to_string_lossy().into_owned();
+        assert_eq!(1, matching_files(&pattern).unwrap().len());
+        assert!(matching_files(&format!("{}*/a.txt", root)).is_err());
+        fs::remove_dir_all(&directory).unwrap();
+    }
+}
@@ -0,0 +1,172 @@
+use crate::cli::arguments::{self, Argument, Arguments};
+use crate::cli::error;
+use fft::complex;
+use fft::epicycle;
+use fft::path;
+use std::error::Error;
+use std::f64::consts::PI;
+use std::hint;
+use std::time::{Duration, Instant};
+
+pub const USAGE: &str = "\
+Times the transforms and the sampling of the drawing on synthetic paths of several sizes.
+
+Usage: fft bench [OPTIONS]
+
+Options:
+  -n, --sizes <LIST>      Numbers of points of the paths [default: 64,256,1024,4096]
+      --samples <COUNT>   Number of points sampled on each drawing [default: 1000]
+  -h, --help              Prints this help
+
+Each measure is repeated for about 100 ms and averaged. The fast transform is only timed for
+powers of two, other sizes falling back to the direct transform.
+";
+
+const DEFAULT_SIZES: [usize; 4] = [64, 256, 1024, 4096];
+
+const DEFAULT_SAMPLES: usize = 1000;
+
+/// Time spent repeating each measure.
+const BUDGET: Duration = Duration::from_millis(100);
+
+/// Settings of the bench command.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Options {
+    pub sizes: Vec<usize>,
+    pub samples: usize,
+}
+
+/// Options given by `arguments`, `None` when help is asked for.
+pub fn parse(arguments: &[String]) -> Result<Option<Options>, Box<dyn Error>> {
+    let mut options = Options {
+        sizes: DEFAULT_SIZES.to_vec(),
+        samples: DEFAULT_SAMPLES,
+    };
+    let mut arguments = Arguments::new(arguments);
+    while let Some(argument) = arguments.next() {
+        match &argument {
+            Argument::Flag(flag) => match flag.as_str() {
+                "-h" | "--help" => return Ok(None),
+                "-n" | "--sizes" => options.sizes = arguments::parse_counts(&arguments.value()?)?,
+                "--samples" => options.samples = arguments.parsed()?,
+                _ => return Err(arguments::unexpected(&argument)),
+            },
+            _ => return Err(arguments::unexpected(&argument)),
+        }
+    }
+    Ok(Some(options))
+}
+
+pub fn run(arguments: &[String]) -> Result<(), Box<dyn Error>> {
+    let options = match parse(arguments).map_err(error::usage)? {
+        Some(options) => options,
+        None => {
+            print!("{}", USAGE);
+            return Ok(());
+        }
+    };
+    println!(
+        "{:>8}  {:>12}  {:>12}  {:>12}",
+        "points",
+        "dft",
+        "fft",
+        format!("sample {}", options.samples)
+    );
+    for &size in &options.sizes {
+        let path = synthetic(size);
+        let dft = time(|| {
+            hint::black_box(fft::fft::dft(path.data.clone()));
+        });
+        let fast = if size.is_power_of_two() {
+            format_duration(time(|| {
+                let mut data = path.data.clone();
+                fft::fft::fft(&mut data);
+                hint::black_box(data);
+            }))
+        } else {
+            "-".to_string()
+        };
+        let epicycle = epicycle::Epicycle::new(&path);
+        let sample = time(|| {
+            hint::black_box(epicycle.sample(epicycle.len(), options.samples));
+        });
+        println!(
+            "{:>8}  {:>12}  {:>12}  {:>12}",
+            size,
+            format_duration(dft),
+            fast,
+            format_duration(sample)
+        );
+    }
+    Ok(())
+}
+
+/// Closed path of `size` points tracing a flower with a few harmonics, so that the drawing uses
+/// terms of various amplitudes.
+pub fn synthetic(size: usize) -> path::Path {
+    path::Path {
+        data: (0..size)
+            .map(|k| {
+                let angle = 2.0 * PI * k as f64 / size as f64;
+                let radius = 1.0 + 0.3 * (5.0 * angle).cos() + 0.1 * (13.0 * angle).sin();
+                complex::Complex::new(radius * angle.cos(), radius * angle.sin())
+            })
+            .collect(),
+    }
+}
+
+/// Average duration of `measure`, repeated for about `BUDGET`.
+fn time<F: FnMut()>(mut measure: F) -> Duration {
+    let start = Instant::now();
+    let mut runs = 0;
+    while runs == 0 || start.elapsed() < BUDGET {
+        measure();
+        runs += 1;
+    }
+    start.elapsed() / runs
+}
+
+/// `duration` in the most readable unit.
+fn format_duration(duration: Duration) -> String {
+    let seconds = duration.as_secs_f64();
+    if seconds >= 1.0 {
+        format!("{:.2} s", seconds)
+    } else if seconds >= 1e-3 {
+        format!("{:.2} ms", seconds * 1e3)
+    } else {
+        format!("{:.2} µs", seconds * 1e6)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn parse_test() {
+        let arguments: Vec<String> = vec!["--sizes=8,16".to_string()];
+        assert_eq!(
+            Options {
+                sizes: vec![8, 16],
+                samples: DEFAULT_SAMPLES,
+            },
+            parse(&arguments).unwrap().unwrap()
+        );
+        assert!(parse(&["in.txt".to_string()]).is_err());
+    }
+
+    #[test]
+    fn synthetic_test() {
+        let path = synthetic(64);
+        assert_eq!(64, path.data.len());
+        assert!((path.data[0].re - 1.3).abs() < 1e-12);
+    }
+
+    #[test]
+    fn format_duration_test() {
+        assert_eq!("1.50 s", format_duration(Duration::from_millis(1500)));
+        assert_eq!("2.00 ms", format_duration(Duration::from_millis(2)));
+        assert_eq!("0.50 µs", format_duration(Duration::from_nanos(500)));
+    }
+}
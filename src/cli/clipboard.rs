@@ -0,0 +1,164 @@
+//! Clipboard of the desktop, given as the file name `@clipboard`. The standard library has no
+//! access to it, so it is read and written through the tools of each platform: pbpaste and pbcopy
+//! on macOS, PowerShell and clip on Windows, and wl-clipboard, xclip or xsel elsewhere.
+
+use crate::cli::convert;
+use std::env;
+use std::io::{self, Write};
+use std::process::{Command, Stdio};
+
+/// File name standing for the clipboard.
+pub const NAME: &str = "@clipboard";
+
+/// Programs with their arguments printing the clipboard, in the order they are tried.
+fn paste_commands() -> Vec<&'static [&'static str]> {
+    if cfg!(target_os = "macos") {
+        vec![&["pbpaste"]]
+    } else if cfg!(windows) {
+        vec![&["powershell", "-NoProfile", "-Command", "Get-Clipboard -Raw"]]
+    } else {
+        let mut commands: Vec<&'static [&'static str]> = Vec::new();
+        if env::var_os("WAYLAND_DISPLAY").is_some() {
+            commands.push(&["wl-paste", "--no-newline"]);
+        }
+        commands.push(&["xclip", "-selection", "clipboard", "-out"]);
+        commands.push(&["xsel", "--clipboard", "--output"]);
+        commands
+    }
+}
+
+/// Programs with their arguments copying their standard input to the clipboard.
+fn copy_commands() -> Vec<&'static [&'static str]> {
+    if cfg!(target_os = "macos") {
+        vec![&["pbcopy"]]
+    } else if cfg!(windows) {
+        vec![&["clip"]]
+    } else {
+        let mut commands: Vec<&'static [&'static str]> = Vec::new();
+        if env::var_os("WAYLAND_DISPLAY").is_some() {
+            commands.push(&["wl-copy"]);
+        }
+        commands.push(&["xclip", "-selection", "clipboard", "-in"]);
+        commands.push(&["xsel", "--clipboard", "--input"]);
+        commands
+    }
+}
+
+/// Text in the clipboard.
+pub fn read() -> io::Result<String> {
+    first_working(&paste_commands(), |command| {
+        let output = Command::new(command[0])
+            .args(&command[1..])
+            .stdin(Stdio::null())
+            .stderr(Stdio::null())
+            .output()?;
+        if !output.status.success() {
+            return Err(failed(command, output.status));
+        }
+        String::from_utf8(output.stdout)
+            .map_err(|_| io::Error::new(io::ErrorKind::InvalidData, "the clipboard is not text"))
+    })
+}
+
+/// Replaces the content of the clipboard with `text`.
+pub fn write(text: &str) -> io::Result<()> {
+    first_working(&copy_commands(), |command| {
+        let mut child = Command::new(command[0])
+            .args(&command[1..])
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()?;
+        let written = child.stdin.take().unwrap().write_all(text.as_bytes());
+        let status = child.wait()?;
+        written?;
+        if !status.success() {
+            return Err(failed(command, status));
+        }
+        Ok(())
+    })
+}
+
+/// Result of `run` for the first of `commands` that is installed and succeeds, or the error of
+/// the last one tried.
+fn first_working<T, F>(commands: &[&[&str]], mut run: F) -> io::Result<T>
+where
+    F: FnMut(&[&str]) -> io::Result<T>,
+{
+    let mut failure = None;
+    for command in commands {
+        match run(command) {
+            Ok(value) => return Ok(value),
+            Err(error) if error.kind() == io::ErrorKind::NotFound && failure.is_some() => {}
+            Err(error) if error.kind() == io::ErrorKind::NotFound => {
+                let names: Vec<&str> = commands.iter().map(|command| command[0]).collect();
+                let message = format!("no clipboard tool found, tried {}", names.join(", "));
+                failure = Some(io::Error::new(io::ErrorKind::NotFound, message));
+            }
+            Err(error) => failure = Some(error),
+        }
+    }
+    Err(failure.unwrap_or_else(|| io::Error::other("no clipboard on this platform")))
+}
+
+/// Error of outputs that cannot be copied to the clipboard, being binary.
+pub fn not_text() -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidInput,
+        "only text, such as SVG images, paths and tables, can be copied to the clipboard",
+    )
+}
+
+fn failed(command: &[&str], status: std::process::ExitStatus) -> io::Error {
+    io::Error::other(format!("{} failed with {}", command[0], status))
+}
+
+/// Format of the path in `text`, as files hold them: JSON arrays start with `[`, SVG documents
+/// with `<` and anything else is read as text.
+pub fn format_of(text: &str) -> convert::Format {
+    match text.trim_start().chars().next() {
+        Some('[') => convert::Format::Json,
+        Some('<') => convert::Format::Svg,
+        _ => convert::Format::Text,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn format_of_test() {
+        assert_eq!(convert::Format::Json, format_of(" [[0, 1], [1, 0]]"));
+        assert_eq!(
+            convert::Format::Svg,
+            format_of("<?xml version=\"1.0\"?>\n<svg>")
+        );
+        assert_eq!(convert::Format::Text, format_of("0, 1\n1, 0\n"));
+        assert_eq!(convert::Format::Text, format_of(""));
+    }
+
+    #[test]
+    fn first_working_test() {
+        let missing = || io::Error::new(io::ErrorKind::NotFound, "missing");
+        let commands: &[&[&str]] = &[&["first"], &["second"], &["third"]];
+        let result = first_working(commands, |command| match command[0] {
+            "third" => Ok(3),
+            _ => Err(missing()),
+        });
+        assert_eq!(3, result.unwrap());
+
+        let error = first_working(commands, |_| Err::<(), _>(missing())).unwrap_err();
+        assert_eq!(
+            "no clipboard tool found, tried first, second, third",
+            error.to_string()
+        );
+        let error = first_working(commands, |command| match command[0] {
+            "second" => Err::<(), _>(io::Error::other("second failed")),
+            _ => Err(missing()),
+        })
+        .unwrap_err();
+        assert_eq!("second failed", error.to_string());
+    }
+}
@@ -0,0 +1,137 @@
+use crate::cli::arguments::{self, Argument, Arguments};
+use crate::cli::error;
+use fft::compare::{self, Comparison};
+use std::error::Error;
+
+pub const USAGE: &str = "\
+Prints how close the shapes of two paths are, whatever their position, size and number of points.
+
+Usage: fft compare <FIRST> <SECOND> [OPTIONS]
+
+Arguments:
+  <FIRST>                 Path to compare, one `x, y` point per line, `-` for the standard input
+  <SECOND>                Path to compare it with
+
+Options:
+  -n, --points <COUNT>    Number of points both paths are resampled to [default: 256]
+  -h, --help              Prints this help
+
+The descriptor distance compares the amplitudes of the terms of both decompositions, from 0 for
+the same shape, however rotated, started or oriented, to 1.41. The deviation is the root mean square
+distance between both paths centered and scaled to a mean radius of 1. The similarity goes from
+0 to 100% along the descriptor distance.
+";
+
+const DEFAULT_POINTS: usize = 256;
+
+/// Settings of the compare command.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Options {
+    pub first: String,
+    pub second: String,
+    pub points: usize,
+}
+
+/// Options given by `arguments`, `None` when help is asked for.
+pub fn parse(arguments: &[String]) -> Result<Option<Options>, Box<dyn Error>> {
+    let mut files = Vec::with_capacity(2);
+    let mut points = DEFAULT_POINTS;
+
+    let mut arguments = Arguments::new(arguments);
+    while let Some(argument) = arguments.next() {
+        match &argument {
+            Argument::Positional(value) if files.len() < 2 => files.push(value.clone()),
+            Argument::Flag(flag) => match flag.as_str() {
+                "-h" | "--help" => return Ok(None),
+                "-n" | "--points" => points = arguments.parsed()?,
+                _ => return Err(arguments::unexpected(&argument)),
+            },
+            _ => return Err(arguments::unexpected(&argument)),
+        }
+    }
+
+    if points == 0 {
+        return Err("Invalid --points 0, it must be positive".into());
+    }
+    let mut files = files.into_iter();
+    let (first, second) = match (files.next(), files.next()) {
+        (Some(first), Some(second)) => (first, second),
+        _ => return Err("Missing the paths to compare".into()),
+    };
+    if first == super::STANDARD_STREAM && second == super::STANDARD_STREAM {
+        return Err("Only one path can be read from the standard input".into());
+    }
+    Ok(Some(Options {
+        first,
+        second,
+        points,
+    }))
+}
+
+pub fn run(arguments: &[String]) -> Result<(), Box<dyn Error>> {
+    let options = match parse(arguments).map_err(error::usage)? {
+        Some(options) => options,
+        None => {
+            print!("{}", USAGE);
+            return Ok(());
+        }
+    };
+    let first = super::read_path(&options.first)?;
+    let second = super::read_path(&options.second)?;
+    let comparison = {
+        let _span = super::log::span("compare");
+        compare::compare(&first, &second, options.points)
+    };
+    print!("{}", report(&comparison));
+    Ok(())
+}
+
+pub fn report(comparison: &Comparison) -> String {
+    format!(
+        "descriptor distance  {:.4}\nrms deviation        {:.4}\nsimilarity           {:.2}%\n",
+        comparison.descriptor_distance,
+        comparison.rms_deviation,
+        comparison.similarity * 100.0
+    )
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    fn arguments(line: &str) -> Vec<String> {
+        line.split_whitespace().map(String::from).collect()
+    }
+
+    #[test]
+    fn parse_test() {
+        let options = parse(&arguments("a.txt b.json -n 64")).unwrap().unwrap();
+        assert_eq!(
+            ("a.txt", "b.json"),
+            (&options.first[..], &options.second[..])
+        );
+        assert_eq!(64, options.points);
+        assert_eq!(
+            DEFAULT_POINTS,
+            parse(&arguments("- b.txt")).unwrap().unwrap().points
+        );
+        assert!(parse(&arguments("a.txt")).is_err());
+        assert!(parse(&arguments("a.txt b.txt c.txt")).is_err());
+        assert!(parse(&arguments("a.txt b.txt -n 0")).is_err());
+        assert!(parse(&arguments("- -")).is_err());
+    }
+
+    #[test]
+    fn report_test() {
+        let report = report(&Comparison {
+            descriptor_distance: 0.25,
+            rms_deviation: 0.5,
+            similarity: 0.8232,
+        });
+        assert_eq!(
+            "descriptor distance  0.2500\nrms deviation        0.5000\nsimilarity           82.32%\n",
+            report
+        );
+    }
+}
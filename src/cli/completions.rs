@@ -0,0 +1,570 @@
+//! Completion scripts for bash, zsh and fish, generated from a description of the flags of each
+//! command. Descriptions are taken from the help of the commands so that both stay in sync.
+
+use crate::cli::arguments::{self, Argument, Arguments};
+use crate::cli::error;
+#[cfg(feature = "serve")]
+use crate::cli::serve;
+use crate::cli::{
+    analyze, animate, batch, bench, compare, convert, generate, info, render, repl, spectrum,
+    trace, validate,
+};
+use std::error::Error;
+
+pub const USAGE: &str = "\
+Prints a completion script for a shell.
+
+Usage: fft completions <SHELL>
+
+Arguments:
+  <SHELL>       bash, zsh or fish
+
+Options:
+  -h, --help    Prints this help
+
+For example, add `source <(fft completions bash)` to ~/.bashrc, write the output of
+`fft completions zsh` to a `_fft` file in a directory of $fpath, or the output of
+`fft completions fish` to ~/.config/fish/completions/fft.fish.
+";
+
+const SHELLS: &[&str] = &["bash", "zsh", "fish"];
+
+/// Values a flag takes.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Values {
+    None,
+    File,
+    Any,
+    Choices(&'static [&'static str]),
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Flag {
+    short: Option<char>,
+    long: &'static str,
+    values: Values,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Command {
+    name: &'static str,
+    usage: &'static str,
+    flags: &'static [Flag],
+    /// Values of the positional arguments, files by default.
+    arguments: Values,
+}
+
+const fn flag(short: Option<char>, long: &'static str, values: Values) -> Flag {
+    Flag {
+        short,
+        long,
+        values,
+    }
+}
+
+const HELP: Flag = flag(Some('h'), "help", Values::None);
+const CONFIG: Flag = flag(None, "config", Values::File);
+const OUTPUT: Flag = flag(Some('o'), "output", Values::File);
+const PRECISION: Flag = flag(Some('p'), "precision", Values::Any);
+const SIZE: Flag = flag(Some('s'), "size", Values::Any);
+const TERMS: Flag = flag(Some('n'), "terms", Values::Any);
+const JOBS: Flag = flag(Some('j'), "jobs", Values::Any);
+const DASHBOARD: Flag = flag(None, "dashboard", Values::None);
+const IMAGE_FORMAT: Flag = flag(Some('f'), "format", Values::Choices(&["png", "svg", "ppm"]));
+const PATH_FORMAT: Flag = flag(
+    Some('f'),
+    "format",
+    Values::Choices(&["txt", "csv", "json", "svg"]),
+);
+
+/// Flags accepted by every command.
+const GLOBAL: &[Flag] = &[
+    flag(Some('v'), "verbose", Values::None),
+    flag(None, "json-errors", Values::None),
+    flag(None, "profile", Values::None),
+    flag(None, "lenient", Values::None),
+];
+
+const COMMANDS: &[Command] = &[
+    Command {
+        name: "info",
+        usage: info::USAGE,
+        flags: &[CONFIG, HELP],
+        arguments: Values::File,
+    },
+    Command {
+        name: "analyze",
+        usage: analyze::USAGE,
+        flags: &[TERMS, CONFIG, HELP],
+        arguments: Values::File,
+    },
+    Command {
+        name: "spectrum",
+        usage: spectrum::USAGE,
+        flags: &[
+            OUTPUT,
+            flag(Some('f'), "format", Values::Choices(&["csv", "json", "p5"])),
+            TERMS,
+            flag(None, "sort", Values::Choices(&["amplitude", "frequency"])),
+            CONFIG,
+            HELP,
+        ],
+        arguments: Values::File,
+    },
+    Command {
+        name: "render",
+        usage: render::USAGE,
+        flags: &[
+            OUTPUT,
+            PRECISION,
+            flag(None, "sweep", Values::Any),
+            flag(None, "sheet", Values::None),
+            JOBS,
+            SIZE,
+            IMAGE_FORMAT,
+            flag(None, "matched", Values::None),
+            CONFIG,
+            HELP,
+        ],
+        arguments: Values::File,
+    },
+    Command {
+        name: "animate",
+        usage: animate::USAGE,
+        flags: &[
+            OUTPUT,
+            PRECISION,
+            SIZE,
+            flag(None, "fps", Values::Any),
+            flag(None, "duration", Values::Any),
+            flag(None, "trail", Values::Choices(&["full", "fade"])),
+            flag(None, "frames", Values::File),
+            flag(None, "resume", Values::None),
+            DASHBOARD,
+            CONFIG,
+            HELP,
+        ],
+        arguments: Values::File,
+    },
+    Command {
+        name: "batch",
+        usage: batch::USAGE,
+        flags: &[
+            flag(Some('o'), "output", Values::Any),
+            PRECISION,
+            SIZE,
+            IMAGE_FORMAT,
+            JOBS,
+            DASHBOARD,
+            CONFIG,
+            HELP,
+        ],
+        arguments: Values::File,
+    },
+    Command {
+        name: "repl",
+        usage: repl::USAGE,
+        flags: &[CONFIG, HELP],
+        arguments: Values::File,
+    },
+    #[cfg(feature = "serve")]
+    Command {
+        name: "serve",
+        usage: serve::USAGE,
+        flags: &[flag(Some('a'), "address", Values::Any), CONFIG, HELP],
+        arguments: Values::None,
+    },
+    Command {
+        name: "convert",
+        usage: convert::USAGE,
+        flags: &[PATH_FORMAT, flag(Some('n'), "points", Values::Any), HELP],
+        arguments: Values::File,
+    },
+    Command {
+        name: "generate",
+        usage: generate::USAGE,
+        flags: &[
+            OUTPUT,
+            flag(Some('n'), "points", Values::Any),
+            PATH_FORMAT,
+            HELP,
+        ],
+        arguments: Values::Choices(&["circle", "square", "star", "heart", "flower"]),
+    },
+    Command {
+        name: "validate",
+        usage: validate::USAGE,
+        flags: &[HELP],
+        arguments: Values::File,
+    },
+    Command {
+        name: "compare",
+        usage: compare::USAGE,
+        flags: &[flag(Some('n'), "points", Values::Any), HELP],
+        arguments: Values::File,
+    },
+    Command {
+        name: "trace",
+        usage: trace::USAGE,
+        flags: &[
+            flag(Some('r'), "fps", Values::Any),
+            flag(Some('n'), "points", Values::Any),
+            HELP,
+        ],
+        arguments: Values::File,
+    },
+    Command {
+        name: "bench",
+        usage: bench::USAGE,
+        flags: &[
+            flag(Some('n'), "sizes", Values::Any),
+            flag(None, "samples", Values::Any),
+            HELP,
+        ],
+        arguments: Values::None,
+    },
+    Command {
+        name: "completions",
+        usage: USAGE,
+        flags: &[HELP],
+        arguments: Values::Choices(SHELLS),
+    },
+];
+
+pub fn run(arguments: &[String]) -> Result<(), Box<dyn Error>> {
+    let mut shell = None;
+    let mut arguments = Arguments::new(arguments);
+    while let Some(argument) = arguments.next() {
+        match &argument {
+            Argument::Positional(value) if shell.is_none() => shell = Some(value.clone()),
+            Argument::Flag(flag) if flag == "-h" || flag == "--help" => {
+                print!("{}", USAGE);
+                return Ok(());
+            }
+            _ => return Err(error::usage(arguments::unexpected(&argument))),
+        }
+    }
+    let script = match shell.as_deref() {
+        Some("bash") => bash(),
+        Some("zsh") => zsh(),
+        Some("fish") => fish(),
+        Some(other) => {
+            let message = format!("Unknown shell {}, expected bash, zsh or fish", other);
+            return Err(error::usage(message.into()));
+        }
+        None => return Err(error::usage("Missing shell".into())),
+    };
+    print!("{}", script);
+    Ok(())
+}
+
+/// Text following `--long` on its line of `usage`, without the default value, for the help of
+/// the flag.
+fn description(usage: &str, long: &str) -> String {
+    let option = format!("--{}", long);
+    let lines: Vec<&str> = usage.lines().collect();
+    let start = match lines.iter().position(|line| {
+        line.trim_start().starts_with('-')
+            && line
+                .split_whitespace()
+                .any(|word| word.trim_end_matches(',') == option)
+    }) {
+        Some(start) => start,
+        None => return String::new(),
+    };
+    let line = lines[start];
+    let after = line[line.find(&option).unwrap() + option.len()..].trim_start();
+    let mut text = match after.strip_prefix('<') {
+        Some(rest) => rest.split_once('>').map_or("", |(_, rest)| rest),
+        None => after,
+    }
+    .to_string();
+    // The description may continue on the following, further indented lines.
+    for next in &lines[start + 1..] {
+        let trimmed = next.trim_start();
+        if trimmed.is_empty() || trimmed.starts_with('-') || next.len() - trimmed.len() < 8 {
+            break;
+        }
+        text.push(' ');
+        text.push_str(trimmed);
+    }
+    clean(&text)
+}
+
+/// Description of `command` in the list of commands of the main help.
+fn command_description(command: &str) -> String {
+    super::USAGE
+        .lines()
+        .find(|line| line.split_whitespace().next() == Some(command) && line.starts_with("  "))
+        .map(|line| clean(&line.trim_start()[command.len()..]))
+        .unwrap_or_default()
+}
+
+/// `text` trimmed, without its default value and the characters quoting would trip on.
+fn clean(text: &str) -> String {
+    let text = text.split(" [default").next().unwrap_or_default();
+    text.chars()
+        .filter(|c| !"[]'\"`:$\\".contains(*c))
+        .collect::<String>()
+        .trim()
+        .to_string()
+}
+
+fn all_flags(command: &Command) -> impl Iterator<Item = &Flag> {
+    command.flags.iter().chain(GLOBAL.iter())
+}
+
+fn flag_description(command: &Command, flag: &Flag) -> String {
+    if GLOBAL.iter().any(|global| global.long == flag.long) {
+        return description(super::USAGE, flag.long);
+    }
+    description(command.usage, flag.long)
+}
+
+pub fn bash() -> String {
+    let mut script = String::from(
+        "_fft() {\n    local cur prev\n    cur=\"${COMP_WORDS[COMP_CWORD]}\"\n    \
+         prev=\"${COMP_WORDS[COMP_CWORD-1]}\"\n    if [ \"$COMP_CWORD\" -eq 1 ]; then\n",
+    );
+    let names: Vec<&str> = COMMANDS.iter().map(|command| command.name).collect();
+    script.push_str(&format!(
+        "        COMPREPLY=($(compgen -W \"{} --help --version\" -- \"$cur\"))\n        return\n    fi\n    case \"${{COMP_WORDS[1]}}\" in\n",
+        names.join(" ")
+    ));
+    for command in COMMANDS {
+        script.push_str(&format!(
+            "        {})\n            case \"$prev\" in\n",
+            command.name
+        ));
+        for flag in all_flags(command) {
+            let patterns = match flag.short {
+                Some(short) => format!("-{}|--{}", short, flag.long),
+                None => format!("--{}", flag.long),
+            };
+            let action = match flag.values {
+                Values::None => continue,
+                Values::File => "COMPREPLY=($(compgen -f -- \"$cur\"))".to_string(),
+                Values::Any => "COMPREPLY=()".to_string(),
+                Values::Choices(choices) => {
+                    format!(
+                        "COMPREPLY=($(compgen -W \"{}\" -- \"$cur\"))",
+                        choices.join(" ")
+                    )
+                }
+            };
+            script.push_str(&format!(
+                "                {}) {}; return ;;\n",
+                patterns, action
+            ));
+        }
+        let flags: Vec<String> = all_flags(command)
+            .flat_map(|flag| {
+                flag.short
+                    .map(|short| format!("-{}", short))
+                    .into_iter()
+                    .chain(Some(format!("--{}", flag.long)))
+            })
+            .collect();
+        let positional = match command.arguments {
+            Values::None | Values::Any => "COMPREPLY=()".to_string(),
+            Values::File => "COMPREPLY=($(compgen -f -- \"$cur\"))".to_string(),
+            Values::Choices(choices) => {
+                format!(
+                    "COMPREPLY=($(compgen -W \"{}\" -- \"$cur\"))",
+                    choices.join(" ")
+                )
+            }
+        };
+        script.push_str(&format!(
+            "            esac\n            if [[ \"$cur\" == -* ]]; then\n                \
+             COMPREPLY=($(compgen -W \"{}\" -- \"$cur\"))\n            else\n                \
+             {}\n            fi\n            ;;\n",
+            flags.join(" "),
+            positional
+        ));
+    }
+    script.push_str("    esac\n}\n\ncomplete -F _fft fft\n");
+    script
+}
+
+pub fn zsh() -> String {
+    let mut script =
+        String::from("#compdef fft\n\n_fft() {\n    local -a commands\n    commands=(\n");
+    for command in COMMANDS {
+        script.push_str(&format!(
+            "        '{}:{}'\n",
+            command.name,
+            command_description(command.name)
+        ));
+    }
+    script.push_str(
+        "    )\n    if (( CURRENT == 2 )); then\n        _describe 'command' commands\n        \
+         return\n    fi\n    case $words[2] in\n",
+    );
+    for command in COMMANDS {
+        script.push_str(&format!(
+            "        {})\n            _arguments",
+            command.name
+        ));
+        for flag in all_flags(command) {
+            let text = flag_description(command, flag);
+            let action = match flag.values {
+                Values::None => String::new(),
+                Values::File => format!(":{}:_files", flag.long),
+                Values::Any => format!(":{}: ", flag.long),
+                Values::Choices(choices) => format!(":{}:({})", flag.long, choices.join(" ")),
+            };
+            let names = flag
+                .short
+                .map(|short| format!("-{}", short))
+                .into_iter()
+                .chain(Some(format!("--{}", flag.long)));
+            for name in names {
+                script.push_str(&format!(
+                    " \\\n                '{}[{}]{}'",
+                    name, text, action
+                ));
+            }
+        }
+        let positional = match command.arguments {
+            Values::None => String::new(),
+            Values::File => " \\\n                '*:file:_files'".to_string(),
+            Values::Any => " \\\n                '*:value: '".to_string(),
+            Values::Choices(choices) => {
+                format!(" \\\n                ':value:({})'", choices.join(" "))
+            }
+        };
+        script.push_str(&positional);
+        script.push_str("\n            ;;\n");
+    }
+    script.push_str("    esac\n}\n\n_fft \"$@\"\n");
+    script
+}
+
+pub fn fish() -> String {
+    let mut script = String::new();
+    let names: Vec<&str> = COMMANDS.iter().map(|command| command.name).collect();
+    let top = format!("not __fish_seen_subcommand_from {}", names.join(" "));
+    for command in COMMANDS {
+        script.push_str(&format!(
+            "complete -c fft -f -n '{}' -a {} -d '{}'\n",
+            top,
+            command.name,
+            command_description(command.name)
+        ));
+    }
+    for flag in GLOBAL {
+        script.push_str(&fish_flag(
+            None,
+            flag,
+            &description(super::USAGE, flag.long),
+        ));
+    }
+    for command in COMMANDS {
+        let condition = format!("__fish_seen_subcommand_from {}", command.name);
+        for flag in command.flags {
+            let text = description(command.usage, flag.long);
+            script.push_str(&fish_flag(Some(&condition), flag, &text));
+        }
+        match command.arguments {
+            Values::None | Values::Any => {
+                script.push_str(&format!("complete -c fft -n '{}' -f\n", condition))
+            }
+            Values::Choices(choices) => script.push_str(&format!(
+                "complete -c fft -n '{}' -f -a '{}'\n",
+                condition,
+                choices.join(" ")
+            )),
+            Values::File => {}
+        }
+    }
+    script
+}
+
+fn fish_flag(condition: Option<&str>, flag: &Flag, text: &str) -> String {
+    let mut line = String::from("complete -c fft");
+    if let Some(condition) = condition {
+        line.push_str(&format!(" -n '{}'", condition));
+    }
+    if let Some(short) = flag.short {
+        line.push_str(&format!(" -s {}", short));
+    }
+    line.push_str(&format!(" -l {}", flag.long));
+    match flag.values {
+        Values::None => {}
+        Values::File => line.push_str(" -r -F"),
+        Values::Any => line.push_str(" -x"),
+        Values::Choices(choices) => line.push_str(&format!(" -x -a '{}'", choices.join(" "))),
+    }
+    line.push_str(&format!(" -d '{}'\n", text));
+    line
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    /// Long flags listed in the options of `usage`.
+    fn documented_flags(usage: &str) -> Vec<String> {
+        usage
+            .lines()
+            .skip_while(|line| !line.starts_with("Options:"))
+            .filter(|line| line.trim_start().starts_with('-'))
+            .flat_map(|line| line.split_whitespace().take(3))
+            .filter(|word| word.starts_with("--"))
+            .map(|word| {
+                word.trim_end_matches(',')
+                    .trim_start_matches("--")
+                    .to_string()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn commands_match_help_test() {
+        for command in COMMANDS {
+            let mut expected = documented_flags(command.usage);
+            expected.sort();
+            let mut described: Vec<String> = command
+                .flags
+                .iter()
+                .map(|flag| flag.long.to_string())
+                .collect();
+            described.sort();
+            assert_eq!(expected, described, "flags of {}", command.name);
+            assert!(!command_description(command.name).is_empty());
+            assert!(super::super::USAGE.contains(&format!("  {} ", command.name)));
+        }
+        let mut global = documented_flags(super::super::USAGE);
+        global.retain(|flag| flag != "help" && flag != "version");
+        let described: Vec<&str> = GLOBAL.iter().map(|flag| flag.long).collect();
+        assert_eq!(described, global);
+    }
+
+    #[test]
+    fn description_test() {
+        assert_eq!(
+            "Number of terms used, all of them by default",
+            description(render::USAGE, "precision")
+        );
+        assert_eq!("Configuration file", description(render::USAGE, "config"));
+        assert_eq!("Prints this help", description(render::USAGE, "help"));
+    }
+
+    #[test]
+    fn scripts_test() {
+        let bash = bash();
+        assert!(bash.contains("-f|--format) COMPREPLY=($(compgen -W \"png svg ppm\" -- \"$cur\"))"));
+        assert!(bash.ends_with("complete -F _fft fft\n"));
+        let zsh = zsh();
+        assert!(zsh.starts_with("#compdef fft\n"));
+        assert!(zsh.contains(
+            "'--sort[amplitude, from the largest, or frequency]:sort:(amplitude frequency)'"
+        ));
+        let fish = fish();
+        assert!(fish.contains(
+            "complete -c fft -n '__fish_seen_subcommand_from completions' -f -a 'bash zsh fish'\n"
+        ));
+    }
+}
@@ -0,0 +1,360 @@
+use crate::cli::arguments;
+use crate::cli::toml::{self, Value};
+use fft::render::gif::GifConfig;
+use fft::render::{Color, RenderConfig};
+use std::collections::BTreeMap;
+use std::convert::TryFrom;
+use std::env;
+use std::error::Error;
+use std::fs;
+use std::path::Path;
+
+/// Configuration file read from the working directory when `--config` is not given.
+pub const DEFAULT_FILE: &str = "fftdraw.toml";
+
+/// Start of the names of the environment variables overriding the configuration file.
+pub const ENVIRONMENT_PREFIX: &str = "FFTDRAW_";
+
+/// Tables of the configuration file, which environment variable names start with.
+const TABLES: [&str; 2] = ["render", "animation"];
+
+/// Settings read from a configuration file, such as:
+///
+/// ```toml
+/// input = "heart.txt"
+/// precision = 32
+/// output = ["heart.png", "heart.svg", "heart.gif"]
+///
+/// [render]
+/// width = 800
+/// height = 600
+/// theme = "dark"
+/// circles = true
+///
+/// [animation]
+/// fps = 30
+/// duration = 6.0
+/// trail = "fade"
+/// ```
+///
+/// Environment variables such as `FFTDRAW_PRECISION` or `FFTDRAW_RENDER_WIDTH` override these
+/// values, and command-line flags override both.
+#[derive(Debug, Clone, Default)]
+pub struct Config {
+    pub input: Option<String>,
+    pub precision: Option<usize>,
+    /// Files to write. Each command writes the ones in the formats it produces.
+    pub outputs: Vec<String>,
+    pub render: RenderConfig,
+    pub animation: GifConfig,
+}
+
+impl Config {
+    pub fn parse(text: &str) -> Result<Config, Box<dyn Error>> {
+        let mut config = Config::default();
+        config.apply(&toml::parse(text)?)?;
+        Ok(config)
+    }
+
+    /// Replaces the settings given by `values`, by their full dotted key such as `render.width`.
+    pub fn apply(&mut self, values: &BTreeMap<String, Value>) -> Result<(), Box<dyn Error>> {
+        let config = self;
+        // The theme replaces the colors, so that single colors can then be changed.
+        if let Some(theme) = values.get("render.theme") {
+            let themed = match string("render.theme", theme)?.as_str() {
+                "light" => RenderConfig::default(),
+                "dark" => RenderConfig::dark(),
+                other => {
+                    return Err(format!("Unknown theme {}, expected light or dark", other).into())
+                }
+            };
+            config.render.background = themed.background;
+            config.render.palette = themed.palette;
+        }
+        let render = &mut config.render;
+        for (key, value) in values {
+            match key.as_str() {
+                "input" => config.input = Some(string(key, value)?),
+                "precision" => config.precision = Some(integer(key, value)?),
+                "output" => {
+                    config.outputs = match value {
+                        Value::Array(items) => items
+                            .iter()
+                            .map(|item| string(key, item))
+                            .collect::<Result<_, _>>()?,
+                        _ => vec![string(key, value)?],
+                    }
+                }
+                "render.theme" => {}
+                "render.width" => render.width = integer(key, value)?,
+                "render.height" => render.height = integer(key, value)?,
+                "render.margin" => render.margin = float(key, value)?,
+                "render.background" => render.background = color(key, value)?,
+                "render.transparent" => render.transparent = boolean(key, value)?,
+                "render.line_width" => render.line_width = float(key, value)?,
+                "render.caption" => render.caption = Some(string(key, value)?),
+                "render.grid" => render.grid = boolean(key, value)?,
+                "render.circles" => render.circles = boolean(key, value)?,
+                "render.max_circles" => render.max_circles = Some(integer(key, value)?),
+                "render.lump_remainder" => render.lump_remainder = boolean(key, value)?,
+                "render.annotate" => render.annotate = boolean(key, value)?,
+                "render.dpi" => render.dpi = Some(integer(key, value)?),
+                "render.supersampling" => render.supersampling = integer(key, value)?,
+                "render.max_chord_error" => render.max_chord_error = float(key, value)?,
+                "render.memory_budget" => render.memory_budget = Some(bytes(key, value)?),
+                "animation.fps" => config.animation.fps = integer(key, value)?,
+                "animation.duration" => config.animation.duration = float(key, value)?,
+                "animation.trail" => {
+                    render.trail = arguments::parse_trail(&string(key, value)?)
+                        .map_err(|error| format!("{}: {}", key, error))?
+                }
+                _ => return Err(format!("Unknown setting {}", key).into()),
+            }
+        }
+        Ok(())
+    }
+
+    pub fn load(filename: &str) -> Result<Config, Box<dyn Error>> {
+        let text = fs::read_to_string(filename)
+            .map_err(|error| format!("Cannot read {}: {}", filename, error))?;
+        Config::parse(&text).map_err(|error| format!("{}: {}", filename, error).into())
+    }
+
+    /// Configuration read from the file given by `--config` in `arguments`, or else from
+    /// `DEFAULT_FILE` when there is one, then overridden by the `FFTDRAW_` environment variables,
+    /// along with the other arguments.
+    pub fn from_arguments(arguments: &[String]) -> Result<(Config, Vec<String>), Box<dyn Error>> {
+        let mut filename = None;
+        let mut rest = Vec::with_capacity(arguments.len());
+        let mut remaining = arguments.iter();
+        while let Some(argument) = remaining.next() {
+            if argument == "--config" {
+                filename = Some(
+                    remaining
+                        .next()
+                        .ok_or("Missing value for --config")?
+                        .clone(),
+                );
+            } else if let Some(value) = argument.strip_prefix("--config=") {
+                filename = Some(value.to_string());
+            } else {
+                rest.push(argument.clone());
+            }
+        }
+        let mut config = match filename {
+            Some(filename) => Config::load(&filename)?,
+            None if Path::new(DEFAULT_FILE).is_file() => Config::load(DEFAULT_FILE)?,
+            None => Config::default(),
+        };
+        config
+            .apply(&environment_values(env::vars())?)
+            .map_err(|error| format!("{} environment variables: {}", ENVIRONMENT_PREFIX, error))?;
+        Ok((config, rest))
+    }
+}
+
+/// Settings given by the environment `variables` starting with `ENVIRONMENT_PREFIX`, by their
+/// dotted key: `FFTDRAW_RENDER_LINE_WIDTH` sets `render.line_width`. Values are read as in
+/// configuration files, except that any other text is taken as a string, so that
+/// `FFTDRAW_RENDER_THEME=dark` needs no quotes.
+pub fn environment_values<I>(variables: I) -> Result<BTreeMap<String, Value>, Box<dyn Error>>
+where
+    I: IntoIterator<Item = (String, String)>,
+{
+    let mut values = BTreeMap::new();
+    for (name, value) in variables {
+        let setting = match name.strip_prefix(ENVIRONMENT_PREFIX) {
+            Some(setting) => setting.to_ascii_lowercase(),
+            None => continue,
+        };
+        let key = TABLES
+            .iter()
+            .find_map(|table| {
+                setting
+                    .strip_prefix(table)
+                    .and_then(|rest| rest.strip_prefix('_'))
+                    .map(|rest| format!("{}.{}", table, rest))
+            })
+            .unwrap_or(setting);
+        values.insert(key, toml::parse_loose(&value));
+    }
+    Ok(values)
+}
+
+fn mismatch(key: &str, expected: &str, value: &Value) -> Box<dyn Error> {
+    format!("{} must be {}, found {}", key, expected, value.kind()).into()
+}
+
+fn string(key: &str, value: &Value) -> Result<String, Box<dyn Error>> {
+    match value {
+        Value::String(text) => Ok(text.clone()),
+        _ => Err(mismatch(key, "a string", value)),
+    }
+}
+
+fn boolean(key: &str, value: &Value) -> Result<bool, Box<dyn Error>> {
+    match value {
+        Value::Boolean(flag) => Ok(*flag),
+        _ => Err(mismatch(key, "a boolean", value)),
+    }
+}
+
+fn integer<T: TryFrom<i64>>(key: &str, value: &Value) -> Result<T, Box<dyn Error>> {
+    match value {
+        Value::Integer(number) => T::try_from(*number)
+            .map_err(|_| format!("{} is out of range for {}", number, key).into()),
+        _ => Err(mismatch(key, "an integer", value)),
+    }
+}
+
+fn float(key: &str, value: &Value) -> Result<f64, Box<dyn Error>> {
+    match value {
+        Value::Float(number) => Ok(*number),
+        Value::Integer(number) => Ok(*number as f64),
+        _ => Err(mismatch(key, "a number", value)),
+    }
+}
+
+/// Number of bytes given as an integer, or as text with a `K`, `M` or `G` suffix for multiples of
+/// 1024 such as `"512M"`.
+fn bytes(key: &str, value: &Value) -> Result<usize, Box<dyn Error>> {
+    let text = match value {
+        Value::Integer(_) => return integer(key, value),
+        Value::String(text) => text.trim(),
+        _ => return Err(mismatch(key, "a number of bytes", value)),
+    };
+    let (digits, unit) = match text.char_indices().last() {
+        Some((index, suffix)) if suffix.is_ascii_alphabetic() => (&text[..index], suffix),
+        _ => (text, 'B'),
+    };
+    let shift = match unit.to_ascii_uppercase() {
+        'B' => 0,
+        'K' => 10,
+        'M' => 20,
+        'G' => 30,
+        _ => return Err(format!("{} must be a size such as 512M, found {}", key, text).into()),
+    };
+    digits
+        .trim()
+        .parse::<usize>()
+        .ok()
+        .and_then(|count| count.checked_mul(1 << shift))
+        .ok_or_else(|| format!("{} must be a size such as 512M, found {}", key, text).into())
+}
+
+fn color(key: &str, value: &Value) -> Result<Color, Box<dyn Error>> {
+    let text = string(key, value)?;
+    Color::from_hex(&text).ok_or_else(|| format!("{} must be a #rrggbb color", key).into())
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use fft::render::Trail;
+
+    #[test]
+    fn parse_test() {
+        let config = Config::parse(
+            "input = \"heart.txt\"\n\
+             precision = 32\n\
+             output = [\"a.png\", \"a.gif\"]\n\
+             [render]\n\
+             width = 800\n\
+             background = \"#102030\"\n\
+             theme = \"dark\"\n\
+             line_width = 2\n\
+             [animation]\n\
+             fps = 30\n\
+             trail = \"fade:0.5\"\n",
+        )
+        .unwrap();
+        assert_eq!(Some("heart.txt".to_string()), config.input);
+        assert_eq!(Some(32), config.precision);
+        assert_eq!(vec!["a.png", "a.gif"], config.outputs);
+        assert_eq!(800, config.render.width);
+        assert_eq!(640, config.render.height);
+        assert_eq!(Color::new(16, 32, 48), config.render.background);
+        assert_eq!(RenderConfig::dark().palette, config.render.palette);
+        assert_eq!(2.0, config.render.line_width);
+        assert_eq!(30, config.animation.fps);
+        assert_eq!(Trail::Fade(0.5), config.render.trail);
+
+        let config = Config::parse("output = \"a.svg\"").unwrap();
+        assert_eq!(vec!["a.svg"], config.outputs);
+    }
+
+    #[test]
+    fn parse_errors_test() {
+        assert!(Config::parse("colour = 1").is_err());
+        assert!(Config::parse("precision = -1").is_err());
+        assert!(Config::parse("precision = \"all\"").is_err());
+        assert!(Config::parse("[render]\nbackground = \"red\"").is_err());
+        assert!(Config::parse("[render]\ntheme = \"sepia\"").is_err());
+        assert!(Config::parse("output = [1]").is_err());
+        assert!(Config::parse("[render]\nmemory_budget = \"12Q\"").is_err());
+        assert!(Config::parse("[render]\nmemory_budget = -1").is_err());
+    }
+
+    #[test]
+    fn bytes_test() {
+        let budget = |value: &str| {
+            Config::parse(&format!("[render]\nmemory_budget = {}", value))
+                .unwrap()
+                .render
+                .memory_budget
+        };
+        assert_eq!(Some(4096), budget("4096"));
+        assert_eq!(Some(4096), budget("\"4k\""));
+        assert_eq!(Some(512 << 20), budget("\"512M\""));
+        assert_eq!(Some(1 << 30), budget("\"1 G\""));
+        assert_eq!(None, Config::default().render.memory_budget);
+    }
+
+    #[test]
+    fn environment_test() {
+        let variables = vec![
+            ("FFTDRAW_PRECISION", "16"),
+            ("FFTDRAW_OUTPUT", "[\"a.png\", \"a.svg\"]"),
+            ("FFTDRAW_RENDER_THEME", "dark"),
+            ("FFTDRAW_RENDER_LINE_WIDTH", "2.5"),
+            ("FFTDRAW_ANIMATION_FPS", "10"),
+            ("HOME", "/root"),
+        ];
+        let values = environment_values(
+            variables
+                .into_iter()
+                .map(|(name, value)| (name.to_string(), value.to_string())),
+        )
+        .unwrap();
+        assert_eq!(5, values.len());
+        let mut config = Config::parse("precision = 8\n[render]\nwidth = 100\n").unwrap();
+        config.apply(&values).unwrap();
+        assert_eq!(Some(16), config.precision);
+        assert_eq!(vec!["a.png", "a.svg"], config.outputs);
+        // The theme of the environment keeps the size of the file.
+        assert_eq!(100, config.render.width);
+        assert_eq!(RenderConfig::dark().palette, config.render.palette);
+        assert_eq!(2.5, config.render.line_width);
+        assert_eq!(10, config.animation.fps);
+
+        let values = environment_values(vec![(
+            "FFTDRAW_RENDER_COLOUR".to_string(),
+            "red".to_string(),
+        )])
+        .unwrap();
+        assert!(Config::default().apply(&values).is_err());
+    }
+
+    #[test]
+    fn from_arguments_test() {
+        let arguments: Vec<String> = ["in.txt", "--config", "missing.toml"]
+            .iter()
+            .map(|a| a.to_string())
+            .collect();
+        let error = Config::from_arguments(&arguments).unwrap_err();
+        assert!(error.to_string().starts_with("Cannot read missing.toml"));
+        let (_, rest) = Config::from_arguments(&arguments[..1]).unwrap();
+        assert_eq!(vec!["in.txt"], rest);
+    }
+}
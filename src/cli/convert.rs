@@ -0,0 +1,173 @@
+use crate::cli::arguments::{self, Argument, Arguments};
+use crate::cli::error;
+use fft::path;
+use fft::reader;
+use std::error::Error;
+
+pub const USAGE: &str = "\
+Writes a path in another format, optionally resampled.
+
+Usage: fft convert <INPUT> <OUTPUT> [OPTIONS]
+
+Arguments:
+  <INPUT>                 Path to convert, as text with one `x, y` point per line, JSON or the
+                          outlines of an SVG document, `-` for text from the standard input
+  <OUTPUT>                File to write, `-` for the standard output
+
+Options:
+  -f, --format <FORMAT>   txt, json or svg, guessed from the output extension by default
+  -n, --points <COUNT>    Number of points, evenly spaced along the path, all of them by default
+  -h, --help              Prints this help
+";
+
+/// Path file formats.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Format {
+    /// One `x, y` point per line.
+    Text,
+    /// Array of `[x, y]` points.
+    Json,
+    /// Outline in an SVG document.
+    Svg,
+}
+
+impl Format {
+    pub fn parse(name: &str) -> Result<Format, Box<dyn Error>> {
+        match name.to_ascii_lowercase().as_str() {
+            "txt" | "csv" => Ok(Format::Text),
+            "json" => Ok(Format::Json),
+            "svg" => Ok(Format::Svg),
+            _ => Err(format!("Unknown path format {}, expected txt, json or svg", name).into()),
+        }
+    }
+
+    /// Format matching the extension of `filename`.
+    pub fn of(filename: &str) -> Result<Format, Box<dyn Error>> {
+        match std::path::Path::new(filename).extension() {
+            Some(extension) => Format::parse(&extension.to_string_lossy()),
+            None => Err(format!("Cannot guess the format of {}, use --format", filename).into()),
+        }
+    }
+
+    pub fn write(&self, path: &path::Path) -> String {
+        match self {
+            Format::Text => path.to_text(),
+            Format::Json => path.to_json(),
+            Format::Svg => path.to_svg(),
+        }
+    }
+
+    #[cfg(feature = "serve")]
+    pub fn read(&self, text: &str) -> Result<path::Path, Box<dyn Error>> {
+        self.read_with(text, path::Mode::Strict)
+            .map(|(path, _)| path)
+    }
+
+    /// Same as `read`, with the lines of text that do not hold a point handled as `mode` says,
+    /// along with the lines skipped.
+    pub fn read_with(
+        &self,
+        text: &str,
+        mode: path::Mode,
+    ) -> Result<(path::Path, Vec<path::Skipped>), Box<dyn Error>> {
+        match self {
+            Format::Text => path::Path::parse_with(text, mode),
+            Format::Json => reader::json(text).map(|path| (path, Vec::new())),
+            Format::Svg => reader::svg(text).map(|path| (path, Vec::new())),
+        }
+    }
+}
+
+/// Settings of the convert command.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Options {
+    pub input: String,
+    pub output: String,
+    pub format: Format,
+    pub points: Option<usize>,
+}
+
+/// Options given by `arguments`, `None` when help is asked for.
+pub fn parse(arguments: &[String]) -> Result<Option<Options>, Box<dyn Error>> {
+    let mut files = Vec::with_capacity(2);
+    let mut format = None;
+    let mut points = None;
+
+    let mut arguments = Arguments::new(arguments);
+    while let Some(argument) = arguments.next() {
+        match &argument {
+            Argument::Positional(value) if files.len() < 2 => files.push(value.clone()),
+            Argument::Flag(flag) => match flag.as_str() {
+                "-h" | "--help" => return Ok(None),
+                "-f" | "--format" => format = Some(Format::parse(&arguments.value()?)?),
+                "-n" | "--points" => points = Some(arguments.parsed()?),
+                _ => return Err(arguments::unexpected(&argument)),
+            },
+            _ => return Err(arguments::unexpected(&argument)),
+        }
+    }
+
+    if files.len() < 2 {
+        return Err("Expected an input and an output file".into());
+    }
+    let output = files.pop().unwrap();
+    let format = match format {
+        Some(format) => format,
+        None => Format::of(&output)?,
+    };
+    Ok(Some(Options {
+        input: files.pop().unwrap(),
+        output,
+        format,
+        points,
+    }))
+}
+
+pub fn run(arguments: &[String]) -> Result<(), Box<dyn Error>> {
+    let options = match parse(arguments).map_err(error::usage)? {
+        Some(options) => options,
+        None => {
+            print!("{}", USAGE);
+            return Ok(());
+        }
+    };
+    let mut path = super::read_path(&options.input)?;
+    if let Some(points) = options.points {
+        path = path.resample(points);
+    }
+    super::write_output(&options.output, options.format.write(&path).as_bytes())?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    fn arguments(line: &str) -> Vec<String> {
+        line.split_whitespace().map(String::from).collect()
+    }
+
+    #[test]
+    fn parse_test() {
+        let options = parse(&arguments("in.txt out.json")).unwrap().unwrap();
+        assert_eq!(
+            Options {
+                input: "in.txt".to_string(),
+                output: "out.json".to_string(),
+                format: Format::Json,
+                points: None,
+            },
+            options
+        );
+        let options = parse(&arguments("in.txt out -f csv")).unwrap().unwrap();
+        assert_eq!(Format::Text, options.format);
+        let options = parse(&arguments("in.svg out.txt --points 1024"))
+            .unwrap()
+            .unwrap();
+        assert_eq!(Some(1024), options.points);
+        assert!(parse(&arguments("in.txt")).is_err());
+        assert!(parse(&arguments("in.txt out.png")).is_err());
+        assert!(parse(&arguments("a b c")).is_err());
+    }
+}
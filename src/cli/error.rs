@@ -0,0 +1,157 @@
+//! Classes of failures of the commands, each with its own exit code so that callers can tell a
+//! bad input file from a failing render without parsing messages.
+
+use std::error::Error;
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Kind {
+    /// Anything not classified below.
+    Other,
+    /// Unknown command, flag or invalid flag value.
+    Usage,
+    /// Input path file missing, unreadable or malformed.
+    Input,
+    /// Configuration file unreadable or invalid.
+    Config,
+    /// Drawing or encoding the output failed.
+    Render,
+    /// Output file could not be written.
+    Output,
+}
+
+impl Kind {
+    pub fn exit_code(self) -> i32 {
+        match self {
+            Kind::Other => 1,
+            Kind::Usage => 2,
+            Kind::Input => 3,
+            Kind::Config => 4,
+            Kind::Render => 5,
+            Kind::Output => 6,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Kind::Other => "other",
+            Kind::Usage => "usage",
+            Kind::Input => "input",
+            Kind::Config => "config",
+            Kind::Render => "render",
+            Kind::Output => "output",
+        }
+    }
+}
+
+/// Error of a known class.
+#[derive(Debug)]
+pub struct CliError {
+    pub kind: Kind,
+    pub message: String,
+}
+
+impl fmt::Display for CliError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+impl Error for CliError {}
+
+/// `error` tagged with `kind`, unless it already has a class.
+pub fn tag(kind: Kind, error: Box<dyn Error>) -> Box<dyn Error> {
+    if error.downcast_ref::<CliError>().is_some() {
+        return error;
+    }
+    Box::new(CliError {
+        kind,
+        message: error.to_string(),
+    })
+}
+
+pub fn usage(error: Box<dyn Error>) -> Box<dyn Error> {
+    tag(Kind::Usage, error)
+}
+
+pub fn input(error: Box<dyn Error>) -> Box<dyn Error> {
+    tag(Kind::Input, error)
+}
+
+pub fn render(error: Box<dyn Error>) -> Box<dyn Error> {
+    tag(Kind::Render, error)
+}
+
+/// Copy of `error` keeping its class, which unlike `error` can be sent to another thread.
+pub fn detach(error: &(dyn Error + 'static)) -> CliError {
+    CliError {
+        kind: kind_of(error),
+        message: error.to_string(),
+    }
+}
+
+/// Class of `error`, `Other` when it has none.
+pub fn kind_of(error: &(dyn Error + 'static)) -> Kind {
+    error
+        .downcast_ref::<CliError>()
+        .map_or(Kind::Other, |error| error.kind)
+}
+
+/// `error` as a JSON object with its class, exit code and message, on one line.
+pub fn to_json(error: &(dyn Error + 'static)) -> String {
+    let kind = kind_of(error);
+    format!(
+        "{{\"error\": {{\"kind\": \"{}\", \"code\": {}, \"message\": {}}}}}",
+        kind.name(),
+        kind.exit_code(),
+        json_string(&error.to_string())
+    )
+}
+
+fn json_string(text: &str) -> String {
+    let mut quoted = String::with_capacity(text.len() + 2);
+    quoted.push('"');
+    for character in text.chars() {
+        match character {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\r' => quoted.push_str("\\r"),
+            '\t' => quoted.push_str("\\t"),
+            c if (c as u32) < 0x20 => quoted.push_str(&format!("\\u{:04x}", c as u32)),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn tag_test() {
+        let error = input("Cannot read in.txt".into());
+        assert_eq!(Kind::Input, kind_of(error.as_ref()));
+        // The first class given is kept.
+        let error = render(error);
+        assert_eq!(Kind::Input, kind_of(error.as_ref()));
+        let error: Box<dyn Error> = "unclassified".into();
+        assert_eq!(Kind::Other, kind_of(error.as_ref()));
+        let detached = detach(render("Too small".into()).as_ref());
+        assert_eq!(Kind::Render, detached.kind);
+        assert_eq!(Kind::Render, kind_of(&detached));
+    }
+
+    #[test]
+    fn to_json_test() {
+        let error = usage("Unknown flag \"--x\"\n\tsee --help".into());
+        assert_eq!(
+            "{\"error\": {\"kind\": \"usage\", \"code\": 2, \
+             \"message\": \"Unknown flag \\\"--x\\\"\\n\\tsee --help\"}}",
+            to_json(error.as_ref())
+        );
+    }
+}
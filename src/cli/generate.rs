@@ -0,0 +1,114 @@
+use crate::cli::arguments::{self, Argument, Arguments};
+use crate::cli::convert::Format;
+use crate::cli::error;
+use fft::shape::Shape;
+use std::error::Error;
+
+pub const USAGE: &str = "\
+Writes the path of a predefined shape, ready to be drawn.
+
+Usage: fft generate <SHAPE> [OPTIONS]
+
+Arguments:
+  <SHAPE>                 circle, square, star, heart or flower
+
+Options:
+  -o, --output <FILE>     File to write, `-` for the standard output [default: -]
+  -n, --points <COUNT>    Number of points, evenly spaced along the outline [default: 256]
+  -f, --format <FORMAT>   txt, json or svg, guessed from the output extension by default
+  -h, --help              Prints this help
+";
+
+const DEFAULT_POINTS: usize = 256;
+
+/// Settings of the generate command.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Options {
+    pub shape: Shape,
+    pub output: String,
+    pub points: usize,
+    pub format: Format,
+}
+
+/// Options given by `arguments`, `None` when help is asked for.
+pub fn parse(arguments: &[String]) -> Result<Option<Options>, Box<dyn Error>> {
+    let mut shape = None;
+    let mut output = super::STANDARD_STREAM.to_string();
+    let mut points = DEFAULT_POINTS;
+    let mut format = None;
+
+    let mut arguments = Arguments::new(arguments);
+    while let Some(argument) = arguments.next() {
+        match &argument {
+            Argument::Positional(value) if shape.is_none() => shape = Some(Shape::parse(value)?),
+            Argument::Flag(flag) => match flag.as_str() {
+                "-h" | "--help" => return Ok(None),
+                "-o" | "--output" => output = arguments.value()?,
+                "-n" | "--points" => points = arguments.parsed()?,
+                "-f" | "--format" => format = Some(Format::parse(&arguments.value()?)?),
+                _ => return Err(arguments::unexpected(&argument)),
+            },
+            _ => return Err(arguments::unexpected(&argument)),
+        }
+    }
+
+    if points == 0 {
+        return Err("Invalid --points 0, it must be positive".into());
+    }
+    let format = match format {
+        Some(format) => format,
+        None if output == super::STANDARD_STREAM => Format::Text,
+        None => Format::of(&output)?,
+    };
+    Ok(Some(Options {
+        shape: shape.ok_or("Missing shape")?,
+        output,
+        points,
+        format,
+    }))
+}
+
+pub fn run(arguments: &[String]) -> Result<(), Box<dyn Error>> {
+    let options = match parse(arguments).map_err(error::usage)? {
+        Some(options) => options,
+        None => {
+            print!("{}", USAGE);
+            return Ok(());
+        }
+    };
+    let path = options.shape.path(options.points);
+    super::write_output(&options.output, options.format.write(&path).as_bytes())
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    fn arguments(line: &str) -> Vec<String> {
+        line.split_whitespace().map(String::from).collect()
+    }
+
+    #[test]
+    fn parse_test() {
+        let options = parse(&arguments("star --points 512 -o star.json"))
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            Options {
+                shape: Shape::Star,
+                output: "star.json".to_string(),
+                points: 512,
+                format: Format::Json,
+            },
+            options
+        );
+        let options = parse(&arguments("heart")).unwrap().unwrap();
+        assert_eq!(("-", DEFAULT_POINTS), (&options.output[..], options.points));
+        assert_eq!(Format::Text, options.format);
+        assert!(parse(&arguments("")).is_err());
+        assert!(parse(&arguments("blob")).is_err());
+        assert!(parse(&arguments("star -n 0")).is_err());
+        assert!(parse(&arguments("star -o star.png")).is_err());
+    }
+}
@@ -0,0 +1,203 @@
+use crate::cli::arguments::{self, Argument, Arguments};
+use crate::cli::config::Config;
+use crate::cli::error;
+use fft::epicycle;
+use fft::path;
+use fft::render::Bounds;
+use std::error::Error;
+
+pub const USAGE: &str = "\
+Prints the size and extent of a path, how the energy of its shape spreads over the terms of its
+decomposition, and the numbers of terms drawing it closely.
+
+Usage: fft info [INPUT] [OPTIONS]
+
+Arguments:
+  [INPUT]               Path to describe, one `x, y` point per line, `-` for the standard
+                        input, from the configuration file by default
+
+Options:
+      --config <FILE>   Configuration file [default: fftdraw.toml when present]
+  -h, --help            Prints this help
+
+The energy of the shape leaves out the constant term, which only places the drawing.
+";
+
+/// Shares of the energy of the shape a suggested precision reaches.
+const SUGGESTED_ENERGY: [f64; 2] = [0.95, 0.99];
+
+/// Input given by `arguments` on top of `config`, `None` when help is asked for.
+pub fn parse(arguments: &[String], config: &Config) -> Result<Option<String>, Box<dyn Error>> {
+    let mut input = None;
+    let mut arguments = Arguments::new(arguments);
+    while let Some(argument) = arguments.next() {
+        match &argument {
+            Argument::Positional(value) if input.is_none() => input = Some(value.clone()),
+            Argument::Flag(flag) if flag == "-h" || flag == "--help" => return Ok(None),
+            _ => return Err(arguments::unexpected(&argument)),
+        }
+    }
+    Ok(Some(
+        input
+            .or_else(|| config.input.clone())
+            .ok_or("Missing input file")?,
+    ))
+}
+
+pub fn run(arguments: &[String], config: &Config) -> Result<(), Box<dyn Error>> {
+    let input = match parse(arguments, config).map_err(error::usage)? {
+        Some(input) => input,
+        None => {
+            print!("{}", USAGE);
+            return Ok(());
+        }
+    };
+    let path = super::read_path(&input)?;
+    let epicycle = super::decompose(&path)?;
+    print!("{}", report(&path, &epicycle));
+    Ok(())
+}
+
+/// Description of `path` and of its decomposition `epicycle`.
+pub fn report(path: &path::Path, epicycle: &epicycle::Epicycle) -> String {
+    let bounds = Bounds::of(&path.data);
+    let mut report = format!("points      {}\n", path.data.len());
+    report.push_str(&format!(
+        "bounds      x {:.4} to {:.4}, y {:.4} to {:.4} ({:.4} x {:.4})\n",
+        bounds.min_x,
+        bounds.max_x,
+        bounds.min_y,
+        bounds.max_y,
+        bounds.width(),
+        bounds.height()
+    ));
+    report.push_str(&format!("perimeter   {:.4}\n", path.perimeter()));
+    report.push_str(&format!("terms       {}\n", epicycle.len()));
+
+    report.push_str("\nenergy of the shape\n");
+    let shares = match energy_shares(epicycle) {
+        Some(shares) => shares,
+        None => {
+            report.push_str(" no shape energy\n");
+            return report;
+        }
+    };
+    report.push_str(" terms   energy\n");
+    let mut terms = 1;
+    while terms <= shares.len() {
+        report.push_str(&format!(
+            "{:>6}  {:>6.2}%\n",
+            terms,
+            shares[terms - 1] * 100.0
+        ));
+        if terms == shares.len() {
+            break;
+        }
+        terms = (terms * 2).min(shares.len());
+    }
+
+    report.push_str("\nsuggested precision\n");
+    for fraction in &SUGGESTED_ENERGY {
+        report.push_str(&format!(
+            "{:>6}  for {:.0}% of the energy\n",
+            epicycle.precision_for(*fraction),
+            fraction * 100.0
+        ));
+    }
+    report
+}
+
+/// Share of the energy of the shape held by the first terms of `epicycle`, for each number of
+/// terms from 1, `None` when it has no shape energy.
+fn energy_shares(epicycle: &epicycle::Epicycle) -> Option<Vec<f64>> {
+    let total = epicycle.shape_energy();
+    if total == 0.0 {
+        return None;
+    }
+    let mut cumulated = 0.0;
+    Some(
+        epicycle
+            .terms()
+            .iter()
+            .map(|term| {
+                cumulated += epicycle::term_energy(term);
+                cumulated / total
+            })
+            .collect(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use fft::complex;
+
+    #[test]
+    fn report_test() {
+        let path = path::Path {
+            data: vec![
+                complex::Complex::new(0.0, 0.0),
+                complex::Complex::new(2.0, 0.0),
+                complex::Complex::new(2.0, 1.0),
+                complex::Complex::new(0.0, 1.0),
+            ],
+        };
+        let epicycle = epicycle::Epicycle::new(&path);
+        let report = report(&path, &epicycle);
+        let lines: Vec<&str> = report.lines().collect();
+        assert_eq!("points      4", lines[0]);
+        assert_eq!(
+            "bounds      x 0.0000 to 2.0000, y 0.0000 to 1.0000 (2.0000 x 1.0000)",
+            lines[1]
+        );
+        assert_eq!("perimeter   6.0000", lines[2]);
+        assert_eq!("terms       4", lines[3]);
+        // Rows for 1, 2 and 4 terms.
+        assert_eq!("     4  100.00%", lines[9]);
+        assert_eq!("suggested precision", lines[11]);
+        assert!(lines[13].ends_with("for 99% of the energy"));
+    }
+
+    #[test]
+    fn energy_shares_test() {
+        let epicycle = epicycle::Epicycle::new(&path::Path {
+            data: vec![
+                complex::Complex::new(0.0, 0.0),
+                complex::Complex::new(1.0, 0.0),
+            ],
+        });
+        // Constant term and frequency 1 with the same amplitude.
+        assert_eq!(Some(vec![0.0, 1.0]), energy_shares(&epicycle));
+    }
+
+    #[test]
+    fn no_shape_energy_test() {
+        let point = path::Path {
+            data: vec![complex::Complex::new(1.0, 1.0)],
+        };
+        // Transforming identical points leaves rounding noise in the other terms.
+        let identical = path::Path {
+            data: vec![complex::Complex::new(0.1, 0.7); 12],
+        };
+        for path in &[point, identical] {
+            let epicycle = epicycle::Epicycle::new(path);
+            assert_eq!(None, energy_shares(&epicycle));
+            assert_eq!(0, epicycle.precision_for(0.99));
+            let report = report(path, &epicycle);
+            assert!(report.ends_with("energy of the shape\n no shape energy\n"));
+            assert!(!report.contains('%'));
+        }
+    }
+
+    #[test]
+    fn parse_test() {
+        let arguments = vec!["in.txt".to_string()];
+        assert_eq!(
+            Some("in.txt".to_string()),
+            parse(&arguments, &Config::default()).unwrap()
+        );
+        assert!(parse(&[], &Config::default()).is_err());
+        assert!(parse(&["-n".to_string()], &Config::default()).is_err());
+    }
+}
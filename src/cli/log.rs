@@ -0,0 +1,92 @@
+//! Messages about the progress of a command written to the standard error, depending on the
+//! verbosity chosen with `-v` (the time taken by each stage) or `-vv` (also when each stage
+//! starts, and details about the data).
+
+use std::fmt::Display;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Instant;
+
+/// Verbosity showing the time taken by each stage.
+pub const TIMING: usize = 1;
+
+/// Verbosity also showing when each stage starts and details about the data.
+pub const DEBUG: usize = 2;
+
+static VERBOSITY: AtomicUsize = AtomicUsize::new(0);
+
+pub fn set_verbosity(verbosity: usize) {
+    VERBOSITY.store(verbosity, Ordering::Relaxed);
+}
+
+pub fn enabled(verbosity: usize) -> bool {
+    VERBOSITY.load(Ordering::Relaxed) >= verbosity
+}
+
+/// Writes `message` when the verbosity is at least `DEBUG`.
+pub fn debug(message: impl Display) {
+    if enabled(DEBUG) {
+        eprintln!("[fft] {}", message);
+    }
+}
+
+/// Verbosity given by `-v`, `-vv`, `--verbose` (which may be repeated) in `arguments`, with the
+/// other arguments.
+pub fn from_arguments(arguments: &[String]) -> (usize, Vec<String>) {
+    let mut verbosity = 0;
+    let mut rest = Vec::with_capacity(arguments.len());
+    for argument in arguments {
+        match argument.as_str() {
+            "--verbose" => verbosity += 1,
+            flag if flag.len() > 1
+                && flag.starts_with('-')
+                && flag[1..].bytes().all(|b| b == b'v') =>
+            {
+                verbosity += flag.len() - 1
+            }
+            _ => rest.push(argument.clone()),
+        }
+    }
+    (verbosity, rest)
+}
+
+/// Stage of a command, reporting its duration when dropped.
+pub struct Span {
+    name: String,
+    start: Instant,
+}
+
+/// Starts the stage `name`, announced when the verbosity is at least `DEBUG`.
+pub fn span(name: impl Into<String>) -> Span {
+    let name = name.into();
+    debug(format!("{}...", name));
+    Span {
+        name,
+        start: Instant::now(),
+    }
+}
+
+impl Drop for Span {
+    fn drop(&mut self) {
+        if enabled(TIMING) {
+            let elapsed = self.start.elapsed().as_secs_f64() * 1000.0;
+            eprintln!("[fft] {}: {:.1} ms", self.name, elapsed);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn from_arguments_test() {
+        let arguments: Vec<String> = ["-vv", "in.txt", "--verbose", "-v", "-o", "-"]
+            .iter()
+            .map(|a| a.to_string())
+            .collect();
+        let (verbosity, rest) = from_arguments(&arguments);
+        assert_eq!(4, verbosity);
+        assert_eq!(vec!["in.txt", "-o", "-"], rest);
+    }
+}
@@ -0,0 +1,466 @@
+mod analyze;
+
+mod animate;
+
+mod arguments;
+
+mod batch;
+
+mod bench;
+
+mod clipboard;
+
+mod compare;
+
+mod completions;
+
+mod config;
+
+mod convert;
+
+mod error;
+
+mod generate;
+
+mod info;
+
+mod log;
+
+mod render;
+
+mod repl;
+
+#[cfg(feature = "serve")]
+mod serve;
+
+mod spectrum;
+
+mod template;
+
+mod toml;
+
+mod trace;
+
+mod validate;
+
+use fft::epicycle;
+use fft::path;
+use fft::render::dashboard::Dashboard;
+use fft::render::RenderConfig;
+use std::cell::RefCell;
+use std::error::Error;
+use std::fs;
+use std::io::{self, Read, Write};
+use std::panic;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::thread;
+
+pub const USAGE: &str = "\
+Draws closed paths from the largest terms of their Fourier decomposition.
+
+Usage: fft <COMMAND> [OPTIONS]
+
+Commands:
+  info         Prints the extent and energy of a path with suggested numbers of terms
+  analyze      Prints the terms of the decomposition of a path
+  spectrum     Writes the terms of the decomposition of a path as CSV or JSON
+  render       Draws the reconstruction of a path as an image
+  animate      Draws the reconstruction of a path being traced as an animated GIF
+  batch        Draws the reconstruction of every path matching a pattern
+  repl         Draws a path repeatedly as settings are changed by typed commands
+  serve        Draws paths sent over HTTP
+  convert      Writes a path in another format
+  generate     Writes the path of a predefined shape
+  validate     Checks that a path file can be drawn
+  compare      Prints how close the shapes of two paths are
+  trace        Writes the outline of the largest shape of each frame of a video as a path
+  bench        Times the transforms and the drawing on synthetic paths
+  completions  Prints a completion script for bash, zsh or fish
+
+Options:
+  -v, --verbose       Prints the time taken by each stage to the standard error, -vv also when
+                      each stage starts and details about the data
+      --json-errors   Prints errors to the standard error as JSON objects with their kind and code
+      --profile       Prints the time and allocations of each stage of the pipeline to the
+                      standard error, when built with the profile feature
+      --lenient       Skips the lines of path files that do not hold a point, with a warning
+                      for each, rather than failing, as long as most lines hold points
+  -h, --help          Prints this help, or the help of a command after its name
+  -V, --version       Prints the version
+
+Files named `-` are the standard input or output, and `@clipboard` the clipboard of the desktop.
+
+Exit codes:
+  0  success                  4  invalid configuration file
+  1  other failure            5  failure while drawing
+  2  invalid command line     6  output cannot be written
+  3  invalid input file
+";
+
+/// Runs the command given by `arguments`, printing its error if it fails, and returns the exit
+/// code of the process.
+pub fn main(arguments: &[String]) -> i32 {
+    let json_errors = arguments.iter().any(|argument| argument == "--json-errors");
+    let profile = arguments.iter().any(|argument| argument == "--profile");
+    let lenient = arguments.iter().any(|argument| argument == "--lenient");
+    LENIENT.store(lenient, Ordering::Relaxed);
+    let arguments: Vec<String> = arguments
+        .iter()
+        .filter(|argument| {
+            *argument != "--json-errors" && *argument != "--profile" && *argument != "--lenient"
+        })
+        .cloned()
+        .collect();
+    let result = if profile && !fft::profile::ENABLED {
+        Err(error::usage(
+            "--profile needs the tool built with the profile feature, \
+             cargo build --release --features profile"
+                .into(),
+        ))
+    } else {
+        run(&arguments)
+    };
+    if profile && fft::profile::ENABLED {
+        eprint!("{}", fft::profile::summary());
+    }
+    match result {
+        Ok(()) => 0,
+        Err(error) => {
+            if json_errors {
+                eprintln!("{}", error::to_json(error.as_ref()));
+            } else {
+                eprintln!("error: {}", error);
+            }
+            error::kind_of(error.as_ref()).exit_code()
+        }
+    }
+}
+
+pub fn run(arguments: &[String]) -> Result<(), Box<dyn Error>> {
+    let (verbosity, arguments) = log::from_arguments(arguments);
+    log::set_verbosity(verbosity);
+    let (command, rest) = match arguments.split_first() {
+        Some((command, rest)) => (command.as_str(), rest),
+        None => {
+            print!("{}", USAGE);
+            return Ok(());
+        }
+    };
+    match command {
+        "-h" | "--help" | "help" => print!("{}", USAGE),
+        "-V" | "--version" => println!("fft {}", env!("CARGO_PKG_VERSION")),
+        "info" => {
+            let (config, rest) = load_config(rest)?;
+            info::run(&rest, &config)?
+        }
+        "analyze" => {
+            let (config, rest) = load_config(rest)?;
+            analyze::run(&rest, &config)?
+        }
+        "spectrum" => {
+            let (config, rest) = load_config(rest)?;
+            spectrum::run(&rest, &config)?
+        }
+        "render" => {
+            let (config, rest) = load_config(rest)?;
+            render::run(&rest, &config)?
+        }
+        "animate" => {
+            let (config, rest) = load_config(rest)?;
+            animate::run(&rest, &config)?
+        }
+        "batch" => {
+            let (config, rest) = load_config(rest)?;
+            batch::run(&rest, &config)?
+        }
+        "repl" => {
+            let (config, rest) = load_config(rest)?;
+            repl::run(&rest, &config)?
+        }
+        #[cfg(feature = "serve")]
+        "serve" => {
+            let (config, rest) = load_config(rest)?;
+            serve::run(&rest, &config)?
+        }
+        #[cfg(not(feature = "serve"))]
+        "serve" => {
+            return Err(error::usage(
+                "serve needs the tool built with the serve feature, \
+                 cargo build --release --features serve"
+                    .into(),
+            ))
+        }
+        "convert" => convert::run(rest)?,
+        "generate" => generate::run(rest)?,
+        "validate" => validate::run(rest)?,
+        "compare" => compare::run(rest)?,
+        "trace" => trace::run(rest)?,
+        "bench" => bench::run(rest)?,
+        "completions" => completions::run(rest)?,
+        _ => {
+            let message = format!("Unknown command {}, see --help", command);
+            return Err(error::usage(message.into()));
+        }
+    }
+    Ok(())
+}
+
+/// Configuration given by `--config` in `arguments`, or the default one, with the other
+/// arguments.
+fn load_config(arguments: &[String]) -> Result<(config::Config, Vec<String>), Box<dyn Error>> {
+    config::Config::from_arguments(arguments)
+        .map_err(|error| error::tag(error::Kind::Config, error))
+}
+
+/// File name standing for the standard input or output.
+const STANDARD_STREAM: &str = "-";
+
+/// Whether path files are read leniently, as asked by `--lenient`.
+static LENIENT: AtomicBool = AtomicBool::new(false);
+
+/// Mode path files are read in.
+fn parse_mode() -> path::Mode {
+    if LENIENT.load(Ordering::Relaxed) {
+        path::Mode::Lenient
+    } else {
+        path::Mode::Strict
+    }
+}
+
+/// Reads the path in `filename`, in the standard input for `-` or in the clipboard for
+/// `@clipboard`, naming the file in errors. Files ending in `.json` or `.svg` are read in these
+/// formats, others as text, while the format of the clipboard is guessed from its content.
+fn read_path(filename: &str) -> Result<path::Path, Box<dyn Error>> {
+    let _span = log::span(format!("read {}", filename));
+    let format = convert::Format::of(filename).unwrap_or(convert::Format::Text);
+    let mode = parse_mode();
+    let read = if filename == clipboard::NAME {
+        clipboard::read()
+            .map_err(Box::from)
+            .and_then(|text| clipboard::format_of(&text).read_with(&text, mode))
+    } else if format == convert::Format::Text {
+        // Parsed as it is read rather than read whole first, as traced paths can be huge.
+        if filename == STANDARD_STREAM {
+            path::Path::read_with(io::stdin().lock(), mode)
+        } else {
+            fs::File::open(filename)
+                .map_err(Box::from)
+                .and_then(|file| path::Path::read_with(io::BufReader::new(file), mode))
+        }
+    } else {
+        let mut text = String::new();
+        if filename == STANDARD_STREAM {
+            io::stdin().read_to_string(&mut text).map(|_| ())
+        } else {
+            fs::read_to_string(filename).map(|content| text = content)
+        }
+        .map_err(Box::from)
+        .and_then(|_| format.read_with(&text, mode))
+    };
+    let (path, skipped) =
+        read.map_err(|error| error::input(format!("Cannot read {}: {}", filename, error).into()))?;
+    for line in &skipped {
+        eprintln!("warning: {}: {}", filename, line);
+    }
+    log::debug(format!("{} points", path.data.len()));
+    Ok(path)
+}
+
+thread_local! {
+    /// Buffer of the transforms of the paths decomposed by this thread, as by a batch job.
+    static SCRATCH: RefCell<fft::fft::Scratch> = RefCell::new(fft::fft::Scratch::new());
+}
+
+/// Decomposition of `path`, timed as a stage, failing when a point or a value of its transform
+/// is not finite.
+fn decompose(path: &path::Path) -> Result<epicycle::Epicycle, Box<dyn Error>> {
+    let _span = log::span("decompose");
+    let epicycle = SCRATCH
+        .with(|scratch| epicycle::Epicycle::try_with_scratch(path, &mut scratch.borrow_mut()))
+        .map_err(|non_finite| {
+            error::input(format!("Cannot decompose the path: {}", non_finite).into())
+        })?;
+    log::debug(format!("{} terms", epicycle.len()));
+    Ok(epicycle)
+}
+
+/// Writes `data` to `filename`, to the standard output for `-` or to the clipboard for
+/// `@clipboard`.
+fn write_output(filename: &str, data: &[u8]) -> Result<(), Box<dyn Error>> {
+    let _span = log::span(format!("write {}", filename));
+    log::debug(format!("{} bytes", data.len()));
+    let result = if filename == STANDARD_STREAM {
+        let mut output = io::stdout().lock();
+        output.write_all(data).and_then(|_| output.flush())
+    } else if filename == clipboard::NAME {
+        match std::str::from_utf8(data) {
+            Ok(text) => clipboard::write(text),
+            Err(_) => Err(clipboard::not_text()),
+        }
+    } else {
+        fs::write(filename, data)
+    };
+    result.map_err(|error| {
+        let message = format!("Cannot write {}: {}", filename, error);
+        error::tag(error::Kind::Output, message.into())
+    })
+}
+
+/// Writes what `write` produces to every file of `filenames` at once, `-` being the standard
+/// output, so that a large output is never held in memory as a whole.
+fn write_streamed<F>(filenames: &[String], write: F) -> Result<(), Box<dyn Error>>
+where
+    F: FnOnce(&mut dyn Write) -> io::Result<()>,
+{
+    let _span = log::span(format!("write {}", filenames.join(", ")));
+    let failure = |filename: &str, error: io::Error| {
+        let message = format!("Cannot write {}: {}", filename, error);
+        error::tag(error::Kind::Output, message.into())
+    };
+    let mut outputs = Outputs(Vec::with_capacity(filenames.len()));
+    for filename in filenames {
+        let output: Box<dyn Write> = if filename == STANDARD_STREAM {
+            Box::new(io::stdout().lock())
+        } else if filename == clipboard::NAME {
+            return Err(failure(filename, clipboard::not_text()));
+        } else {
+            let file = fs::File::create(filename).map_err(|error| failure(filename, error))?;
+            Box::new(io::BufWriter::new(file))
+        };
+        outputs.0.push((filename.clone(), output));
+    }
+    // Errors name the output failing.
+    write(&mut outputs).map_err(|error| {
+        let message = format!("Cannot write {}", error);
+        error::tag(error::Kind::Output, message.into())
+    })
+}
+
+/// Writer copying everything to several outputs.
+struct Outputs(Vec<(String, Box<dyn Write>)>);
+
+impl Outputs {
+    fn each<F>(&mut self, mut operation: F) -> io::Result<()>
+    where
+        F: FnMut(&mut dyn Write) -> io::Result<()>,
+    {
+        for (filename, output) in &mut self.0 {
+            operation(output.as_mut()).map_err(|error| {
+                io::Error::new(error.kind(), format!("{}: {}", filename, error))
+            })?;
+        }
+        Ok(())
+    }
+}
+
+impl Write for Outputs {
+    fn write(&mut self, data: &[u8]) -> io::Result<usize> {
+        self.each(|output| output.write_all(data))?;
+        Ok(data.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.each(|output| output.flush())
+    }
+}
+
+/// Name of the path file for captions, none when read from the standard input or the clipboard.
+fn source_name(filename: &str) -> Option<String> {
+    if filename == STANDARD_STREAM || filename == clipboard::NAME {
+        None
+    } else {
+        Some(filename.to_string())
+    }
+}
+
+/// Size of the previews of dashboards, in characters.
+const DASHBOARD_PREVIEW: (usize, usize) = (48, 16);
+
+/// Dashboard titled `title` previewing drawings made with `config`.
+fn dashboard(title: &str, config: &RenderConfig) -> Dashboard {
+    let (columns, rows) = DASHBOARD_PREVIEW;
+    Dashboard::new(title, columns, rows, config)
+}
+
+/// Result of `task`, run as the stage `name` of `dashboard` if any.
+fn in_stage<T, F>(
+    dashboard: &mut Option<Dashboard>,
+    name: &str,
+    task: F,
+) -> Result<T, Box<dyn Error>>
+where
+    F: FnOnce() -> Result<T, Box<dyn Error>>,
+{
+    if let Some(dashboard) = dashboard {
+        dashboard.start(name);
+        dashboard.print(true);
+    }
+    let result = task();
+    end_stage(dashboard, name, result.is_ok());
+    result
+}
+
+/// Marks the stage `name` of `dashboard`, if any, as done or failed.
+fn end_stage(dashboard: &mut Option<Dashboard>, name: &str, succeeded: bool) {
+    if let Some(dashboard) = dashboard {
+        if succeeded {
+            dashboard.finish(name);
+        } else {
+            dashboard.fail(name);
+        }
+        dashboard.print(true);
+    }
+}
+
+/// Results of `task` for each index below `count`, in order. Up to `jobs` threads share the work,
+/// each taking the next index once done with the previous one.
+fn run_jobs<T, F>(jobs: usize, count: usize, task: F) -> Vec<T>
+where
+    T: Send,
+    F: Fn(usize) -> T + Sync,
+{
+    if jobs <= 1 || count <= 1 {
+        return (0..count).map(task).collect();
+    }
+    let next = AtomicUsize::new(0);
+    let mut results: Vec<(usize, T)> = thread::scope(|scope| {
+        let workers: Vec<_> = (0..jobs.min(count))
+            .map(|_| {
+                scope.spawn(|| {
+                    let mut done = Vec::new();
+                    loop {
+                        let index = next.fetch_add(1, Ordering::Relaxed);
+                        if index >= count {
+                            return done;
+                        }
+                        done.push((index, task(index)));
+                    }
+                })
+            })
+            .collect();
+        workers
+            .into_iter()
+            .flat_map(|worker| {
+                worker
+                    .join()
+                    .unwrap_or_else(|panic| panic::resume_unwind(panic))
+            })
+            .collect()
+    });
+    results.sort_by_key(|(index, _)| *index);
+    results.into_iter().map(|(_, result)| result).collect()
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn run_jobs_test() {
+        let squares: Vec<usize> = (0..50).map(|index| index * index).collect();
+        assert_eq!(squares, run_jobs(4, 50, |index| index * index));
+        assert_eq!(squares, run_jobs(1, 50, |index| index * index));
+        assert!(run_jobs(4, 0, |index| index).is_empty());
+    }
+}
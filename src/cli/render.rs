@@ -0,0 +1,444 @@
+use crate::cli::arguments::{self, Argument, Arguments};
+use crate::cli::config::Config;
+use crate::cli::error;
+use crate::cli::template;
+use fft::epicycle;
+use fft::render;
+use fft::render::canvas::Canvas;
+use fft::render::{png, ppm, renderer, sheet, svg};
+use std::error::Error;
+use std::f64::consts::PI;
+
+pub const USAGE: &str = "\
+Draws the reconstruction of a path as an image.
+
+Usage: fft render [INPUT] [OPTIONS]
+
+Arguments:
+  [INPUT]                   Path to draw, one `x, y` point per line, `-` for the standard
+                            input, from the configuration file by default
+
+Options:
+  -o, --output <FILE>       Image to write, `-` for the standard output, may be repeated.
+                            {stem} and {name} are replaced by the name of the input without and
+                            with its extension, {precision} by the number of terms drawn and
+                            {size}, {width} and {height} by the image size
+  -p, --precision <TERMS>   Number of terms used, all of them by default
+      --sweep <LIST>        Draws each of the comma separated numbers of terms, such as 1,2,4,8,
+                            to outputs named with {precision} replaced, or suffixed with it
+      --sheet               Draws all the numbers of terms of --sweep side by side in one image
+  -j, --jobs <COUNT>        Numbers of terms of --sweep drawn at once, 0 for one per processor
+                            [default: 1]
+  -s, --size <WxH>          Image size in pixels, a single number for a square [default: 640]
+  -f, --format <FORMAT>     png, svg or ppm, guessed from the output extension by default
+      --matched             Draws svg images the same way as png and ppm ones, with the circles
+                            and annotation they show, rather than as the trace alone
+      --config <FILE>       Configuration file [default: fftdraw.toml when present]
+  -h, --help                Prints this help
+";
+
+/// Placeholders of output names, all but `{index}` which only batches fill.
+const RENDER_PLACEHOLDERS: &[&str] = &["stem", "name", "precision", "size", "width", "height"];
+
+/// Image file formats.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Format {
+    Png,
+    Svg,
+    Ppm,
+}
+
+impl Format {
+    pub fn parse(name: &str) -> Result<Format, Box<dyn Error>> {
+        match name.to_ascii_lowercase().as_str() {
+            "png" => Ok(Format::Png),
+            "svg" => Ok(Format::Svg),
+            "ppm" => Ok(Format::Ppm),
+            "gif" => Err("Animations are written by the animate command".into()),
+            _ => Err(format!("Unknown format {}, expected png, svg or ppm", name).into()),
+        }
+    }
+
+    /// Format matching the extension of `filename`.
+    pub fn of(filename: &str) -> Result<Format, Box<dyn Error>> {
+        match std::path::Path::new(filename).extension() {
+            Some(extension) => Format::parse(&extension.to_string_lossy()),
+            None => Err(format!("Cannot guess the format of {}, use --format", filename).into()),
+        }
+    }
+}
+
+/// Settings of the render command.
+#[derive(Debug, Clone)]
+pub struct Options {
+    pub input: String,
+    /// Files to write with their formats.
+    pub outputs: Vec<(String, Format)>,
+    pub precision: Option<usize>,
+    /// Numbers of terms drawn instead of `precision`, each to its own files unless `sheet`.
+    pub sweep: Vec<usize>,
+    /// Whether the numbers of terms of `sweep` are drawn together in a contact sheet.
+    pub sheet: bool,
+    /// Number of threads drawing the numbers of terms of `sweep`.
+    pub jobs: usize,
+    /// Whether SVG images are drawn by the same calls as raster images.
+    pub matched: bool,
+    pub config: render::RenderConfig,
+}
+
+/// Options given by `arguments` on top of `config`, `None` when help is asked for. Without
+/// `--output`, the images listed in the configuration are written and its animations left out.
+pub fn parse(arguments: &[String], config: &Config) -> Result<Option<Options>, Box<dyn Error>> {
+    let mut input = None;
+    let mut outputs = Vec::new();
+    let mut precision = config.precision;
+    let mut render = config.render.clone();
+    let mut format = None;
+    let mut sweep = Vec::new();
+    let mut sheet = false;
+    let mut jobs = 1;
+    let mut matched = false;
+
+    let mut arguments = Arguments::new(arguments);
+    while let Some(argument) = arguments.next() {
+        match &argument {
+            Argument::Positional(value) if input.is_none() => input = Some(value.clone()),
+            Argument::Flag(flag) => match flag.as_str() {
+                "-h" | "--help" => return Ok(None),
+                "-o" | "--output" => outputs.push(arguments.value()?),
+                "-p" | "--precision" => precision = Some(arguments.parsed()?),
+                "--sweep" => sweep = arguments::parse_counts(&arguments.value()?)?,
+                "--sheet" => sheet = true,
+                "-j" | "--jobs" => jobs = arguments::parse_jobs(&arguments.value()?)?,
+                "-s" | "--size" => {
+                    let (width, height) = arguments::parse_size(&arguments.value()?)?;
+                    render.width = width;
+                    render.height = height;
+                }
+                "-f" | "--format" => format = Some(Format::parse(&arguments.value()?)?),
+                "--matched" => matched = true,
+                _ => return Err(arguments::unexpected(&argument)),
+            },
+            _ => return Err(arguments::unexpected(&argument)),
+        }
+    }
+    if sheet && sweep.is_empty() {
+        return Err("--sheet needs the numbers of terms given by --sweep".into());
+    }
+
+    let input = input
+        .or_else(|| config.input.clone())
+        .ok_or("Missing input file")?;
+    if outputs.is_empty() {
+        outputs = config
+            .outputs
+            .iter()
+            .filter(|output| !output.to_ascii_lowercase().ends_with(".gif"))
+            .cloned()
+            .collect();
+    }
+    if outputs.is_empty() {
+        return Err("Missing --output".into());
+    }
+    for output in &outputs {
+        template::check(output, RENDER_PLACEHOLDERS)?;
+    }
+    let outputs = outputs
+        .into_iter()
+        .map(|output| {
+            let format = match format {
+                Some(format) => format,
+                None => Format::of(&output)?,
+            };
+            Ok((output, format))
+        })
+        .collect::<Result<Vec<_>, Box<dyn Error>>>()?;
+    if sheet && outputs.iter().any(|(_, format)| *format == Format::Svg) {
+        return Err("Contact sheets are written as png or ppm images".into());
+    }
+    Ok(Some(Options {
+        input,
+        outputs,
+        precision,
+        sweep,
+        sheet,
+        jobs,
+        matched,
+        config: render,
+    }))
+}
+
+pub fn run(arguments: &[String], config: &Config) -> Result<(), Box<dyn Error>> {
+    match parse(arguments, config).map_err(error::usage)? {
+        Some(options) => draw(&options),
+        None => {
+            print!("{}", USAGE);
+            Ok(())
+        }
+    }
+}
+
+/// Writes the reconstruction of the input path to the output files, once per number of terms of
+/// the sweep if any.
+pub fn draw(options: &Options) -> Result<(), Box<dyn Error>> {
+    let path = super::read_path(&options.input)?;
+    draw_decomposed(&super::decompose(&path)?, options)
+}
+
+/// Same as `draw` with the input path already decomposed into `epicycle`.
+pub fn draw_decomposed(
+    epicycle: &epicycle::Epicycle,
+    options: &Options,
+) -> Result<(), Box<dyn Error>> {
+    let config = render::RenderConfig {
+        source: super::source_name(&options.input),
+        ..options.config.clone()
+    };
+    // Names of the outputs for `precision`, given as text for contact sheets.
+    let named = |outputs: &[(String, Format)], precision: String| -> Vec<(String, Format)> {
+        let mut values = template::input_values(&options.input).to_vec();
+        values.extend_from_slice(&template::size_values(config.width, config.height));
+        values.push(("precision", precision));
+        outputs
+            .iter()
+            .map(|(output, format)| (template::fill(output, &values), *format))
+            .collect()
+    };
+    if options.sheet {
+        let canvas = {
+            let _span = super::log::span("draw contact sheet");
+            sheet::draw(epicycle, &options.sweep, &config).map_err(error::render)?
+        };
+        let precisions: Vec<String> = options.sweep.iter().map(usize::to_string).collect();
+        for (output, format) in named(&options.outputs, precisions.join("-")) {
+            super::write_output(&output, &encode(&canvas, format, &config))?;
+        }
+        return Ok(());
+    }
+    if options.sweep.is_empty() {
+        let precision = options.precision.unwrap_or_else(|| epicycle.len());
+        let outputs = named(&options.outputs, precision.to_string());
+        return draw_precision(epicycle, precision, &outputs, options.matched, &config);
+    }
+    // Every thread draws from the same decomposition.
+    let results = super::run_jobs(options.jobs, options.sweep.len(), |index| {
+        let precision = options.sweep[index];
+        let outputs: Vec<_> = options
+            .outputs
+            .iter()
+            .map(|(output, format)| (sweep_name(output, precision), *format))
+            .collect();
+        let outputs = named(&outputs, precision.to_string());
+        draw_precision(epicycle, precision, &outputs, options.matched, &config)
+            .map_err(|failure| error::detach(failure.as_ref()))
+    });
+    for result in results {
+        result?;
+    }
+    Ok(())
+}
+
+/// Name of the output for `precision` terms in a sweep: `output` with `{precision}` replaced, or
+/// with the number of terms added before the extension.
+pub fn sweep_name(output: &str, precision: usize) -> String {
+    if output.contains("{precision}") {
+        return output.replace("{precision}", &precision.to_string());
+    }
+    let path = std::path::Path::new(output);
+    match (path.file_stem(), path.extension()) {
+        _ if output == super::STANDARD_STREAM => output.to_string(),
+        (Some(stem), Some(extension)) => path
+            .with_file_name(format!(
+                "{}_{}.{}",
+                stem.to_string_lossy(),
+                precision,
+                extension.to_string_lossy()
+            ))
+            .to_string_lossy()
+            .into_owned(),
+        _ => format!("{}_{}", output, precision),
+    }
+}
+
+/// Writes the reconstruction with `precision` terms to `outputs`, rasterizing it once for all the
+/// raster formats. SVG images are drawn like raster ones when `matched`.
+pub fn draw_precision(
+    epicycle: &epicycle::Epicycle,
+    precision: usize,
+    outputs: &[(String, Format)],
+    matched: bool,
+    config: &render::RenderConfig,
+) -> Result<(), Box<dyn Error>> {
+    let mut canvas = None;
+    for (output, format) in outputs {
+        if *format == Format::Svg {
+            let document = {
+                let _span = super::log::span(format!("draw svg with {} terms", precision));
+                if matched {
+                    svg::matched(epicycle, precision, config).map_err(error::render)?
+                } else {
+                    svg::trace(epicycle, precision, config)
+                }
+            };
+            super::write_output(output, document.as_bytes())?;
+            continue;
+        }
+        if canvas.is_none() {
+            let _span = super::log::span(format!("draw image with {} terms", precision));
+            canvas = Some(
+                render::rasterize(config, |canvas, config| {
+                    renderer::draw_frame(canvas, epicycle, precision, 2.0 * PI, config)
+                })
+                .map_err(error::render)?,
+            );
+        }
+        let canvas = canvas.as_ref().unwrap();
+        let data = {
+            let _span = super::log::span(format!("encode {:?}", format).to_lowercase());
+            encode(canvas, *format, config)
+        };
+        super::write_output(output, &data)?;
+    }
+    Ok(())
+}
+
+/// Bytes of the reconstruction with `precision` terms as an image in `format`.
+#[cfg(feature = "serve")]
+pub fn image(
+    epicycle: &epicycle::Epicycle,
+    precision: usize,
+    format: Format,
+    config: &render::RenderConfig,
+) -> Result<Vec<u8>, Box<dyn Error>> {
+    if format == Format::Svg {
+        return Ok(svg::trace(epicycle, precision, config).into_bytes());
+    }
+    let canvas = render::rasterize(config, |canvas, config| {
+        renderer::draw_frame(canvas, epicycle, precision, 2.0 * PI, config)
+    })
+    .map_err(error::render)?;
+    Ok(encode(&canvas, format, config))
+}
+
+/// Bytes of `canvas` in the raster `format`.
+fn encode(canvas: &Canvas, format: Format, config: &render::RenderConfig) -> Vec<u8> {
+    match format {
+        Format::Png => {
+            let mut data = Vec::new();
+            png::encode_into_with(canvas, config, &mut data);
+            data
+        }
+        _ => ppm::encode(canvas),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    fn arguments(line: &str) -> Vec<String> {
+        line.split_whitespace().map(String::from).collect()
+    }
+
+    #[test]
+    fn parse_test() {
+        let config = Config::default();
+        let options = parse(
+            &arguments("in.txt --output=out.svg -p 12 --size 800x600 -o out.png"),
+            &config,
+        )
+        .unwrap()
+        .unwrap();
+        assert_eq!("in.txt", options.input);
+        assert_eq!(
+            vec![
+                ("out.svg".to_string(), Format::Svg),
+                ("out.png".to_string(), Format::Png)
+            ],
+            options.outputs
+        );
+        assert_eq!(Some(12), options.precision);
+        assert_eq!((800, 600), (options.config.width, options.config.height));
+        assert!(!options.matched);
+        let options = parse(
+            &arguments("in.txt -o out.svg -o out.png --matched"),
+            &config,
+        )
+        .unwrap()
+        .unwrap();
+        assert!(options.matched);
+        let options = parse(&arguments("in.txt -o out -f PNG"), &config)
+            .unwrap()
+            .unwrap();
+        assert_eq!(vec![("out".to_string(), Format::Png)], options.outputs);
+        assert_eq!(None, options.precision);
+        assert!(parse(&arguments("in.txt --help"), &config)
+            .unwrap()
+            .is_none());
+
+        assert!(parse(&arguments("-o out.png"), &config).is_err());
+        assert!(parse(&arguments("in.txt"), &config).is_err());
+        assert!(parse(&arguments("in.txt -o out"), &config).is_err());
+        assert!(parse(&arguments("in.txt -o out.png -p"), &config).is_err());
+        assert!(parse(&arguments("in.txt -o out.png --colour red"), &config).is_err());
+        assert!(parse(&arguments("in.txt other.txt -o out.png"), &config).is_err());
+        assert!(parse(&arguments("in.txt -o out.gif"), &config).is_err());
+
+        let options = parse(&arguments("- -o - -f ppm"), &config)
+            .unwrap()
+            .unwrap();
+        assert_eq!("-", options.input);
+        assert_eq!(vec![("-".to_string(), Format::Ppm)], options.outputs);
+        assert!(parse(&arguments("- -o -"), &config).is_err());
+    }
+
+    #[test]
+    fn parse_sweep_test() {
+        let config = Config::default();
+        let options = parse(&arguments("in.txt -o out.png --sweep 1,2,4"), &config)
+            .unwrap()
+            .unwrap();
+        assert_eq!(vec![1, 2, 4], options.sweep);
+        assert!(!options.sheet);
+        assert_eq!(1, options.jobs);
+        let options = parse(&arguments("in.txt -o out.png --sweep 1,2 -j 2"), &config)
+            .unwrap()
+            .unwrap();
+        assert_eq!(2, options.jobs);
+        assert!(parse(&arguments("in.txt -o out.png --sheet"), &config).is_err());
+        assert!(parse(&arguments("in.txt -o {stem}_{size}.png"), &config).is_ok());
+        assert!(parse(&arguments("in.txt -o {index}.png"), &config).is_err());
+        assert!(parse(&arguments("in.txt -o out.svg --sweep 1,2 --sheet"), &config).is_err());
+    }
+
+    #[test]
+    fn sweep_name_test() {
+        assert_eq!("out/heart_8.png", sweep_name("out/heart.png", 8));
+        assert_eq!("p8/heart.svg", sweep_name("p{precision}/heart.svg", 8));
+        assert_eq!("heart_8", sweep_name("heart", 8));
+        assert_eq!("-", sweep_name("-", 8));
+    }
+
+    #[test]
+    fn parse_with_config_test() {
+        let config = Config::parse(
+            "input = \"heart.txt\"\nprecision = 8\noutput = [\"a.png\", \"a.gif\"]\n\
+             [render]\nwidth = 100\nheight = 50\n",
+        )
+        .unwrap();
+        let options = parse(&[], &config).unwrap().unwrap();
+        assert_eq!("heart.txt", options.input);
+        assert_eq!(vec![("a.png".to_string(), Format::Png)], options.outputs);
+        assert_eq!(Some(8), options.precision);
+        assert_eq!((100, 50), (options.config.width, options.config.height));
+
+        let options = parse(&arguments("other.txt -p 2 -s 10 -o b.svg"), &config)
+            .unwrap()
+            .unwrap();
+        assert_eq!("other.txt", options.input);
+        assert_eq!(vec![("b.svg".to_string(), Format::Svg)], options.outputs);
+        assert_eq!(Some(2), options.precision);
+        assert_eq!((10, 10), (options.config.width, options.config.height));
+    }
+}
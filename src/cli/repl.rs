@@ -0,0 +1,385 @@
+use crate::cli::arguments::{self, Argument, Arguments};
+use crate::cli::config::Config;
+use crate::cli::error;
+use crate::cli::render::Format;
+use fft::epicycle;
+use fft::render;
+use fft::render::gif::{self, GifConfig};
+use std::error::Error;
+use std::io::{self, BufRead, IsTerminal, Write};
+
+pub const USAGE: &str = "\
+Reads and decomposes a path once, then draws it as settings are changed by commands typed one
+per line.
+
+Usage: fft repl [INPUT] [OPTIONS]
+
+Arguments:
+  [INPUT]                   Path to draw, one `x, y` point per line, from the configuration
+                            file by default
+
+Options:
+      --config <FILE>       Configuration file [default: fftdraw.toml when present]
+  -h, --help                Prints this help
+
+Type `help` at the prompt for the commands.
+";
+
+const COMMANDS: &str = "\
+Commands:
+  load <FILE>           Reads and decomposes another path
+  precision <TERMS>     Number of terms drawn, `all` for all of them
+  band <LOW>-<HIGH>     Draws only the terms turning LOW to HIGH times per turn, `all` for all
+  size <WxH>            Image size in pixels, a single number for a square
+  speed <FACTOR>        Speed of the animations, 0.5 for half as fast
+  fps <FRAMES>          Frames per second of the animations
+  render <FILE>...      Draws the reconstruction to png, svg or ppm images
+  animate <FILE>...     Draws the reconstruction being traced to GIF files
+  show                  Prints the current settings
+  help                  Prints this help
+  quit                  Leaves, as does the end of the input
+";
+
+/// Path being drawn with the settings changed by the commands.
+#[derive(Debug, Clone)]
+pub struct Session {
+    pub input: String,
+    pub epicycle: epicycle::Epicycle,
+    pub precision: Option<usize>,
+    /// Lowest and highest frequencies drawn, in turns per turn of the pen either way.
+    pub band: Option<(u64, u64)>,
+    pub config: render::RenderConfig,
+    pub animation: GifConfig,
+    /// Factor dividing the duration of the animations.
+    pub speed: f64,
+}
+
+/// Whether to keep reading commands after one.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Flow {
+    Continue,
+    Quit,
+}
+
+impl Session {
+    /// Session drawing the path in `input` with the settings of `config`.
+    pub fn open(input: &str, config: &Config) -> Result<Session, Box<dyn Error>> {
+        let path = super::read_path(input)?;
+        Ok(Session {
+            input: input.to_string(),
+            epicycle: super::decompose(&path)?,
+            precision: config.precision,
+            band: None,
+            config: config.render.clone(),
+            animation: config.animation.clone(),
+            speed: 1.0,
+        })
+    }
+
+    /// Runs the command on `line`, printing its results to `output`.
+    pub fn execute(&mut self, line: &str, output: &mut dyn Write) -> Result<Flow, Box<dyn Error>> {
+        let words: Vec<&str> = line.split_whitespace().collect();
+        let (command, values) = match words.split_first() {
+            Some((command, values)) => (*command, values),
+            None => return Ok(Flow::Continue),
+        };
+        let value = || match values {
+            [value] => Ok(*value),
+            _ => Err(format!("{} takes one value, see help", command)),
+        };
+        match command {
+            "load" => {
+                let input = value()?;
+                let path = super::read_path(input)?;
+                self.epicycle = super::decompose(&path)?;
+                self.input = input.to_string();
+                writeln!(output, "{} terms", self.epicycle.len())?;
+            }
+            "precision" => {
+                self.precision = match value()? {
+                    "all" => None,
+                    terms => Some(terms.parse().map_err(|error| {
+                        format!("Invalid number of terms {}: {}", terms, error)
+                    })?),
+                };
+            }
+            "band" => {
+                self.band = match value()? {
+                    "all" => None,
+                    band => Some(parse_band(band)?),
+                };
+            }
+            "size" => {
+                let (width, height) = arguments::parse_size(value()?)?;
+                self.config.width = width;
+                self.config.height = height;
+            }
+            "speed" => {
+                let text = value()?;
+                let speed: f64 = text
+                    .parse()
+                    .map_err(|error| format!("Invalid speed {}: {}", text, error))?;
+                if !(speed.is_finite() && speed > 0.0) {
+                    return Err(format!("Invalid speed {}, it must be positive", speed).into());
+                }
+                self.speed = speed;
+            }
+            "fps" => {
+                let text = value()?;
+                let fps: u32 = text
+                    .parse()
+                    .map_err(|error| format!("Invalid fps {}: {}", text, error))?;
+                if fps == 0 {
+                    return Err("Invalid fps 0, it must be positive".into());
+                }
+                self.animation.fps = fps;
+            }
+            "render" => {
+                if values.is_empty() {
+                    return Err("render takes the images to write, see help".into());
+                }
+                let outputs = values
+                    .iter()
+                    .map(|output| Ok((output.to_string(), Format::of(output)?)))
+                    .collect::<Result<Vec<_>, Box<dyn Error>>>()?;
+                super::render::draw_precision(
+                    &self.drawn(),
+                    self.terms(),
+                    &outputs,
+                    false,
+                    &self.render_config(),
+                )?;
+            }
+            "animate" => {
+                if values.is_empty() {
+                    return Err("animate takes the GIF files to write, see help".into());
+                }
+                let animation = GifConfig {
+                    duration: self.animation.duration / self.speed,
+                    ..self.animation.clone()
+                };
+                let data = {
+                    let _span = super::log::span("draw animation");
+                    gif::encode(
+                        &self.drawn(),
+                        self.terms(),
+                        &self.render_config(),
+                        &animation,
+                    )?
+                };
+                for output in values {
+                    super::write_output(output, &data)?;
+                }
+            }
+            "show" => {
+                writeln!(output, "input      {}", self.input)?;
+                writeln!(
+                    output,
+                    "precision  {} of {} terms",
+                    self.terms(),
+                    self.drawn().len()
+                )?;
+                if let Some((lowest, highest)) = self.band {
+                    writeln!(output, "band       {} to {} turns", lowest, highest)?;
+                }
+                writeln!(
+                    output,
+                    "size       {}x{}",
+                    self.config.width, self.config.height
+                )?;
+                writeln!(
+                    output,
+                    "animation  {} fps, {} s per turn",
+                    self.animation.fps,
+                    self.animation.duration / self.speed
+                )?;
+            }
+            "help" => write!(output, "{}", COMMANDS)?,
+            "quit" | "exit" => return Ok(Flow::Quit),
+            _ => return Err(format!("Unknown command {}, see help", command).into()),
+        }
+        Ok(Flow::Continue)
+    }
+
+    /// Decomposition with the terms of the band, derived from the terms already computed.
+    fn drawn(&self) -> epicycle::Epicycle {
+        match self.band {
+            Some((lowest, highest)) => self.epicycle.band(lowest, highest),
+            None => self.epicycle.clone(),
+        }
+    }
+
+    /// Number of terms drawn.
+    fn terms(&self) -> usize {
+        let available = self.drawn().len();
+        self.precision.unwrap_or(available).min(available)
+    }
+
+    fn render_config(&self) -> render::RenderConfig {
+        render::RenderConfig {
+            source: super::source_name(&self.input),
+            ..self.config.clone()
+        }
+    }
+}
+
+/// Lowest and highest frequencies of a `LOW-HIGH` band.
+fn parse_band(band: &str) -> Result<(u64, u64), Box<dyn Error>> {
+    let invalid = |reason: String| format!("Invalid band {}: {}", band, reason);
+    let (lowest, highest) = band
+        .split_once('-')
+        .ok_or_else(|| invalid("expected LOW-HIGH".to_string()))?;
+    let lowest: u64 = lowest
+        .parse()
+        .map_err(|error| invalid(format!("{}", error)))?;
+    let highest: u64 = highest
+        .parse()
+        .map_err(|error| invalid(format!("{}", error)))?;
+    if lowest > highest {
+        return Err(invalid("LOW is above HIGH".to_string()).into());
+    }
+    Ok((lowest, highest))
+}
+
+/// Input file given by `arguments` on top of `config`, `None` when help is asked for.
+pub fn parse(arguments: &[String], config: &Config) -> Result<Option<String>, Box<dyn Error>> {
+    let mut input = None;
+    let mut arguments = Arguments::new(arguments);
+    while let Some(argument) = arguments.next() {
+        match &argument {
+            Argument::Positional(value) if input.is_none() => input = Some(value.clone()),
+            Argument::Flag(flag) if flag == "-h" || flag == "--help" => return Ok(None),
+            _ => return Err(arguments::unexpected(&argument)),
+        }
+    }
+    let input = input
+        .or_else(|| config.input.clone())
+        .ok_or("Missing input file")?;
+    if input == super::STANDARD_STREAM {
+        return Err("The standard input is read for commands, give a path file".into());
+    }
+    Ok(Some(input))
+}
+
+pub fn run(arguments: &[String], config: &Config) -> Result<(), Box<dyn Error>> {
+    let input = match parse(arguments, config).map_err(error::usage)? {
+        Some(input) => input,
+        None => {
+            print!("{}", USAGE);
+            return Ok(());
+        }
+    };
+    let mut session = Session::open(&input, config)?;
+    let interactive = io::stdin().is_terminal();
+    if interactive {
+        println!(
+            "{} terms read from {}, type help for the commands",
+            session.epicycle.len(),
+            input
+        );
+    }
+    let stdin = io::stdin();
+    let mut lines = stdin.lock().lines();
+    loop {
+        if interactive {
+            print!("> ");
+            io::stdout().flush()?;
+        }
+        let line = match lines.next() {
+            Some(line) => line?,
+            None => break,
+        };
+        // Failing commands are reported without leaving, so that a typo does not lose the session.
+        match session.execute(&line, &mut io::stdout()) {
+            Ok(Flow::Quit) => break,
+            Ok(Flow::Continue) => {}
+            Err(error) => eprintln!("error: {}", error),
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use fft::complex;
+    use fft::path;
+
+    fn session() -> Session {
+        let path = path::Path {
+            data: (0..8)
+                .map(|k| complex::Complex::new(k as f64, (k * k % 5) as f64))
+                .collect(),
+        };
+        Session {
+            input: "in.txt".to_string(),
+            epicycle: epicycle::Epicycle::new(&path),
+            precision: None,
+            band: None,
+            config: render::RenderConfig::default(),
+            animation: GifConfig::default(),
+            speed: 1.0,
+        }
+    }
+
+    #[test]
+    fn execute_test() {
+        let mut session = session();
+        let mut output = Vec::new();
+        for line in &["precision 3", "", "size 20x10", "speed 0.5", "fps 10"] {
+            assert_eq!(Flow::Continue, session.execute(line, &mut output).unwrap());
+        }
+        assert_eq!(Some(3), session.precision);
+        assert_eq!((20, 10), (session.config.width, session.config.height));
+        session.execute("show", &mut output).unwrap();
+        let shown = String::from_utf8(output).unwrap();
+        assert!(shown.contains("precision  3 of 8 terms"));
+        assert!(shown.contains("animation  10 fps, 8 s per turn"));
+
+        session.execute("precision all", &mut Vec::new()).unwrap();
+        assert_eq!(None, session.precision);
+
+        let mut output = Vec::new();
+        session.execute("band 1-2", &mut output).unwrap();
+        session.execute("show", &mut output).unwrap();
+        assert_eq!(Some((1, 2)), session.band);
+        let shown = String::from_utf8(output).unwrap();
+        assert!(shown.contains("precision  4 of 4 terms"));
+        assert!(shown.contains("band       1 to 2 turns"));
+        session.execute("band all", &mut Vec::new()).unwrap();
+        assert_eq!(None, session.band);
+        assert_eq!(
+            Flow::Quit,
+            session.execute("quit", &mut Vec::new()).unwrap()
+        );
+
+        for line in &[
+            "precision",
+            "precision 1 2",
+            "speed -1",
+            "fps 0",
+            "band 2",
+            "band 3-1",
+            "band -1-2",
+            "render",
+            "render out.gif",
+            "draw",
+        ] {
+            assert!(session.execute(line, &mut Vec::new()).is_err(), "{}", line);
+        }
+    }
+
+    #[test]
+    fn parse_test() {
+        let config = Config::default();
+        let arguments = vec!["in.txt".to_string()];
+        assert_eq!(
+            Some("in.txt".to_string()),
+            parse(&arguments, &config).unwrap()
+        );
+        assert!(parse(&["-h".to_string()], &config).unwrap().is_none());
+        assert!(parse(&[], &config).is_err());
+        assert!(parse(&["-".to_string()], &config).is_err());
+    }
+}
@@ -0,0 +1,761 @@
+use crate::cli::arguments::{self, Argument, Arguments};
+use crate::cli::config::Config;
+use crate::cli::convert;
+use crate::cli::error;
+use crate::cli::render::Format;
+use crate::cli::spectrum;
+use fft::epicycle;
+use fft::render;
+use fft::render::gif::{self, GifConfig};
+use std::collections::VecDeque;
+use std::error::Error;
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread;
+use std::time::Duration;
+
+pub const USAGE: &str = "\
+Draws paths sent over HTTP, decomposing each once for all the drawings asked for.
+
+Usage: fft serve [OPTIONS]
+
+Options:
+  -a, --address <ADDRESS>   Address and port to listen on [default: 127.0.0.1:8080]
+      --config <FILE>       Configuration file giving the default drawing settings
+                            [default: fftdraw.toml when present]
+  -h, --help                Prints this help
+
+Endpoints:
+  POST /paths                   Decomposes the path in the body, as text, json or svg given by
+                                ?format=, text by default, and responds with its id
+  GET  /paths/<ID>/terms        Terms of the decomposition as JSON, the ?terms= largest ones
+  GET  /paths/<ID>/image        Reconstruction as a png, svg or ppm image given by ?format=,
+                                with ?precision= terms and ?size= pixels
+  GET  /paths/<ID>/animation    Reconstruction being traced as a GIF, with ?precision=, ?size=,
+                                ?fps= and ?duration=
+
+Uploaded paths hold at most 1048576 points, or 8192 when their number is not a power of two.
+The oldest are forgotten once more than 1024 paths or 4194304 terms are kept. Drawings are at
+most 4096 pixels wide and high, and animations at most 3000 frames long and 268435456 pixels
+over all their frames. Failures are answered with JSON objects giving their kind and message.
+Needs the tool built with the serve feature.
+";
+
+const DEFAULT_ADDRESS: &str = "127.0.0.1:8080";
+
+/// Largest request body read, in bytes.
+const MAX_BODY: usize = 16 << 20;
+
+/// Largest request line and headers read, in bytes.
+const MAX_HEAD: usize = 16 << 10;
+
+/// Largest number of points of an uploaded path.
+const MAX_POINTS: usize = 1 << 20;
+
+/// Largest number of points of an uploaded path whose length is not a power of two, going through
+/// the slower transform, whose time grows with the square of the length.
+const MAX_SLOW_POINTS: usize = 1 << 13;
+
+/// Largest number of requests handled at once, the next connections waiting for one to finish.
+const MAX_HANDLERS: usize = 32;
+
+/// Largest width and height of the drawings, in pixels.
+const MAX_SIZE: u32 = 4096;
+
+/// Largest number of frames of an animation.
+const MAX_FRAMES: usize = 3000;
+
+/// Largest number of pixels drawn for an animation, over all its frames, so that size and length
+/// cannot both be at their largest.
+const MAX_PIXELS: u64 = 1 << 28;
+
+/// Largest number of uploaded paths kept, the oldest being forgotten first.
+const MAX_PATHS: usize = 1024;
+
+/// Largest number of terms of the uploaded paths kept, the oldest being forgotten first.
+const MAX_TERMS: usize = 1 << 22;
+
+/// Time given to clients to send their requests.
+const READ_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Settings of the serve command.
+#[derive(Debug, Clone)]
+pub struct Options {
+    pub address: String,
+    pub config: render::RenderConfig,
+    pub animation: GifConfig,
+}
+
+/// Options given by `arguments` on top of `config`, `None` when help is asked for.
+pub fn parse(arguments: &[String], config: &Config) -> Result<Option<Options>, Box<dyn Error>> {
+    let mut address = DEFAULT_ADDRESS.to_string();
+    let mut arguments = Arguments::new(arguments);
+    while let Some(argument) = arguments.next() {
+        match &argument {
+            Argument::Flag(flag) => match flag.as_str() {
+                "-h" | "--help" => return Ok(None),
+                "-a" | "--address" => address = arguments.value()?,
+                _ => return Err(arguments::unexpected(&argument)),
+            },
+            _ => return Err(arguments::unexpected(&argument)),
+        }
+    }
+    Ok(Some(Options {
+        address,
+        config: config.render.clone(),
+        animation: config.animation.clone(),
+    }))
+}
+
+pub fn run(arguments: &[String], config: &Config) -> Result<(), Box<dyn Error>> {
+    let options = match parse(arguments, config).map_err(error::usage)? {
+        Some(options) => options,
+        None => {
+            print!("{}", USAGE);
+            return Ok(());
+        }
+    };
+    let listener = TcpListener::bind(&options.address).map_err(|failure| {
+        let message = format!("Cannot listen on {}: {}", options.address, failure);
+        error::tag(error::Kind::Output, message.into())
+    })?;
+    eprintln!("Listening on http://{}", listener.local_addr()?);
+    let server = Arc::new(Server::new(options));
+    let handlers = Arc::new(Handlers::default());
+    for stream in listener.incoming() {
+        let stream = match stream {
+            Ok(stream) => stream,
+            Err(failure) => {
+                super::log::debug(format!("connection failed: {}", failure));
+                continue;
+            }
+        };
+        let server = Arc::clone(&server);
+        let handler = handlers.start();
+        thread::spawn(move || {
+            let _handler = handler;
+            if let Err(failure) = server.handle(stream) {
+                super::log::debug(format!("connection failed: {}", failure));
+            }
+        });
+    }
+    Ok(())
+}
+
+/// Number of requests being handled, with a signal when one of them is done.
+#[derive(Debug, Default)]
+struct Handlers {
+    running: Mutex<usize>,
+    done: Condvar,
+}
+
+impl Handlers {
+    /// Waits for fewer than `MAX_HANDLERS` requests to be handled, then counts one more until the
+    /// handler returned is dropped.
+    fn start(self: &Arc<Self>) -> Handler {
+        let mut running = self.running.lock().unwrap();
+        while *running >= MAX_HANDLERS {
+            running = self.done.wait(running).unwrap();
+        }
+        *running += 1;
+        Handler(Arc::clone(self))
+    }
+}
+
+/// Request counted by `Handlers` as being handled.
+#[derive(Debug)]
+struct Handler(Arc<Handlers>);
+
+impl Drop for Handler {
+    fn drop(&mut self) {
+        *self.0.running.lock().unwrap() -= 1;
+        self.0.done.notify_one();
+    }
+}
+
+/// HTTP request, with the query of its target split apart.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Request {
+    pub method: String,
+    pub path: String,
+    pub query: Vec<(String, String)>,
+    pub body: Vec<u8>,
+}
+
+impl Request {
+    /// Reads a request from `reader`, `None` when the connection closes before one starts.
+    pub fn read(reader: &mut dyn BufRead) -> Result<Option<Request>, Box<dyn Error>> {
+        let mut head = reader.take(MAX_HEAD as u64);
+        let mut line = String::new();
+        if read_head_line(&mut head, &mut line)? == 0 {
+            return Ok(None);
+        }
+        let mut parts = line.split_whitespace();
+        let (method, target) = match (parts.next(), parts.next(), parts.next()) {
+            (Some(method), Some(target), Some(version)) if version.starts_with("HTTP/") => {
+                (method.to_string(), target.to_string())
+            }
+            _ => return Err(format!("Invalid request line {}", line.trim_end()).into()),
+        };
+
+        let mut length = 0;
+        loop {
+            line.clear();
+            if read_head_line(&mut head, &mut line)? == 0 {
+                return Err("Connection closed within the headers".into());
+            }
+            let header = line.trim_end();
+            if header.is_empty() {
+                break;
+            }
+            if let Some((name, value)) = header.split_once(':') {
+                if name.trim().eq_ignore_ascii_case("content-length") {
+                    length = value.trim().parse().map_err(|failure| {
+                        format!("Invalid Content-Length {}: {}", value, failure)
+                    })?;
+                }
+            }
+        }
+        if length > MAX_BODY {
+            return Err(format!("Body of {} bytes, larger than {}", length, MAX_BODY).into());
+        }
+        let mut body = vec![0; length];
+        head.into_inner().read_exact(&mut body)?;
+
+        let (path, query) = match target.split_once('?') {
+            Some((path, query)) => (path.to_string(), query),
+            None => (target, ""),
+        };
+        let query = query
+            .split('&')
+            .filter(|pair| !pair.is_empty())
+            .map(|pair| match pair.split_once('=') {
+                Some((key, value)) => Ok((decode(key)?, decode(value)?)),
+                None => Ok((decode(pair)?, String::new())),
+            })
+            .collect::<Result<_, Box<dyn Error>>>()?;
+        Ok(Some(Request {
+            method,
+            path,
+            query,
+            body,
+        }))
+    }
+
+    /// Value of the query parameter `key`, parsed by `parse`, `None` when missing.
+    fn parameter<T>(
+        &self,
+        key: &str,
+        parse: impl Fn(&str) -> Result<T, Box<dyn Error>>,
+    ) -> Result<Option<T>, Box<dyn Error>> {
+        match self.query.iter().find(|(name, _)| name == key) {
+            Some((_, value)) => parse(value)
+                .map(Some)
+                .map_err(|failure| error::usage(format!("Invalid {}: {}", key, failure).into())),
+            None => Ok(None),
+        }
+    }
+}
+
+/// HTTP response.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Response {
+    pub status: u16,
+    pub content_type: &'static str,
+    pub body: Vec<u8>,
+}
+
+impl Response {
+    fn new(status: u16, content_type: &'static str, body: Vec<u8>) -> Response {
+        Response {
+            status,
+            content_type,
+            body,
+        }
+    }
+
+    /// Response describing `failure`, with a status matching its class.
+    fn failure(status: u16, failure: &(dyn Error + 'static)) -> Response {
+        let body = format!("{}\n", error::to_json(failure)).into_bytes();
+        Response::new(status, "application/json", body)
+    }
+
+    fn reason(&self) -> &'static str {
+        match self.status {
+            200 => "OK",
+            201 => "Created",
+            400 => "Bad Request",
+            404 => "Not Found",
+            405 => "Method Not Allowed",
+            _ => "Internal Server Error",
+        }
+    }
+
+    pub fn write(&self, output: &mut dyn Write) -> io::Result<()> {
+        write!(
+            output,
+            "HTTP/1.1 {} {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+            self.status,
+            self.reason(),
+            self.content_type,
+            self.body.len()
+        )?;
+        output.write_all(&self.body)?;
+        output.flush()
+    }
+}
+
+/// Decompositions of the last uploaded paths, numbered from 1 in the order of upload.
+#[derive(Default)]
+struct Paths {
+    /// Number of the oldest path kept, less one.
+    forgotten: usize,
+    kept: VecDeque<Arc<epicycle::Epicycle>>,
+    terms: usize,
+}
+
+impl Paths {
+    fn get(&self, id: usize) -> Option<Arc<epicycle::Epicycle>> {
+        let index = id.checked_sub(self.forgotten + 1)?;
+        self.kept.get(index).cloned()
+    }
+
+    /// Keeps `epicycle`, forgetting the oldest paths past `MAX_PATHS` paths or `MAX_TERMS` terms,
+    /// and gives its number.
+    fn push(&mut self, epicycle: epicycle::Epicycle) -> usize {
+        self.terms += epicycle.len();
+        self.kept.push_back(Arc::new(epicycle));
+        while self.kept.len() > MAX_PATHS || self.terms > MAX_TERMS {
+            match self.kept.pop_front() {
+                Some(oldest) => {
+                    self.terms -= oldest.len();
+                    self.forgotten += 1;
+                }
+                None => break,
+            }
+        }
+        self.forgotten + self.kept.len()
+    }
+}
+
+pub struct Server {
+    options: Options,
+    paths: Mutex<Paths>,
+}
+
+impl Server {
+    pub fn new(options: Options) -> Server {
+        Server {
+            options,
+            paths: Mutex::new(Paths::default()),
+        }
+    }
+
+    /// Answers the request read from `stream`, then closes it.
+    fn handle(&self, stream: TcpStream) -> Result<(), Box<dyn Error>> {
+        stream.set_read_timeout(Some(READ_TIMEOUT))?;
+        let mut reader = BufReader::new(stream.try_clone()?);
+        let response = match Request::read(&mut reader) {
+            Ok(Some(request)) => {
+                let response = self.respond(&request);
+                super::log::debug(format!(
+                    "{} {} {}",
+                    request.method, request.path, response.status
+                ));
+                response
+            }
+            Ok(None) => return Ok(()),
+            Err(failure) => Response::failure(400, error::usage(failure).as_ref()),
+        };
+        let mut stream = stream;
+        response.write(&mut stream)?;
+        Ok(())
+    }
+
+    /// Response to `request`.
+    pub fn respond(&self, request: &Request) -> Response {
+        let segments: Vec<&str> = request.path.trim_matches('/').split('/').collect();
+        let result = match (request.method.as_str(), &segments[..]) {
+            ("POST", ["paths"]) => self.upload(request),
+            ("GET", ["paths", id, resource]) => match self.path(id) {
+                Some(epicycle) => match *resource {
+                    "terms" => self.terms(&epicycle, request),
+                    "image" => self.image(&epicycle, request),
+                    "animation" => self.animation(&epicycle, request),
+                    _ => return not_found(&request.path),
+                },
+                None => return not_found(&request.path),
+            },
+            (_, ["paths"]) | (_, ["paths", _, _]) => {
+                let message = format!("{} is not allowed on {}", request.method, request.path);
+                return Response::failure(405, error::usage(message.into()).as_ref());
+            }
+            _ => return not_found(&request.path),
+        };
+        result.unwrap_or_else(|failure| {
+            let status = match error::kind_of(failure.as_ref()) {
+                error::Kind::Usage | error::Kind::Input => 400,
+                _ => 500,
+            };
+            Response::failure(status, failure.as_ref())
+        })
+    }
+
+    fn path(&self, id: &str) -> Option<Arc<epicycle::Epicycle>> {
+        self.paths.lock().unwrap().get(id.parse().ok()?)
+    }
+
+    fn upload(&self, request: &Request) -> Result<Response, Box<dyn Error>> {
+        let format = request
+            .parameter("format", convert::Format::parse)?
+            .unwrap_or(convert::Format::Text);
+        let text = String::from_utf8(request.body.clone())
+            .map_err(|failure| error::input(format!("Invalid path: {}", failure).into()))?;
+        let path = format
+            .read(&text)
+            .map_err(|failure| error::input(format!("Invalid path: {}", failure).into()))?;
+        let points = path.data.len();
+        if points > MAX_POINTS || (points > MAX_SLOW_POINTS && !points.is_power_of_two()) {
+            let message = format!(
+                "Path of {} points, more than {}, or {} when not a power of two",
+                points, MAX_POINTS, MAX_SLOW_POINTS
+            );
+            return Err(error::input(message.into()));
+        }
+        let epicycle = super::decompose(&path)?;
+        let terms = epicycle.len();
+        let id = self.paths.lock().unwrap().push(epicycle);
+        let body = format!(
+            "{{\"id\": {}, \"points\": {}, \"terms\": {}}}\n",
+            id,
+            path.data.len(),
+            terms
+        );
+        Ok(Response::new(201, "application/json", body.into_bytes()))
+    }
+
+    fn terms(
+        &self,
+        epicycle: &epicycle::Epicycle,
+        request: &Request,
+    ) -> Result<Response, Box<dyn Error>> {
+        let options = spectrum::Options {
+            input: String::new(),
+            output: String::new(),
+            format: spectrum::Format::Json,
+            terms: request.parameter("terms", parse_number)?,
+            by_frequency: false,
+        };
+        let table = spectrum::write(epicycle, &options);
+        Ok(Response::new(200, "application/json", table.into_bytes()))
+    }
+
+    fn image(
+        &self,
+        epicycle: &epicycle::Epicycle,
+        request: &Request,
+    ) -> Result<Response, Box<dyn Error>> {
+        let format = request
+            .parameter("format", Format::parse)?
+            .unwrap_or(Format::Png);
+        let (precision, config) = self.drawing(epicycle, request)?;
+        let data = super::render::image(epicycle, precision, format, &config)?;
+        let content_type = match format {
+            Format::Png => "image/png",
+            Format::Svg => "image/svg+xml",
+            Format::Ppm => "image/x-portable-pixmap",
+        };
+        Ok(Response::new(200, content_type, data))
+    }
+
+    fn animation(
+        &self,
+        epicycle: &epicycle::Epicycle,
+        request: &Request,
+    ) -> Result<Response, Box<dyn Error>> {
+        let (precision, config) = self.drawing(epicycle, request)?;
+        let mut animation = self.options.animation.clone();
+        if let Some(fps) = request.parameter("fps", parse_number::<u32>)? {
+            if fps == 0 {
+                return Err(error::usage("Invalid fps: it must be positive".into()));
+            }
+            animation.fps = fps;
+        }
+        if let Some(duration) = request.parameter("duration", arguments::parse_duration)? {
+            animation.duration = duration;
+        }
+        if animation.frame_count() > MAX_FRAMES {
+            let message = format!(
+                "Animation of {} frames, longer than {}",
+                animation.frame_count(),
+                MAX_FRAMES
+            );
+            return Err(error::usage(message.into()));
+        }
+        let pixels = u64::from(config.width) * u64::from(config.height);
+        if pixels * animation.frame_count() as u64 > MAX_PIXELS {
+            let message = format!(
+                "Animation of {} frames of {}x{} pixels, more than {} pixels",
+                animation.frame_count(),
+                config.width,
+                config.height,
+                MAX_PIXELS
+            );
+            return Err(error::usage(message.into()));
+        }
+        let data = gif::encode(epicycle, precision, &config, &animation)?;
+        Ok(Response::new(200, "image/gif", data))
+    }
+
+    /// Number of terms and drawing settings given by the query of `request`.
+    fn drawing(
+        &self,
+        epicycle: &epicycle::Epicycle,
+        request: &Request,
+    ) -> Result<(usize, render::RenderConfig), Box<dyn Error>> {
+        let precision = request
+            .parameter("precision", parse_number)?
+            .map_or(epicycle.len(), |precision: usize| {
+                precision.min(epicycle.len())
+            });
+        let mut config = self.options.config.clone();
+        if let Some((width, height)) = request.parameter("size", arguments::parse_size)? {
+            if width > MAX_SIZE || height > MAX_SIZE {
+                let message = format!(
+                    "Invalid size: {}x{} is larger than {}",
+                    width, height, MAX_SIZE
+                );
+                return Err(error::usage(message.into()));
+            }
+            config.width = width;
+            config.height = height;
+        }
+        Ok((precision, config))
+    }
+}
+
+/// Query component `text` with its `+` turned into spaces and its `%XX` escapes into the bytes
+/// they stand for.
+fn decode(text: &str) -> Result<String, Box<dyn Error>> {
+    let mut bytes = Vec::with_capacity(text.len());
+    let mut rest = text.as_bytes();
+    while let Some((first, tail)) = rest.split_first() {
+        match first {
+            b'+' => bytes.push(b' '),
+            b'%' => {
+                let escape = tail
+                    .get(..2)
+                    .and_then(|hex| std::str::from_utf8(hex).ok())
+                    .and_then(|hex| u8::from_str_radix(hex, 16).ok())
+                    .ok_or_else(|| format!("Invalid escape in {}", text))?;
+                bytes.push(escape);
+                rest = &tail[2..];
+                continue;
+            }
+            _ => bytes.push(*first),
+        }
+        rest = tail;
+    }
+    String::from_utf8(bytes).map_err(|_| format!("Invalid UTF-8 in {}", text).into())
+}
+
+/// Reads a line of the request line and headers from `head`, failing once they are longer than
+/// it allows.
+fn read_head_line(head: &mut io::Take<&mut dyn BufRead>, line: &mut String) -> io::Result<usize> {
+    let read = head.read_line(line)?;
+    if head.limit() == 0 && !line.ends_with('\n') {
+        let message = format!("Request line and headers longer than {} bytes", MAX_HEAD);
+        return Err(io::Error::new(io::ErrorKind::InvalidData, message));
+    }
+    Ok(read)
+}
+
+fn not_found(path: &str) -> Response {
+    let message = format!("Nothing at {}", path);
+    Response::failure(404, error::usage(message.into()).as_ref())
+}
+
+fn parse_number<T>(text: &str) -> Result<T, Box<dyn Error>>
+where
+    T: std::str::FromStr,
+    T::Err: Error + 'static,
+{
+    text.parse().map_err(Box::from)
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use fft::complex;
+    use fft::shape;
+
+    fn server() -> Server {
+        Server::new(parse(&[], &Config::default()).unwrap().unwrap())
+    }
+
+    fn request(method: &str, target: &str, body: &str) -> Request {
+        let raw = format!(
+            "{} {} HTTP/1.1\r\nHost: localhost\r\nContent-Length: {}\r\n\r\n{}",
+            method,
+            target,
+            body.len(),
+            body
+        );
+        Request::read(&mut raw.as_bytes()).unwrap().unwrap()
+    }
+
+    #[test]
+    fn parse_test() {
+        let config = Config::default();
+        assert_eq!(
+            DEFAULT_ADDRESS,
+            parse(&[], &config).unwrap().unwrap().address
+        );
+        let arguments = vec!["-a".to_string(), "0.0.0.0:3000".to_string()];
+        assert_eq!(
+            "0.0.0.0:3000",
+            parse(&arguments, &config).unwrap().unwrap().address
+        );
+        assert!(parse(&["in.txt".to_string()], &config).is_err());
+    }
+
+    #[test]
+    fn read_test() {
+        let image = request("GET", "/paths/2/image?size=64&format=svg&flag", "");
+        assert_eq!("GET", image.method);
+        assert_eq!("/paths/2/image", image.path);
+        assert_eq!(
+            vec![
+                ("size".to_string(), "64".to_string()),
+                ("format".to_string(), "svg".to_string()),
+                ("flag".to_string(), String::new())
+            ],
+            image.query
+        );
+        let posted = request("POST", "/paths", "0, 0\n1, 0\n");
+        assert_eq!(b"0, 0\n1, 0\n".to_vec(), posted.body);
+
+        let encoded = request(
+            "GET",
+            "/paths/1/image?for%6Dat=svg&duration=1.5%20s&a+b=%C3%A9",
+            "",
+        );
+        assert_eq!(
+            vec![
+                ("format".to_string(), "svg".to_string()),
+                ("duration".to_string(), "1.5 s".to_string()),
+                ("a b".to_string(), "é".to_string())
+            ],
+            encoded.query
+        );
+        assert!(Request::read(&mut "GET /?size=%4 HTTP/1.1\r\n\r\n".as_bytes()).is_err());
+        assert!(Request::read(&mut "GET /?size=%FF HTTP/1.1\r\n\r\n".as_bytes()).is_err());
+
+        assert!(Request::read(&mut "".as_bytes()).unwrap().is_none());
+        assert!(Request::read(&mut "GET /\r\n\r\n".as_bytes()).is_err());
+        assert!(Request::read(&mut "GET / HTTP/1.1\r\nHost: a\r\n".as_bytes()).is_err());
+        let long = format!("GET / HTTP/1.1\r\nCookie: {}\r\n\r\n", "a".repeat(MAX_HEAD));
+        assert!(Request::read(&mut long.as_bytes()).is_err());
+    }
+
+    #[test]
+    fn respond_test() {
+        let server = server();
+        let square = "0, 0\n1, 0\n1, 1\n0, 1\n";
+        let response = server.respond(&request("POST", "/paths", square));
+        assert_eq!(201, response.status);
+        assert_eq!(
+            "{\"id\": 1, \"points\": 4, \"terms\": 4}\n",
+            String::from_utf8(response.body).unwrap()
+        );
+
+        let response = server.respond(&request("GET", "/paths/1/terms?terms=2", ""));
+        assert_eq!(200, response.status);
+        assert_eq!(
+            2,
+            String::from_utf8(response.body)
+                .unwrap()
+                .matches('{')
+                .count()
+        );
+        let response = server.respond(&request("GET", "/paths/1/image?size=32", ""));
+        assert_eq!(("image/png", 200), (response.content_type, response.status));
+        assert_eq!(b"\x89PNG", &response.body[..4]);
+        let response = server.respond(&request("GET", "/paths/1/image?format=svg", ""));
+        assert!(response.body.starts_with(b"<svg") || response.body.starts_with(b"<?xml"));
+        let response = server.respond(&request(
+            "GET",
+            "/paths/1/animation?size=16&fps=10&duration=0.5s&precision=2",
+            "",
+        ));
+        assert_eq!(("image/gif", 200), (response.content_type, response.status));
+        assert_eq!(b"GIF89a", &response.body[..6]);
+
+        let status = |method, target, body| server.respond(&request(method, target, body)).status;
+        assert_eq!(400, status("POST", "/paths?format=json", "not a path"));
+        assert_eq!(400, status("POST", "/paths?format=png", square));
+        let odd = "0, 0\n".repeat(MAX_SLOW_POINTS + 1);
+        assert_eq!(400, status("POST", "/paths", &odd));
+        assert_eq!(400, status("GET", "/paths/1/image?precision=many", ""));
+        assert_eq!(400, status("GET", "/paths/1/animation?fps=0", ""));
+        assert_eq!(400, status("GET", "/paths/1/image?size=100000", ""));
+        assert_eq!(400, status("GET", "/paths/1/image?size=64x5000", ""));
+        assert_eq!(
+            400,
+            status("GET", "/paths/1/animation?size=16&duration=1000s", "")
+        );
+        assert_eq!(
+            400,
+            status("GET", "/paths/1/animation?size=4096&fps=1&duration=17s", "")
+        );
+        assert_eq!(404, status("GET", "/paths/2/terms", ""));
+        assert_eq!(404, status("GET", "/paths/0/terms", ""));
+        assert_eq!(404, status("GET", "/paths/1/sound", ""));
+        assert_eq!(404, status("GET", "/", ""));
+        assert_eq!(405, status("DELETE", "/paths/1/terms", ""));
+        assert_eq!(405, status("GET", "/paths", ""));
+    }
+
+    #[test]
+    fn paths_test() {
+        let mut paths = Paths::default();
+        let epicycle = || epicycle::Epicycle::new(&shape::Shape::Circle.path(4));
+        for id in 1..=MAX_PATHS {
+            assert_eq!(id, paths.push(epicycle()));
+        }
+        assert_eq!(MAX_PATHS + 1, paths.push(epicycle()));
+        assert!(paths.get(1).is_none());
+        assert!(paths.get(2).is_some());
+        assert!(paths.get(MAX_PATHS + 1).is_some());
+        assert!(paths.get(MAX_PATHS + 2).is_none());
+        assert!(paths.get(0).is_none());
+
+        let mut paths = Paths::default();
+        let large = || {
+            let terms = vec![(complex::Complex::new(1.0, 0.0), 1); MAX_TERMS / 2];
+            epicycle::Epicycle::from_terms(terms)
+        };
+        assert_eq!(1, paths.push(large()));
+        assert_eq!(2, paths.push(large()));
+        assert_eq!(3, paths.push(epicycle()));
+        assert!(paths.get(1).is_none());
+        assert_eq!(MAX_TERMS / 2 + 4, paths.terms);
+    }
+
+    #[test]
+    fn handlers_test() {
+        let handlers = Arc::new(Handlers::default());
+        let mut started: Vec<Handler> = (0..MAX_HANDLERS).map(|_| handlers.start()).collect();
+        assert_eq!(MAX_HANDLERS, *handlers.running.lock().unwrap());
+        let waiting = {
+            let handlers = Arc::clone(&handlers);
+            thread::spawn(move || drop(handlers.start()))
+        };
+        started.pop();
+        waiting.join().unwrap();
+        drop(started);
+        assert_eq!(0, *handlers.running.lock().unwrap());
+    }
+}
@@ -0,0 +1,231 @@
+use crate::cli::analyze;
+use crate::cli::arguments::{self, Argument, Arguments};
+use crate::cli::config::Config;
+use crate::cli::error;
+use fft::epicycle;
+use std::error::Error;
+
+pub const USAGE: &str = "\
+Writes the terms of the decomposition of a path as a table.
+
+Usage: fft spectrum [INPUT] [OPTIONS]
+
+Arguments:
+  [INPUT]                   Path to decompose, one `x, y` point per line, `-` for the standard
+                            input, from the configuration file by default
+
+Options:
+  -o, --output <FILE>       File to write [default: the standard output]
+  -f, --format <FORMAT>     csv or json, guessed from the output extension, csv by default, or
+                            p5 for the JSON objects of the p5.js epicycle sketches
+  -n, --terms <TERMS>       Number of terms written, the largest first, all of them by default
+      --sort <ORDER>        amplitude, from the largest, or frequency [default: amplitude]
+      --config <FILE>       Configuration file [default: fftdraw.toml when present]
+  -h, --help                Prints this help
+
+Each term has a frequency, in turns per period, an amplitude and a phase in radians.
+";
+
+/// Table formats.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Format {
+    Csv,
+    Json,
+    /// `{re, im, freq, amp, phase}` objects, as used by the Coding Train p5.js sketches.
+    P5,
+}
+
+impl Format {
+    pub fn parse(name: &str) -> Result<Format, Box<dyn Error>> {
+        match name.to_ascii_lowercase().as_str() {
+            "csv" => Ok(Format::Csv),
+            "json" => Ok(Format::Json),
+            "p5" => Ok(Format::P5),
+            _ => Err(format!("Unknown table format {}, expected csv, json or p5", name).into()),
+        }
+    }
+}
+
+/// Settings of the spectrum command.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Options {
+    pub input: String,
+    pub output: String,
+    pub format: Format,
+    pub terms: Option<usize>,
+    /// Whether terms are listed by increasing frequency instead of decreasing amplitude.
+    pub by_frequency: bool,
+}
+
+/// Options given by `arguments` on top of `config`, `None` when help is asked for.
+pub fn parse(arguments: &[String], config: &Config) -> Result<Option<Options>, Box<dyn Error>> {
+    let mut input = None;
+    let mut output = None;
+    let mut format = None;
+    let mut terms = None;
+    let mut by_frequency = false;
+
+    let mut arguments = Arguments::new(arguments);
+    while let Some(argument) = arguments.next() {
+        match &argument {
+            Argument::Positional(value) if input.is_none() => input = Some(value.clone()),
+            Argument::Flag(flag) => match flag.as_str() {
+                "-h" | "--help" => return Ok(None),
+                "-o" | "--output" => output = Some(arguments.value()?),
+                "-f" | "--format" => format = Some(Format::parse(&arguments.value()?)?),
+                "-n" | "--terms" => terms = Some(arguments.parsed()?),
+                "--sort" => {
+                    by_frequency = match arguments.value()?.as_str() {
+                        "amplitude" => false,
+                        "frequency" => true,
+                        other => {
+                            return Err(format!(
+                                "Unknown order {}, expected amplitude or frequency",
+                                other
+                            )
+                            .into())
+                        }
+                    }
+                }
+                _ => return Err(arguments::unexpected(&argument)),
+            },
+            _ => return Err(arguments::unexpected(&argument)),
+        }
+    }
+
+    let output = output.unwrap_or_else(|| super::STANDARD_STREAM.to_string());
+    let format = match format {
+        Some(format) => format,
+        None => match std::path::Path::new(&output).extension() {
+            Some(extension) => Format::parse(&extension.to_string_lossy())?,
+            None => Format::Csv,
+        },
+    };
+    Ok(Some(Options {
+        input: input
+            .or_else(|| config.input.clone())
+            .ok_or("Missing input file")?,
+        output,
+        format,
+        terms,
+        by_frequency,
+    }))
+}
+
+pub fn run(arguments: &[String], config: &Config) -> Result<(), Box<dyn Error>> {
+    let options = match parse(arguments, config).map_err(error::usage)? {
+        Some(options) => options,
+        None => {
+            print!("{}", USAGE);
+            return Ok(());
+        }
+    };
+    let path = super::read_path(&options.input)?;
+    let epicycle = super::decompose(&path)?;
+    let table = write(&epicycle, &options);
+    super::write_output(&options.output, table.as_bytes())
+}
+
+/// Table of the terms of `epicycle` selected by `options`.
+pub fn write(epicycle: &epicycle::Epicycle, options: &Options) -> String {
+    let mut terms: Vec<_> = epicycle
+        .terms()
+        .iter()
+        .take(options.terms.unwrap_or_else(|| epicycle.len()))
+        .collect();
+    if options.by_frequency {
+        terms.sort_by_key(|(_, frequency)| *frequency);
+    }
+    let rows = terms.iter().map(|(coefficient, frequency)| {
+        (
+            *frequency,
+            coefficient.amplitude(),
+            analyze::phase(coefficient),
+        )
+    });
+    match options.format {
+        Format::Csv => {
+            let mut table = String::from("frequency,amplitude,phase\n");
+            for (frequency, amplitude, phase) in rows {
+                table.push_str(&format!("{},{},{}\n", frequency, amplitude, phase));
+            }
+            table
+        }
+        Format::Json => {
+            let rows: Vec<String> = rows
+                .map(|(frequency, amplitude, phase)| {
+                    format!(
+                        "  {{\"frequency\": {}, \"amplitude\": {}, \"phase\": {}}}",
+                        frequency, amplitude, phase
+                    )
+                })
+                .collect();
+            format!("[\n{}\n]\n", rows.join(",\n"))
+        }
+        Format::P5 => epicycle::p5_json(terms),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use fft::complex;
+    use fft::path;
+
+    fn arguments(line: &str) -> Vec<String> {
+        line.split_whitespace().map(String::from).collect()
+    }
+
+    #[test]
+    fn parse_test() {
+        let config = Config::default();
+        let options = parse(&arguments("in.txt"), &config).unwrap().unwrap();
+        assert_eq!("-", options.output);
+        assert_eq!(Format::Csv, options.format);
+        let options = parse(&arguments("in.txt -o terms.json --sort frequency"), &config)
+            .unwrap()
+            .unwrap();
+        assert_eq!(Format::Json, options.format);
+        assert!(options.by_frequency);
+        assert!(parse(&arguments("in.txt -o terms.txt"), &config).is_err());
+        assert!(parse(&arguments("in.txt --sort phase"), &config).is_err());
+    }
+
+    #[test]
+    fn write_test() {
+        // Constant term 1 and the frequency -1 with amplitude 2.
+        let epicycle = epicycle::Epicycle::new(&path::Path {
+            data: (0..4)
+                .map(|k| {
+                    let angle = std::f64::consts::PI * f64::from(k) / 2.0;
+                    complex::Complex::new(1.0 + 2.0 * angle.cos(), -2.0 * angle.sin())
+                })
+                .collect(),
+        });
+        let mut options = Options {
+            input: String::new(),
+            output: String::new(),
+            format: Format::Csv,
+            terms: Some(2),
+            by_frequency: true,
+        };
+        let table = write(&epicycle, &options);
+        let lines: Vec<_> = table.lines().collect();
+        assert_eq!(3, lines.len());
+        assert_eq!("frequency,amplitude,phase", lines[0]);
+        assert!(lines[1].starts_with("-1,2,"));
+        assert!(lines[2].starts_with("0,1,"));
+
+        options.format = Format::Json;
+        options.terms = Some(1);
+        let table = write(&epicycle, &options);
+        assert!(table.starts_with("[\n  {\"frequency\": -1, \"amplitude\": 2, \"phase\": "));
+        assert!(table.ends_with("}\n]\n"));
+
+        options.format = Format::P5;
+        let table = write(&epicycle, &options);
+        assert!(table.starts_with("[\n  {\"re\": 2, "));
+        assert!(table.contains("\"freq\": -1, \"amp\": 2, \"phase\": "));
+    }
+}
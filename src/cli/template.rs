@@ -0,0 +1,101 @@
+//! Output file names with placeholders, such as `{stem}_{precision}p_{size}.png`, so that the
+//! files written by batches and sweeps tell what they hold.
+
+use std::error::Error;
+use std::path::Path;
+
+/// Placeholders allowed in output file names.
+pub const PLACEHOLDERS: [&str; 7] = [
+    "stem",
+    "name",
+    "index",
+    "precision",
+    "size",
+    "width",
+    "height",
+];
+
+/// Fails when `template` has a placeholder other than `placeholders` or an unclosed one.
+pub fn check(template: &str, placeholders: &[&str]) -> Result<(), Box<dyn Error>> {
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        let after = &rest[start + 1..];
+        let end = after
+            .find('}')
+            .ok_or_else(|| format!("Unclosed placeholder in {}", template))?;
+        let name = &after[..end];
+        if !placeholders.contains(&name) {
+            return Err(format!(
+                "Unknown placeholder {{{}}} in {}, expected {{{}}}",
+                name,
+                template,
+                placeholders.join("}, {")
+            )
+            .into());
+        }
+        rest = &after[end + 1..];
+    }
+    Ok(())
+}
+
+/// `template` with the placeholders named in `values` replaced, others being kept for a later
+/// stage to fill.
+pub fn fill(template: &str, values: &[(&str, String)]) -> String {
+    values
+        .iter()
+        .fold(template.to_string(), |name, (key, value)| {
+            name.replace(&format!("{{{}}}", key), value)
+        })
+}
+
+/// Values of `{stem}` and `{name}` for the path file `input`: its name without and with its
+/// extension, `stdin` for the standard input.
+pub fn input_values(input: &str) -> [(&'static str, String); 2] {
+    if input == super::STANDARD_STREAM {
+        return [("stem", "stdin".to_string()), ("name", "stdin".to_string())];
+    }
+    let path = Path::new(input);
+    let part = |part: Option<&std::ffi::OsStr>| part.unwrap_or_default().to_string_lossy().into();
+    [
+        ("stem", part(path.file_stem())),
+        ("name", part(path.file_name())),
+    ]
+}
+
+/// Values of `{size}`, `{width}` and `{height}` for an image of `width` by `height` pixels.
+pub fn size_values(width: u32, height: u32) -> [(&'static str, String); 3] {
+    [
+        ("size", format!("{}x{}", width, height)),
+        ("width", width.to_string()),
+        ("height", height.to_string()),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn check_test() {
+        assert!(check("out/{stem}_{precision}p_{size}.png", &PLACEHOLDERS).is_ok());
+        assert!(check("plain.png", &PLACEHOLDERS).is_ok());
+        assert!(check("{colour}.png", &PLACEHOLDERS).is_err());
+        assert!(check("{stem.png", &PLACEHOLDERS).is_err());
+        assert!(check("{index}.png", &["stem"]).is_err());
+    }
+
+    #[test]
+    fn fill_test() {
+        let mut values = input_values("in/heart.txt").to_vec();
+        values.extend_from_slice(&size_values(800, 600));
+        assert_eq!(
+            "heart_{precision}p_800x600 (heart.txt, 600).png",
+            fill("{stem}_{precision}p_{size} ({name}, {height}).png", &values)
+        );
+        assert_eq!(
+            "stdin.png",
+            fill("{stem}.png", &input_values(super::super::STANDARD_STREAM))
+        );
+    }
+}
@@ -0,0 +1,294 @@
+//! Reader for the subset of TOML used by configuration files: tables, `key = value` pairs,
+//! strings, integers, floats, booleans, arrays of those and `#` comments.
+
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::iter::Peekable;
+use std::str::Chars;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    String(String),
+    Integer(i64),
+    Float(f64),
+    Boolean(bool),
+    Array(Vec<Value>),
+}
+
+impl Value {
+    /// Name of the type of the value, for error messages.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Value::String(_) => "a string",
+            Value::Integer(_) => "an integer",
+            Value::Float(_) => "a float",
+            Value::Boolean(_) => "a boolean",
+            Value::Array(_) => "an array",
+        }
+    }
+}
+
+/// Values of a document by their full dotted key, such as `render.width` for the key `width` of
+/// the table `[render]`.
+pub fn parse(text: &str) -> Result<BTreeMap<String, Value>, Box<dyn Error>> {
+    let mut values = BTreeMap::new();
+    let mut table = String::new();
+    let mut lines = text.lines().enumerate();
+    while let Some((index, line)) = lines.next() {
+        let error = |message: String| format!("Line {}: {}", index + 1, message);
+        let mut statement = strip_comment(line).trim().to_string();
+        if statement.is_empty() {
+            continue;
+        }
+        if statement.starts_with('[') {
+            let name = statement
+                .strip_prefix('[')
+                .and_then(|rest| rest.strip_suffix(']'))
+                .map(str::trim)
+                .filter(|name| !name.is_empty() && name.split('.').all(is_bare_key))
+                .ok_or_else(|| error(format!("Invalid table header {}", statement)))?;
+            table = format!("{}.", name);
+            continue;
+        }
+
+        // Arrays may continue over the following lines until their brackets are balanced.
+        while depth(&statement) > 0 {
+            match lines.next() {
+                Some((_, next)) => {
+                    statement.push(' ');
+                    statement.push_str(strip_comment(next).trim());
+                }
+                None => return Err(error("Unterminated array".to_string()).into()),
+            }
+        }
+        let (key, value) = statement
+            .split_once('=')
+            .ok_or_else(|| error(format!("Expected key = value, found {}", statement)))?;
+        let key = key.trim();
+        if !key.split('.').all(is_bare_key) {
+            return Err(error(format!("Invalid key {}", key)).into());
+        }
+        let mut characters = value.trim().chars().peekable();
+        let value = parse_value(&mut characters).map_err(error)?;
+        if characters.any(|c| !c.is_whitespace()) {
+            return Err(error(format!("Unexpected text after the value of {}", key)).into());
+        }
+        let full_key = format!("{}{}", table, key);
+        if values.insert(full_key.clone(), value).is_some() {
+            return Err(error(format!("Duplicate key {}", full_key)).into());
+        }
+    }
+    Ok(values)
+}
+
+/// Value written in `text`, or `text` itself as a string when it is not a value, for settings
+/// given outside of a document where quotes would be a burden.
+pub fn parse_loose(text: &str) -> Value {
+    let mut characters = text.trim().chars().peekable();
+    match parse_value(&mut characters) {
+        Ok(value) if characters.all(char::is_whitespace) => value,
+        _ => Value::String(text.to_string()),
+    }
+}
+
+fn is_bare_key(key: &str) -> bool {
+    !key.is_empty()
+        && key
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+}
+
+/// `line` without its comment, `#` characters inside strings being kept.
+fn strip_comment(line: &str) -> &str {
+    let mut quote = None;
+    let mut escaped = false;
+    for (index, character) in line.char_indices() {
+        match (quote, character) {
+            (Some('"'), '\\') if !escaped => {
+                escaped = true;
+                continue;
+            }
+            (Some(open), c) if c == open && !escaped => quote = None,
+            (None, '"') | (None, '\'') => quote = Some(character),
+            (None, '#') => return &line[..index],
+            _ => {}
+        }
+        escaped = false;
+    }
+    line
+}
+
+/// Number of brackets left open at the end of `text`, outside strings.
+fn depth(text: &str) -> i32 {
+    let mut depth = 0;
+    let mut quote = None;
+    let mut escaped = false;
+    for character in text.chars() {
+        match (quote, character) {
+            (Some('"'), '\\') if !escaped => {
+                escaped = true;
+                continue;
+            }
+            (Some(open), c) if c == open && !escaped => quote = None,
+            (None, '"') | (None, '\'') => quote = Some(character),
+            (None, '[') => depth += 1,
+            (None, ']') => depth -= 1,
+            _ => {}
+        }
+        escaped = false;
+    }
+    depth
+}
+
+fn parse_value(characters: &mut Peekable<Chars>) -> Result<Value, String> {
+    while characters.peek().is_some_and(|c| c.is_whitespace()) {
+        characters.next();
+    }
+    match characters.peek() {
+        Some('"') => {
+            characters.next();
+            let mut text = String::new();
+            loop {
+                match characters.next() {
+                    Some('"') => return Ok(Value::String(text)),
+                    Some('\\') => match characters.next() {
+                        Some('n') => text.push('\n'),
+                        Some('t') => text.push('\t'),
+                        Some('"') => text.push('"'),
+                        Some('\\') => text.push('\\'),
+                        other => return Err(format!("Unsupported escape \\{:?}", other)),
+                    },
+                    Some(c) => text.push(c),
+                    None => return Err("Unterminated string".to_string()),
+                }
+            }
+        }
+        Some('\'') => {
+            characters.next();
+            let mut text = String::new();
+            loop {
+                match characters.next() {
+                    Some('\'') => return Ok(Value::String(text)),
+                    Some(c) => text.push(c),
+                    None => return Err("Unterminated string".to_string()),
+                }
+            }
+        }
+        Some('[') => {
+            characters.next();
+            let mut items = Vec::new();
+            loop {
+                while characters
+                    .peek()
+                    .is_some_and(|c| c.is_whitespace() || *c == ',')
+                {
+                    characters.next();
+                }
+                if characters.peek() == Some(&']') {
+                    characters.next();
+                    return Ok(Value::Array(items));
+                }
+                if characters.peek().is_none() {
+                    return Err("Unterminated array".to_string());
+                }
+                items.push(parse_value(characters)?);
+                while characters.peek().is_some_and(|c| c.is_whitespace()) {
+                    characters.next();
+                }
+                match characters.peek() {
+                    Some(',') | Some(']') => {}
+                    _ => return Err("Expected , or ] in array".to_string()),
+                }
+            }
+        }
+        Some(_) => {
+            let mut word = String::new();
+            while let Some(&c) = characters.peek() {
+                if c == ',' || c == ']' || c.is_whitespace() {
+                    break;
+                }
+                word.push(c);
+                characters.next();
+            }
+            let number = word.replace('_', "");
+            match word.as_str() {
+                "true" => Ok(Value::Boolean(true)),
+                "false" => Ok(Value::Boolean(false)),
+                _ => {
+                    if let Ok(integer) = number.parse() {
+                        Ok(Value::Integer(integer))
+                    } else if let Ok(float) = number.parse() {
+                        Ok(Value::Float(float))
+                    } else {
+                        Err(format!("Invalid value {}", word))
+                    }
+                }
+            }
+        }
+        None => Err("Missing value".to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn parse_test() {
+        let values = parse(
+            "# Project\n\
+             input = \"heart.txt\" # trailing comment\n\
+             precision = 1_024\n\
+             \n\
+             [render]\n\
+             line_width = 2.5\n\
+             caption = 'Terms # {precision}'\n\
+             grid = true\n\
+             output = [\"a.png\",\n  \"b \\\"svg\\\".svg\", # second\n]\n",
+        )
+        .unwrap();
+        assert_eq!(
+            Some(&Value::String("heart.txt".to_string())),
+            values.get("input")
+        );
+        assert_eq!(Some(&Value::Integer(1024)), values.get("precision"));
+        assert_eq!(Some(&Value::Float(2.5)), values.get("render.line_width"));
+        assert_eq!(
+            Some(&Value::String("Terms # {precision}".to_string())),
+            values.get("render.caption")
+        );
+        assert_eq!(Some(&Value::Boolean(true)), values.get("render.grid"));
+        assert_eq!(
+            Some(&Value::Array(vec![
+                Value::String("a.png".to_string()),
+                Value::String("b \"svg\".svg".to_string()),
+            ])),
+            values.get("render.output")
+        );
+    }
+
+    #[test]
+    fn parse_loose_test() {
+        assert_eq!(Value::Integer(800), parse_loose("800"));
+        assert_eq!(Value::Boolean(true), parse_loose(" true "));
+        assert_eq!(Value::String("dark".to_string()), parse_loose("dark"));
+        assert_eq!(Value::String("#102030".to_string()), parse_loose("#102030"));
+        assert_eq!(Value::String("a b".to_string()), parse_loose("\"a b\""));
+        assert_eq!(Value::String("1 2".to_string()), parse_loose("1 2"));
+    }
+
+    #[test]
+    fn parse_errors_test() {
+        assert!(parse("input").is_err());
+        assert!(parse("input = ").is_err());
+        assert!(parse("input = \"open").is_err());
+        assert!(parse("a = 1\na = 2").is_err());
+        assert!(parse("a = [1, 2").is_err());
+        assert!(parse("a = 1 2").is_err());
+        assert!(parse("[render").is_err());
+        assert!(parse("a b = 1").is_err());
+        let error = parse("a = 1\nb = yes").unwrap_err().to_string();
+        assert!(error.starts_with("Line 2:"));
+    }
+}
@@ -0,0 +1,176 @@
+use crate::cli::arguments::{self, Argument, Arguments};
+use crate::cli::error;
+use std::error::Error;
+
+pub const USAGE: &str = "\
+Writes the outline of the largest shape of each frame of a video as a path, ready to be drawn by
+the batch command.
+
+Usage: fft trace <VIDEO> <DIRECTORY> [OPTIONS]
+
+Arguments:
+  <VIDEO>                 Video to decode with ffmpeg, which must be installed
+  <DIRECTORY>             Directory where frame_0001.txt, frame_0002.txt and so on are written
+
+Options:
+  -r, --fps <RATE>        Number of frames traced per second of video, all of them by default
+  -n, --points <COUNT>    Number of points, evenly spaced along each outline [default: 256]
+  -h, --help              Prints this help
+
+Shapes are told apart from the background, taken to be the brightness of most of the border of
+the frame. Frames without any shape are skipped. Needs the tool built with the video feature.
+";
+
+const DEFAULT_POINTS: usize = 256;
+
+/// Settings of the trace command.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Options {
+    pub video: String,
+    pub directory: String,
+    pub fps: Option<f64>,
+    pub points: usize,
+}
+
+/// Options given by `arguments`, `None` when help is asked for.
+pub fn parse(arguments: &[String]) -> Result<Option<Options>, Box<dyn Error>> {
+    let mut files = Vec::with_capacity(2);
+    let mut fps = None;
+    let mut points = DEFAULT_POINTS;
+
+    let mut arguments = Arguments::new(arguments);
+    while let Some(argument) = arguments.next() {
+        match &argument {
+            Argument::Positional(value) if files.len() < 2 => files.push(value.clone()),
+            Argument::Flag(flag) => match flag.as_str() {
+                "-h" | "--help" => return Ok(None),
+                "-r" | "--fps" => fps = Some(arguments.parsed()?),
+                "-n" | "--points" => points = arguments.parsed()?,
+                _ => return Err(arguments::unexpected(&argument)),
+            },
+            _ => return Err(arguments::unexpected(&argument)),
+        }
+    }
+
+    if points == 0 {
+        return Err("Invalid --points 0, it must be positive".into());
+    }
+    if let Some(fps) = fps.filter(|fps: &f64| !(fps.is_finite() && *fps > 0.0)) {
+        return Err(format!("Invalid --fps {}, it must be positive", fps).into());
+    }
+    let mut files = files.into_iter();
+    let (video, directory) = match (files.next(), files.next()) {
+        (Some(video), Some(directory)) => (video, directory),
+        (Some(_), None) => return Err("Missing the output directory".into()),
+        _ => return Err("Missing the video".into()),
+    };
+    Ok(Some(Options {
+        video,
+        directory,
+        fps,
+        points,
+    }))
+}
+
+/// Name of the path file of the frame `index`, from 1, numbered so that they sort in order.
+#[cfg(feature = "video")]
+pub fn frame_name(index: usize) -> String {
+    format!("frame_{:04}.txt", index)
+}
+
+pub fn run(arguments: &[String]) -> Result<(), Box<dyn Error>> {
+    let options = match parse(arguments).map_err(error::usage)? {
+        Some(options) => options,
+        None => {
+            print!("{}", USAGE);
+            return Ok(());
+        }
+    };
+    trace(&options)
+}
+
+#[cfg(feature = "video")]
+fn trace(options: &Options) -> Result<(), Box<dyn Error>> {
+    use fft::video;
+    use std::fs;
+    use std::path::Path;
+
+    let frames = video::frames(&options.video, options.fps)
+        .map_err(|failure| error::input(format!("{}: {}", options.video, failure).into()))?;
+    fs::create_dir_all(&options.directory)
+        .map_err(|failure| error::tag(error::Kind::Output, failure.into()))?;
+    let _span = super::log::span(format!("trace {}", options.video));
+    let (mut traced, mut skipped) = (0, 0);
+    for (index, frame) in frames.enumerate() {
+        let frame = frame
+            .map_err(|failure| error::input(format!("{}: {}", options.video, failure).into()))?;
+        let path = match video::dominant_contour(&frame) {
+            Some(path) => path,
+            None => {
+                eprintln!("{}: no shape in frame {}", options.video, index + 1);
+                skipped += 1;
+                continue;
+            }
+        };
+        let output = Path::new(&options.directory).join(frame_name(index + 1));
+        super::write_output(
+            &output.to_string_lossy(),
+            path.resample(options.points).to_text().as_bytes(),
+        )?;
+        traced += 1;
+    }
+    super::log::debug(format!("{} frames traced, {} skipped", traced, skipped));
+    if traced == 0 {
+        return Err(error::input(
+            format!("{}: no shape found in any frame", options.video).into(),
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(not(feature = "video"))]
+fn trace(_options: &Options) -> Result<(), Box<dyn Error>> {
+    Err(error::usage(
+        "trace needs the tool built with the video feature, \
+         cargo build --release --features video"
+            .into(),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    fn arguments(line: &str) -> Vec<String> {
+        line.split_whitespace().map(String::from).collect()
+    }
+
+    #[test]
+    fn parse_test() {
+        let options = parse(&arguments("dance.mp4 frames --fps 12.5 -n 512"))
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            Options {
+                video: "dance.mp4".to_string(),
+                directory: "frames".to_string(),
+                fps: Some(12.5),
+                points: 512,
+            },
+            options
+        );
+        let options = parse(&arguments("dance.mp4 frames")).unwrap().unwrap();
+        assert_eq!((None, DEFAULT_POINTS), (options.fps, options.points));
+        assert!(parse(&arguments("dance.mp4")).is_err());
+        assert!(parse(&arguments("dance.mp4 frames -r 0")).is_err());
+        assert!(parse(&arguments("dance.mp4 frames -n 0")).is_err());
+    }
+
+    #[cfg(feature = "video")]
+    #[test]
+    fn frame_name_test() {
+        assert_eq!("frame_0007.txt", frame_name(7));
+        assert_eq!("frame_12345.txt", frame_name(12345));
+    }
+}
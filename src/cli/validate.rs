@@ -0,0 +1,379 @@
+use crate::cli::arguments::{self, Argument, Arguments};
+use crate::cli::error;
+use fft::complex;
+use fft::path;
+use std::error::Error;
+use std::fmt;
+use std::fs;
+use std::io::{self, Read};
+
+pub const USAGE: &str = "\
+Checks that a path file can be drawn and suggests how to fix it otherwise.
+
+Usage: fft validate <INPUT> [OPTIONS]
+
+Arguments:
+  <INPUT>        Path to check, one `x, y` point per line, `-` for the standard input
+
+Options:
+  -h, --help     Prints this help
+
+Exits with an error when the file cannot be drawn; warnings only point out what would make the
+drawing slower or less accurate.
+";
+
+/// Number of points below which a path is reported as too short to draw a meaningful shape.
+const MIN_POINTS: usize = 4;
+
+/// Number of points from which the slower transform used for other lengths becomes noticeable.
+const SLOW_LENGTH: usize = 256;
+
+/// Gap between the last and first points, relative to the mean distance between consecutive
+/// points, from which the path is reported as open.
+const OPEN_GAP: f64 = 5.0;
+
+/// Number of lines listed for an issue found on many lines.
+const LISTED_LINES: usize = 5;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Severity {
+    Warning,
+    Error,
+}
+
+/// Problem found in a path file, with what to do about it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Issue {
+    pub severity: Severity,
+    pub message: String,
+    pub suggestion: String,
+}
+
+impl Issue {
+    fn error(message: String, suggestion: &str) -> Issue {
+        Issue {
+            severity: Severity::Error,
+            message,
+            suggestion: suggestion.to_string(),
+        }
+    }
+
+    fn warning(message: String, suggestion: String) -> Issue {
+        Issue {
+            severity: Severity::Warning,
+            message,
+            suggestion,
+        }
+    }
+}
+
+impl fmt::Display for Issue {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let severity = match self.severity {
+            Severity::Warning => "warning",
+            Severity::Error => "error",
+        };
+        write!(
+            f,
+            "{}: {}\n  help: {}",
+            severity, self.message, self.suggestion
+        )
+    }
+}
+
+pub fn run(arguments: &[String]) -> Result<(), Box<dyn Error>> {
+    let mut input = None;
+    let mut arguments = Arguments::new(arguments);
+    while let Some(argument) = arguments.next() {
+        match &argument {
+            Argument::Positional(value) if input.is_none() => input = Some(value.clone()),
+            Argument::Flag(flag) if flag == "-h" || flag == "--help" => {
+                print!("{}", USAGE);
+                return Ok(());
+            }
+            _ => return Err(error::usage(arguments::unexpected(&argument))),
+        }
+    }
+    let input = input.ok_or_else(|| error::usage("Missing input file".into()))?;
+
+    let mut text = String::new();
+    let read = if input == super::STANDARD_STREAM {
+        io::stdin().read_to_string(&mut text).map(|_| ())
+    } else {
+        fs::read_to_string(&input).map(|content| text = content)
+    };
+    read.map_err(|e| error::input(format!("Cannot read {}: {}", input, e).into()))?;
+
+    let (points, issues) = check(&text, super::parse_mode());
+    println!("{}: {} points", input, points);
+    for issue in &issues {
+        println!("{}", issue);
+    }
+    let errors = issues
+        .iter()
+        .filter(|issue| issue.severity == Severity::Error)
+        .count();
+    if errors > 0 {
+        let message = format!("{} cannot be drawn, {} error(s) found", input, errors);
+        return Err(error::input(message.into()));
+    }
+    Ok(())
+}
+
+/// Number of points read from `text`, in the format of path files, and the issues found when it
+/// is read in `mode`.
+pub fn check(text: &str, mode: path::Mode) -> (usize, Vec<Issue>) {
+    let mut issues = Vec::new();
+    let mut points = Vec::new();
+    // Line numbers, from 1, of each point.
+    let mut numbers = Vec::new();
+    let mut blank = Vec::new();
+    let mut malformed = Vec::new();
+    let mut infinite = Vec::new();
+    for (index, line) in text.lines().enumerate() {
+        if line.trim().is_empty() {
+            blank.push(index + 1);
+            continue;
+        }
+        match parse_point(line) {
+            Some(point) if point.re.is_finite() && point.im.is_finite() => {
+                points.push(point);
+                numbers.push(index + 1);
+            }
+            Some(_) => infinite.push(index + 1),
+            None => malformed.push(index + 1),
+        }
+    }
+    // Lines without a point only fail strict reads, lenient ones skipping them.
+    let line_issue = |message: String, suggestion: &str| match mode {
+        path::Mode::Strict => Issue::error(message, suggestion),
+        path::Mode::Lenient => Issue::warning(message, "skipped with --lenient".to_string()),
+    };
+    if !blank.is_empty() {
+        issues.push(line_issue(
+            format!("blank {}", lines(&blank)),
+            "remove the blank lines, every line must hold one point",
+        ));
+    }
+    if !malformed.is_empty() {
+        issues.push(line_issue(
+            format!("{} not an `x, y` pair of numbers", lines(&malformed)),
+            "write each point as two numbers separated by a comma, such as `1.5, -2`",
+        ));
+    }
+    if !infinite.is_empty() {
+        issues.push(line_issue(
+            format!("infinite or NaN coordinates on {}", lines(&infinite)),
+            "fix or remove these points",
+        ));
+    }
+
+    let count = points.len();
+    let skipped = blank.len() + malformed.len() + infinite.len();
+    if mode == path::Mode::Lenient && count > 0 && skipped > count {
+        issues.push(Issue::error(
+            format!("only {} of {} lines hold points", count, count + skipped),
+            "check that this is a path file, with one `x, y` point per line",
+        ));
+        return (count, issues);
+    }
+    if count == 0 {
+        issues.push(Issue::error(
+            "no points".to_string(),
+            "write one `x, y` point per line",
+        ));
+        return (count, issues);
+    }
+    if count < MIN_POINTS {
+        issues.push(Issue::warning(
+            format!("only {} points", count),
+            format!(
+                "trace the shape with at least {} points for the drawing to follow it",
+                MIN_POINTS
+            ),
+        ));
+    }
+    if !count.is_power_of_two() && count >= SLOW_LENGTH {
+        issues.push(Issue::warning(
+            format!(
+                "{} points is not a power of two, so the slower transform is used",
+                count
+            ),
+            format!(
+                "resample to {} points for the fast Fourier transform",
+                count.next_power_of_two()
+            ),
+        ));
+    }
+
+    let distance =
+        |a: &complex::Complex, b: &complex::Complex| complex::Complex::minus(a, b).amplitude();
+    let repeated: Vec<usize> = (1..count)
+        .filter(|&index| distance(&points[index - 1], &points[index]) == 0.0)
+        .map(|index| numbers[index])
+        .collect();
+    if !repeated.is_empty() {
+        issues.push(Issue::warning(
+            format!(
+                "point repeated from the previous line on {}",
+                lines(&repeated)
+            ),
+            "remove consecutive duplicates, they slow the pen down without changing the shape"
+                .to_string(),
+        ));
+    }
+    if count > 2 {
+        let (first, last) = (points[0], points[count - 1]);
+        let mean_step = (1..count)
+            .map(|index| distance(&points[index - 1], &points[index]))
+            .fold(0.0, |sum, step| sum + step)
+            / (count - 1) as f64;
+        let gap = distance(&last, &first);
+        if gap == 0.0 {
+            issues.push(Issue::warning(
+                format!(
+                    "the last point, line {}, repeats the first one",
+                    numbers[count - 1]
+                ),
+                "remove the last point, paths are closed implicitly".to_string(),
+            ));
+        } else if gap > OPEN_GAP * mean_step {
+            issues.push(Issue::warning(
+                format!(
+                    "the path is open, its ends are {:.4} apart while points are {:.4} apart on average",
+                    gap, mean_step
+                ),
+                "trace a closed shape, the drawing jumps straight from the last point to the first"
+                    .to_string(),
+            ));
+        }
+    }
+    (count, issues)
+}
+
+/// Point in `line`, read as the path reader reads it so that both accept the same files.
+fn parse_point(line: &str) -> Option<complex::Complex> {
+    path::Path::parse(line).ok().map(|path| path.data[0])
+}
+
+/// `line 3` or `lines 3, 4, 8...` for the line numbers `numbers`.
+fn lines(numbers: &[usize]) -> String {
+    let listed: Vec<String> = numbers
+        .iter()
+        .take(LISTED_LINES)
+        .map(|n| n.to_string())
+        .collect();
+    let more = if numbers.len() > LISTED_LINES {
+        format!(" and {} more", numbers.len() - LISTED_LINES)
+    } else {
+        String::new()
+    };
+    let plural = if numbers.len() > 1 { "s" } else { "" };
+    format!("line{} {}{}", plural, listed.join(", "), more)
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    fn messages(text: &str) -> Vec<(Severity, String)> {
+        check(text, path::Mode::Strict)
+            .1
+            .into_iter()
+            .map(|issue| (issue.severity, issue.message))
+            .collect()
+    }
+
+    #[test]
+    fn check_valid_test() {
+        assert_eq!(
+            (4, Vec::new()),
+            check("0, 0\n1, 0\n1, 1\n0, 1\n", path::Mode::Strict)
+        );
+    }
+
+    #[test]
+    fn check_errors_test() {
+        assert_eq!(
+            vec![
+                (Severity::Error, "blank line 2".to_string()),
+                (
+                    Severity::Error,
+                    "lines 4, 5 not an `x, y` pair of numbers".to_string()
+                ),
+                (
+                    Severity::Error,
+                    "infinite or NaN coordinates on line 6".to_string()
+                ),
+            ],
+            messages("0, 0\n\n1, 0\n1\n1, a\nNaN, 1\n1, 1\n0, 1\n")
+        );
+        assert_eq!(
+            vec![(Severity::Error, "no points".to_string())],
+            messages("")
+        );
+    }
+
+    #[test]
+    fn check_lenient_test() {
+        let text = "x, y\n0, 0\n1, 0\n\n1, 1\nNaN, 2\n0, 1\n";
+        let (points, issues) = check(text, path::Mode::Lenient);
+        assert_eq!(4, points);
+        assert!(issues
+            .iter()
+            .all(|issue| issue.severity == Severity::Warning));
+        assert_eq!(3, issues.len());
+
+        let (_, issues) = check("a\nb\n0, 0\nc\n", path::Mode::Lenient);
+        assert_eq!(Severity::Error, issues.last().unwrap().severity);
+        assert_eq!(
+            "only 1 of 4 lines hold points",
+            issues.last().unwrap().message
+        );
+    }
+
+    #[test]
+    fn check_windows_text_test() {
+        let (points, issues) = check(
+            "\u{feff}0, 0\r\n1,\u{a0}0\r\n1, 1\r\n0, 1\r\n",
+            path::Mode::Strict,
+        );
+        assert_eq!(4, points);
+        assert!(issues.is_empty());
+    }
+
+    #[test]
+    fn check_warnings_test() {
+        let issues = messages("0, 0\n1, 0\n1, 0\n1, 1\n0, 1\n0, 0\n");
+        assert_eq!(
+            vec![
+                (
+                    Severity::Warning,
+                    "point repeated from the previous line on line 3".to_string()
+                ),
+                (
+                    Severity::Warning,
+                    "the last point, line 6, repeats the first one".to_string()
+                ),
+            ],
+            issues
+        );
+        let issues = messages("0, 0\n1, 0\n2, 0\n3, 0\n4, 0\n5, 0\n6, 0\n");
+        assert_eq!(1, issues.len());
+        assert!(issues[0].1.starts_with("the path is open"));
+
+        let circle: String = (0..300)
+            .map(|k| {
+                let angle = 2.0 * std::f64::consts::PI * f64::from(k) / 300.0;
+                format!("{}, {}\n", angle.cos(), angle.sin())
+            })
+            .collect();
+        let (count, issues) = check(&circle, path::Mode::Strict);
+        assert_eq!(300, count);
+        assert_eq!(
+            "resample to 512 points for the fast Fourier transform",
+            issues[0].suggestion
+        );
+    }
+}
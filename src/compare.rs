@@ -0,0 +1,287 @@
+//! Distances between the shapes of two closed paths, whatever their number of points, position and
+//! size.
+
+use crate::complex;
+use crate::epicycle;
+use crate::path;
+use crate::profile;
+
+/// Number of frequencies on each side of the constant term kept as Fourier descriptors.
+pub const DESCRIPTOR_TERMS: i64 = 32;
+
+/// How far apart the shapes of two paths are.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Comparison {
+    /// Distance between the Fourier descriptors, from 0 for the same shape to √2. Descriptors are
+    /// the amplitudes of the terms, which do not depend on the position, size or rotation of the
+    /// shape nor on where the path starts. Paths going around in opposite directions are compared
+    /// with one of them reversed.
+    pub descriptor_distance: f64,
+    /// Root mean square distance between matching points once both paths are centered, scaled to
+    /// a root mean square radius of 1 and started at the points bringing them closest.
+    pub rms_deviation: f64,
+    /// Score from 0 to 1, 1 for the same shape, derived from `descriptor_distance`.
+    pub similarity: f64,
+}
+
+/// Compares the shapes of `first` and `second`, both resampled to `n_points` evenly spaced points.
+/// An empty path has no shape, at an infinite deviation from any other path.
+pub fn compare(first: &path::Path, second: &path::Path, n_points: usize) -> Comparison {
+    let first = normalize(&scaled(first).resample(n_points));
+    let second = normalize(&scaled(second).resample(n_points));
+    let reversed = path::Path {
+        data: second.data.iter().rev().copied().collect(),
+    };
+    let descriptors_of_first = descriptors(&first);
+    let descriptor_distance = distance(&descriptors_of_first, &descriptors(&second))
+        .min(distance(&descriptors_of_first, &descriptors(&reversed)));
+    Comparison {
+        descriptor_distance,
+        rms_deviation: rms_deviation(&first, &second).min(rms_deviation(&first, &reversed)),
+        similarity: (1.0 - descriptor_distance / 2f64.sqrt()).clamp(0.0, 1.0),
+    }
+}
+
+/// Amplitudes of the terms of `path` from frequency -`DESCRIPTOR_TERMS` to `DESCRIPTOR_TERMS`,
+/// without the constant term, scaled to a norm of 1.
+pub fn descriptors(path: &path::Path) -> Vec<f64> {
+    let epicycle = epicycle::Epicycle::new(path);
+    let mut amplitudes = vec![0.0; 2 * DESCRIPTOR_TERMS as usize];
+    for (coefficient, frequency) in epicycle.terms() {
+        if *frequency == 0 || frequency.abs() > DESCRIPTOR_TERMS {
+            continue;
+        }
+        let index = if *frequency < 0 {
+            frequency + DESCRIPTOR_TERMS
+        } else {
+            frequency + DESCRIPTOR_TERMS - 1
+        };
+        amplitudes[index as usize] = coefficient.amplitude();
+    }
+    let norm = amplitudes.iter().map(|a| a * a).sum::<f64>().sqrt();
+    if norm > 0.0 {
+        amplitudes
+            .iter_mut()
+            .for_each(|amplitude| *amplitude /= norm);
+    }
+    amplitudes
+}
+
+/// `path` moved to be centered on the origin and scaled to a root mean square radius of 1, or only
+/// moved when all its points are the same. Coordinates are divided by the largest of them before
+/// being squared, so that tiny or huge paths are scaled without their squares underflowing or
+/// overflowing into zero or infinite radii, which would fill the path with NaN.
+fn normalize(path: &path::Path) -> path::Path {
+    let _stage = profile::stage("normalize");
+    let count = path.data.len().max(1) as f64;
+    let center = complex::Complex::new(
+        path.data.iter().map(|point| point.re / count).sum::<f64>(),
+        path.data.iter().map(|point| point.im / count).sum::<f64>(),
+    );
+    let centered: Vec<complex::Complex> = path
+        .data
+        .iter()
+        .map(|point| complex::Complex::minus(point, &center))
+        .collect();
+    let extent = centered
+        .iter()
+        .map(|point| point.re.abs().max(point.im.abs()))
+        .fold(0.0, f64::max);
+    if !(extent > 0.0 && extent.is_finite()) {
+        return path::Path { data: centered };
+    }
+    // Root mean square radius divided by `extent`, between 1 / √count and √2.
+    let radius = (centered
+        .iter()
+        .map(|point| {
+            let (x, y) = (point.re / extent, point.im / extent);
+            x * x + y * y
+        })
+        .sum::<f64>()
+        / count)
+        .sqrt();
+    path::Path {
+        data: centered
+            .iter()
+            .map(|point| {
+                complex::Complex::new(point.re / extent / radius, point.im / extent / radius)
+            })
+            .collect(),
+    }
+}
+
+/// `path` divided by its largest coordinate, bringing tiny or huge paths to a size where their
+/// lengths can be measured as they are resampled, or `path` itself when all its coordinates are 0
+/// or one is not finite.
+fn scaled(path: &path::Path) -> path::Path {
+    let extent = path
+        .data
+        .iter()
+        .map(|point| point.re.abs().max(point.im.abs()))
+        .fold(0.0, f64::max);
+    let extent = if extent > 0.0 && extent.is_finite() {
+        extent
+    } else {
+        1.0
+    };
+    path::Path {
+        data: path
+            .data
+            .iter()
+            .map(|point| complex::Complex::new(point.re / extent, point.im / extent))
+            .collect(),
+    }
+}
+
+fn distance(first: &[f64], second: &[f64]) -> f64 {
+    first
+        .iter()
+        .zip(second)
+        .map(|(a, b)| (a - b) * (a - b))
+        .sum::<f64>()
+        .sqrt()
+}
+
+/// Smallest root mean square distance between the points of `first` and `second`, the same
+/// length, over all the points `second` can start from. Infinite when only one of them is empty.
+fn rms_deviation(first: &path::Path, second: &path::Path) -> f64 {
+    let count = first.data.len();
+    if count != second.data.len() {
+        return f64::INFINITY;
+    }
+    if count == 0 {
+        return 0.0;
+    }
+    (0..count)
+        .map(|shift| {
+            let sum: f64 = first
+                .data
+                .iter()
+                .enumerate()
+                .map(|(index, point)| {
+                    complex::Complex::minus(point, &second.data[(index + shift) % count])
+                        .amplitude()
+                        .powi(2)
+                })
+                .sum();
+            (sum / count as f64).sqrt()
+        })
+        .fold(f64::INFINITY, f64::min)
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::shape::Shape;
+
+    #[test]
+    fn compare_test() {
+        let circle = Shape::Circle.path(100);
+        // The same circle, larger, elsewhere and started half a turn later.
+        let moved = path::Path {
+            data: (0..100)
+                .map(|k| {
+                    let point = circle.data[(k + 50) % 100];
+                    complex::Complex::new(3.0 * point.re + 10.0, 3.0 * point.im - 4.0)
+                })
+                .collect(),
+        };
+        let comparison = compare(&circle, &moved, 100);
+        assert!(comparison.descriptor_distance < 1e-9);
+        assert!(comparison.rms_deviation < 1e-9);
+        assert!((comparison.similarity - 1.0).abs() < 1e-9);
+        let clockwise = path::Path {
+            data: circle.data.iter().rev().copied().collect(),
+        };
+        assert!(compare(&circle, &clockwise, 100).descriptor_distance < 1e-9);
+
+        let square = Shape::Square.path(100);
+        let star = Shape::Star.path(100);
+        let to_square = compare(&circle, &square, 64);
+        let to_star = compare(&circle, &star, 64);
+        assert!(to_square.similarity < 1.0);
+        assert!(to_star.similarity < to_square.similarity);
+        assert!(to_star.rms_deviation > to_square.rms_deviation);
+
+        let empty = path::Path { data: Vec::new() };
+        assert_eq!(f64::INFINITY, compare(&empty, &circle, 16).rms_deviation);
+        assert_eq!(f64::INFINITY, compare(&circle, &empty, 16).rms_deviation);
+        assert_eq!(0.0, compare(&empty, &empty, 16).rms_deviation);
+        let dot = path::Path {
+            data: vec![complex::Complex::new(1.0, 1.0)],
+        };
+        assert_eq!(0.0, compare(&dot, &dot, 16).descriptor_distance);
+    }
+
+    #[test]
+    fn scale_test() {
+        let diamond = |scale: f64| path::Path {
+            data: vec![
+                complex::Complex::new(scale, 0.0),
+                complex::Complex::new(0.0, scale),
+                complex::Complex::new(-scale, 0.0),
+                complex::Complex::new(0.0, -scale),
+            ],
+        };
+        for scale in &[1e300, 1e-300, 1e-320] {
+            let comparison = compare(&diamond(1.0), &diamond(*scale), 64);
+            assert!(comparison.similarity > 0.999, "{}", scale);
+            assert!(comparison.rms_deviation < 1e-6, "{}", scale);
+        }
+        assert!((diamond(1e300).perimeter() / 4e300 - 2f64.sqrt()).abs() < 1e-12);
+        assert!(diamond(1e-300).perimeter() > 0.0);
+    }
+
+    #[test]
+    fn normalize_test() {
+        let star = Shape::Star.path(50);
+        let expected = normalize(&star);
+        for factor in &[1e-310, 1e-160, 1e160, 1e300] {
+            let scaled = path::Path {
+                data: star
+                    .data
+                    .iter()
+                    .map(|point| complex::Complex::new(point.re * factor, point.im * factor))
+                    .collect(),
+            };
+            for (point, expected) in normalize(&scaled).data.iter().zip(&expected.data) {
+                assert!(
+                    complex::Complex::minus(point, expected).amplitude() < 1e-6,
+                    "{}",
+                    factor
+                );
+            }
+        }
+        // A single repeated point, or a vertical segment, stays finite.
+        let dot = path::Path {
+            data: vec![complex::Complex::new(3.0, -2.0); 4],
+        };
+        assert!(normalize(&dot)
+            .data
+            .iter()
+            .all(|point| point.amplitude() == 0.0));
+        let segment = path::Path {
+            data: vec![
+                complex::Complex::new(5.0, 1.0),
+                complex::Complex::new(5.0, 3.0),
+            ],
+        };
+        let normalized = normalize(&segment);
+        assert_eq!(complex::Complex::new(0.0, -1.0), normalized.data[0]);
+        assert_eq!(complex::Complex::new(0.0, 1.0), normalized.data[1]);
+        assert!(normalize(&path::Path { data: Vec::new() }).data.is_empty());
+    }
+
+    #[test]
+    fn descriptors_test() {
+        let circle = descriptors(&Shape::Circle.path(64));
+        assert_eq!(2 * DESCRIPTOR_TERMS as usize, circle.len());
+        // A counterclockwise circle is the frequency 1 alone.
+        assert!((circle[DESCRIPTOR_TERMS as usize] - 1.0).abs() < 1e-9);
+        let dot = vec![complex::Complex::new(1.0, 1.0); 8];
+        assert!(descriptors(&path::Path { data: dot })
+            .iter()
+            .all(|amplitude| *amplitude == 0.0));
+    }
+}
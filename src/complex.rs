@@ -1,4 +1,4 @@
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy)]
 pub struct Complex {
     pub re: f64,
     pub im: f64,
@@ -14,12 +14,12 @@ impl std::cmp::PartialEq for Complex {
 }
 
 impl Complex {
-    pub fn new(re: f64, im: f64) -> Complex {
+    pub const fn new(re: f64, im: f64) -> Complex {
         Complex { re, im }
     }
 
     pub fn phase(self) -> angular::Angle {
-        angular::atan(self.im / self.re)
+        angular::atan2(self.im, self.re)
     }
 
     pub fn amplitude(self) -> f64 {
@@ -52,6 +52,12 @@ mod tests {
         assert_eq!(angular::Angle::<f64>::quarter(), c.phase());
     }
 
+    #[test]
+    fn phase_negative_real_test() {
+        let c = Complex::new(-1.0, 0.0);
+        assert_eq!(angular::Angle::<f64>::half(), c.phase());
+    }
+
     #[test]
     fn amplitude_test() {
         let c = Complex::new(3.0, 4.0);
@@ -0,0 +1,140 @@
+//! Pen and circles of a reconstruction at any frame, for game engines driving epicycle drawings
+//! from their own update loop, such as a Bevy system or a Godot node.
+//!
+//! Frames are counted at a fixed rate and loop over the turns of the drawing. States are plain
+//! `#[repr(C)]` values of `f32`, written into slices owned by the caller so that no frame
+//! allocates and they can be copied as they are into engine buffers.
+
+use crate::complex;
+use crate::epicycle;
+use std::f64::consts::PI;
+
+/// Position in the plane of the path.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Point {
+    pub x: f32,
+    pub y: f32,
+}
+
+impl From<complex::Complex> for Point {
+    fn from(point: complex::Complex) -> Point {
+        Point {
+            x: point.re as f32,
+            y: point.im as f32,
+        }
+    }
+}
+
+/// Circle of one term, whose arm goes from `center` to `end`, the center of the next circle.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct CircleState {
+    pub center: Point,
+    pub radius: f32,
+    pub end: Point,
+}
+
+/// States of the reconstruction using `precision` terms at the frames of an engine running at
+/// `fps` frames per second, the pen tracing the whole path every `period` seconds. Consecutive
+/// frames are the cheapest to move to.
+#[derive(Debug, Clone)]
+pub struct Sampler {
+    phasors: epicycle::Phasors,
+    frames_per_turn: u64,
+    count: usize,
+    circles: Vec<epicycle::Circle>,
+}
+
+impl Sampler {
+    pub fn new(epicycle: &epicycle::Epicycle, precision: usize, fps: u32, period: f64) -> Sampler {
+        let frames_per_turn = ((f64::from(fps) * period).round() as u64).max(1);
+        let count = precision.min(epicycle.len());
+        Sampler {
+            phasors: epicycle::Phasors::new(epicycle, precision, 2.0 * PI / frames_per_turn as f64),
+            frames_per_turn,
+            count,
+            circles: Vec::with_capacity(count),
+        }
+    }
+
+    /// Number of frames taken to trace the whole path once.
+    pub fn frames_per_turn(&self) -> u64 {
+        self.frames_per_turn
+    }
+
+    /// Time of the pen at `frame`, between 0 and 2π.
+    pub fn time(&self, frame: u64) -> f64 {
+        2.0 * PI * (frame % self.frames_per_turn) as f64 / self.frames_per_turn as f64
+    }
+
+    /// Number of circles written by `circles`, one per term used.
+    pub fn circle_count(&self) -> usize {
+        self.count
+    }
+
+    /// Position of the pen at `frame`.
+    pub fn pen(&mut self, frame: u64) -> Point {
+        self.move_to(frame);
+        self.circles
+            .last()
+            .map_or_else(Point::default, |circle| Point::from(circle.end))
+    }
+
+    /// Writes the circles at `frame`, the largest first, into `circles` until it is full, and
+    /// returns the position of the pen.
+    pub fn circles(&mut self, frame: u64, circles: &mut [CircleState]) -> Point {
+        let pen = self.pen(frame);
+        for (state, circle) in circles.iter_mut().zip(&self.circles) {
+            *state = CircleState {
+                center: Point::from(circle.center),
+                radius: circle.radius as f32,
+                end: Point::from(circle.end),
+            };
+        }
+        pen
+    }
+
+    fn move_to(&mut self, frame: u64) {
+        self.phasors
+            .move_to((frame % self.frames_per_turn) as usize);
+        self.phasors.circles_into(&mut self.circles);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::path;
+
+    #[test]
+    fn sampler_test() {
+        let epicycle = epicycle::Epicycle::new(&path::Path {
+            data: vec![
+                complex::Complex::new(0.0, 0.0),
+                complex::Complex::new(1.0, 0.0),
+                complex::Complex::new(1.0, 1.0),
+                complex::Complex::new(0.0, 1.0),
+            ],
+        });
+        let mut sampler = Sampler::new(&epicycle, 3, 60, 2.0);
+        assert_eq!(120, sampler.frames_per_turn());
+        assert_eq!(3, sampler.circle_count());
+
+        for frame in (0..360).step_by(7) {
+            let expected = epicycle.get_coordinate_for(3, sampler.time(frame));
+            let pen = sampler.pen(frame);
+            assert!((pen.x - expected.re as f32).abs() < 1e-5, "{}", frame);
+            assert!((pen.y - expected.im as f32).abs() < 1e-5, "{}", frame);
+        }
+
+        let mut circles = [CircleState::default(); 2];
+        let pen = sampler.circles(150, &mut circles);
+        let expected = epicycle.circles_at(3, sampler.time(150));
+        assert_eq!(pen, sampler.pen(30));
+        assert_eq!(Point::from(expected[0].end), circles[0].end);
+        assert_eq!(circles[0].end, circles[1].center);
+        assert_eq!(expected[1].radius as f32, circles[1].radius);
+    }
+}
@@ -0,0 +1,1285 @@
+use crate::complex;
+use crate::fft;
+use crate::path;
+use crate::profile;
+use std::cmp::Ordering;
+use std::collections::BTreeMap;
+use std::f64::consts::PI;
+use std::sync::Arc;
+use std::thread;
+
+/// Number of terms times samples from which `sample` splits the samples between threads, each
+/// computing at least that much.
+const PARALLEL_WORK: usize = 1 << 20;
+
+/// Smallest number of evenly spaced intervals `sample_adaptive` starts from.
+const INITIAL_INTERVALS: usize = 64;
+
+/// Largest number of times `sample_adaptive` halves one of its initial intervals.
+const MAX_SUBDIVISIONS: u32 = 12;
+
+/// Number of points moved after which `Editable` transforms its path again, rather than letting
+/// rounding errors add up in its transform.
+const RESYNC_EDITS: usize = 256;
+
+/// Number of moves after which `Phasors` computes its terms again from their phases.
+const RESYNC_MOVES: usize = 64;
+
+/// Largest number of distinct moves `Phasors` keeps the rotations of.
+const MAX_ROTATIONS: usize = 16;
+
+/// Share of the energy of all the terms below which the energy of the shape is only rounding
+/// noise, as left by the transform of identical points.
+const SHAPE_NOISE: f64 = 1e-20;
+
+/// One rotating term of a reconstruction at a given time: a circle whose arm goes from `center`
+/// to `end`. The end of a circle is the center of the next one.
+#[derive(Debug, Clone, Copy)]
+pub struct Circle {
+    pub center: complex::Complex,
+    pub radius: f64,
+    pub end: complex::Complex,
+}
+
+/// Fourier decomposition of a closed path.
+///
+/// Each term is a coefficient paired with its signed frequency. Terms are sorted by decreasing
+/// amplitude so that a reconstruction using the first `precision` terms uses the most
+/// contributing components first.
+///
+/// An `Epicycle` is immutable once built and its terms are shared behind an `Arc`: cloning is
+/// cheap and every clone can be sampled concurrently from different threads.
+#[derive(Debug, Clone)]
+pub struct Epicycle {
+    terms: Arc<[(complex::Complex, i64)]>,
+    polar: Arc<Polar>,
+}
+
+/// The terms in polar form, computed once rather than at every sample, with one array per field
+/// so that sampling walks each of them in order.
+#[derive(Debug)]
+struct Polar {
+    amplitudes: Vec<f64>,
+    frequencies: Vec<f64>,
+    /// In radians.
+    phases: Vec<f64>,
+}
+
+impl Polar {
+    /// Amplitude, frequency and phase of the `precision` largest terms.
+    fn largest(&self, precision: usize) -> impl Iterator<Item = (f64, f64, f64)> + '_ {
+        let count = precision.min(self.amplitudes.len());
+        self.amplitudes[..count]
+            .iter()
+            .zip(&self.frequencies[..count])
+            .zip(&self.phases[..count])
+            .map(|((amplitude, frequency), phase)| (*amplitude, *frequency, *phase))
+    }
+}
+
+impl Epicycle {
+    /// Decomposition of `path`, one term per point. A single point is the constant term alone,
+    /// drawn as a dot, and two points the circle whose diameter joins them. An empty path has no
+    /// terms, its reconstruction staying at the origin.
+    pub fn new(path: &path::Path) -> Epicycle {
+        Epicycle::with_scratch(path, &mut fft::Scratch::new())
+    }
+
+    /// Decomposition of `path` transformed in `scratch`, which can be reused for the next path.
+    pub fn with_scratch(path: &path::Path, scratch: &mut fft::Scratch) -> Epicycle {
+        let transformed = {
+            let _stage = profile::stage("fft");
+            scratch.transform(&path.data)
+        };
+        Epicycle::from_transform(transformed)
+    }
+
+    /// Same as `new`, failing when a point of `path` or a value of its transform is not finite,
+    /// which would draw nothing.
+    pub fn try_new(path: &path::Path) -> Result<Epicycle, fft::NonFinite> {
+        Epicycle::try_with_scratch(path, &mut fft::Scratch::new())
+    }
+
+    /// Same as `with_scratch`, failing like `try_new`.
+    pub fn try_with_scratch(
+        path: &path::Path,
+        scratch: &mut fft::Scratch,
+    ) -> Result<Epicycle, fft::NonFinite> {
+        let transformed = {
+            let _stage = profile::stage("fft");
+            scratch.try_transform(&path.data)?
+        };
+        Ok(Epicycle::from_transform(transformed))
+    }
+
+    /// Decomposition of the path whose discrete Fourier transform is `transformed`.
+    fn from_transform(transformed: &[complex::Complex]) -> Epicycle {
+        let length = transformed.len();
+        let terms = {
+            let _stage = profile::stage("normalize");
+            transformed
+                .iter()
+                .enumerate()
+                .map(|(index, value)| {
+                    let coefficient =
+                        complex::Complex::new(value.re / length as f64, value.im / length as f64);
+                    (coefficient, frequency(index, length))
+                })
+                .collect()
+        };
+        Epicycle::from_terms(terms)
+    }
+
+    /// Decomposition made of `terms`, coefficients and their frequencies, in any order.
+    pub fn from_terms(mut terms: Vec<(complex::Complex, i64)>) -> Epicycle {
+        let _stage = profile::stage("sort");
+        terms.sort_by(|a, b| {
+            b.0.amplitude()
+                .partial_cmp(&a.0.amplitude())
+                .unwrap_or(Ordering::Equal)
+        });
+        let polar = Polar {
+            amplitudes: terms
+                .iter()
+                .map(|(coefficient, _)| coefficient.amplitude())
+                .collect(),
+            frequencies: terms
+                .iter()
+                .map(|(_, frequency)| *frequency as f64)
+                .collect(),
+            phases: terms
+                .iter()
+                .map(|(coefficient, _)| coefficient.phase().in_radians())
+                .collect(),
+        };
+        Epicycle {
+            terms: terms.into(),
+            polar: Arc::new(polar),
+        }
+    }
+
+    /// Linear interpolation between this epicycle and `other`, `alpha` being the weight of
+    /// `other` between 0 and 1. Terms are matched by frequency, a frequency missing from one side
+    /// counting as a zero coefficient, so paths with different numbers of points can be blended.
+    pub fn blend(&self, other: &Epicycle, alpha: f64) -> Epicycle {
+        let alpha = alpha.clamp(0.0, 1.0);
+        let mut coefficients: BTreeMap<i64, (complex::Complex, complex::Complex)> = BTreeMap::new();
+        let zero = complex::Complex::new(0.0, 0.0);
+        for (coefficient, frequency) in self.terms.iter() {
+            coefficients.entry(*frequency).or_insert((zero, zero)).0 = *coefficient;
+        }
+        for (coefficient, frequency) in other.terms.iter() {
+            coefficients.entry(*frequency).or_insert((zero, zero)).1 = *coefficient;
+        }
+        let terms = coefficients
+            .into_iter()
+            .map(|(frequency, (from, to))| {
+                let coefficient = complex::Complex::new(
+                    from.re * (1.0 - alpha) + to.re * alpha,
+                    from.im * (1.0 - alpha) + to.im * alpha,
+                );
+                (coefficient, frequency)
+            })
+            .collect();
+        Epicycle::from_terms(terms)
+    }
+
+    /// Epicycle with only the terms whose frequency is kept by `keep`, taken from the terms
+    /// already computed: neither the transform nor the sort by amplitude is run again, so
+    /// filters can be changed interactively. Precisions stay a parameter of the drawing methods
+    /// for the same reason.
+    pub fn filtered<F: FnMut(i64) -> bool>(&self, mut keep: F) -> Epicycle {
+        let kept: Vec<usize> = (0..self.terms.len())
+            .filter(|index| keep(self.terms[*index].1))
+            .collect();
+        let select = |values: &[f64]| kept.iter().map(|index| values[*index]).collect();
+        let polar = Polar {
+            amplitudes: select(&self.polar.amplitudes),
+            frequencies: select(&self.polar.frequencies),
+            phases: select(&self.polar.phases),
+        };
+        Epicycle {
+            terms: kept.iter().map(|index| self.terms[*index]).collect(),
+            polar: Arc::new(polar),
+        }
+    }
+
+    /// Epicycle with only the terms turning between `lowest` and `highest` times per turn, in
+    /// either direction, both included.
+    pub fn band(&self, lowest: u64, highest: u64) -> Epicycle {
+        self.filtered(|frequency| (lowest..=highest).contains(&frequency.unsigned_abs()))
+    }
+
+    /// Read-only view of the terms, sorted by decreasing amplitude.
+    pub fn terms(&self) -> &[(complex::Complex, i64)] {
+        &self.terms
+    }
+
+    pub fn len(&self) -> usize {
+        self.terms.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.terms.is_empty()
+    }
+
+    /// Position of the pen at time `t` (one full turn is `2π`) using the `precision` largest terms.
+    pub fn get_coordinate_for(&self, precision: usize, t: f64) -> complex::Complex {
+        let mut x = 0.0;
+        let mut y = 0.0;
+        for (amplitude, frequency, phase) in self.polar.largest(precision) {
+            let angle = frequency * t + phase;
+            x += amplitude * angle.cos();
+            y += amplitude * angle.sin();
+        }
+        complex::Complex::new(x, y)
+    }
+
+    /// Chain of circles formed by the `precision` largest terms at time `t`, largest first. The
+    /// end of the last circle is the pen position.
+    pub fn circles_at(&self, precision: usize, t: f64) -> Vec<Circle> {
+        let mut circles = Vec::with_capacity(precision.min(self.len()));
+        self.circles_into(precision, t, &mut circles);
+        circles
+    }
+
+    /// Replaces the content of `circles` with the chain of circles given by `circles_at`, reusing
+    /// its allocation from one time to the next.
+    pub fn circles_into(&self, precision: usize, t: f64, circles: &mut Vec<Circle>) {
+        circles.clear();
+        let mut center = complex::Complex::new(0.0, 0.0);
+        circles.extend(
+            self.polar
+                .largest(precision)
+                .map(|(radius, frequency, phase)| {
+                    let angle = frequency * t + phase;
+                    let end = complex::Complex::new(
+                        center.re + radius * angle.cos(),
+                        center.im + radius * angle.sin(),
+                    );
+                    let circle = Circle {
+                        center,
+                        radius,
+                        end,
+                    };
+                    center = end;
+                    circle
+                }),
+        );
+    }
+
+    /// Root mean square distance between the points of `path` and the reconstruction using
+    /// `precision` terms at the matching times.
+    pub fn rms_error(&self, path: &path::Path, precision: usize) -> f64 {
+        if path.data.is_empty() {
+            return 0.0;
+        }
+        let reconstruction = self.sample(precision, path.data.len());
+        let sum: f64 = path
+            .data
+            .iter()
+            .zip(reconstruction.iter())
+            .map(|(original, estimated)| {
+                let difference = complex::Complex::minus(original, estimated);
+                difference.re * difference.re + difference.im * difference.im
+            })
+            .sum();
+        (sum / path.data.len() as f64).sqrt()
+    }
+
+    /// Root mean square error of the reconstruction using `precision` terms over the points of the
+    /// path this epicycle was computed from, without needing the path: by Parseval's theorem it
+    /// is the norm of the terms left out.
+    pub fn truncation_error(&self, precision: usize) -> f64 {
+        self.terms
+            .iter()
+            .skip(precision)
+            .map(|(coefficient, _)| energy(coefficient))
+            .fold(0.0, |sum, square| sum + square)
+            .sqrt()
+    }
+
+    /// Energy of the shape, that is of all the terms but the constant one, which only places the
+    /// drawing. Zero when it is only rounding noise next to the energy of the constant term, as
+    /// for a single point or identical points.
+    pub fn shape_energy(&self) -> f64 {
+        let all: f64 = self
+            .terms
+            .iter()
+            .map(|(coefficient, _)| energy(coefficient))
+            .sum();
+        let shape: f64 = self.terms.iter().map(term_energy).sum();
+        if shape > SHAPE_NOISE * all {
+            shape
+        } else {
+            0.0
+        }
+    }
+
+    /// Smallest precision whose terms hold at least `fraction` of the energy of the shape, that is
+    /// of all the terms but the constant one, which only places the drawing. The constant term
+    /// is counted in the precision when it ranks among the terms used. Zero when there is no
+    /// shape energy, see `shape_energy`.
+    pub fn precision_for(&self, fraction: f64) -> usize {
+        let total = self.shape_energy();
+        if total == 0.0 {
+            return 0;
+        }
+        let mut cumulated = 0.0;
+        for (index, term) in self.terms.iter().enumerate() {
+            if cumulated >= fraction * total {
+                return index;
+            }
+            cumulated += term_energy(term);
+        }
+        self.terms.len()
+    }
+
+    /// SVG path data (the `d` attribute) of the reconstruction sampled at `n_points` evenly spaced
+    /// times. Coordinates are written as-is, keep in mind that the SVG y axis points down.
+    pub fn to_svg_path(&self, precision: usize, n_points: usize) -> String {
+        let mut result = String::new();
+        for (index, point) in self.sample(precision, n_points).iter().enumerate() {
+            let command = if index == 0 { "M" } else { " L" };
+            result.push_str(&format!("{}{:.3} {:.3}", command, point.re, point.im));
+        }
+        if !result.is_empty() {
+            result.push_str(" Z");
+        }
+        result
+    }
+
+    /// Standalone SVG document containing the reconstruction, with a view box fitted to the curve.
+    pub fn to_svg_document(&self, precision: usize, n_points: usize) -> String {
+        let points = self.sample(precision, n_points);
+        let (mut min_x, mut min_y) = (f64::INFINITY, f64::INFINITY);
+        let (mut max_x, mut max_y) = (f64::NEG_INFINITY, f64::NEG_INFINITY);
+        for point in &points {
+            min_x = min_x.min(point.re);
+            min_y = min_y.min(point.im);
+            max_x = max_x.max(point.re);
+            max_y = max_y.max(point.im);
+        }
+        if points.is_empty() {
+            min_x = 0.0;
+            min_y = 0.0;
+            max_x = 0.0;
+            max_y = 0.0;
+        }
+        let margin = 0.05 * (max_x - min_x).max(max_y - min_y).max(1.0);
+
+        format!(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" viewBox=\"{:.3} {:.3} {:.3} {:.3}\">\n\
+             <path d=\"{}\" fill=\"none\" stroke=\"black\" vector-effect=\"non-scaling-stroke\"/>\n\
+             </svg>\n",
+            min_x - margin,
+            min_y - margin,
+            max_x - min_x + 2.0 * margin,
+            max_y - min_y + 2.0 * margin,
+            self.to_svg_path(precision, n_points)
+        )
+    }
+
+    /// JSON array of the terms, the largest first, as the `{re, im, freq, amp, phase}` objects
+    /// computed by the Coding Train p5.js epicycle sketches, so that they can draw it as is.
+    pub fn export_p5_json(&self) -> String {
+        p5_json(self.terms())
+    }
+
+    /// Pen positions at `n_points` evenly spaced times over one full turn. Many samples of many
+    /// terms are computed on several threads, each taking a contiguous range of times.
+    pub fn sample(&self, precision: usize, n_points: usize) -> Vec<complex::Complex> {
+        let mut points = Vec::new();
+        self.sample_into(precision, n_points, &mut points);
+        points
+    }
+
+    /// Replaces the content of `points` with the samples given by `sample`, reusing its
+    /// allocation.
+    pub fn sample_into(
+        &self,
+        precision: usize,
+        n_points: usize,
+        points: &mut Vec<complex::Complex>,
+    ) {
+        let _stage = profile::stage("sample");
+        let work = precision.min(self.len()).saturating_mul(n_points);
+        let threads = thread::available_parallelism()
+            .map_or(1, |threads| threads.get())
+            .min(work / PARALLEL_WORK)
+            .max(1);
+        self.sample_on(threads, precision, n_points, points);
+    }
+
+    /// Samples given by `sample` computed on `threads` threads.
+    fn sample_on(
+        &self,
+        threads: usize,
+        precision: usize,
+        n_points: usize,
+        points: &mut Vec<complex::Complex>,
+    ) {
+        points.clear();
+        if threads <= 1 {
+            points.extend(self.samples(precision, n_points));
+            return;
+        }
+        points.resize(n_points, complex::Complex::new(0.0, 0.0));
+        let chunk_size = n_points.div_ceil(threads).max(1);
+        thread::scope(|scope| {
+            for (chunk_index, chunk) in points.chunks_mut(chunk_size).enumerate() {
+                scope.spawn(move || {
+                    let first = chunk_index * chunk_size;
+                    for (offset, point) in chunk.iter_mut().enumerate() {
+                        *point = self.sample_at(precision, first + offset, n_points);
+                    }
+                });
+            }
+        });
+    }
+
+    /// The samples given by `sample`, each computed when the iterator reaches it.
+    pub fn samples(
+        &self,
+        precision: usize,
+        n_points: usize,
+    ) -> impl ExactSizeIterator<Item = complex::Complex> + '_ {
+        (0..n_points).map(move |index| self.sample_at(precision, index, n_points))
+    }
+
+    /// Sample number `index` of `n_points` evenly spaced ones.
+    fn sample_at(&self, precision: usize, index: usize, n_points: usize) -> complex::Complex {
+        let t = 2.0 * PI * index as f64 / n_points as f64;
+        self.get_coordinate_for(precision, t)
+    }
+
+    /// Samples of the reconstruction over one full turn, denser where the curve bends: intervals
+    /// are halved until the curve at their middle is within `tolerance` of the chord joining
+    /// their ends. The first point is at time 0 and the last one comes just before the end of
+    /// the turn.
+    ///
+    /// The initial intervals are short enough for the highest frequency used to complete at
+    /// most a quarter of a turn over each of them, so that no loop is skipped.
+    pub fn sample_adaptive(&self, precision: usize, tolerance: f64) -> Vec<complex::Complex> {
+        self.sample_adaptive_within(precision, tolerance, usize::MAX)
+    }
+
+    /// Same as `sample_adaptive` with at most `max_points` points, so that dense curves fit in a
+    /// memory budget. Once the points added where the curve bends reach the limit, the following
+    /// intervals are no longer halved. When the initial intervals alone are too many, the
+    /// reconstruction is sampled at `max_points` evenly spaced times instead.
+    pub fn sample_adaptive_within(
+        &self,
+        precision: usize,
+        tolerance: f64,
+        max_points: usize,
+    ) -> Vec<complex::Complex> {
+        let highest = self
+            .terms
+            .iter()
+            .take(precision)
+            .map(|(_, frequency)| frequency.unsigned_abs() as usize)
+            .max()
+            .unwrap_or(0);
+        let intervals = INITIAL_INTERVALS.max(4 * highest);
+        if intervals > max_points {
+            return self.sample(precision, max_points);
+        }
+        let mut refinement = Refinement {
+            epicycle: self,
+            precision,
+            tolerance,
+            spare: max_points - intervals,
+            points: Vec::with_capacity(intervals),
+        };
+        // The ends of the initial intervals are sampled at once, on several threads when they are
+        // many, and only the refinement runs in order.
+        let mut ends = self.sample(precision, intervals);
+        ends.push(self.get_coordinate_for(precision, 2.0 * PI));
+        let _stage = profile::stage("sample");
+        for index in 0..intervals {
+            let start = (2.0 * PI * index as f64 / intervals as f64, ends[index]);
+            let t = 2.0 * PI * (index + 1) as f64 / intervals as f64;
+            refinement.points.push(start.1);
+            refinement.refine(start, (t, ends[index + 1]), 0);
+        }
+        refinement.points
+    }
+}
+
+/// Adaptive sampling of the reconstruction of `epicycle` using `precision` terms.
+struct Refinement<'a> {
+    epicycle: &'a Epicycle,
+    precision: usize,
+    tolerance: f64,
+    /// Number of points that can still be added between the initial ones.
+    spare: usize,
+    points: Vec<complex::Complex>,
+}
+
+impl Refinement<'_> {
+    /// Pushes the points needed between `start` and `end`, both excluded, to follow the curve
+    /// within `tolerance`, as long as there are spare points.
+    fn refine(&mut self, start: (f64, complex::Complex), end: (f64, complex::Complex), depth: u32) {
+        if self.spare == 0 {
+            return;
+        }
+        let t = (start.0 + end.0) / 2.0;
+        let middle = (t, self.epicycle.get_coordinate_for(self.precision, t));
+        if depth >= MAX_SUBDIVISIONS
+            || distance_to_chord(middle.1, start.1, end.1) <= self.tolerance
+        {
+            return;
+        }
+        self.refine(start, middle, depth + 1);
+        if self.spare == 0 {
+            return;
+        }
+        self.spare -= 1;
+        self.points.push(middle.1);
+        self.refine(middle, end, depth + 1);
+    }
+}
+
+/// Terms of a reconstruction at times which are whole numbers of a fixed step, turned from one
+/// time to the next by multiplying each of them with its rotation over the steps moved instead of
+/// evaluating a cosine and a sine per term. Rotations are kept for each number of steps moved,
+/// and the terms are computed again from their phases every `RESYNC_MOVES` moves to bound the
+/// drift of the rounding errors, or when moving backwards.
+#[derive(Debug, Clone)]
+pub struct Phasors {
+    polar: Arc<Polar>,
+    count: usize,
+    step: f64,
+    /// Time in steps.
+    position: usize,
+    terms: Vec<complex::Complex>,
+    /// Rotation of each term for a number of steps.
+    rotations: Vec<(usize, Vec<complex::Complex>)>,
+    moves: usize,
+}
+
+impl Phasors {
+    /// The `precision` largest terms of `epicycle` at time 0, moving by multiples of `step`.
+    pub fn new(epicycle: &Epicycle, precision: usize, step: f64) -> Phasors {
+        let mut phasors = Phasors {
+            polar: Arc::clone(&epicycle.polar),
+            count: precision.min(epicycle.len()),
+            step,
+            position: 0,
+            terms: Vec::new(),
+            rotations: Vec::new(),
+            moves: 0,
+        };
+        phasors.synchronize();
+        phasors
+    }
+
+    /// Time of the terms, `position` steps.
+    pub fn time(&self) -> f64 {
+        self.position as f64 * self.step
+    }
+
+    /// Turns the terms to the time of `position` steps.
+    pub fn move_to(&mut self, position: usize) {
+        if position == self.position {
+            return;
+        }
+        let backwards = position < self.position;
+        let steps = position.wrapping_sub(self.position);
+        self.position = position;
+        self.moves += 1;
+        if backwards || self.moves >= RESYNC_MOVES {
+            self.synchronize();
+            return;
+        }
+        let known = self.rotations.iter().position(|(moved, _)| *moved == steps);
+        let index = match known {
+            Some(index) => index,
+            None if self.rotations.len() < MAX_ROTATIONS => {
+                let angle = steps as f64 * self.step;
+                let rotation = self
+                    .polar
+                    .largest(self.count)
+                    .map(|(_, frequency, _)| {
+                        complex::Complex::new((frequency * angle).cos(), (frequency * angle).sin())
+                    })
+                    .collect();
+                self.rotations.push((steps, rotation));
+                self.rotations.len() - 1
+            }
+            None => {
+                self.synchronize();
+                return;
+            }
+        };
+        for (term, rotation) in self.terms.iter_mut().zip(&self.rotations[index].1) {
+            *term = complex::Complex::multiply(term, rotation);
+        }
+    }
+
+    /// Chain of circles formed by the terms, as `Epicycle::circles_into` at the same time.
+    pub fn circles_into(&self, circles: &mut Vec<Circle>) {
+        circles.clear();
+        let mut center = complex::Complex::new(0.0, 0.0);
+        circles.extend(
+            self.terms
+                .iter()
+                .zip(&self.polar.amplitudes)
+                .map(|(term, radius)| {
+                    let end = complex::Complex::add(&center, term);
+                    let circle = Circle {
+                        center,
+                        radius: *radius,
+                        end,
+                    };
+                    center = end;
+                    circle
+                }),
+        );
+    }
+
+    fn synchronize(&mut self) {
+        let t = self.time();
+        self.terms.clear();
+        self.terms.extend(
+            self.polar
+                .largest(self.count)
+                .map(|(amplitude, frequency, phase)| {
+                    let angle = frequency * t + phase;
+                    complex::Complex::new(amplitude * angle.cos(), amplitude * angle.sin())
+                }),
+        );
+        self.moves = 0;
+    }
+}
+
+/// Path being edited, typically while it is drawn, with its transform kept up to date so that
+/// its decomposition can be previewed after every edit.
+///
+/// Moving a point updates the transform in one pass over the frequencies, without transforming
+/// the whole path again, which keeps previews of long paths live: most lengths are not powers of
+/// two and would take a quadratic `dft`. Adding a point changes the length of the path, and with
+/// it every frequency, so the path is transformed again.
+#[derive(Debug, Clone)]
+pub struct Editable {
+    points: Vec<complex::Complex>,
+    /// Discrete Fourier transform of `points`.
+    transform: Vec<complex::Complex>,
+    /// `e^(-2πi j/N)` for every `j` below the length `N` of the path.
+    roots: Vec<complex::Complex>,
+    scratch: fft::Scratch,
+    /// Points moved since the path was last transformed.
+    edits: usize,
+}
+
+impl Editable {
+    pub fn new(path: path::Path) -> Editable {
+        let mut editable = Editable {
+            points: path.data,
+            transform: Vec::new(),
+            roots: Vec::new(),
+            scratch: fft::Scratch::new(),
+            edits: 0,
+        };
+        editable.synchronize();
+        editable
+    }
+
+    pub fn points(&self) -> &[complex::Complex] {
+        &self.points
+    }
+
+    /// Moves the point at `index` to `point`.
+    ///
+    /// # Panics
+    ///
+    /// When `index` is not the index of a point.
+    pub fn set(&mut self, index: usize, point: complex::Complex) {
+        let offset = complex::Complex::minus(&point, &self.points[index]);
+        self.points[index] = point;
+        self.edits += 1;
+        if self.edits >= RESYNC_EDITS {
+            self.synchronize();
+            return;
+        }
+        // The term of frequency k gains offset · e^(-2πi k index/N), the root of rank
+        // k · index mod N, found by stepping through the roots.
+        let length = self.points.len();
+        let mut rank = 0;
+        for value in self.transform.iter_mut() {
+            let term = complex::Complex::multiply(&offset, &self.roots[rank]);
+            *value = complex::Complex::add(value, &term);
+            rank += index;
+            if rank >= length {
+                rank -= length;
+            }
+        }
+    }
+
+    /// Adds `point` at the end of the path.
+    pub fn push(&mut self, point: complex::Complex) {
+        self.points.push(point);
+        self.synchronize();
+    }
+
+    /// Decomposition of the path as it currently is.
+    pub fn epicycle(&self) -> Epicycle {
+        Epicycle::from_transform(&self.transform)
+    }
+
+    fn synchronize(&mut self) {
+        let length = self.points.len();
+        if self.roots.len() != length {
+            self.roots.clear();
+            self.roots.extend((0..length).map(|j| {
+                let angle = 2.0 * PI * j as f64 / length as f64;
+                complex::Complex::new(angle.cos(), -angle.sin())
+            }));
+        }
+        let transformed = {
+            let _stage = profile::stage("fft");
+            self.scratch.transform(&self.points)
+        };
+        self.transform.clear();
+        self.transform.extend_from_slice(transformed);
+        self.edits = 0;
+    }
+}
+
+/// Distance from `point` to the segment going from `from` to `to`.
+fn distance_to_chord(point: complex::Complex, from: complex::Complex, to: complex::Complex) -> f64 {
+    let chord = complex::Complex::minus(&to, &from);
+    let offset = complex::Complex::minus(&point, &from);
+    let length = chord.re * chord.re + chord.im * chord.im;
+    if length == 0.0 {
+        return offset.amplitude();
+    }
+    let along = ((offset.re * chord.re + offset.im * chord.im) / length).clamp(0.0, 1.0);
+    complex::Complex::new(offset.re - along * chord.re, offset.im - along * chord.im).amplitude()
+}
+
+/// Energy of a term with the given `coefficient`.
+fn energy(coefficient: &complex::Complex) -> f64 {
+    coefficient.re * coefficient.re + coefficient.im * coefficient.im
+}
+
+/// Energy `term` adds to the shape of the drawing, none for the constant term.
+pub fn term_energy((coefficient, frequency): &(complex::Complex, i64)) -> f64 {
+    if *frequency == 0 {
+        0.0
+    } else {
+        energy(coefficient)
+    }
+}
+
+/// JSON array of `terms` in the format of `Epicycle::export_p5_json`, phases being in radians
+/// between -π and π like those given by `atan2` in JavaScript.
+pub fn p5_json<'a, I>(terms: I) -> String
+where
+    I: IntoIterator<Item = &'a (complex::Complex, i64)>,
+{
+    let rows: Vec<String> = terms
+        .into_iter()
+        .map(|(coefficient, frequency)| {
+            let mut phase = coefficient.phase().in_radians();
+            if phase > PI {
+                phase -= 2.0 * PI;
+            }
+            format!(
+                "  {{\"re\": {}, \"im\": {}, \"freq\": {}, \"amp\": {}, \"phase\": {}}}",
+                coefficient.re,
+                coefficient.im,
+                frequency,
+                coefficient.amplitude(),
+                phase
+            )
+        })
+        .collect();
+    if rows.is_empty() {
+        return String::from("[]\n");
+    }
+    format!("[\n{}\n]\n", rows.join(",\n"))
+}
+
+/// Signed frequency of the FFT output at `index`: the upper half of the spectrum holds the
+/// negative frequencies.
+fn frequency(index: usize, length: usize) -> i64 {
+    if index <= length / 2 {
+        index as i64
+    } else {
+        index as i64 - length as i64
+    }
+}
+
+/// Decomposition of `path::unit_square`, shared by the tests of the crate.
+#[cfg(test)]
+pub(crate) fn unit_square() -> Epicycle {
+    Epicycle::new(&path::unit_square())
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    fn square() -> path::Path {
+        path::Path {
+            data: vec![
+                complex::Complex::new(0.0, 0.0),
+                complex::Complex::new(1.0, 0.0),
+                complex::Complex::new(2.0, 0.0),
+                complex::Complex::new(2.0, 1.0),
+                complex::Complex::new(2.0, 2.0),
+                complex::Complex::new(1.0, 2.0),
+                complex::Complex::new(0.0, 2.0),
+                complex::Complex::new(0.0, 1.0),
+            ],
+        }
+    }
+
+    #[test]
+    fn frequency_test() {
+        assert_eq!(0, frequency(0, 8));
+        assert_eq!(3, frequency(3, 8));
+        assert_eq!(4, frequency(4, 8));
+        assert_eq!(-3, frequency(5, 8));
+        assert_eq!(-1, frequency(7, 8));
+    }
+
+    #[test]
+    fn terms_sorted_by_amplitude_test() {
+        let epicycle = Epicycle::new(&square());
+        assert_eq!(8, epicycle.len());
+        for pair in epicycle.terms.windows(2) {
+            assert!(pair[0].0.amplitude() >= pair[1].0.amplitude());
+        }
+    }
+
+    #[test]
+    fn full_precision_reconstruction_test() {
+        let path = square();
+        let epicycle = Epicycle::new(&path);
+        for (index, point) in path.data.iter().enumerate() {
+            let t = 2.0 * PI * index as f64 / path.data.len() as f64;
+            assert_eq!(*point, epicycle.get_coordinate_for(epicycle.len(), t));
+        }
+    }
+
+    #[test]
+    fn non_power_of_two_reconstruction_test() {
+        let path = path::Path {
+            data: vec![
+                complex::Complex::new(0.0, 0.0),
+                complex::Complex::new(3.0, 1.0),
+                complex::Complex::new(1.0, 4.0),
+            ],
+        };
+        let epicycle = Epicycle::new(&path);
+        assert_eq!(path.data[1], epicycle.get_coordinate_for(3, 2.0 * PI / 3.0));
+    }
+
+    #[test]
+    fn tiny_path_test() {
+        let empty = Epicycle::new(&path::Path { data: Vec::new() });
+        assert!(empty.is_empty());
+        assert_eq!(
+            complex::Complex::new(0.0, 0.0),
+            empty.get_coordinate_for(4, 1.0)
+        );
+        assert!(empty.circles_at(4, 1.0).is_empty());
+        assert_eq!(0, empty.precision_for(0.99));
+        assert_eq!(0.0, empty.truncation_error(0));
+
+        let point = complex::Complex::new(3.0, -1.0);
+        let dot = Epicycle::new(&path::Path { data: vec![point] });
+        assert_eq!(1, dot.len());
+        assert!(dot.sample(1, 8).iter().all(|sample| *sample == point));
+        assert_eq!(1, dot.circles_at(1, 2.0).len());
+
+        let ends = vec![
+            complex::Complex::new(0.0, 0.0),
+            complex::Complex::new(2.0, 0.0),
+        ];
+        let pair = Epicycle::new(&path::Path { data: ends.clone() });
+        assert_eq!(2, pair.len());
+        assert_eq!(ends[0], pair.get_coordinate_for(2, 0.0));
+        let other_end = pair.get_coordinate_for(2, PI);
+        assert!(complex::Complex::minus(&other_end, &ends[1]).amplitude() < 1e-12);
+        let middle = complex::Complex::new(1.0, 0.0);
+        let quarter = pair.get_coordinate_for(2, PI / 2.0);
+        assert!((complex::Complex::minus(&quarter, &middle).amplitude() - 1.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn circles_at_test() {
+        let epicycle = Epicycle::new(&square());
+        let circles = epicycle.circles_at(5, 0.7);
+        assert_eq!(5, circles.len());
+        assert_eq!(complex::Complex::new(0.0, 0.0), circles[0].center);
+        for pair in circles.windows(2) {
+            assert_eq!(pair[0].end, pair[1].center);
+            assert!(pair[0].radius >= pair[1].radius);
+        }
+        assert_eq!(epicycle.get_coordinate_for(5, 0.7), circles[4].end);
+    }
+
+    #[test]
+    fn rms_error_test() {
+        let path = square();
+        let epicycle = Epicycle::new(&path);
+        assert!(epicycle.rms_error(&path, 8) < 1E-9);
+        assert!(epicycle.rms_error(&path, 1) > epicycle.rms_error(&path, 4));
+        let single = Epicycle::new(&path::Path {
+            data: vec![complex::Complex::new(3.0, 4.0)],
+        });
+        let origin = path::Path {
+            data: vec![complex::Complex::new(0.0, 0.0)],
+        };
+        assert_eq!(5.0, single.rms_error(&origin, 1));
+        assert_eq!(0.0, single.rms_error(&origin, 0));
+    }
+
+    #[test]
+    fn scratch_test() {
+        let mut scratch = fft::Scratch::new();
+        let decomposed = Epicycle::with_scratch(&square(), &mut scratch);
+        let triangle = path::Path {
+            data: vec![
+                complex::Complex::new(0.0, 0.0),
+                complex::Complex::new(2.0, 0.0),
+                complex::Complex::new(1.0, 1.0),
+            ],
+        };
+        let reused = Epicycle::with_scratch(&triangle, &mut scratch);
+        assert_eq!(Epicycle::new(&square()).terms(), decomposed.terms());
+        assert_eq!(Epicycle::new(&triangle).terms(), reused.terms());
+
+        let mut points = Vec::new();
+        decomposed.sample_into(8, 16, &mut points);
+        assert_eq!(decomposed.sample(8, 16), points);
+        decomposed.sample_into(8, 4, &mut points);
+        assert_eq!(decomposed.sample(8, 4), points);
+        let mut circles = vec![];
+        decomposed.circles_into(3, 0.7, &mut circles);
+        decomposed.circles_into(5, 0.7, &mut circles);
+        assert_eq!(5, circles.len());
+        assert_eq!(decomposed.get_coordinate_for(5, 0.7), circles[4].end);
+    }
+
+    #[test]
+    fn phasors_test() {
+        let path = path::Path {
+            data: (0..64)
+                .map(|k| complex::Complex::new((k as f64 * 0.3).sin() * 2.0, (k * k % 7) as f64))
+                .collect(),
+        };
+        let epicycle = Epicycle::new(&path);
+        let step = 2.0 * PI / 2048.0;
+        let mut phasors = Phasors::new(&epicycle, 40, step);
+        let (mut expected, mut circles) = (Vec::new(), Vec::new());
+        // Moves of 20 and 21 steps as with 100 frames of 2048 points, more than 16 distinct
+        // moves, then back to the start.
+        let positions = (1..=100)
+            .map(|frame| frame * 2048 / 100)
+            .chain((0..30).map(|k| 2000 + k * (k + 1) / 2))
+            .chain([5, 5, 6]);
+        for position in positions {
+            phasors.move_to(position);
+            assert_eq!(position as f64 * step, phasors.time());
+            epicycle.circles_into(40, phasors.time(), &mut expected);
+            phasors.circles_into(&mut circles);
+            assert_eq!(40, circles.len());
+            for (expected, actual) in expected.iter().zip(&circles) {
+                assert_eq!(expected.radius, actual.radius);
+                assert!(complex::Complex::minus(&expected.end, &actual.end).amplitude() < 1e-12);
+            }
+        }
+    }
+
+    #[test]
+    fn blend_test() {
+        let square = Epicycle::new(&square());
+        let point = Epicycle::new(&path::Path {
+            data: vec![complex::Complex::new(2.0, 2.0); 2],
+        });
+        assert_eq!(square.len(), square.blend(&point, 0.0).len());
+
+        let half = square.blend(&point, 0.5);
+        for k in 0..8 {
+            let t = 2.0 * PI * k as f64 / 8.0;
+            let from = square.get_coordinate_for(8, t);
+            let expected = complex::Complex::new((from.re + 2.0) / 2.0, (from.im + 2.0) / 2.0);
+            assert_eq!(expected, half.get_coordinate_for(8, t));
+        }
+        let end = square.blend(&point, 1.0);
+        assert_eq!(
+            complex::Complex::new(2.0, 2.0),
+            end.get_coordinate_for(8, 1.0)
+        );
+    }
+
+    #[test]
+    fn sample_on_test() {
+        let epicycle = Epicycle::new(&square());
+        let expected: Vec<complex::Complex> = epicycle.samples(4, 10).collect();
+        let mut points = vec![complex::Complex::new(1.0, 1.0); 3];
+        for threads in 1..=12 {
+            epicycle.sample_on(threads, 4, 10, &mut points);
+            assert_eq!(expected, points, "{} threads", threads);
+        }
+        epicycle.sample_on(3, 4, 0, &mut points);
+        assert!(points.is_empty());
+        assert_eq!(expected, epicycle.sample(4, 10));
+    }
+
+    #[test]
+    fn editable_test() {
+        let mut points: Vec<complex::Complex> = (0..12)
+            .map(|k| complex::Complex::new(k as f64, (k * k % 5) as f64))
+            .collect();
+        let mut editable = Editable::new(path::Path {
+            data: points.clone(),
+        });
+        let same = |editable: &Editable, points: &[complex::Complex]| {
+            let expected = Epicycle::new(&path::Path {
+                data: points.to_vec(),
+            });
+            let edited = editable.epicycle();
+            assert_eq!(points, editable.points());
+            assert_eq!(expected.len(), edited.len());
+            for k in 0..points.len() {
+                let t = 2.0 * PI * k as f64 / points.len() as f64;
+                assert_eq!(
+                    expected.get_coordinate_for(expected.len(), t),
+                    edited.get_coordinate_for(edited.len(), t)
+                );
+            }
+        };
+        same(&editable, &points);
+
+        for (index, point) in [(0, (3.0, -1.0)), (7, (0.5, 2.5)), (11, (-4.0, 0.0))] {
+            let point = complex::Complex::new(point.0, point.1);
+            editable.set(index, point);
+            points[index] = point;
+        }
+        same(&editable, &points);
+
+        let point = complex::Complex::new(1.0, 1.0);
+        editable.push(point);
+        points.push(point);
+        same(&editable, &points);
+
+        // Enough moves to transform the path again on the way.
+        for edit in 0..RESYNC_EDITS + 10 {
+            let point = complex::Complex::new(edit as f64 % 7.0, edit as f64 % 3.0);
+            let index = edit % points.len();
+            editable.set(index, point);
+            points[index] = point;
+        }
+        same(&editable, &points);
+    }
+
+    #[test]
+    fn band_test() {
+        let path = path::Path {
+            data: (0..16)
+                .map(|k| complex::Complex::new(k as f64, (k * k % 7) as f64))
+                .collect(),
+        };
+        let epicycle = Epicycle::new(&path);
+        let band = epicycle.band(1, 3);
+        let expected = Epicycle::from_terms(
+            epicycle
+                .terms()
+                .iter()
+                .filter(|(_, frequency)| (1..=3).contains(&frequency.abs()))
+                .cloned()
+                .collect(),
+        );
+        assert_eq!(6, band.len());
+        assert_eq!(expected.terms(), band.terms());
+        for k in 0..8 {
+            let t = 2.0 * PI * k as f64 / 8.0;
+            assert_eq!(
+                expected.get_coordinate_for(4, t),
+                band.get_coordinate_for(4, t)
+            );
+        }
+        assert_eq!(epicycle.len(), epicycle.filtered(|_| true).len());
+        assert!(epicycle.filtered(|_| false).is_empty());
+    }
+
+    #[test]
+    fn sample_adaptive_test() {
+        let circle = Epicycle::new(&path::Path {
+            data: (0..8)
+                .map(|k| {
+                    let angle = 2.0 * PI * f64::from(k) / 8.0;
+                    complex::Complex::new(angle.cos(), angle.sin())
+                })
+                .collect(),
+        });
+        let fine = circle.sample_adaptive(8, 1E-4);
+        let coarse = circle.sample_adaptive(8, 1E-2);
+        assert!(fine.len() > coarse.len());
+        assert!(coarse.len() >= INITIAL_INTERVALS);
+        assert_eq!(circle.get_coordinate_for(8, 0.0), fine[0]);
+        for pair in fine.windows(2) {
+            let middle = complex::Complex::new(
+                (pair[0].re + pair[1].re) / 2.0,
+                (pair[0].im + pair[1].im) / 2.0,
+            );
+            assert!(1.0 - middle.amplitude() <= 1E-4);
+        }
+    }
+
+    #[test]
+    fn sample_adaptive_within_test() {
+        let circle = Epicycle::new(&path::Path {
+            data: (0..8)
+                .map(|k| {
+                    let angle = 2.0 * PI * k as f64 / 8.0;
+                    complex::Complex::new(angle.cos(), angle.sin())
+                })
+                .collect(),
+        });
+        let unlimited = circle.sample_adaptive(8, 1e-6);
+        assert!(unlimited.len() > 200);
+        assert_eq!(
+            unlimited,
+            circle.sample_adaptive_within(8, 1e-6, unlimited.len())
+        );
+        let limited = circle.sample_adaptive_within(8, 1e-6, 200);
+        assert_eq!(200, limited.len());
+        assert_eq!(unlimited[..100], limited[..100]);
+        assert_eq!(
+            circle.sample(8, 10),
+            circle.sample_adaptive_within(8, 1e-6, 10)
+        );
+    }
+
+    #[test]
+    fn distance_to_chord_test() {
+        let from = complex::Complex::new(0.0, 0.0);
+        let to = complex::Complex::new(2.0, 0.0);
+        assert_eq!(
+            1.0,
+            distance_to_chord(complex::Complex::new(1.0, 1.0), from, to)
+        );
+        assert_eq!(
+            5.0,
+            distance_to_chord(complex::Complex::new(5.0, 4.0), from, to)
+        );
+        assert_eq!(
+            5.0,
+            distance_to_chord(complex::Complex::new(3.0, 4.0), from, from)
+        );
+    }
+
+    #[test]
+    fn truncation_error_test() {
+        let path = square();
+        let epicycle = Epicycle::new(&path);
+        for precision in 0..=8 {
+            let expected = epicycle.rms_error(&path, precision);
+            assert!((expected - epicycle.truncation_error(precision)).abs() < 1E-9);
+        }
+    }
+
+    #[test]
+    fn precision_for_test() {
+        // Constant term 3 ranking first, then frequencies 1 and -1 with 90% and 10% of the
+        // energy of the shape.
+        let epicycle = Epicycle::new(&path::Path {
+            data: (0..8)
+                .map(|k| {
+                    let angle = 2.0 * PI * f64::from(k) / 8.0;
+                    let small = 0.1f64.sqrt();
+                    complex::Complex::new(
+                        3.0 + 0.9f64.sqrt() * angle.cos() + small * angle.cos(),
+                        0.9f64.sqrt() * angle.sin() - small * angle.sin(),
+                    )
+                })
+                .collect(),
+        });
+        assert_eq!(0, epicycle.precision_for(0.0));
+        assert_eq!(2, epicycle.precision_for(0.5));
+        assert_eq!(2, epicycle.precision_for(0.8));
+        assert_eq!(3, epicycle.precision_for(0.95));
+        assert_eq!(3, epicycle.precision_for(1.0));
+    }
+
+    #[test]
+    fn shape_energy_test() {
+        let epicycle = unit_square();
+        // All the energy but that of the constant term 0.5 + 0.5i.
+        let shape = epicycle.truncation_error(0).powi(2) - 0.5;
+        assert!((epicycle.shape_energy() - shape).abs() < 1e-12);
+
+        let identical = Epicycle::new(&path::Path {
+            data: vec![complex::Complex::new(0.1, 0.7); 12],
+        });
+        // Rounding noise left by the transform is not shape.
+        assert!(identical.terms().iter().any(|term| term_energy(term) > 0.0));
+        assert_eq!(0.0, identical.shape_energy());
+        assert_eq!(0, identical.precision_for(1.0));
+    }
+
+    #[test]
+    fn concurrent_sampling_test() {
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<Epicycle>();
+
+        let epicycle = Epicycle::new(&square());
+        let expected = epicycle.get_coordinate_for(8, 1.0);
+        let handles: Vec<_> = (0..4)
+            .map(|_| {
+                let shared = epicycle.clone();
+                std::thread::spawn(move || shared.get_coordinate_for(8, 1.0))
+            })
+            .collect();
+        for handle in handles {
+            assert_eq!(expected, handle.join().unwrap());
+        }
+        assert!(std::ptr::eq(
+            epicycle.terms().as_ptr(),
+            epicycle.clone().terms().as_ptr()
+        ));
+    }
+
+    #[test]
+    fn to_svg_path_test() {
+        let epicycle = Epicycle::new(&square());
+        let d = epicycle.to_svg_path(8, 8);
+        assert!(d.starts_with("M0.000 0.000 L1.000 0.000"));
+        assert!(d.ends_with(" Z"));
+        assert_eq!(7, d.matches(" L").count());
+        assert_eq!("", epicycle.to_svg_path(8, 0));
+    }
+
+    #[test]
+    fn to_svg_document_test() {
+        let epicycle = Epicycle::new(&square());
+        let document = epicycle.to_svg_document(8, 8);
+        assert!(document.starts_with("<svg"));
+        assert!(document.contains("viewBox=\"-0.100 -0.100 2.200 2.200\""));
+        assert!(document.contains(&epicycle.to_svg_path(8, 8)));
+    }
+
+    #[test]
+    fn export_p5_json_test() {
+        let epicycle = Epicycle::new(&path::Path {
+            data: vec![
+                complex::Complex::new(0.0, 0.0),
+                complex::Complex::new(0.0, -1.0),
+            ],
+        });
+        assert_eq!(
+            "[\n  {\"re\": 0, \"im\": -0.5, \"freq\": 0, \"amp\": 0.5, \"phase\": -1.5707963267948966},\n  \
+             {\"re\": 0, \"im\": 0.5, \"freq\": 1, \"amp\": 0.5, \"phase\": 1.5707963267948966}\n]\n",
+            epicycle.export_p5_json()
+        );
+        assert_eq!("[]\n", p5_json(&[]));
+    }
+}
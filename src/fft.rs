@@ -1,31 +1,166 @@
 use crate::complex;
+use std::error::Error;
+use std::f64::consts::FRAC_1_SQRT_2;
+use std::fmt;
 
-pub fn butterfly<T>(data: &mut Vec<T>) {
-    let mut target: u32 = 0;
-    for position in 0..data.len() {
-        let u_target = target as usize;
-        if u_target > position {
-            data.swap(position, u_target);
+/// Whether `Scratch::try_transform` checks every pass of the butterflies for values that are not
+/// finite, that is whether the `checks` feature is on. Otherwise, and for inputs of up to 16
+/// values going through the kernels, only its input and output are.
+pub const CHECKED: bool = cfg!(feature = "checks");
+
+/// Number of high and of low bits of the indices going through one block of `butterfly`.
+const BLOCK_BITS: u32 = 3;
+
+/// Puts the elements of `data`, whose length must be a power of two, in bit-reversed order: the
+/// element at index `i` is swapped with the one at the index written with the bits of `i` in
+/// reverse.
+///
+/// Indices are taken in blocks sharing their middle bits, so that both the elements of a block
+/// and the elements they are swapped with lie in a few short runs instead of all over `data`.
+pub fn butterfly<T>(data: &mut [T]) {
+    let length = data.len();
+    if length < 2 {
+        return;
+    }
+    let bits = length.trailing_zeros();
+    let reverse = |index: usize| index.reverse_bits() >> (usize::BITS - bits);
+    if bits < 2 * BLOCK_BITS {
+        for index in 0..length {
+            let target = reverse(index);
+            if target > index {
+                data.swap(index, target);
+            }
         }
-        let mut mask: u32 = data.len() as u32;
-        mask >>= 1;
-        let zero = 0;
-        while (target & mask) != zero {
-            target &= !mask;
-            mask >>= 1;
+        return;
+    }
+    // Reversed indices within a block, with its middle bits reversed once per block.
+    let side = 1 << BLOCK_BITS;
+    let mut reversed = [0; 1 << BLOCK_BITS];
+    for (index, reversed) in reversed.iter_mut().enumerate() {
+        *reversed = index.reverse_bits() >> (usize::BITS - BLOCK_BITS);
+    }
+    let high_shift = bits - BLOCK_BITS;
+    for middle in 0..length >> (2 * BLOCK_BITS) {
+        let middle = middle << BLOCK_BITS;
+        let reversed_middle = reverse(middle);
+        for high in 0..side {
+            for low in 0..side {
+                let index = (high << high_shift) | middle | low;
+                let target = (reversed[low] << high_shift) | reversed_middle | reversed[high];
+                if target > index {
+                    data.swap(index, target);
+                }
+            }
         }
-        target |= mask;
     }
 }
 
-pub fn fft(data: &mut Vec<complex::Complex>) {
+/// `cos(π/8)` and `sin(π/8)`, parts of the twiddle factors of the kernel of 16 elements.
+const COS_PI_8: f64 = 0.923_879_532_511_286_7;
+const SIN_PI_8: f64 = 0.382_683_432_365_089_8;
+
+/// Twiddle factors `e^(-2iπk/8)` for `k` in `0..4`.
+const TWIDDLES_8: [complex::Complex; 4] = [
+    complex::Complex::new(1.0, 0.0),
+    complex::Complex::new(FRAC_1_SQRT_2, -FRAC_1_SQRT_2),
+    complex::Complex::new(0.0, -1.0),
+    complex::Complex::new(-FRAC_1_SQRT_2, -FRAC_1_SQRT_2),
+];
+
+/// Twiddle factors `e^(-2iπk/16)` for `k` in `0..8`.
+const TWIDDLES_16: [complex::Complex; 8] = [
+    complex::Complex::new(1.0, 0.0),
+    complex::Complex::new(COS_PI_8, -SIN_PI_8),
+    complex::Complex::new(FRAC_1_SQRT_2, -FRAC_1_SQRT_2),
+    complex::Complex::new(SIN_PI_8, -COS_PI_8),
+    complex::Complex::new(0.0, -1.0),
+    complex::Complex::new(-SIN_PI_8, -COS_PI_8),
+    complex::Complex::new(-FRAC_1_SQRT_2, -FRAC_1_SQRT_2),
+    complex::Complex::new(-COS_PI_8, -SIN_PI_8),
+];
+
+/// Fast Fourier transform of `data` in place, whose length must be a power of two. Lengths up to
+/// 16 go through kernels written out for their size, longer ones through the butterflies.
+pub fn fft(data: &mut [complex::Complex]) {
+    match data.len() {
+        2 => kernel_2(data),
+        4 => kernel_4(data),
+        8 => kernel_8(data),
+        16 => kernel_16(data),
+        _ => radix_2(data, |_, _| {}),
+    }
+}
+
+fn kernel_2(data: &mut [complex::Complex]) {
+    let (a, b) = (data[0], data[1]);
+    data[0] = complex::Complex::add(&a, &b);
+    data[1] = complex::Complex::minus(&a, &b);
+}
+
+fn kernel_4(data: &mut [complex::Complex]) {
+    let sum_02 = complex::Complex::add(&data[0], &data[2]);
+    let difference_02 = complex::Complex::minus(&data[0], &data[2]);
+    let sum_13 = complex::Complex::add(&data[1], &data[3]);
+    let difference_13 = complex::Complex::minus(&data[1], &data[3]);
+    data[0] = complex::Complex::add(&sum_02, &sum_13);
+    data[2] = complex::Complex::minus(&sum_02, &sum_13);
+    // Adding and subtracting the second difference turned by -i.
+    data[1] = complex::Complex::new(
+        difference_02.re + difference_13.im,
+        difference_02.im - difference_13.re,
+    );
+    data[3] = complex::Complex::new(
+        difference_02.re - difference_13.im,
+        difference_02.im + difference_13.re,
+    );
+}
+
+fn kernel_8(data: &mut [complex::Complex]) {
+    let mut even = [data[0], data[2], data[4], data[6]];
+    let mut odd = [data[1], data[3], data[5], data[7]];
+    kernel_4(&mut even);
+    kernel_4(&mut odd);
+    combine(&even, &odd, &TWIDDLES_8, data);
+}
+
+fn kernel_16(data: &mut [complex::Complex]) {
+    let mut even = [
+        data[0], data[2], data[4], data[6], data[8], data[10], data[12], data[14],
+    ];
+    let mut odd = [
+        data[1], data[3], data[5], data[7], data[9], data[11], data[13], data[15],
+    ];
+    kernel_8(&mut even);
+    kernel_8(&mut odd);
+    combine(&even, &odd, &TWIDDLES_16, data);
+}
+
+/// Writes to `output` the transform of a sequence whose elements at even and odd indices have
+/// the transforms `even` and `odd`.
+fn combine(
+    even: &[complex::Complex],
+    odd: &[complex::Complex],
+    twiddles: &[complex::Complex],
+    output: &mut [complex::Complex],
+) {
+    let half = even.len();
+    for k in 0..half {
+        let product = complex::Complex::multiply(&twiddles[k], &odd[k]);
+        output[k] = complex::Complex::add(&even[k], &product);
+        output[k + half] = complex::Complex::minus(&even[k], &product);
+    }
+}
+
+/// Transform by butterflies, calling `after_pass` with the width of the transforms each pass
+/// combines into and the values once it is done.
+fn radix_2<F: FnMut(usize, &[complex::Complex])>(data: &mut [complex::Complex], mut after_pass: F) {
     butterfly(data);
     let mut step = 1;
     let length = data.len();
     while step < length {
         let jump = step << 1;
 
-        let delta = -1.0 * std::f64::consts::PI / step as f64;
+        let delta = -std::f64::consts::PI / step as f64;
 
         let temp_sin = (delta * 0.5).sin();
 
@@ -44,26 +179,145 @@ pub fn fft(data: &mut Vec<complex::Complex>) {
                 &factor,
             );
         }
+        after_pass(jump, data);
 
         step <<= 1;
     }
 }
 
 pub fn dft(data: Vec<complex::Complex>) -> Vec<complex::Complex> {
-    let mut results: Vec<complex::Complex> = Vec::new();
+    let mut results = Vec::with_capacity(data.len());
+    dft_into(&data, &mut results);
+    results
+}
 
+/// Replaces the content of `results` with the discrete Fourier transform of `data`.
+pub fn dft_into(data: &[complex::Complex], results: &mut Vec<complex::Complex>) {
+    results.clear();
     for term in 0..data.len() {
         let mut sum = complex::Complex::new(0.0, 0.0);
-        for n in 0..data.len() {
+        for (n, value) in data.iter().enumerate() {
             let angle =
                 std::f64::consts::PI * 2.0 * (term as f64) * (n as f64) / (data.len() as f64);
-            let exp = complex::Complex::new(angle.cos(), -1.0 * angle.sin());
-            let mult = complex::Complex::multiply(&data[n], &exp);
+            let exp = complex::Complex::new(angle.cos(), -angle.sin());
+            let mult = complex::Complex::multiply(value, &exp);
             sum = complex::Complex::add(&sum, &mult);
         }
         results.push(sum);
     }
-    results
+}
+
+/// Buffer kept from one transform to the next, so that transforming many inputs in turn only
+/// allocates for the first one and for any longer than all the previous ones.
+#[derive(Debug, Clone, Default)]
+pub struct Scratch {
+    data: Vec<complex::Complex>,
+}
+
+impl Scratch {
+    pub fn new() -> Scratch {
+        Scratch::default()
+    }
+
+    /// Discrete Fourier transform of `input`, computed with `fft` when its length is a power of
+    /// two and with `dft` otherwise. The result is overwritten by the next transform.
+    pub fn transform(&mut self, input: &[complex::Complex]) -> &[complex::Complex] {
+        if input.len().is_power_of_two() {
+            self.data.clear();
+            self.data.extend_from_slice(input);
+            fft(&mut self.data);
+        } else {
+            dft_into(input, &mut self.data);
+        }
+        &self.data
+    }
+
+    /// Same as `transform`, failing with the first value that is not finite in `input` or in the
+    /// result. With the `checks` feature, the values are also checked after every pass of the
+    /// butterflies, to tell where an overflow first happened.
+    pub fn try_transform(
+        &mut self,
+        input: &[complex::Complex],
+    ) -> Result<&[complex::Complex], NonFinite> {
+        check(input, Stage::Input)?;
+        let mut first = None;
+        if CHECKED && input.len() > 16 && input.len().is_power_of_two() {
+            self.data.clear();
+            self.data.extend_from_slice(input);
+            radix_2(&mut self.data, |width, data| {
+                if first.is_none() {
+                    first = check(data, Stage::Pass(width)).err();
+                }
+            });
+        } else {
+            self.transform(input);
+        }
+        if let Some(non_finite) = first {
+            return Err(non_finite);
+        }
+        check(&self.data, Stage::Output)?;
+        Ok(&self.data)
+    }
+}
+
+/// Step of a transform at which values are checked.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Stage {
+    /// The values transformed.
+    Input,
+    /// The pass of the butterflies combining transforms into transforms of the given width.
+    Pass(usize),
+    /// The transform as a whole.
+    Output,
+}
+
+/// Value that is not finite, NaN or infinite, found by `Scratch::try_transform`. A single one in
+/// the input spreads to every value of the transform, drawing nothing.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct NonFinite {
+    pub stage: Stage,
+    pub index: usize,
+    pub value: complex::Complex,
+}
+
+impl fmt::Display for NonFinite {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        let kind = if self.value.re.is_nan() || self.value.im.is_nan() {
+            "NaN"
+        } else {
+            "Infinite value"
+        };
+        match self.stage {
+            Stage::Input => write!(formatter, "{} at index {} of the input", kind, self.index),
+            Stage::Pass(width) => write!(
+                formatter,
+                "{} at index {} after the butterflies of width {}",
+                kind, self.index, width
+            ),
+            Stage::Output => write!(
+                formatter,
+                "{} at index {} of the transform",
+                kind, self.index
+            ),
+        }
+    }
+}
+
+impl Error for NonFinite {}
+
+/// First value of `data` that is not finite, found at `stage`.
+fn check(data: &[complex::Complex], stage: Stage) -> Result<(), NonFinite> {
+    match data
+        .iter()
+        .position(|value| !(value.re.is_finite() && value.im.is_finite()))
+    {
+        Some(index) => Err(NonFinite {
+            stage,
+            index,
+            value: data[index],
+        }),
+        None => Ok(()),
+    }
 }
 
 #[cfg(test)]
@@ -94,6 +348,21 @@ mod tests {
         assert_eq!(complex::Complex::new(2.65, -7.0), data[7]);
     }
 
+    #[test]
+    fn butterfly_blocks_test() {
+        for bits in 0..14 {
+            let length = 1usize << bits;
+            let mut data: Vec<usize> = (0..length).collect();
+            butterfly(&mut data);
+            for (index, value) in data.iter().enumerate() {
+                // Reverses the bits one at a time.
+                let expected =
+                    (0..bits).fold(0, |reversed, bit| (reversed << 1) | ((index >> bit) & 1));
+                assert_eq!(expected, *value, "index {} of {}", index, length);
+            }
+        }
+    }
+
     fn assert_float_eq(expected: f64, actual: f64, epsilon: f64) {
         let diff = (expected - actual).abs();
         if diff > epsilon {
@@ -190,4 +459,73 @@ mod tests {
             1E-6,
         );
     }
+
+    #[test]
+    fn scratch_test() {
+        let data: Vec<complex::Complex> = (0..16)
+            .map(|n| complex::Complex::new(n as f64, (n * n) as f64 / 4.0))
+            .collect();
+        let mut scratch = Scratch::new();
+        let mut expected = data.clone();
+        fft(&mut expected);
+        assert_eq!(&expected[..], scratch.transform(&data));
+        let capacity = scratch.data.capacity();
+
+        let odd = &data[..6];
+        let expected = dft(odd.to_vec());
+        let transformed = scratch.transform(odd);
+        assert_eq!(6, transformed.len());
+        for (expected, actual) in expected.iter().zip(transformed) {
+            assert_complex_eq(expected, actual, 1E-9);
+        }
+        // Shorter inputs reuse the buffer.
+        assert_eq!(capacity, scratch.data.capacity());
+        assert!(scratch.transform(&[]).is_empty());
+    }
+
+    #[test]
+    fn kernels_test() {
+        for length in [1, 2, 4, 8, 16] {
+            let data: Vec<complex::Complex> = (0..length)
+                .map(|n| complex::Complex::new((n as f64 * 1.7).sin(), (n * n) as f64 / 8.0 - 1.0))
+                .collect();
+            let mut kernel = data.clone();
+            fft(&mut kernel);
+            let mut butterflies = data.clone();
+            radix_2(&mut butterflies, |_, _| {});
+            let expected = dft(data);
+            for index in 0..length {
+                assert_complex_eq(&expected[index], &kernel[index], 1E-12);
+                assert_complex_eq(&butterflies[index], &kernel[index], 1E-12);
+            }
+        }
+    }
+
+    #[test]
+    fn try_transform_test() {
+        let mut scratch = Scratch::new();
+        let mut data: Vec<complex::Complex> = (0..64)
+            .map(|n| complex::Complex::new(n as f64, -(n as f64)))
+            .collect();
+        let expected = scratch.transform(&data).to_vec();
+        assert_eq!(&expected[..], scratch.try_transform(&data).unwrap());
+
+        data[5].im = f64::NAN;
+        let non_finite = scratch.try_transform(&data).unwrap_err();
+        assert_eq!((Stage::Input, 5), (non_finite.stage, non_finite.index));
+        assert_eq!("NaN at index 5 of the input", non_finite.to_string());
+
+        // Finite values whose sum overflows.
+        let huge = vec![complex::Complex::new(f64::MAX, 0.0); 64];
+        let non_finite = scratch.try_transform(&huge).unwrap_err();
+        let stage = if CHECKED {
+            Stage::Pass(2)
+        } else {
+            Stage::Output
+        };
+        assert_eq!((stage, 0), (non_finite.stage, non_finite.index));
+        assert!(!non_finite.value.re.is_finite());
+        assert!(scratch.try_transform(&huge[..6]).is_err());
+        assert!(scratch.try_transform(&[]).unwrap().is_empty());
+    }
 }
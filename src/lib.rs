@@ -1,5 +1,38 @@
+#[cfg(feature = "capi")]
+pub mod capi;
+
+pub mod compare;
+
 pub mod complex;
 
+#[cfg(feature = "engine")]
+pub mod engine;
+
+pub mod epicycle;
+
 pub mod fft;
 
+pub mod metrics;
+
 pub mod path;
+
+pub mod profile;
+
+pub mod proto;
+
+pub mod reader;
+
+#[cfg(feature = "render")]
+pub mod render;
+
+pub mod shape;
+
+#[cfg(feature = "stream")]
+pub mod stream;
+
+pub mod task;
+
+#[cfg(feature = "video")]
+pub mod video;
+
+pub mod watch;
@@ -0,0 +1,14 @@
+mod cli;
+
+use std::env;
+use std::process;
+
+/// Counts the allocations of each stage for `--profile`.
+#[cfg(feature = "profile")]
+#[global_allocator]
+static ALLOCATOR: fft::profile::Counting = fft::profile::Counting;
+
+fn main() {
+    let arguments: Vec<String> = env::args().skip(1).collect();
+    process::exit(cli::main(&arguments));
+}
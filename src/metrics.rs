@@ -0,0 +1,145 @@
+//! Geometric distances between a path and its reconstruction, telling whether corners and fine
+//! features survive the truncation where the root mean square error averages them away.
+//!
+//! Both distances are in the units of the path. The Hausdorff distance is the largest distance
+//! from a point of either path to the closest point of the other, whatever their order, while the
+//! discrete Fréchet distance also follows the order of the points, catching reconstructions that
+//! go back on themselves or skip a loop.
+
+use crate::complex;
+use crate::epicycle;
+use crate::path;
+
+/// Distances between a path and a reconstruction.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Metrics {
+    /// Root mean square distance between the points at matching times.
+    pub rms: f64,
+    pub hausdorff: f64,
+    pub frechet: f64,
+}
+
+/// Distances between `path` and the reconstruction of `epicycle` using `precision` terms, sampled
+/// at the times of the points of `path`. `epicycle` is meant to be the decomposition of `path`.
+pub fn measure(epicycle: &epicycle::Epicycle, path: &path::Path, precision: usize) -> Metrics {
+    let reconstruction = epicycle.sample(precision, path.data.len());
+    Metrics {
+        rms: epicycle.rms_error(path, precision),
+        hausdorff: hausdorff(&path.data, &reconstruction),
+        frechet: frechet(&path.data, &reconstruction),
+    }
+}
+
+/// Largest distance from a point of `from` to the closest point of `to`, 0 when `from` is empty
+/// and infinite when only `to` is.
+pub fn directed_hausdorff(from: &[complex::Complex], to: &[complex::Complex]) -> f64 {
+    from.iter()
+        .map(|point| {
+            to.iter()
+                .map(|other| squared_distance(point, other))
+                .fold(f64::INFINITY, f64::min)
+        })
+        .fold(0.0, f64::max)
+        .sqrt()
+}
+
+/// Hausdorff distance between the points of `first` and `second`, the larger of the directed
+/// distances both ways.
+pub fn hausdorff(first: &[complex::Complex], second: &[complex::Complex]) -> f64 {
+    directed_hausdorff(first, second).max(directed_hausdorff(second, first))
+}
+
+/// Discrete Fréchet distance between the closed paths through `first` and `second`, both starting
+/// and ending at their first point: the shortest leash joining two walkers stepping forward along
+/// each path, at most one point at a time each. 0 when both are empty, infinite when only one is.
+pub fn frechet(first: &[complex::Complex], second: &[complex::Complex]) -> f64 {
+    match (first.is_empty(), second.is_empty()) {
+        (true, true) => return 0.0,
+        (true, false) | (false, true) => return f64::INFINITY,
+        _ => {}
+    }
+    let closed = |points: &[complex::Complex]| -> Vec<complex::Complex> {
+        points.iter().chain(points.first()).copied().collect()
+    };
+    let (first, second) = (closed(first), closed(second));
+    // Leash needed to reach each point of `second` with the points of `first` up to the current
+    // one, one row of the table at a time.
+    let mut previous = vec![0.0f64; second.len()];
+    let mut current = vec![0.0; second.len()];
+    for (i, point) in first.iter().enumerate() {
+        for (j, other) in second.iter().enumerate() {
+            let distance = squared_distance(point, other);
+            let reached = match (i, j) {
+                (0, 0) => 0.0,
+                (0, _) => current[j - 1],
+                (_, 0) => previous[0],
+                _ => previous[j].min(previous[j - 1]).min(current[j - 1]),
+            };
+            current[j] = distance.max(reached);
+        }
+        std::mem::swap(&mut previous, &mut current);
+    }
+    previous[second.len() - 1].sqrt()
+}
+
+fn squared_distance(first: &complex::Complex, second: &complex::Complex) -> f64 {
+    let difference = complex::Complex::minus(first, second);
+    difference.re * difference.re + difference.im * difference.im
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::shape::Shape;
+
+    fn points(coordinates: &[(f64, f64)]) -> Vec<complex::Complex> {
+        coordinates
+            .iter()
+            .map(|(x, y)| complex::Complex::new(*x, *y))
+            .collect()
+    }
+
+    #[test]
+    fn hausdorff_test() {
+        let square = points(&[(0.0, 0.0), (1.0, 0.0), (1.0, 1.0), (0.0, 1.0)]);
+        let spike = points(&[(0.0, 0.0), (1.0, 0.0), (3.0, 1.0), (0.0, 1.0)]);
+        assert_eq!(0.0, hausdorff(&square, &square));
+        assert_eq!(0.0, directed_hausdorff(&square[..2], &spike));
+        assert_eq!(2.0, directed_hausdorff(&spike, &square));
+        assert_eq!(2.0, hausdorff(&square, &spike));
+        assert_eq!(0.0, hausdorff(&[], &[]));
+        assert_eq!(f64::INFINITY, hausdorff(&square, &[]));
+    }
+
+    #[test]
+    fn frechet_test() {
+        let square = points(&[(0.0, 0.0), (1.0, 0.0), (1.0, 1.0), (0.0, 1.0)]);
+        assert_eq!(0.0, frechet(&square, &square));
+        // The same points walked the other way round: close as sets, not as paths.
+        let reversed = points(&[(0.0, 0.0), (0.0, 1.0), (1.0, 1.0), (1.0, 0.0)]);
+        assert_eq!(0.0, hausdorff(&square, &reversed));
+        assert_eq!(1.0, frechet(&square, &reversed));
+        // A point added along a side is at most half a side from the ones walked past.
+        let denser = points(&[(0.0, 0.0), (0.5, 0.0), (1.0, 0.0), (1.0, 1.0), (0.0, 1.0)]);
+        assert!((frechet(&square, &denser) - 0.5).abs() < 1e-12);
+        assert_eq!(0.0, frechet(&[], &[]));
+        assert_eq!(f64::INFINITY, frechet(&[], &square));
+    }
+
+    #[test]
+    fn measure_test() {
+        let path = Shape::Square.path(64);
+        let epicycle = epicycle::Epicycle::new(&path);
+        let exact = measure(&epicycle, &path, epicycle.len());
+        assert!(exact.rms < 1e-9 && exact.hausdorff < 1e-9 && exact.frechet < 1e-9);
+
+        let rough = measure(&epicycle, &path, 5);
+        let fine = measure(&epicycle, &path, 20);
+        // The corners are where the reconstruction strays furthest.
+        assert!(rough.hausdorff > rough.rms);
+        assert!(rough.frechet >= rough.hausdorff);
+        assert!(fine.hausdorff < rough.hausdorff);
+        assert!(fine.frechet < rough.frechet);
+    }
+}
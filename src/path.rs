@@ -1,6 +1,9 @@
 use crate::complex;
+use crate::profile;
 use std::error::Error;
+use std::fmt;
 use std::fs;
+use std::io::{BufRead, BufReader};
 
 #[derive(Debug)]
 pub struct Path {
@@ -9,30 +12,401 @@ pub struct Path {
 
 impl Path {
     pub fn new(filename: &str) -> Result<Path, Box<dyn Error>> {
-        let data = read_from_file(filename)?;
-        Ok(Path { data })
+        Path::read(BufReader::new(fs::File::open(filename)?))
     }
-}
 
-fn read_from_file(filename: &str) -> Result<Vec<complex::Complex>, Box<dyn Error>> {
-    let file_content = fs::read_to_string(filename)?;
+    /// Path from text with one `x, y` point per line read from `reader` a line at a time, so that
+    /// large files are never held in memory as a whole. Text without any point is an error, there
+    /// being nothing to draw, while paths of one or two points are drawn as a dot or a loop.
+    pub fn read<R: BufRead>(reader: R) -> Result<Path, Box<dyn Error>> {
+        Path::read_with(reader, Mode::Strict).map(|(path, _)| path)
+    }
 
-    let result = file_content
-        .lines()
-        .map(|line| {
-            let parts = line.trim().split(',').collect::<Vec<&str>>();
-            if parts.len() != 2 {
-                return Err("Wrong number of arguments in input lines");
-            }
-            Ok(complex::Complex::new(
-                parts[0].trim().parse::<f64>().unwrap(),
-                parts[1].trim().parse::<f64>().unwrap(),
+    /// Same as `read`, with the lines that do not hold a point handled as `mode` says, along with
+    /// the lines skipped.
+    pub fn read_with<R: BufRead>(
+        mut reader: R,
+        mode: Mode,
+    ) -> Result<(Path, Vec<Skipped>), Box<dyn Error>> {
+        let _stage = profile::stage("parse");
+        let mut points = Points::new(mode);
+        let mut line = Vec::new();
+        while reader.read_until(b'\n', &mut line)? > 0 {
+            points.push(&line)?;
+            line.clear();
+        }
+        points.finish()
+    }
+
+    /// Path from text with one `x, y` point per line, the content of the files read by `new`.
+    pub fn parse(text: &str) -> Result<Path, Box<dyn Error>> {
+        Path::parse_with(text, Mode::Strict).map(|(path, _)| path)
+    }
+
+    /// Same as `parse`, with the lines that do not hold a point handled as `mode` says, along
+    /// with the lines skipped.
+    pub fn parse_with(text: &str, mode: Mode) -> Result<(Path, Vec<Skipped>), Box<dyn Error>> {
+        let _stage = profile::stage("parse");
+        let mut points = Points::new(mode);
+        for line in text.lines() {
+            points.push(line.as_bytes())?;
+        }
+        points.finish()
+    }
+
+    /// Text with one `x, y` point per line, the format read by `new`.
+    pub fn to_text(&self) -> String {
+        self.data
+            .iter()
+            .map(|point| format!("{}, {}\n", point.re, point.im))
+            .collect()
+    }
+
+    /// JSON array of `[x, y]` points.
+    pub fn to_json(&self) -> String {
+        let points: Vec<String> = self
+            .data
+            .iter()
+            .map(|point| format!("[{}, {}]", point.re, point.im))
+            .collect();
+        format!("[{}]", points.join(", "))
+    }
+
+    /// SVG document drawing this path as a closed outline, the y axis pointing down as in SVG.
+    pub fn to_svg(&self) -> String {
+        let bounds = Bounds::of(&self.data);
+        let commands: Vec<String> = self
+            .data
+            .iter()
+            .enumerate()
+            .map(|(index, point)| {
+                let command = if index == 0 { 'M' } else { 'L' };
+                format!("{}{} {}", command, point.re, -point.im)
+            })
+            .collect();
+        format!(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" viewBox=\"{} {} {} {}\">\n\
+             <path d=\"{} Z\" fill=\"none\" stroke=\"black\" vector-effect=\"non-scaling-stroke\"/>\n\
+             </svg>\n",
+            bounds.min_x,
+            -bounds.max_y,
+            bounds.width(),
+            bounds.height(),
+            commands.join(" ")
+        )
+    }
+
+    /// Length of the path closed back to its first point.
+    pub fn perimeter(&self) -> f64 {
+        let length = self.data.len();
+        (0..length).fold(0.0, |sum, index| {
+            sum + segment_length(complex::Complex::minus(
+                &self.data[(index + 1) % length],
+                &self.data[index],
             ))
         })
-        .map(|val| val.unwrap())
-        .collect();
+    }
+
+    /// Path of `n_points` points evenly spaced along this path, closed back to its first point.
+    pub fn resample(&self, n_points: usize) -> Path {
+        let length = self.data.len();
+        if length == 0 || n_points == 0 {
+            return Path { data: Vec::new() };
+        }
+        let segment = |index: usize| {
+            complex::Complex::minus(&self.data[(index + 1) % length], &self.data[index])
+        };
+        let perimeter = self.perimeter();
+        if perimeter == 0.0 {
+            return Path {
+                data: vec![self.data[0]; n_points],
+            };
+        }
+        let mut data = Vec::with_capacity(n_points);
+        let (mut index, mut start) = (0, 0.0);
+        for k in 0..n_points {
+            let target = perimeter * k as f64 / n_points as f64;
+            while index < length - 1 && start + segment_length(segment(index)) < target {
+                start += segment_length(segment(index));
+                index += 1;
+            }
+            let step = segment(index);
+            let fraction = if segment_length(step) > 0.0 {
+                ((target - start) / segment_length(step)).min(1.0)
+            } else {
+                0.0
+            };
+            data.push(complex::Complex::new(
+                self.data[index].re + step.re * fraction,
+                self.data[index].im + step.im * fraction,
+            ));
+        }
+        Path { data }
+    }
+}
+
+/// Length of `segment`, computed without squaring its sides, which would overflow or underflow for
+/// huge or tiny paths.
+fn segment_length(segment: complex::Complex) -> f64 {
+    segment.re.hypot(segment.im)
+}
+
+/// Axis-aligned bounding box of a set of points.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Bounds {
+    pub min_x: f64,
+    pub min_y: f64,
+    pub max_x: f64,
+    pub max_y: f64,
+}
+
+impl Bounds {
+    /// Bounding box of `points`, a box reduced to the origin when there are none.
+    pub fn of(points: &[complex::Complex]) -> Bounds {
+        Bounds::of_iter(points.iter().copied())
+    }
+
+    /// Same as `of` for points produced one at a time, such as the samples of a reconstruction,
+    /// which are not kept.
+    pub fn of_iter<I>(points: I) -> Bounds
+    where
+        I: IntoIterator<Item = complex::Complex>,
+    {
+        let bounds = points.into_iter().fold(
+            Bounds {
+                min_x: f64::INFINITY,
+                min_y: f64::INFINITY,
+                max_x: f64::NEG_INFINITY,
+                max_y: f64::NEG_INFINITY,
+            },
+            |bounds, point| Bounds {
+                min_x: bounds.min_x.min(point.re),
+                min_y: bounds.min_y.min(point.im),
+                max_x: bounds.max_x.max(point.re),
+                max_y: bounds.max_y.max(point.im),
+            },
+        );
+        if bounds.min_x > bounds.max_x {
+            return Bounds {
+                min_x: 0.0,
+                min_y: 0.0,
+                max_x: 0.0,
+                max_y: 0.0,
+            };
+        }
+        bounds
+    }
+
+    pub fn width(&self) -> f64 {
+        self.max_x - self.min_x
+    }
+
+    pub fn height(&self) -> f64 {
+        self.max_y - self.min_y
+    }
+}
+
+/// Powers of ten represented exactly as `f64`.
+const EXACT_POWERS_OF_TEN: [f64; 23] = [
+    1e0, 1e1, 1e2, 1e3, 1e4, 1e5, 1e6, 1e7, 1e8, 1e9, 1e10, 1e11, 1e12, 1e13, 1e14, 1e15, 1e16,
+    1e17, 1e18, 1e19, 1e20, 1e21, 1e22,
+];
+
+/// How the readers of path files treat the lines that do not hold a point, such as the junk
+/// lines of scraped or traced data.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mode {
+    /// Any such line is an error.
+    Strict,
+    /// Such lines, and points with infinite or NaN coordinates, are skipped and reported, as long
+    /// as points remain on most lines.
+    Lenient,
+}
+
+/// Line skipped by a lenient read.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Skipped {
+    /// Number of the line, from 1.
+    pub line: usize,
+    pub reason: String,
+}
+
+impl fmt::Display for Skipped {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {} skipped: {}", self.line, self.reason)
+    }
+}
+
+/// Points of a path file being read one line at a time.
+struct Points {
+    mode: Mode,
+    data: Vec<complex::Complex>,
+    skipped: Vec<Skipped>,
+    lines: usize,
+}
+
+impl Points {
+    fn new(mode: Mode) -> Points {
+        Points {
+            mode,
+            data: Vec::new(),
+            skipped: Vec::new(),
+            lines: 0,
+        }
+    }
+
+    fn push(&mut self, line: &[u8]) -> Result<(), Box<dyn Error>> {
+        self.lines += 1;
+        let reason = match (parse_point(line), self.mode) {
+            (Ok(point), Mode::Strict) => {
+                self.data.push(point);
+                return Ok(());
+            }
+            (Err(error), Mode::Strict) => {
+                return Err(format!("{} on line {}", error, self.lines).into());
+            }
+            (Ok(point), Mode::Lenient) if point.re.is_finite() && point.im.is_finite() => {
+                self.data.push(point);
+                return Ok(());
+            }
+            (Ok(_), Mode::Lenient) => "infinite or NaN coordinates".to_string(),
+            (Err(_), Mode::Lenient) if trim(line).is_empty() => "blank line".to_string(),
+            (Err(error), Mode::Lenient) => error.to_string(),
+        };
+        self.skipped.push(Skipped {
+            line: self.lines,
+            reason,
+        });
+        Ok(())
+    }
+
+    fn finish(self) -> Result<(Path, Vec<Skipped>), Box<dyn Error>> {
+        if self.data.is_empty() {
+            return Err("No points, expected one `x, y` point per line".into());
+        }
+        if self.skipped.len() > self.data.len() {
+            return Err(format!(
+                "Only {} of {} lines hold points, is it a path file?",
+                self.data.len(),
+                self.lines
+            )
+            .into());
+        }
+        Ok((Path { data: self.data }, self.skipped))
+    }
+}
 
-    Ok(result)
+/// Point written as `x, y` in `line`, whose line ending, if any, is ignored.
+fn parse_point(line: &[u8]) -> Result<complex::Complex, Box<dyn Error>> {
+    let line = trim(line);
+    let comma = match line.iter().position(|byte| *byte == b',') {
+        Some(comma) if !line[comma + 1..].contains(&b',') => comma,
+        _ => return Err("Expected two numbers separated by a comma".into()),
+    };
+    let coordinate = |bytes: &[u8]| {
+        parse_number(trim(bytes))
+            .ok_or_else(|| format!("Invalid number {:?}", String::from_utf8_lossy(trim(bytes))))
+    };
+    Ok(complex::Complex::new(
+        coordinate(&line[..comma])?,
+        coordinate(&line[comma + 1..])?,
+    ))
+}
+
+/// `bytes` without the spaces around them: ASCII whitespace, including the carriage returns of
+/// Windows line endings, Unicode spaces such as the no-break spaces some locales put around
+/// numbers, and the byte order mark some Windows tools start files with.
+fn trim(bytes: &[u8]) -> &[u8] {
+    let trimmed = bytes.trim_ascii();
+    if trimmed.is_ascii() {
+        return trimmed;
+    }
+    match std::str::from_utf8(trimmed) {
+        Ok(text) => text
+            .trim_matches(|character: char| character.is_whitespace() || character == '\u{feff}')
+            .as_bytes(),
+        Err(_) => trimmed,
+    }
+}
+
+/// Number written in decimal in `bytes`, as `str::parse::<f64>` reads it.
+///
+/// Numbers with at most 15 significant digits, such as the usual coordinates, are read directly
+/// from the bytes: their digits make an integer exactly represented as `f64`, which a single
+/// multiplication or division by an exact power of ten then rounds correctly. Others go through
+/// `str::parse`.
+fn parse_number(bytes: &[u8]) -> Option<f64> {
+    let slow = || std::str::from_utf8(bytes).ok()?.parse::<f64>().ok();
+    let (negative, digits) = match bytes.first() {
+        Some(b'-') => (true, &bytes[1..]),
+        Some(b'+') => (false, &bytes[1..]),
+        _ => (false, bytes),
+    };
+    let mut mantissa: u64 = 0;
+    let mut significant = 0;
+    let mut exponent: i64 = 0;
+    let mut seen_digit = false;
+    let mut seen_point = false;
+    let mut index = 0;
+    while index < digits.len() {
+        match digits[index] {
+            digit @ b'0'..=b'9' => {
+                seen_digit = true;
+                if mantissa > 0 || digit != b'0' {
+                    significant += 1;
+                }
+                if significant > 15 {
+                    return slow();
+                }
+                mantissa = mantissa * 10 + u64::from(digit - b'0');
+                if seen_point {
+                    exponent -= 1;
+                }
+            }
+            b'.' if !seen_point => seen_point = true,
+            b'e' | b'E' => break,
+            _ => return slow(),
+        }
+        index += 1;
+    }
+    if !seen_digit {
+        return slow();
+    }
+    if index < digits.len() {
+        // Exponent after the `e`, too large to matter when it does not fit.
+        let written = std::str::from_utf8(&digits[index + 1..])
+            .ok()
+            .and_then(|written| written.parse::<i64>().ok());
+        match written.and_then(|written| exponent.checked_add(written)) {
+            Some(total) => exponent = total,
+            None => return slow(),
+        }
+    }
+    let value = mantissa as f64;
+    let value = if exponent == 0 || mantissa == 0 {
+        value
+    } else if exponent > 0 && exponent < EXACT_POWERS_OF_TEN.len() as i64 {
+        value * EXACT_POWERS_OF_TEN[exponent as usize]
+    } else if exponent < 0 && -exponent < EXACT_POWERS_OF_TEN.len() as i64 {
+        value / EXACT_POWERS_OF_TEN[-exponent as usize]
+    } else {
+        return slow();
+    };
+    Some(if negative { -value } else { value })
+}
+
+/// Corners of the unit square from the origin, counterclockwise, shared by the tests of the
+/// crate.
+#[cfg(test)]
+pub(crate) fn unit_square() -> Path {
+    Path {
+        data: vec![
+            complex::Complex::new(0.0, 0.0),
+            complex::Complex::new(1.0, 0.0),
+            complex::Complex::new(1.0, 1.0),
+            complex::Complex::new(0.0, 1.0),
+        ],
+    }
 }
 
 #[cfg(test)]
@@ -54,4 +428,201 @@ mod tests {
 
         assert_eq!(10, path.data.len());
     }
+
+    #[test]
+    fn parse_test() {
+        let path = Path::parse("0, 1\n2.5,-3\n").unwrap();
+        assert_eq!(
+            vec![
+                complex::Complex::new(0.0, 1.0),
+                complex::Complex::new(2.5, -3.0)
+            ],
+            path.data
+        );
+    }
+
+    #[test]
+    fn read_test() {
+        let text = "0, 1\r\n2.5,-3\n4, 4";
+        let path = Path::read(text.as_bytes()).unwrap();
+        assert_eq!(Path::parse(text).unwrap().data, path.data);
+        assert_eq!(3, path.data.len());
+        assert!(Path::read("0, 1\n2\n".as_bytes()).is_err());
+        assert!(Path::read("".as_bytes()).is_err());
+        assert!(Path::parse("").is_err());
+        assert_eq!(1, Path::parse("3, 4").unwrap().data.len());
+    }
+
+    #[test]
+    fn lenient_test() {
+        let text = "x, y\n0, 0\n1, 0\n\n1, 1\nNaN, 2\n0, 1\n";
+        assert_eq!(
+            "Invalid number \"x\" on line 1",
+            Path::parse(text).unwrap_err().to_string()
+        );
+        let (path, skipped) = Path::parse_with(text, Mode::Lenient).unwrap();
+        assert_eq!(4, path.data.len());
+        assert_eq!(complex::Complex::new(0.0, 1.0), path.data[3]);
+        let lines: Vec<usize> = skipped.iter().map(|skipped| skipped.line).collect();
+        assert_eq!(vec![1, 4, 6], lines);
+        assert_eq!("line 4 skipped: blank line", skipped[1].to_string());
+        assert_eq!(
+            "line 6 skipped: infinite or NaN coordinates",
+            skipped[2].to_string()
+        );
+        let (read, _) = Path::read_with(text.as_bytes(), Mode::Lenient).unwrap();
+        assert_eq!(path.data, read.data);
+
+        // Mostly junk is more likely another kind of file than a path.
+        let error = Path::parse_with("a\nb\n0, 0\n", Mode::Lenient).unwrap_err();
+        assert_eq!(
+            "Only 1 of 3 lines hold points, is it a path file?",
+            error.to_string()
+        );
+        assert!(Path::parse_with("\n\n", Mode::Lenient).is_err());
+    }
+
+    #[test]
+    fn windows_text_test() {
+        let expected = vec![
+            complex::Complex::new(0.0, 1.0),
+            complex::Complex::new(-2.5, 3.0),
+            complex::Complex::new(4.0, 5.0),
+        ];
+        // Byte order mark, Windows line endings, no-break and narrow no-break spaces.
+        let text = "\u{feff}0, 1\r\n\u{a0}-2.5\u{a0},\u{202f}3\r\n4 ,\t5\u{3000}\r\n";
+        assert_eq!(expected, Path::parse(text).unwrap().data);
+        assert_eq!(expected, Path::read(text.as_bytes()).unwrap().data);
+
+        let error = Path::parse("0, 1\r\n2; 3\r\n").unwrap_err();
+        assert_eq!(
+            "Expected two numbers separated by a comma on line 2",
+            error.to_string()
+        );
+        let error = Path::read("0, 1\n1, 2\n1, 2 3\n".as_bytes()).unwrap_err();
+        assert_eq!("Invalid number \"2 3\" on line 3", error.to_string());
+    }
+
+    #[test]
+    fn resample_test() {
+        let square = Path {
+            data: vec![
+                complex::Complex::new(0.0, 0.0),
+                complex::Complex::new(2.0, 0.0),
+                complex::Complex::new(2.0, 2.0),
+                complex::Complex::new(0.0, 2.0),
+            ],
+        };
+        let resampled = square.resample(8);
+        let expected = [
+            (0.0, 0.0),
+            (1.0, 0.0),
+            (2.0, 0.0),
+            (2.0, 1.0),
+            (2.0, 2.0),
+            (1.0, 2.0),
+            (0.0, 2.0),
+            (0.0, 1.0),
+        ];
+        assert_eq!(8.0, square.perimeter());
+        assert_eq!(8, resampled.data.len());
+        for (point, actual) in expected.iter().zip(&resampled.data) {
+            assert!((point.0 - actual.re).abs() < 1e-12 && (point.1 - actual.im).abs() < 1e-12);
+        }
+        assert_eq!(
+            3,
+            Path {
+                data: vec![complex::Complex::new(1.0, 1.0)]
+            }
+            .resample(3)
+            .data
+            .len()
+        );
+        assert!(Path { data: vec![] }.resample(3).data.is_empty());
+    }
+
+    #[test]
+    fn to_svg_test() {
+        let path = Path {
+            data: vec![
+                complex::Complex::new(0.0, 0.0),
+                complex::Complex::new(2.0, 1.0),
+            ],
+        };
+        let document = path.to_svg();
+        assert!(document.contains("viewBox=\"0 -1 2 1\""));
+        assert!(document.contains("d=\"M0 -0 L2 -1 Z\""));
+        assert_eq!(
+            path.data.len(),
+            crate::reader::svg(&document).unwrap().data.len()
+        );
+    }
+
+    #[test]
+    fn to_text_test() {
+        let path = Path {
+            data: vec![
+                complex::Complex::new(0.0, -1.5),
+                complex::Complex::new(2.0, 0.25),
+            ],
+        };
+        assert_eq!("0, -1.5\n2, 0.25\n", path.to_text());
+        assert_eq!("[[0, -1.5], [2, 0.25]]", path.to_json());
+        assert_eq!("[]", Path { data: vec![] }.to_json());
+    }
+
+    #[test]
+    fn parse_number_test() {
+        let numbers = [
+            "0",
+            "-0",
+            "1",
+            "+2.5",
+            "-3.25",
+            "0.1",
+            ".5",
+            "5.",
+            "123.456789",
+            "1e3",
+            "2.5E-4",
+            "-7e+2",
+            "0.000000000",
+            "1.300000000",
+            "9007199254740993",
+            "1234567890.123456789",
+            "1e23",
+            "1e-30",
+            "4.9e-324",
+            "1e400",
+            "inf",
+            "-infinity",
+            "NaN",
+            "00012.5",
+        ];
+        for number in numbers {
+            let expected = number.parse::<f64>().unwrap();
+            let parsed = parse_number(number.as_bytes()).unwrap();
+            if expected.is_nan() {
+                assert!(parsed.is_nan(), "{}", number);
+            } else {
+                assert_eq!(expected.to_bits(), parsed.to_bits(), "{}", number);
+            }
+        }
+        for invalid in [
+            "", "-", ".", "1.2.3", "1e", "1e+", "e5", "1 2", "0x10", "1,5",
+        ] {
+            assert_eq!(None, parse_number(invalid.as_bytes()), "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn parse_point_test() {
+        assert_eq!(
+            complex::Complex::new(1.5, -2.0),
+            parse_point(b"  1.5 ,-2\r\n").unwrap()
+        );
+        assert!(parse_point(b"1.5").is_err());
+        assert!(parse_point(b"1, 2, 3").is_err());
+        assert!(parse_point(b"1, two").is_err());
+    }
 }
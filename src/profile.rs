@@ -0,0 +1,206 @@
+//! Wall time and allocations of the stages of the pipeline (parse, normalize, fft, sort, sample,
+//! draw, encode), recorded with the `profile` feature and summed over the whole process.
+//!
+//! Without the feature, stages record nothing and cost nothing. Allocations are only counted by
+//! programs installing `Counting` as their global allocator, and each stage counts those of its
+//! own thread. Stages nest, a stage including the time and allocations of those it runs.
+
+use std::alloc::{GlobalAlloc, Layout, System};
+#[cfg(feature = "profile")]
+use std::cell::Cell;
+use std::sync::{Mutex, MutexGuard};
+use std::time::Duration;
+#[cfg(feature = "profile")]
+use std::time::Instant;
+
+/// Whether stages are recorded, that is whether the `profile` feature is on.
+pub const ENABLED: bool = cfg!(feature = "profile");
+
+/// Totals of a stage over every time it ran.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Record {
+    pub name: &'static str,
+    pub calls: usize,
+    pub time: Duration,
+    pub allocations: usize,
+    /// Bytes asked for by the allocations.
+    pub bytes: usize,
+}
+
+/// Stays empty without the `profile` feature.
+static RECORDS: Mutex<Vec<Record>> = Mutex::new(Vec::new());
+
+#[cfg(feature = "profile")]
+thread_local! {
+    /// Allocations and bytes allocated by the current thread.
+    static ALLOCATED: Cell<(usize, usize)> = const { Cell::new((0, 0)) };
+}
+
+/// Stage running until dropped.
+#[derive(Debug)]
+pub struct Stage {
+    #[cfg(feature = "profile")]
+    name: &'static str,
+    #[cfg(feature = "profile")]
+    start: Instant,
+    #[cfg(feature = "profile")]
+    allocated: (usize, usize),
+}
+
+/// Starts the stage `name`.
+#[inline]
+pub fn stage(name: &'static str) -> Stage {
+    #[cfg(not(feature = "profile"))]
+    let _ = name;
+    Stage {
+        #[cfg(feature = "profile")]
+        name,
+        #[cfg(feature = "profile")]
+        start: Instant::now(),
+        #[cfg(feature = "profile")]
+        allocated: allocated(),
+    }
+}
+
+#[cfg(feature = "profile")]
+impl Drop for Stage {
+    fn drop(&mut self) {
+        let time = self.start.elapsed();
+        let (allocations, bytes) = allocated();
+        let mut records = locked();
+        let index = match records.iter().position(|record| record.name == self.name) {
+            Some(index) => index,
+            None => {
+                records.push(Record {
+                    name: self.name,
+                    calls: 0,
+                    time: Duration::ZERO,
+                    allocations: 0,
+                    bytes: 0,
+                });
+                records.len() - 1
+            }
+        };
+        let record = &mut records[index];
+        record.calls += 1;
+        record.time += time;
+        record.allocations += allocations - self.allocated.0;
+        record.bytes += bytes - self.allocated.1;
+    }
+}
+
+#[cfg(feature = "profile")]
+fn allocated() -> (usize, usize) {
+    ALLOCATED.try_with(Cell::get).unwrap_or((0, 0))
+}
+
+/// Global allocator counting the allocations of each thread for the stages, forwarding to the
+/// system allocator. Installed with `#[global_allocator]`, it counts nothing without the
+/// `profile` feature.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Counting;
+
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        count(layout.size());
+        System.alloc(layout)
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        count(layout.size());
+        System.alloc_zeroed(layout)
+    }
+
+    unsafe fn realloc(&self, pointer: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        count(new_size);
+        System.realloc(pointer, layout, new_size)
+    }
+
+    unsafe fn dealloc(&self, pointer: *mut u8, layout: Layout) {
+        System.dealloc(pointer, layout)
+    }
+}
+
+#[inline]
+fn count(size: usize) {
+    #[cfg(feature = "profile")]
+    let _ = ALLOCATED.try_with(|allocated| {
+        let (allocations, bytes) = allocated.get();
+        allocated.set((allocations + 1, bytes + size));
+    });
+    #[cfg(not(feature = "profile"))]
+    let _ = size;
+}
+
+/// Records, still usable after a panic while they were locked.
+fn locked() -> MutexGuard<'static, Vec<Record>> {
+    RECORDS
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+/// Totals of the stages run so far, in the order they first ran.
+pub fn records() -> Vec<Record> {
+    locked().clone()
+}
+
+/// Forgets the stages run so far.
+pub fn reset() {
+    locked().clear();
+}
+
+/// Table of the stages run so far, one per line.
+pub fn summary() -> String {
+    let mut summary = format!(
+        "{:<12} {:>8} {:>12} {:>12} {:>14}\n",
+        "stage", "calls", "time (ms)", "allocations", "bytes"
+    );
+    for record in records() {
+        summary.push_str(&format!(
+            "{:<12} {:>8} {:>12.1} {:>12} {:>14}\n",
+            record.name,
+            record.calls,
+            record.time.as_secs_f64() * 1000.0,
+            record.allocations,
+            record.bytes
+        ));
+    }
+    summary
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[cfg(feature = "profile")]
+    #[global_allocator]
+    static ALLOCATOR: Counting = Counting;
+
+    #[test]
+    fn stage_test() {
+        {
+            let _stage = stage("profile test");
+            let data = vec![0u8; 1000];
+            assert_eq!(1000, std::hint::black_box(data).len());
+        }
+        {
+            let _stage = stage("profile test");
+        }
+
+        let records = records();
+        let record = records.iter().find(|record| record.name == "profile test");
+        if ENABLED {
+            let record = record.unwrap();
+            assert_eq!(2, record.calls);
+            assert!(record.allocations >= 1);
+            assert!(record.bytes >= 1000);
+            assert!(summary()
+                .lines()
+                .any(|line| line.starts_with("profile test")));
+        } else {
+            assert_eq!(None, record);
+            assert_eq!(1, summary().lines().count());
+        }
+    }
+}
@@ -0,0 +1,306 @@
+//! Paths and decompositions as protocol buffers following the schema in `proto/fft.proto`, for
+//! web and mobile clients to exchange them using code generated from that schema.
+//!
+//! Messages are written the way proto3 encoders write them, leaving out fields holding their
+//! default value. Fields this version does not know are skipped when reading, while messages of a
+//! newer `VERSION` are rejected.
+
+use crate::complex;
+use crate::epicycle::Epicycle;
+use crate::path;
+use std::convert::{TryFrom, TryInto};
+use std::error::Error;
+
+/// Version written in the messages, the newest one read.
+pub const VERSION: u32 = 1;
+
+/// Wire types of the fields.
+const VARINT: u64 = 0;
+const FIXED64: u64 = 1;
+const BYTES: u64 = 2;
+const FIXED32: u64 = 5;
+
+/// `Path` message holding `path`.
+pub fn encode_path(path: &path::Path) -> Vec<u8> {
+    let mut message = Vec::with_capacity(4 + 20 * path.data.len());
+    write_varint_field(&mut message, 1, u64::from(VERSION));
+    let mut point = Vec::with_capacity(18);
+    for position in &path.data {
+        point.clear();
+        write_double(&mut point, 1, position.re);
+        write_double(&mut point, 2, position.im);
+        write_bytes(&mut message, 2, &point);
+    }
+    message
+}
+
+/// Path held by the `Path` message `data`.
+pub fn decode_path(data: &[u8]) -> Result<path::Path, Box<dyn Error>> {
+    let mut points = Vec::new();
+    for field in Fields::new(data) {
+        match field? {
+            (1, Value::Varint(version)) => check_version(version)?,
+            (2, Value::Bytes(point)) => {
+                let mut position = complex::Complex::new(0.0, 0.0);
+                for field in Fields::new(point) {
+                    match field? {
+                        (1, Value::Fixed64(bits)) => position.re = f64::from_bits(bits),
+                        (2, Value::Fixed64(bits)) => position.im = f64::from_bits(bits),
+                        (number @ 1..=2, _) => return Err(mismatch("Point", number)),
+                        _ => {}
+                    }
+                }
+                points.push(position);
+            }
+            (number @ 1..=2, _) => return Err(mismatch("Path", number)),
+            _ => {}
+        }
+    }
+    Ok(path::Path { data: points })
+}
+
+/// `Epicycle` message holding the terms of `epicycle`.
+pub fn encode_epicycle(epicycle: &Epicycle) -> Vec<u8> {
+    let mut message = Vec::with_capacity(4 + 24 * epicycle.len());
+    write_varint_field(&mut message, 1, u64::from(VERSION));
+    let mut term = Vec::with_capacity(29);
+    for (coefficient, frequency) in epicycle.terms() {
+        term.clear();
+        write_double(&mut term, 1, coefficient.re);
+        write_double(&mut term, 2, coefficient.im);
+        if *frequency != 0 {
+            write_varint_field(&mut term, 3, zigzag(*frequency));
+        }
+        write_bytes(&mut message, 2, &term);
+    }
+    message
+}
+
+/// Decomposition made of the terms of the `Epicycle` message `data`.
+pub fn decode_epicycle(data: &[u8]) -> Result<Epicycle, Box<dyn Error>> {
+    let mut terms = Vec::new();
+    for field in Fields::new(data) {
+        match field? {
+            (1, Value::Varint(version)) => check_version(version)?,
+            (2, Value::Bytes(term)) => {
+                let mut coefficient = complex::Complex::new(0.0, 0.0);
+                let mut frequency = 0;
+                for field in Fields::new(term) {
+                    match field? {
+                        (1, Value::Fixed64(bits)) => coefficient.re = f64::from_bits(bits),
+                        (2, Value::Fixed64(bits)) => coefficient.im = f64::from_bits(bits),
+                        (3, Value::Varint(value)) => frequency = unzigzag(value),
+                        (number @ 1..=3, _) => return Err(mismatch("Term", number)),
+                        _ => {}
+                    }
+                }
+                terms.push((coefficient, frequency));
+            }
+            (number @ 1..=2, _) => return Err(mismatch("Epicycle", number)),
+            _ => {}
+        }
+    }
+    Ok(Epicycle::from_terms(terms))
+}
+
+fn check_version(version: u64) -> Result<(), Box<dyn Error>> {
+    if version > u64::from(VERSION) {
+        return Err(format!(
+            "Unsupported message version {}, expected at most {}",
+            version, VERSION
+        )
+        .into());
+    }
+    Ok(())
+}
+
+fn mismatch(message: &str, number: u64) -> Box<dyn Error> {
+    format!("Unexpected wire type for field {} of {}", number, message).into()
+}
+
+fn zigzag(value: i64) -> u64 {
+    ((value << 1) ^ (value >> 63)) as u64
+}
+
+fn unzigzag(value: u64) -> i64 {
+    (value >> 1) as i64 ^ -((value & 1) as i64)
+}
+
+fn write_varint(output: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        output.push(value as u8 | 0x80);
+        value >>= 7;
+    }
+    output.push(value as u8);
+}
+
+fn write_varint_field(output: &mut Vec<u8>, number: u64, value: u64) {
+    write_varint(output, number << 3 | VARINT);
+    write_varint(output, value);
+}
+
+/// Writes `value` unless it is 0, the default value, keeping -0.
+fn write_double(output: &mut Vec<u8>, number: u64, value: f64) {
+    if value.to_bits() != 0 {
+        write_varint(output, number << 3 | FIXED64);
+        output.extend_from_slice(&value.to_le_bytes());
+    }
+}
+
+fn write_bytes(output: &mut Vec<u8>, number: u64, bytes: &[u8]) {
+    write_varint(output, number << 3 | BYTES);
+    write_varint(output, bytes.len() as u64);
+    output.extend_from_slice(bytes);
+}
+
+/// Value of a field, as given by its wire type.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Value<'a> {
+    Varint(u64),
+    Fixed64(u64),
+    Bytes(&'a [u8]),
+    Fixed32(u32),
+}
+
+/// Numbers and values of the fields of a message, in the order they were written.
+struct Fields<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> Fields<'a> {
+    fn new(data: &'a [u8]) -> Fields<'a> {
+        Fields { data }
+    }
+
+    fn varint(&mut self) -> Result<u64, Box<dyn Error>> {
+        let mut value = 0;
+        for (index, byte) in self.data.iter().enumerate().take(10) {
+            value |= u64::from(byte & 0x7F) << (7 * index);
+            if byte & 0x80 == 0 {
+                self.data = &self.data[index + 1..];
+                return Ok(value);
+            }
+        }
+        Err("Truncated or overlong varint".into())
+    }
+
+    fn take(&mut self, length: usize) -> Result<&'a [u8], Box<dyn Error>> {
+        if length > self.data.len() {
+            return Err(format!(
+                "Truncated message: {} bytes expected, {} left",
+                length,
+                self.data.len()
+            )
+            .into());
+        }
+        let (taken, rest) = self.data.split_at(length);
+        self.data = rest;
+        Ok(taken)
+    }
+
+    fn field(&mut self) -> Result<(u64, Value<'a>), Box<dyn Error>> {
+        let key = self.varint()?;
+        let value = match key & 7 {
+            VARINT => Value::Varint(self.varint()?),
+            FIXED64 => {
+                let bytes = self.take(8)?;
+                Value::Fixed64(u64::from_le_bytes(bytes.try_into().unwrap()))
+            }
+            BYTES => {
+                let length = self.varint()?;
+                Value::Bytes(self.take(usize::try_from(length)?)?)
+            }
+            FIXED32 => {
+                let bytes = self.take(4)?;
+                Value::Fixed32(u32::from_le_bytes(bytes.try_into().unwrap()))
+            }
+            other => return Err(format!("Unsupported wire type {}", other).into()),
+        };
+        Ok((key >> 3, value))
+    }
+}
+
+impl<'a> Iterator for Fields<'a> {
+    type Item = Result<(u64, Value<'a>), Box<dyn Error>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.data.is_empty() {
+            return None;
+        }
+        let field = self.field();
+        if field.is_err() {
+            self.data = &[];
+        }
+        Some(field)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    const SCHEMA: &str = include_str!("../proto/fft.proto");
+
+    #[test]
+    fn schema_test() {
+        for field in &[
+            "double x = 1;",
+            "double y = 2;",
+            "uint32 version = 1;",
+            "repeated Point points = 2;",
+            "double re = 1;",
+            "double im = 2;",
+            "sint64 frequency = 3;",
+            "repeated Term terms = 2;",
+        ] {
+            assert!(SCHEMA.contains(field), "{} is not declared", field);
+        }
+    }
+
+    #[test]
+    fn path_test() {
+        let path = path::Path {
+            data: vec![complex::Complex::new(1.0, 0.0)],
+        };
+        assert_eq!(
+            vec![0x08, 0x01, 0x12, 0x09, 0x09, 0, 0, 0, 0, 0, 0, 0xF0, 0x3F],
+            encode_path(&path)
+        );
+        assert_eq!(
+            path::unit_square().data,
+            decode_path(&encode_path(&path::unit_square()))
+                .unwrap()
+                .data
+        );
+
+        // Unknown fields of every wire type are skipped.
+        let mut message = encode_path(&path);
+        message.extend_from_slice(&[0x18, 0xAC, 0x02, 0x21, 0, 0, 0, 0, 0, 0, 0, 0]);
+        message.extend_from_slice(&[0x2A, 0x01, 0xFF, 0x35, 0, 0, 0, 0]);
+        assert_eq!(path.data, decode_path(&message).unwrap().data);
+
+        assert!(decode_path(&[0x08, 0x02]).is_err());
+        assert!(decode_path(&[0x12, 0x09, 0x09, 0]).is_err());
+        assert!(decode_path(&[0x12, 0x02, 0x08, 0x01]).is_err());
+        assert!(decode_path(&[0x80]).is_err());
+        assert!(decode_path(&[]).unwrap().data.is_empty());
+    }
+
+    #[test]
+    fn epicycle_test() {
+        let epicycle = Epicycle::new(&path::unit_square());
+        let decoded = decode_epicycle(&encode_epicycle(&epicycle)).unwrap();
+        assert_eq!(epicycle.terms(), decoded.terms());
+
+        for value in &[0, 1, -1, 63, -64, i64::MAX, i64::MIN] {
+            assert_eq!(*value, unzigzag(zigzag(*value)));
+        }
+        assert_eq!(1, zigzag(-1));
+        assert_eq!(vec![0xAC, 0x02], {
+            let mut output = Vec::new();
+            write_varint(&mut output, 300);
+            output
+        });
+    }
+}
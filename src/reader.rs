@@ -0,0 +1,457 @@
+//! Readers of paths stored in other formats than the `x, y` text files of `path::Path::new`.
+
+use crate::complex;
+use crate::path;
+use std::error::Error;
+use std::f64::consts::PI;
+
+/// Number of segments each curve and arc of an SVG path is flattened into.
+pub const CURVE_SEGMENTS: usize = 16;
+
+/// Path from a JSON array of `[x, y]` points, as written by `path::Path::to_json`.
+pub fn json(text: &str) -> Result<path::Path, Box<dyn Error>> {
+    let inner = text
+        .trim_start_matches('\u{feff}')
+        .trim()
+        .strip_prefix('[')
+        .and_then(|rest| rest.strip_suffix(']'))
+        .ok_or("Expected a JSON array of [x, y] points")?;
+    let mut data = Vec::new();
+    let mut rest = inner.trim();
+    while !rest.is_empty() {
+        let point = rest
+            .strip_prefix('[')
+            .ok_or_else(|| format!("Expected [x, y], found {}", excerpt(rest)))?;
+        let (pair, after) = point.split_once(']').ok_or("Unterminated [x, y] point")?;
+        let coordinates: Vec<&str> = pair.split(',').map(str::trim).collect();
+        if coordinates.len() != 2 {
+            return Err(format!("Expected [x, y], found [{}]", pair).into());
+        }
+        data.push(complex::Complex::new(
+            coordinates[0].parse()?,
+            coordinates[1].parse()?,
+        ));
+        rest = after.trim_start();
+        rest = rest.strip_prefix(',').unwrap_or(rest).trim_start();
+    }
+    if data.is_empty() {
+        return Err("No points in the JSON array".into());
+    }
+    Ok(path::Path { data })
+}
+
+/// Path made of the outlines of the `<path>`, `<polygon>` and `<polyline>` elements of an SVG
+/// document, one after the other in document order. Curves and arcs are flattened into
+/// `CURVE_SEGMENTS` segments and the y axis is flipped to point up as in path files. Transforms
+/// and other shapes are ignored.
+pub fn svg(text: &str) -> Result<path::Path, Box<dyn Error>> {
+    let mut points = Vec::new();
+    let mut rest = text;
+    while let Some(start) = rest.find('<') {
+        let end = rest[start..]
+            .find('>')
+            .map(|end| start + end)
+            .ok_or("Unterminated SVG element")?;
+        let element = &rest[start + 1..end];
+        let name = element.split_whitespace().next().unwrap_or_default();
+        match name {
+            "path" => {
+                if let Some(data) = attribute(element, "d") {
+                    points.extend(path_data(data)?);
+                }
+            }
+            "polygon" | "polyline" => {
+                if let Some(list) = attribute(element, "points") {
+                    let mut cursor = Cursor::new(list);
+                    while cursor.has_number() {
+                        points.push((cursor.number()?, cursor.number()?));
+                    }
+                }
+            }
+            _ => {}
+        }
+        rest = &rest[end + 1..];
+    }
+    if points.is_empty() {
+        return Err("No path, polygon or polyline found in the SVG document".into());
+    }
+    Ok(path::Path {
+        data: points
+            .into_iter()
+            .map(|(x, y)| complex::Complex::new(x, -y))
+            .collect(),
+    })
+}
+
+/// Value of the attribute `name` of an element, given the text between its angle brackets.
+fn attribute<'a>(element: &'a str, name: &str) -> Option<&'a str> {
+    let mut rest = element;
+    loop {
+        let index = rest.find(name)?;
+        let preceded = rest[..index].ends_with(char::is_whitespace);
+        let after = rest[index + name.len()..].trim_start();
+        if preceded {
+            if let Some(value) = after.strip_prefix('=') {
+                let value = value.trim_start();
+                let quote = value.chars().next()?;
+                if quote == '"' || quote == '\'' {
+                    let value = &value[1..];
+                    return value.find(quote).map(|end| &value[..end]);
+                }
+            }
+        }
+        rest = &rest[index + name.len()..];
+    }
+}
+
+fn excerpt(text: &str) -> String {
+    text.chars().take(20).collect()
+}
+
+/// Reader of the numbers, flags and commands of SVG path data.
+struct Cursor<'a> {
+    text: &'a [u8],
+    position: usize,
+}
+
+impl<'a> Cursor<'a> {
+    fn new(text: &'a str) -> Cursor<'a> {
+        Cursor {
+            text: text.as_bytes(),
+            position: 0,
+        }
+    }
+
+    fn skip_separators(&mut self) {
+        while self.position < self.text.len()
+            && (self.text[self.position].is_ascii_whitespace() || self.text[self.position] == b',')
+        {
+            self.position += 1;
+        }
+    }
+
+    fn peek(&mut self) -> Option<u8> {
+        self.skip_separators();
+        self.text.get(self.position).copied()
+    }
+
+    fn has_number(&mut self) -> bool {
+        matches!(
+            self.peek(),
+            Some(b'0'..=b'9') | Some(b'-') | Some(b'+') | Some(b'.')
+        )
+    }
+
+    fn number(&mut self) -> Result<f64, Box<dyn Error>> {
+        self.skip_separators();
+        let start = self.position;
+        let mut end = start;
+        let bytes = self.text;
+        if end < bytes.len() && (bytes[end] == b'-' || bytes[end] == b'+') {
+            end += 1;
+        }
+        let mut dot = false;
+        while end < bytes.len() && (bytes[end].is_ascii_digit() || (bytes[end] == b'.' && !dot)) {
+            dot |= bytes[end] == b'.';
+            end += 1;
+        }
+        if end < bytes.len() && (bytes[end] == b'e' || bytes[end] == b'E') {
+            let mut exponent = end + 1;
+            if exponent < bytes.len() && (bytes[exponent] == b'-' || bytes[exponent] == b'+') {
+                exponent += 1;
+            }
+            if exponent < bytes.len() && bytes[exponent].is_ascii_digit() {
+                end = exponent;
+                while end < bytes.len() && bytes[end].is_ascii_digit() {
+                    end += 1;
+                }
+            }
+        }
+        let text = std::str::from_utf8(&bytes[start..end])?;
+        let number = text.parse().map_err(|_| {
+            let found = String::from_utf8_lossy(&bytes[start..bytes.len().min(start + 20)]);
+            format!("Expected a number in SVG path data, found {}", found)
+        })?;
+        self.position = end;
+        Ok(number)
+    }
+
+    /// Arc flag, which may be written without a separator before the next number.
+    fn flag(&mut self) -> Result<bool, Box<dyn Error>> {
+        match self.peek() {
+            Some(b'0') => {
+                self.position += 1;
+                Ok(false)
+            }
+            Some(b'1') => {
+                self.position += 1;
+                Ok(true)
+            }
+            _ => Err("Expected an arc flag, 0 or 1, in SVG path data".into()),
+        }
+    }
+
+    fn point(&mut self) -> Result<(f64, f64), Box<dyn Error>> {
+        Ok((self.number()?, self.number()?))
+    }
+}
+
+/// Points of the outline described by the `d` attribute of an SVG path.
+fn path_data(data: &str) -> Result<Vec<(f64, f64)>, Box<dyn Error>> {
+    let mut cursor = Cursor::new(data);
+    let mut points = Vec::new();
+    let mut current = (0.0, 0.0);
+    let mut start = (0.0, 0.0);
+    // Control point of the last curve, for the smooth curve commands.
+    let mut control: Option<(char, (f64, f64))> = None;
+    let mut command = None;
+    while let Some(next) = cursor.peek() {
+        if next.is_ascii_alphabetic() {
+            cursor.position += 1;
+            command = Some(next as char);
+        } else if command.is_none() {
+            return Err("SVG path data must start with a command".into());
+        }
+        let letter = command.unwrap();
+        let relative = letter.is_ascii_lowercase();
+        let offset = |point: (f64, f64), current: (f64, f64)| {
+            if relative {
+                (point.0 + current.0, point.1 + current.1)
+            } else {
+                point
+            }
+        };
+        let mut last_control = None;
+        match letter.to_ascii_uppercase() {
+            'M' => {
+                current = offset(cursor.point()?, current);
+                start = current;
+                points.push(current);
+                // Further pairs are implicit line commands.
+                command = Some(if relative { 'l' } else { 'L' });
+            }
+            'L' => {
+                current = offset(cursor.point()?, current);
+                points.push(current);
+            }
+            'H' => {
+                let x = cursor.number()?;
+                current.0 = if relative { current.0 + x } else { x };
+                points.push(current);
+            }
+            'V' => {
+                let y = cursor.number()?;
+                current.1 = if relative { current.1 + y } else { y };
+                points.push(current);
+            }
+            'C' | 'S' => {
+                let first = if letter.eq_ignore_ascii_case(&'C') {
+                    offset(cursor.point()?, current)
+                } else {
+                    reflect(control, 'C', current)
+                };
+                let second = offset(cursor.point()?, current);
+                let end = offset(cursor.point()?, current);
+                for step in 1..=CURVE_SEGMENTS {
+                    let t = step as f64 / CURVE_SEGMENTS as f64;
+                    let u = 1.0 - t;
+                    let weights = [u * u * u, 3.0 * u * u * t, 3.0 * u * t * t, t * t * t];
+                    points.push(combine(&[current, first, second, end], &weights));
+                }
+                last_control = Some(('C', second));
+                current = end;
+            }
+            'Q' | 'T' => {
+                let middle = if letter.eq_ignore_ascii_case(&'Q') {
+                    offset(cursor.point()?, current)
+                } else {
+                    reflect(control, 'Q', current)
+                };
+                let end = offset(cursor.point()?, current);
+                for step in 1..=CURVE_SEGMENTS {
+                    let t = step as f64 / CURVE_SEGMENTS as f64;
+                    let u = 1.0 - t;
+                    points.push(combine(
+                        &[current, middle, end],
+                        &[u * u, 2.0 * u * t, t * t],
+                    ));
+                }
+                last_control = Some(('Q', middle));
+                current = end;
+            }
+            'A' => {
+                let radii = (cursor.number()?, cursor.number()?);
+                let rotation = cursor.number()?;
+                let large = cursor.flag()?;
+                let sweep = cursor.flag()?;
+                let end = offset(cursor.point()?, current);
+                points.extend(arc(current, radii, rotation, large, sweep, end));
+                current = end;
+            }
+            'Z' => {
+                current = start;
+                // Closing takes no arguments, a number after it starts an implicit line.
+                command = Some(if relative { 'l' } else { 'L' });
+            }
+            other => return Err(format!("Unsupported SVG path command {}", other).into()),
+        }
+        control = last_control;
+    }
+    Ok(points)
+}
+
+/// First control point of a smooth curve: the reflection of the last control point of the
+/// previous curve of the same `kind` about `current`, or `current` itself.
+fn reflect(control: Option<(char, (f64, f64))>, kind: char, current: (f64, f64)) -> (f64, f64) {
+    match control {
+        Some((previous, point)) if previous == kind => {
+            (2.0 * current.0 - point.0, 2.0 * current.1 - point.1)
+        }
+        _ => current,
+    }
+}
+
+fn combine(points: &[(f64, f64)], weights: &[f64]) -> (f64, f64) {
+    points
+        .iter()
+        .zip(weights)
+        .fold((0.0, 0.0), |(x, y), (point, weight)| {
+            (x + point.0 * weight, y + point.1 * weight)
+        })
+}
+
+/// Points of an elliptical arc from `from` to `to`, following the endpoint parameterization of
+/// the SVG specification, without `from`.
+fn arc(
+    from: (f64, f64),
+    radii: (f64, f64),
+    rotation: f64,
+    large: bool,
+    sweep: bool,
+    to: (f64, f64),
+) -> Vec<(f64, f64)> {
+    let (mut rx, mut ry) = (radii.0.abs(), radii.1.abs());
+    if rx == 0.0 || ry == 0.0 || from == to {
+        return vec![to];
+    }
+    let phi = rotation.to_radians();
+    let (sin, cos) = phi.sin_cos();
+    let (dx, dy) = ((from.0 - to.0) / 2.0, (from.1 - to.1) / 2.0);
+    let (x1, y1) = (cos * dx + sin * dy, -sin * dx + cos * dy);
+    let lambda = (x1 * x1) / (rx * rx) + (y1 * y1) / (ry * ry);
+    if lambda > 1.0 {
+        rx *= lambda.sqrt();
+        ry *= lambda.sqrt();
+    }
+    let numerator = rx * rx * ry * ry - rx * rx * y1 * y1 - ry * ry * x1 * x1;
+    let denominator = rx * rx * y1 * y1 + ry * ry * x1 * x1;
+    let sign = if large == sweep { -1.0 } else { 1.0 };
+    let coefficient = sign * (numerator / denominator).max(0.0).sqrt();
+    let (cx1, cy1) = (coefficient * rx * y1 / ry, -coefficient * ry * x1 / rx);
+    let center = (
+        cos * cx1 - sin * cy1 + (from.0 + to.0) / 2.0,
+        sin * cx1 + cos * cy1 + (from.1 + to.1) / 2.0,
+    );
+    let angle = |u: (f64, f64), v: (f64, f64)| (u.0 * v.1 - u.1 * v.0).atan2(u.0 * v.0 + u.1 * v.1);
+    let start = ((x1 - cx1) / rx, (y1 - cy1) / ry);
+    let end = ((-x1 - cx1) / rx, (-y1 - cy1) / ry);
+    let theta = angle((1.0, 0.0), start);
+    let mut delta = angle(start, end);
+    if !sweep && delta > 0.0 {
+        delta -= 2.0 * PI;
+    } else if sweep && delta < 0.0 {
+        delta += 2.0 * PI;
+    }
+    let mut points: Vec<(f64, f64)> = (1..CURVE_SEGMENTS)
+        .map(|step| {
+            let t = theta + delta * step as f64 / CURVE_SEGMENTS as f64;
+            let (x, y) = (rx * t.cos(), ry * t.sin());
+            (center.0 + cos * x - sin * y, center.1 + sin * x + cos * y)
+        })
+        .collect();
+    points.push(to);
+    points
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    fn assert_points(expected: &[(f64, f64)], path: &path::Path) {
+        assert_eq!(expected.len(), path.data.len(), "{:?}", path.data);
+        for (point, actual) in expected.iter().zip(&path.data) {
+            assert!(
+                (point.0 - actual.re).abs() < 1e-9 && (point.1 - actual.im).abs() < 1e-9,
+                "expected {:?}, found {:?}",
+                point,
+                actual
+            );
+        }
+    }
+
+    #[test]
+    fn json_test() {
+        let path = json(" [[0, -1.5], [2, 0.25] ,[3e2,4]]\n").unwrap();
+        assert_points(&[(0.0, -1.5), (2.0, 0.25), (300.0, 4.0)], &path);
+        assert!(json("[]").is_err());
+        assert_points(&[(1.0, 2.0)], &json("\u{feff}[[1, 2]]\r\n").unwrap());
+        assert!(json("[[1, 2, 3]]").is_err());
+        assert!(json("{\"x\": 1}").is_err());
+    }
+
+    #[test]
+    fn svg_lines_test() {
+        let document = "<svg xmlns=\"http://www.w3.org/2000/svg\">\n\
+                        <path id='d' d=\"M0 0 H10 v10 l-10,0 Z m1-1 1-1\"/>\n\
+                        <polygon points='0,0 1,1'/><circle r='4'/></svg>";
+        let path = svg(document).unwrap();
+        assert_points(
+            &[
+                (0.0, 0.0),
+                (10.0, 0.0),
+                (10.0, -10.0),
+                (0.0, -10.0),
+                (1.0, 1.0),
+                (2.0, 2.0),
+                (0.0, 0.0),
+                (1.0, -1.0),
+            ],
+            &path,
+        );
+        assert!(svg("<svg><circle r='4'/></svg>").is_err());
+        assert!(svg("<svg><path d='M 0 0 X 1'/></svg>").is_err());
+    }
+
+    #[test]
+    fn svg_curves_test() {
+        let path = svg("<path d='M0,0 Q5,10 10,0 T20,0'/>").unwrap();
+        assert_eq!(1 + 2 * CURVE_SEGMENTS, path.data.len());
+        // The middle of the first curve and, reflected, of the second.
+        assert_points(
+            &[(5.0, -5.0)],
+            &path::Path {
+                data: vec![path.data[CURVE_SEGMENTS / 2]],
+            },
+        );
+        let second = path.data[CURVE_SEGMENTS + CURVE_SEGMENTS / 2];
+        assert!((second.re - 15.0).abs() < 1e-9 && (second.im - 5.0).abs() < 1e-9);
+
+        let path = svg("<path d='M0,0 C0,10 10,10 10,0'/>").unwrap();
+        let middle = path.data[CURVE_SEGMENTS / 2];
+        assert!((middle.re - 5.0).abs() < 1e-9 && (middle.im + 7.5).abs() < 1e-9);
+    }
+
+    #[test]
+    fn svg_arc_test() {
+        // Half circle of radius 5 from (0, 0) to (10, 0) through (5, -5) in SVG coordinates.
+        let path = svg("<path d='M0 0A5 5 0 0110 0'/>").unwrap();
+        assert_eq!(1 + CURVE_SEGMENTS, path.data.len());
+        for point in &path.data {
+            let distance = ((point.re - 5.0).powi(2) + point.im.powi(2)).sqrt();
+            assert!((distance - 5.0).abs() < 1e-9);
+        }
+        let middle = path.data[CURVE_SEGMENTS / 2];
+        assert!((middle.re - 5.0).abs() < 1e-9 && (middle.im - 5.0).abs() < 1e-9);
+    }
+}
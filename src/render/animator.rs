@@ -0,0 +1,400 @@
+use crate::epicycle;
+use crate::profile;
+use crate::render;
+use crate::render::canvas::Canvas;
+use crate::render::{Projection, RenderConfig};
+use std::f64::consts::PI;
+
+/// Time spent drawing a segment of the trace per radian the trace turns at its start, relative
+/// to the time spent on a straight segment, when slowing down at corners.
+const CORNER_SLOWDOWN: f64 = 8.0;
+
+/// Produces the frames of the animation of a reconstruction being traced over one full turn.
+///
+/// The canvases are allocated once and reused for every frame, which is why frames are borrowed
+/// one at a time instead of being returned by an `Iterator`.
+pub struct Animator {
+    epicycle: epicycle::Epicycle,
+    precision: usize,
+    config: RenderConfig,
+    projection: Projection,
+    projected: Vec<(f64, f64)>,
+    /// Terms turned from one frame to the next when drawing the circles.
+    phasors: Option<epicycle::Phasors>,
+    /// Circles of the current frame, kept to reuse their allocation.
+    circles: Vec<epicycle::Circle>,
+    /// Annotation of the current frame, kept for the same reason.
+    annotation: String,
+    /// Cumulative share of the animation time spent reaching each point of the trace, when the
+    /// pen slows down at corners.
+    schedule: Option<Vec<f64>>,
+    background: Canvas,
+    trace: Canvas,
+    canvas: Canvas,
+    /// Frame scaled down to the requested size when supersampling.
+    output: Option<Canvas>,
+    supersampling: u32,
+    frame_count: usize,
+    frame: usize,
+    drawn: usize,
+    /// Whether the segment closing the trace has been drawn.
+    closed: bool,
+}
+
+impl Animator {
+    pub fn new(
+        epicycle: &epicycle::Epicycle,
+        precision: usize,
+        config: &RenderConfig,
+        frame_count: usize,
+    ) -> Animator {
+        let output = if config.supersampling > 1 {
+            Some(Canvas::new(config.width, config.height, config.background))
+        } else {
+            None
+        };
+        let supersampling = config.supersampling;
+        let config = &config.supersampled().with_caption_for(epicycle, precision);
+        let points = epicycle.sample(precision, render::TRACE_SAMPLES);
+        let projection = config.projection(&points);
+        let projected: Vec<(f64, f64)> = points.iter().map(|p| projection.project(p)).collect();
+        let schedule = if config.timing.slow_at_corners {
+            Some(schedule(&projected))
+        } else {
+            None
+        };
+
+        let mut background = Canvas::new(config.width, config.height, config.background);
+        render::draw_background(&mut background, &projection, config);
+
+        let phasors = if config.circles {
+            let step = 2.0 * PI / projected.len().max(1) as f64;
+            Some(epicycle::Phasors::new(epicycle, precision, step))
+        } else {
+            None
+        };
+        Animator {
+            epicycle: epicycle.clone(),
+            precision,
+            config: config.clone(),
+            projection,
+            projected,
+            phasors,
+            circles: Vec::new(),
+            annotation: String::new(),
+            schedule,
+            trace: background.clone(),
+            canvas: background.clone(),
+            background,
+            output,
+            supersampling,
+            frame_count: frame_count.max(1),
+            frame: 0,
+            drawn: 0,
+            closed: false,
+        }
+    }
+
+    pub fn frame_count(&self) -> usize {
+        self.frame_count
+    }
+
+    /// Width and height of the frames.
+    pub fn frame_size(&self) -> (u32, u32) {
+        let frame = self.output.as_ref().unwrap_or(&self.canvas);
+        (frame.width(), frame.height())
+    }
+
+    /// Time of the pen on the last frame drawn, between 0 and 2π.
+    pub fn time(&self) -> f64 {
+        2.0 * PI * self.drawn as f64 / self.projected.len().max(1) as f64
+    }
+
+    /// Number of points of the trace reached by the pen at the current frame.
+    fn target(&self) -> usize {
+        let progress = self.config.timing.progress(self.frame, self.frame_count);
+        let length = self.projected.len();
+        match &self.schedule {
+            Some(schedule) => schedule.iter().take_while(|time| **time < progress).count() + 1,
+            None => (length as f64 * progress).round() as usize,
+        }
+        .min(length)
+    }
+
+    /// Draws the next frame, or returns `None` once every frame has been produced.
+    pub fn next_frame(&mut self) -> Option<&Canvas> {
+        if self.frame >= self.frame_count {
+            return None;
+        }
+        let _stage = profile::stage("draw");
+        self.advance_trace();
+        let config = &self.config;
+        match config.trail {
+            render::Trail::Full => self.canvas.copy_from(&self.trace),
+            render::Trail::Fade(length) => {
+                self.canvas.copy_from(&self.background);
+                let visible = &self.projected[..=self.drawn];
+                render::draw_fading_trace(
+                    &mut self.canvas,
+                    visible,
+                    self.projected.len(),
+                    length,
+                    config,
+                );
+            }
+        }
+
+        let t = self.time();
+        if let Some(phasors) = &mut self.phasors {
+            phasors.move_to(self.drawn);
+            phasors.circles_into(&mut self.circles);
+            render::draw_circles(&mut self.canvas, &self.projection, &self.circles, config);
+        }
+        render::draw_caption(&mut self.canvas, config);
+        if config.annotate {
+            render::annotation_into(&self.epicycle, self.precision, t, &mut self.annotation);
+            render::draw_annotation(&mut self.canvas, &self.annotation, config);
+        }
+
+        self.frame += 1;
+        match &mut self.output {
+            Some(output) => {
+                self.canvas.downsample_into(self.supersampling, output);
+                Some(output)
+            }
+            None => Some(&self.canvas),
+        }
+    }
+
+    /// Moves past the next `frames` frames without drawing them, as when resuming an export
+    /// whose first frames were already written. Later frames are the same as without skipping.
+    pub fn skip(&mut self, frames: usize) {
+        for _ in 0..frames.min(self.frame_count - self.frame) {
+            self.advance_trace();
+            self.frame += 1;
+        }
+    }
+
+    /// Moves the pen to its position at the current frame, extending the full trace kept from
+    /// one frame to the next.
+    fn advance_trace(&mut self) {
+        let target = self.target();
+        let config = &self.config;
+        let projected = &self.projected;
+        if let render::Trail::Fade(_) = config.trail {
+            self.drawn = target.max(1) - 1;
+            return;
+        }
+        let color = config.palette.trace;
+        if target < self.drawn + 1 {
+            self.trace.copy_from(&self.background);
+            self.drawn = 0;
+            self.closed = false;
+        }
+        while self.drawn + 1 < target {
+            let segment = (projected[self.drawn], projected[self.drawn + 1]);
+            self.trace
+                .draw_line(segment.0, segment.1, color, config.line_width);
+            self.drawn += 1;
+        }
+        if target == projected.len() && projected.len() > 1 && !self.closed {
+            let segment = (projected[projected.len() - 1], projected[0]);
+            self.trace
+                .draw_line(segment.0, segment.1, color, config.line_width);
+            self.closed = true;
+        }
+    }
+}
+
+/// Cumulative share of the animation time at which the pen reaches each point of `points`, each
+/// segment taking longer the more the trace turns at its start.
+fn schedule(points: &[(f64, f64)]) -> Vec<f64> {
+    let length = points.len();
+    let heading = |from: (f64, f64), to: (f64, f64)| (to.1 - from.1).atan2(to.0 - from.0);
+    let mut times = Vec::with_capacity(length);
+    let mut total = 0.0;
+    for index in 0..length {
+        times.push(total);
+        let previous = points[(index + length - 1) % length];
+        let (current, next) = (points[index], points[(index + 1) % length]);
+        let mut turn = (heading(current, next) - heading(previous, current)).abs();
+        if turn > PI {
+            turn = 2.0 * PI - turn;
+        }
+        total += 1.0 + CORNER_SLOWDOWN * turn;
+    }
+    if total > 0.0 {
+        for time in times.iter_mut() {
+            *time /= total;
+        }
+    }
+    times
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::complex;
+    use crate::path;
+    use crate::render::Color;
+
+    #[test]
+    fn next_frame_test() {
+        let epicycle = epicycle::Epicycle::new(&path::Path {
+            data: vec![
+                complex::Complex::new(0.0, 0.0),
+                complex::Complex::new(1.0, 0.0),
+                complex::Complex::new(1.0, 1.0),
+                complex::Complex::new(0.0, 1.0),
+            ],
+        });
+        let config = RenderConfig {
+            width: 32,
+            height: 32,
+            ..RenderConfig::default()
+        };
+        let mut animator = Animator::new(&epicycle, 4, &config, 3);
+        let mut inked = Vec::new();
+        while let Some(canvas) = animator.next_frame() {
+            inked.push(
+                canvas
+                    .pixels()
+                    .iter()
+                    .filter(|p| **p != Color::WHITE)
+                    .count(),
+            );
+        }
+        assert_eq!(3, inked.len());
+        assert!(inked[0] > 0);
+        assert!(inked[0] < inked[1] && inked[1] < inked[2]);
+        assert!(animator.next_frame().is_none());
+    }
+
+    #[test]
+    fn skip_test() {
+        let epicycle = epicycle::Epicycle::new(&path::Path {
+            data: vec![
+                complex::Complex::new(0.0, 0.0),
+                complex::Complex::new(1.0, 0.0),
+                complex::Complex::new(1.0, 1.0),
+                complex::Complex::new(0.0, 1.0),
+            ],
+        });
+        for trail in &[render::Trail::Full, render::Trail::Fade(0.25)] {
+            let config = RenderConfig {
+                width: 32,
+                height: 32,
+                trail: *trail,
+                ..RenderConfig::default()
+            };
+            let mut animator = Animator::new(&epicycle, 4, &config, 6);
+            let mut frames = Vec::new();
+            while let Some(canvas) = animator.next_frame() {
+                frames.push(canvas.pixels().to_vec());
+            }
+            let mut resumed = Animator::new(&epicycle, 4, &config, 6);
+            resumed.skip(4);
+            assert_eq!(frames[4], resumed.next_frame().unwrap().pixels());
+            assert_eq!(frames[5], resumed.next_frame().unwrap().pixels());
+            resumed.skip(1);
+            assert!(resumed.next_frame().is_none());
+        }
+    }
+
+    #[test]
+    fn supersampled_frame_test() {
+        let epicycle = epicycle::Epicycle::new(&path::Path {
+            data: vec![
+                complex::Complex::new(0.0, 0.0),
+                complex::Complex::new(1.0, 0.0),
+                complex::Complex::new(0.0, 1.0),
+            ],
+        });
+        let config = RenderConfig {
+            width: 16,
+            height: 12,
+            supersampling: 4,
+            ..RenderConfig::default()
+        };
+        let mut animator = Animator::new(&epicycle, 3, &config, 1);
+        let canvas = animator.next_frame().unwrap();
+        assert_eq!((16, 12), (canvas.width(), canvas.height()));
+        let gray = |p: &&Color| **p != Color::WHITE && **p != Color::BLACK;
+        assert!(canvas.pixels().iter().any(|p| gray(&p)));
+    }
+
+    #[test]
+    fn annotated_frame_test() {
+        let epicycle = epicycle::Epicycle::new(&path::Path {
+            data: vec![
+                complex::Complex::new(0.0, 0.0),
+                complex::Complex::new(1.0, 0.0),
+                complex::Complex::new(0.0, 1.0),
+            ],
+        });
+        let config = RenderConfig {
+            width: 200,
+            height: 100,
+            annotate: true,
+            ..RenderConfig::default()
+        };
+        let mut animator = Animator::new(&epicycle, 1, &config, 1);
+        let canvas = animator.next_frame().unwrap();
+        let bottom_rows = &canvas.pixels()[200 * 85..200 * 96];
+        assert!(bottom_rows.contains(&config.palette.text));
+    }
+
+    #[test]
+    fn schedule_test() {
+        let points = vec![
+            (0.0, 0.0),
+            (1.0, 0.0),
+            (2.0, 0.0),
+            (2.0, 1.0),
+            (2.0, 2.0),
+            (0.0, 2.0),
+        ];
+        let times = schedule(&points);
+        assert_eq!(0.0, times[0]);
+        // The segment after the corner at (2, 0) takes longer than the one reaching it.
+        assert!(times[3] - times[2] > times[2] - times[1]);
+        assert!(times.windows(2).all(|pair| pair[0] < pair[1]));
+    }
+
+    #[test]
+    fn ping_pong_test() {
+        let epicycle = epicycle::Epicycle::new(&path::Path {
+            data: vec![
+                complex::Complex::new(0.0, 0.0),
+                complex::Complex::new(1.0, 0.0),
+                complex::Complex::new(1.0, 1.0),
+                complex::Complex::new(0.0, 1.0),
+            ],
+        });
+        let config = RenderConfig {
+            width: 32,
+            height: 32,
+            timing: render::Timing {
+                ping_pong: true,
+                slow_at_corners: true,
+                easing: render::Easing::EaseInOut,
+            },
+            ..RenderConfig::default()
+        };
+        let mut animator = Animator::new(&epicycle, 4, &config, 4);
+        let mut inked = Vec::new();
+        while let Some(canvas) = animator.next_frame() {
+            inked.push(
+                canvas
+                    .pixels()
+                    .iter()
+                    .filter(|p| **p != Color::WHITE)
+                    .count(),
+            );
+        }
+        assert!(inked[0] < inked[1]);
+        assert!(inked[2] < inked[1]);
+        assert!(inked[3] <= 1);
+    }
+}
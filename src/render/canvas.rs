@@ -0,0 +1,280 @@
+use crate::render::font;
+use crate::render::Color;
+
+/// In-memory RGB raster image.
+#[derive(Debug, Clone)]
+pub struct Canvas {
+    width: u32,
+    height: u32,
+    pixels: Vec<Color>,
+}
+
+impl Canvas {
+    pub fn new(width: u32, height: u32, background: Color) -> Canvas {
+        Canvas {
+            width,
+            height,
+            pixels: vec![background; width as usize * height as usize],
+        }
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// Pixels in row-major order, starting from the top left corner.
+    pub fn pixels(&self) -> &[Color] {
+        &self.pixels
+    }
+
+    /// Pixels as RGBA bytes in row-major order, fully opaque.
+    pub fn to_rgba(&self) -> Vec<u8> {
+        let mut output = Vec::with_capacity(self.pixels.len() * 4);
+        for pixel in &self.pixels {
+            output.extend_from_slice(&[pixel.r, pixel.g, pixel.b, 255]);
+        }
+        output
+    }
+
+    pub fn fill(&mut self, color: Color) {
+        for pixel in self.pixels.iter_mut() {
+            *pixel = color;
+        }
+    }
+
+    /// Copies the content of a canvas of the same size, reusing the pixel buffer.
+    pub fn copy_from(&mut self, other: &Canvas) {
+        self.pixels.copy_from_slice(&other.pixels);
+    }
+
+    /// Copies `other` with its top left corner at `(x, y)`, clipping what falls outside.
+    pub fn draw_canvas(&mut self, other: &Canvas, x: u32, y: u32) {
+        let width = other.width.min(self.width.saturating_sub(x)) as usize;
+        for row in 0..other.height.min(self.height.saturating_sub(y)) {
+            let from = row as usize * other.width as usize;
+            let to = (y + row) as usize * self.width as usize + x as usize;
+            self.pixels[to..to + width].copy_from_slice(&other.pixels[from..from + width]);
+        }
+    }
+
+    /// Scales this canvas down by `factor` into `output`, each output pixel being the average of a
+    /// `factor` by `factor` block. Blocks falling outside of this canvas are left unchanged.
+    pub fn downsample_into(&self, factor: u32, output: &mut Canvas) {
+        let factor = factor.max(1);
+        let width = output.width.min(self.width / factor);
+        let height = output.height.min(self.height / factor);
+        let count = factor * factor;
+        for y in 0..height {
+            for x in 0..width {
+                let (mut r, mut g, mut b) = (0u32, 0u32, 0u32);
+                for dy in 0..factor {
+                    for dx in 0..factor {
+                        let pixel = self.pixel(x * factor + dx, y * factor + dy);
+                        r += u32::from(pixel.r);
+                        g += u32::from(pixel.g);
+                        b += u32::from(pixel.b);
+                    }
+                }
+                let average = |sum: u32| ((sum + count / 2) / count) as u8;
+                let index = y as usize * output.width as usize + x as usize;
+                output.pixels[index] = Color::new(average(r), average(g), average(b));
+            }
+        }
+    }
+
+    pub fn pixel(&self, x: u32, y: u32) -> Color {
+        self.pixels[y as usize * self.width as usize + x as usize]
+    }
+
+    /// Sets a pixel, ignoring coordinates falling outside of the canvas.
+    pub fn set_pixel(&mut self, x: i64, y: i64, color: Color) {
+        if x < 0 || y < 0 || x >= i64::from(self.width) || y >= i64::from(self.height) {
+            return;
+        }
+        self.pixels[y as usize * self.width as usize + x as usize] = color;
+    }
+
+    /// Draws a line of the given width in pixels using Bresenham's algorithm.
+    pub fn draw_line(&mut self, from: (f64, f64), to: (f64, f64), color: Color, width: f64) {
+        let (mut x, mut y) = (from.0.round() as i64, from.1.round() as i64);
+        let (end_x, end_y) = (to.0.round() as i64, to.1.round() as i64);
+        let dx = (end_x - x).abs();
+        let dy = -(end_y - y).abs();
+        let step_x = if x < end_x { 1 } else { -1 };
+        let step_y = if y < end_y { 1 } else { -1 };
+        let mut error = dx + dy;
+        loop {
+            if width > 1.0 {
+                self.fill_disc((x as f64, y as f64), width / 2.0, color);
+            } else {
+                self.set_pixel(x, y, color);
+            }
+            if x == end_x && y == end_y {
+                break;
+            }
+            let doubled = 2 * error;
+            if doubled >= dy {
+                error += dy;
+                x += step_x;
+            }
+            if doubled <= dx {
+                error += dx;
+                y += step_y;
+            }
+        }
+    }
+
+    /// Draws `text` with its top left corner at `position`, each font pixel being a `scale` by
+    /// `scale` square.
+    pub fn draw_text(&mut self, position: (i64, i64), text: &str, color: Color, scale: u32) {
+        let scale = i64::from(scale.max(1));
+        let advance = i64::from(font::GLYPH_WIDTH + font::SPACING) * scale;
+        for (index, character) in text.chars().enumerate() {
+            let left = position.0 + index as i64 * advance;
+            for (column, bits) in font::glyph(character).iter().enumerate() {
+                for row in 0..font::GLYPH_HEIGHT as i64 {
+                    if bits & (1 << row) == 0 {
+                        continue;
+                    }
+                    for dy in 0..scale {
+                        for dx in 0..scale {
+                            let x = left + column as i64 * scale + dx;
+                            self.set_pixel(x, position.1 + row * scale + dy, color);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    /// Fills a disc of the given radius, in pixels.
+    pub fn fill_disc(&mut self, center: (f64, f64), radius: f64, color: Color) {
+        let extent = radius.ceil() as i64;
+        let (center_x, center_y) = (center.0.round() as i64, center.1.round() as i64);
+        for dy in -extent..=extent {
+            for dx in -extent..=extent {
+                if ((dx * dx + dy * dy) as f64) <= radius * radius {
+                    self.set_pixel(center_x + dx, center_y + dy, color);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn set_pixel_clipping_test() {
+        let mut canvas = Canvas::new(2, 2, Color::WHITE);
+        canvas.set_pixel(-1, 0, Color::BLACK);
+        canvas.set_pixel(2, 1, Color::BLACK);
+        canvas.set_pixel(1, 1, Color::BLACK);
+        assert_eq!(Color::WHITE, canvas.pixel(0, 0));
+        assert_eq!(Color::BLACK, canvas.pixel(1, 1));
+        assert_eq!(
+            1,
+            canvas
+                .pixels()
+                .iter()
+                .filter(|p| **p == Color::BLACK)
+                .count()
+        );
+    }
+
+    #[test]
+    fn to_rgba_test() {
+        let mut canvas = Canvas::new(2, 1, Color::WHITE);
+        canvas.set_pixel(1, 0, Color::new(1, 2, 3));
+        assert_eq!(vec![255, 255, 255, 255, 1, 2, 3, 255], canvas.to_rgba());
+    }
+
+    #[test]
+    fn fill_and_copy_test() {
+        let mut canvas = Canvas::new(3, 2, Color::WHITE);
+        let mut other = Canvas::new(3, 2, Color::WHITE);
+        other.fill(Color::BLACK);
+        canvas.copy_from(&other);
+        assert!(canvas.pixels().iter().all(|p| *p == Color::BLACK));
+    }
+
+    #[test]
+    fn draw_canvas_test() {
+        let mut canvas = Canvas::new(4, 3, Color::WHITE);
+        let other = Canvas::new(2, 2, Color::BLACK);
+        canvas.draw_canvas(&other, 3, 2);
+        let black = canvas
+            .pixels()
+            .iter()
+            .filter(|p| **p == Color::BLACK)
+            .count();
+        assert_eq!(1, black);
+        assert_eq!(Color::BLACK, canvas.pixel(3, 2));
+    }
+
+    #[test]
+    fn draw_wide_line_test() {
+        let mut canvas = Canvas::new(7, 5, Color::WHITE);
+        canvas.draw_line((1.0, 2.0), (5.0, 2.0), Color::BLACK, 3.0);
+        for x in 1..=5 {
+            assert_eq!(Color::BLACK, canvas.pixel(x, 1));
+            assert_eq!(Color::BLACK, canvas.pixel(x, 3));
+        }
+        assert_eq!(Color::WHITE, canvas.pixel(3, 0));
+    }
+
+    #[test]
+    fn draw_text_test() {
+        let mut canvas = Canvas::new(12, 8, Color::WHITE);
+        canvas.draw_text((0, 0), "I-", Color::BLACK, 1);
+        assert_eq!(Color::BLACK, canvas.pixel(2, 0));
+        assert_eq!(Color::BLACK, canvas.pixel(2, 6));
+        assert_eq!(Color::WHITE, canvas.pixel(0, 3));
+        assert_eq!(Color::BLACK, canvas.pixel(6, 3));
+        assert_eq!(Color::WHITE, canvas.pixel(6, 2));
+    }
+
+    #[test]
+    fn fill_disc_test() {
+        let mut canvas = Canvas::new(5, 5, Color::WHITE);
+        canvas.fill_disc((2.0, 2.0), 1.0, Color::BLACK);
+        assert_eq!(Color::BLACK, canvas.pixel(2, 1));
+        assert_eq!(Color::BLACK, canvas.pixel(3, 2));
+        assert_eq!(Color::WHITE, canvas.pixel(3, 3));
+    }
+
+    #[test]
+    fn draw_line_test() {
+        let mut canvas = Canvas::new(5, 5, Color::WHITE);
+        canvas.draw_line((0.0, 0.0), (4.0, 4.0), Color::BLACK, 1.0);
+        for i in 0..5 {
+            assert_eq!(Color::BLACK, canvas.pixel(i, i));
+        }
+        assert_eq!(
+            5,
+            canvas
+                .pixels()
+                .iter()
+                .filter(|p| **p == Color::BLACK)
+                .count()
+        );
+    }
+
+    #[test]
+    fn downsample_into_test() {
+        let mut canvas = Canvas::new(4, 2, Color::WHITE);
+        canvas.set_pixel(0, 0, Color::BLACK);
+        canvas.set_pixel(1, 1, Color::BLACK);
+        canvas.set_pixel(2, 0, Color::new(0, 0, 255));
+        let mut output = Canvas::new(2, 1, Color::BLACK);
+        canvas.downsample_into(2, &mut output);
+        assert_eq!(Color::new(128, 128, 128), output.pixel(0, 0));
+        assert_eq!(Color::new(191, 191, 255), output.pixel(1, 0));
+    }
+}
@@ -0,0 +1,278 @@
+//! Text screen following a long render: the state of each of its stages, the largest terms of the
+//! reconstruction being drawn and a preview of its current frame.
+
+use crate::epicycle;
+use crate::render::{terminal, RenderConfig};
+use std::io::Write;
+use std::time::{Duration, Instant};
+
+/// Number of terms listed in the spectrum.
+const SPECTRUM_TERMS: usize = 8;
+
+/// Width of the bars of the spectrum and of the progress bars, in characters.
+const BAR_WIDTH: usize = 24;
+
+/// Largest number of stages listed one per line, the others being counted.
+const LISTED_STAGES: usize = 12;
+
+/// Shortest time between two screens printed by `print`.
+const REFRESH: Duration = Duration::from_millis(100);
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Stage {
+    Pending,
+    /// Started at the given time, with the share completed when known.
+    Running(Instant, Option<f64>),
+    /// Done in the given time.
+    Done(Duration),
+    Failed,
+}
+
+/// State of a render shown as text, updated as its stages go.
+#[derive(Debug, Clone)]
+pub struct Dashboard {
+    title: String,
+    stages: Vec<(String, Stage)>,
+    /// Reconstruction drawn with its number of terms and the time of its current frame.
+    drawing: Option<(epicycle::Epicycle, usize, f64)>,
+    config: RenderConfig,
+    columns: usize,
+    rows: usize,
+    printed: Option<Instant>,
+}
+
+impl Dashboard {
+    /// Dashboard titled `title` with previews of `columns` by `rows` characters drawn with
+    /// `config`.
+    pub fn new(title: &str, columns: usize, rows: usize, config: &RenderConfig) -> Dashboard {
+        Dashboard {
+            title: title.to_string(),
+            stages: Vec::new(),
+            drawing: None,
+            config: config.clone(),
+            columns,
+            rows,
+            printed: None,
+        }
+    }
+
+    /// Adds the stage `name` as pending, unless already listed.
+    pub fn add(&mut self, name: &str) {
+        self.set(name, Stage::Pending);
+    }
+
+    pub fn start(&mut self, name: &str) {
+        self.set(name, Stage::Running(Instant::now(), None));
+    }
+
+    /// Records that `fraction` of the stage `name` is completed, starting it if needed.
+    pub fn advance(&mut self, name: &str, fraction: f64) {
+        let started = match self.stage(name) {
+            Some(Stage::Running(started, _)) => started,
+            _ => Instant::now(),
+        };
+        self.set(
+            name,
+            Stage::Running(started, Some(fraction.clamp(0.0, 1.0))),
+        );
+    }
+
+    pub fn finish(&mut self, name: &str) {
+        let elapsed = match self.stage(name) {
+            Some(Stage::Running(started, _)) => started.elapsed(),
+            _ => Duration::from_secs(0),
+        };
+        self.set(name, Stage::Done(elapsed));
+    }
+
+    pub fn fail(&mut self, name: &str) {
+        self.set(name, Stage::Failed);
+    }
+
+    pub fn stage(&self, name: &str) -> Option<Stage> {
+        self.stages
+            .iter()
+            .find(|(stage, _)| stage == name)
+            .map(|(_, state)| *state)
+    }
+
+    /// Shows the reconstruction using `precision` terms of `epicycle` traced up to time `t`.
+    pub fn show(&mut self, epicycle: &epicycle::Epicycle, precision: usize, t: f64) {
+        self.drawing = Some((epicycle.clone(), precision, t));
+    }
+
+    /// The dashboard as lines of text.
+    pub fn screen(&self) -> String {
+        let mut screen = format!("{}\n\n", self.title);
+        let listed: Vec<&(String, Stage)> = if self.stages.len() <= LISTED_STAGES {
+            self.stages.iter().collect()
+        } else {
+            let count = |matches: fn(&Stage) -> bool| {
+                self.stages
+                    .iter()
+                    .filter(|(_, state)| matches(state))
+                    .count()
+            };
+            screen.push_str(&format!(
+                "  {} done, {} running, {} failed, {} pending\n",
+                count(|state| matches!(state, Stage::Done(_))),
+                count(|state| matches!(state, Stage::Running(..))),
+                count(|state| *state == Stage::Failed),
+                count(|state| *state == Stage::Pending)
+            ));
+            self.stages
+                .iter()
+                .filter(|(_, state)| matches!(state, Stage::Running(..) | Stage::Failed))
+                .take(LISTED_STAGES)
+                .collect()
+        };
+        let width = listed.iter().map(|(name, _)| name.len()).max().unwrap_or(0);
+        for (name, state) in listed {
+            let state = match state {
+                Stage::Pending => "pending".to_string(),
+                Stage::Running(started, None) => {
+                    format!("running {:.1}s", started.elapsed().as_secs_f64())
+                }
+                Stage::Running(_, Some(fraction)) => {
+                    format!("{} {:>3.0}%", bar(*fraction), fraction * 100.0)
+                }
+                Stage::Done(elapsed) => format!("done in {:.2}s", elapsed.as_secs_f64()),
+                Stage::Failed => "failed".to_string(),
+            };
+            screen.push_str(&format!("  {:<width$}  {}\n", name, state, width = width));
+        }
+
+        if let Some((epicycle, precision, t)) = &self.drawing {
+            screen.push_str(&format!("\n{} of {} terms\n", precision, epicycle.len()));
+            let terms: Vec<_> = epicycle
+                .terms()
+                .iter()
+                .filter(|(_, frequency)| *frequency != 0)
+                .take((*precision).min(SPECTRUM_TERMS))
+                .collect();
+            let largest = terms
+                .first()
+                .map_or(0.0, |(coefficient, _)| coefficient.amplitude());
+            for (coefficient, frequency) in terms {
+                let amplitude = coefficient.amplitude();
+                let share = if largest > 0.0 {
+                    amplitude / largest
+                } else {
+                    0.0
+                };
+                screen.push_str(&format!(
+                    "  {:>5}  {}  {:.4}\n",
+                    frequency,
+                    bar(share),
+                    amplitude
+                ));
+            }
+            screen.push('\n');
+            screen.push_str(&terminal::preview_at(
+                epicycle,
+                *precision,
+                *t,
+                self.columns,
+                self.rows,
+                &self.config,
+            ));
+        }
+        screen
+    }
+
+    /// Prints the dashboard over the previous one on the standard error, unless printed less than
+    /// a tenth of a second ago and not `forced`.
+    pub fn print(&mut self, forced: bool) {
+        if !forced
+            && self
+                .printed
+                .is_some_and(|printed| printed.elapsed() < REFRESH)
+        {
+            return;
+        }
+        self.printed = Some(Instant::now());
+        let mut stderr = std::io::stderr();
+        // Moves to the top left corner and clears the terminal.
+        let _ = write!(stderr, "\x1b[H\x1b[2J{}", self.screen());
+        let _ = stderr.flush();
+    }
+
+    fn set(&mut self, name: &str, state: Stage) {
+        match self.stages.iter_mut().find(|(stage, _)| stage == name) {
+            Some((_, current)) => *current = state,
+            None => self.stages.push((name.to_string(), state)),
+        }
+    }
+}
+
+/// Bar filled for `fraction` of its width.
+fn bar(fraction: f64) -> String {
+    let filled = (fraction.clamp(0.0, 1.0) * BAR_WIDTH as f64).round() as usize;
+    format!("{}{}", "#".repeat(filled), ".".repeat(BAR_WIDTH - filled))
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::complex;
+    use crate::path;
+
+    #[test]
+    fn stages_test() {
+        let mut dashboard = Dashboard::new("animate", 20, 10, &RenderConfig::default());
+        dashboard.add("read");
+        dashboard.add("draw frames");
+        dashboard.start("read");
+        dashboard.finish("read");
+        dashboard.advance("draw frames", 0.5);
+        assert!(matches!(dashboard.stage("read"), Some(Stage::Done(_))));
+        assert!(matches!(
+            dashboard.stage("draw frames"),
+            Some(Stage::Running(_, Some(fraction))) if fraction == 0.5
+        ));
+        assert_eq!(None, dashboard.stage("write"));
+
+        let screen = dashboard.screen();
+        let lines: Vec<&str> = screen.lines().collect();
+        assert_eq!("animate", lines[0]);
+        assert!(lines[2].starts_with("  read         done in "));
+        assert_eq!("  draw frames  ############............  50%", lines[3]);
+    }
+
+    #[test]
+    fn many_stages_test() {
+        let mut dashboard = Dashboard::new("batch", 20, 10, &RenderConfig::default());
+        for index in 0..20 {
+            dashboard.add(&format!("{}.txt", index));
+        }
+        dashboard.fail("3.txt");
+        dashboard.start("4.txt");
+        let screen = dashboard.screen();
+        let lines: Vec<&str> = screen.lines().collect();
+        assert_eq!("  0 done, 1 running, 1 failed, 18 pending", lines[2]);
+        assert_eq!("  3.txt  failed", lines[3]);
+        assert!(lines[4].starts_with("  4.txt  running "));
+        assert_eq!(5, lines.len());
+    }
+
+    #[test]
+    fn show_test() {
+        let epicycle = epicycle::Epicycle::new(&path::Path {
+            data: vec![
+                complex::Complex::new(0.0, 0.0),
+                complex::Complex::new(1.0, 0.0),
+                complex::Complex::new(1.0, 1.0),
+                complex::Complex::new(0.0, 1.0),
+            ],
+        });
+        let mut dashboard = Dashboard::new("render", 20, 10, &RenderConfig::default());
+        dashboard.show(&epicycle, 4, std::f64::consts::PI);
+        let screen = dashboard.screen();
+        let lines: Vec<&str> = screen.lines().collect();
+        assert_eq!("4 of 4 terms", lines[3]);
+        // The constant term only places the drawing and is not listed.
+        assert!(lines[4].starts_with("      1  ########################  "));
+        assert_eq!(4 + 3 + 1 + 10, lines.len());
+    }
+}
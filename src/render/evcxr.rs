@@ -0,0 +1,104 @@
+//! Inline display in Rust Jupyter notebooks, through the evcxr kernel.
+//!
+//! The kernel shows the value of a cell by calling its `evcxr_display` method, which prints the
+//! content between `EVCXR_BEGIN_CONTENT <mime type>` and `EVCXR_END_CONTENT` lines, images being
+//! base64 encoded. Canvases and decompositions show as PNG images, SVG renders as their last
+//! document and animations with `gif`.
+
+use crate::epicycle;
+use crate::render::canvas::Canvas;
+use crate::render::svg::SvgRenderer;
+use crate::render::{self, png, renderer, RenderConfig};
+use std::f64::consts::PI;
+
+const BASE64: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+impl Canvas {
+    pub fn evcxr_display(&self) {
+        print!("{}", content("image/png", &base64(&png::encode(self))));
+    }
+}
+
+impl epicycle::Epicycle {
+    /// Shows the reconstruction using every term, drawn with the default settings.
+    pub fn evcxr_display(&self) {
+        let drawn = render::rasterize(&RenderConfig::default(), |canvas, config| {
+            renderer::draw_frame(canvas, self, self.len(), 2.0 * PI, config)
+        });
+        match drawn {
+            Ok(canvas) => canvas.evcxr_display(),
+            Err(error) => print!("{}", content("text/plain", &error.to_string())),
+        }
+    }
+}
+
+impl SvgRenderer {
+    /// Shows the last frame completed, nothing before the first one.
+    pub fn evcxr_display(&self) {
+        if let Some(document) = self.documents().last() {
+            print!("{}", content("image/svg+xml", document));
+        }
+    }
+}
+
+/// Shows the animated GIF `data`, such as made by `gif::encode`.
+pub fn gif(data: &[u8]) {
+    print!("{}", content("image/gif", &base64(data)));
+}
+
+fn content(mime: &str, data: &str) -> String {
+    format!(
+        "EVCXR_BEGIN_CONTENT {}\n{}\nEVCXR_END_CONTENT\n",
+        mime, data
+    )
+}
+
+fn base64(data: &[u8]) -> String {
+    let mut text = String::with_capacity(data.len().div_ceil(3) * 4);
+    for chunk in data.chunks(3) {
+        let bits = chunk.iter().enumerate().fold(0u32, |bits, (i, byte)| {
+            bits | u32::from(*byte) << (16 - 8 * i)
+        });
+        for i in 0..4 {
+            if i <= chunk.len() {
+                text.push(BASE64[(bits >> (18 - 6 * i) & 63) as usize] as char);
+            } else {
+                text.push('=');
+            }
+        }
+    }
+    text
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::render::renderer::Renderer;
+    use crate::render::Color;
+
+    #[test]
+    fn base64_test() {
+        assert_eq!("", base64(b""));
+        assert_eq!("Zg==", base64(b"f"));
+        assert_eq!("Zm8=", base64(b"fo"));
+        assert_eq!("Zm9v", base64(b"foo"));
+        assert_eq!("Zm9vYmFy", base64(b"foobar"));
+        assert_eq!("+/8=", base64(&[0xFB, 0xFF]));
+    }
+
+    #[test]
+    fn content_test() {
+        assert_eq!(
+            "EVCXR_BEGIN_CONTENT image/png\niVBORw==\nEVCXR_END_CONTENT\n",
+            content(
+                "image/png",
+                &base64(&png::encode(&Canvas::new(1, 1, Color::WHITE))[..4])
+            )
+        );
+        let mut svg = SvgRenderer::new();
+        svg.begin_frame(4, 4, Color::WHITE);
+        svg.end_frame().unwrap();
+        assert!(content("image/svg+xml", &svg.documents()[0]).contains("<svg"));
+    }
+}
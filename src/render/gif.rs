@@ -0,0 +1,624 @@
+use crate::epicycle;
+use crate::profile;
+use crate::render;
+use crate::render::animator::Animator;
+use crate::render::canvas::Canvas;
+use crate::render::pipeline;
+use crate::render::progress::{self, Progress, Tracker};
+use crate::render::Color;
+use std::error::Error;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+
+/// How many times the animation is played.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Repeat {
+    Forever,
+    Count(u16),
+}
+
+/// Timing of an animated GIF.
+#[derive(Debug, Clone)]
+pub struct GifConfig {
+    pub fps: u32,
+    /// Duration of one full turn of the drawing, in seconds.
+    pub duration: f64,
+    pub repeat: Repeat,
+}
+
+impl Default for GifConfig {
+    fn default() -> GifConfig {
+        GifConfig {
+            fps: 25,
+            duration: 4.0,
+            repeat: Repeat::Forever,
+        }
+    }
+}
+
+impl GifConfig {
+    /// Number of frames lasting `duration` once played with the delay actually written, which is
+    /// rounded to hundredths of a second: at 30 fps frames last 3/100 s rather than 1/30 s.
+    pub fn frame_count(&self) -> usize {
+        ((self.duration * 100.0 / f64::from(self.frame_delay())).round() as usize).max(1)
+    }
+
+    /// Delay between two frames in hundredths of a second, the unit used by the GIF format. Viewers
+    /// slow down shorter delays, so frames are shown for at least 2/100 s, that is at most 50 fps.
+    pub fn frame_delay(&self) -> u16 {
+        ((100.0 / f64::from(self.fps.max(1))).round() as u16).max(2)
+    }
+}
+
+/// Animated GIF of the reconstruction using `precision` terms being traced over one full turn.
+/// Fails when the configured size does not fit in a GIF, as checked by `check_size`.
+pub fn encode(
+    epicycle: &epicycle::Epicycle,
+    precision: usize,
+    config: &render::RenderConfig,
+    animation: &GifConfig,
+) -> io::Result<Vec<u8>> {
+    encode_with_progress(
+        epicycle,
+        precision,
+        config,
+        animation,
+        &mut progress::silent,
+    )
+}
+
+/// Same as `encode`, calling `progress` after every frame.
+pub fn encode_with_progress(
+    epicycle: &epicycle::Epicycle,
+    precision: usize,
+    config: &render::RenderConfig,
+    animation: &GifConfig,
+    progress: &mut dyn FnMut(Progress),
+) -> io::Result<Vec<u8>> {
+    let mut output = Vec::new();
+    encode_to(
+        &mut output,
+        epicycle,
+        precision,
+        config,
+        animation,
+        progress,
+    )?;
+    Ok(output)
+}
+
+/// Same as `encode_with_progress`, writing each frame to `output` as soon as it is drawn, so
+/// that only a few frames are held in memory however long the animation. Frames are encoded on
+/// other threads while the next ones are drawn.
+pub fn encode_to(
+    output: &mut dyn Write,
+    epicycle: &epicycle::Epicycle,
+    precision: usize,
+    config: &render::RenderConfig,
+    animation: &GifConfig,
+    progress: &mut dyn FnMut(Progress),
+) -> io::Result<()> {
+    let mut buffer = Vec::new();
+    write_header(&mut buffer, config, animation)?;
+    output.write_all(&buffer)?;
+
+    let animator = Animator::new(epicycle, precision, config, animation.frame_count());
+    let mut tracker = Tracker::new(animator.frame_count(), progress);
+    let delay = animation.frame_delay();
+    let encode = |canvas: &Canvas, frame: &mut Vec<u8>| {
+        frame.clear();
+        write_frame(frame, canvas, delay);
+    };
+    pipeline::encode_frames(animator, config.memory_budget, encode, |_, frame| {
+        output.write_all(frame)?;
+        tracker.frame_done();
+        Ok(())
+    })?;
+
+    output.write_all(&[0x3B])?;
+    output.flush()
+}
+
+/// Animated GIF made of `frames`, which must all have the configured size.
+pub fn encode_canvases<I>(
+    frames: I,
+    config: &render::RenderConfig,
+    animation: &GifConfig,
+) -> io::Result<Vec<u8>>
+where
+    I: IntoIterator<Item = Canvas>,
+{
+    let mut output = Vec::new();
+    write_header(&mut output, config, animation)?;
+    for canvas in frames {
+        check_size(canvas.width(), canvas.height())?;
+        write_frame(&mut output, &canvas, animation.frame_delay());
+    }
+    output.push(0x3B);
+    Ok(output)
+}
+
+pub fn write(
+    epicycle: &epicycle::Epicycle,
+    precision: usize,
+    config: &render::RenderConfig,
+    animation: &GifConfig,
+    filename: &str,
+) -> Result<(), Box<dyn Error>> {
+    write_with_progress(
+        epicycle,
+        precision,
+        config,
+        animation,
+        filename,
+        &mut progress::silent,
+    )
+}
+
+/// Same as `write`, calling `progress` after every frame.
+pub fn write_with_progress(
+    epicycle: &epicycle::Epicycle,
+    precision: usize,
+    config: &render::RenderConfig,
+    animation: &GifConfig,
+    filename: &str,
+    progress: &mut dyn FnMut(Progress),
+) -> Result<(), Box<dyn Error>> {
+    let mut output = BufWriter::new(File::create(filename)?);
+    encode_to(
+        &mut output,
+        epicycle,
+        precision,
+        config,
+        animation,
+        progress,
+    )?;
+    Ok(())
+}
+
+/// Fails unless images of `width` by `height` pixels fit in a GIF, whose sides are written on 16
+/// bits.
+pub fn check_size(width: u32, height: u32) -> io::Result<()> {
+    if width > u32::from(u16::MAX) || height > u32::from(u16::MAX) {
+        let message = format!(
+            "GIF images are at most {} pixels wide and high, not {}x{}",
+            u16::MAX,
+            width,
+            height
+        );
+        return Err(io::Error::new(io::ErrorKind::InvalidInput, message));
+    }
+    Ok(())
+}
+
+fn write_header(
+    output: &mut Vec<u8>,
+    config: &render::RenderConfig,
+    animation: &GifConfig,
+) -> io::Result<()> {
+    check_size(config.width, config.height)?;
+    output.extend_from_slice(b"GIF89a");
+    push_u16(output, config.width as u16);
+    push_u16(output, config.height as u16);
+    // Global color table of 256 entries, 8 bits per primary color.
+    output.extend_from_slice(&[0xF7, 0, 0]);
+    for index in 0..=255u8 {
+        let color = palette_color(index);
+        output.extend_from_slice(&[color.r, color.g, color.b]);
+    }
+
+    let count = match animation.repeat {
+        Repeat::Forever => 0,
+        Repeat::Count(0) => return Ok(()),
+        Repeat::Count(count) => count,
+    };
+    output.extend_from_slice(&[0x21, 0xFF, 0x0B]);
+    output.extend_from_slice(b"NETSCAPE2.0");
+    output.extend_from_slice(&[0x03, 0x01]);
+    push_u16(output, count);
+    output.push(0);
+    Ok(())
+}
+
+fn write_frame(output: &mut Vec<u8>, canvas: &Canvas, delay: u16) {
+    let _stage = profile::stage("encode");
+    output.extend_from_slice(&[0x21, 0xF9, 0x04, 0x00]);
+    push_u16(output, delay);
+    output.extend_from_slice(&[0, 0]);
+
+    output.push(0x2C);
+    push_u16(output, 0);
+    push_u16(output, 0);
+    push_u16(output, canvas.width() as u16);
+    push_u16(output, canvas.height() as u16);
+    output.push(0);
+
+    output.push(8);
+    // The codes are written in one run, then moved apart to make room for the length of each
+    // data sub-block of at most 255 bytes, so that neither the palette indices nor the codes of
+    // the whole frame are held in a buffer of their own.
+    let start = output.len();
+    let indices = canvas.pixels().iter().map(|color| palette_index(*color));
+    lzw_encode(indices, |byte| output.push(byte));
+    let length = output.len() - start;
+    let blocks = length.div_ceil(255);
+    output.resize(start + length + blocks, 0);
+    for block in (0..blocks).rev() {
+        let from = start + block * 255;
+        let size = (length - block * 255).min(255);
+        let to = start + block * 256 + 1;
+        output.copy_within(from..from + size, to);
+        output[to - 1] = size as u8;
+    }
+    output.push(0);
+}
+
+/// Index of `color` in the 3-3-2 bits RGB palette.
+fn palette_index(color: Color) -> u8 {
+    (color.r & 0xE0) | ((color.g >> 3) & 0x1C) | (color.b >> 6)
+}
+
+fn palette_color(index: u8) -> Color {
+    Color::new(
+        (u32::from(index >> 5) * 255 / 7) as u8,
+        (u32::from((index >> 2) & 0x07) * 255 / 7) as u8,
+        (u32::from(index & 0x03) * 255 / 3) as u8,
+    )
+}
+
+/// LZW stream of `indices`, given to `push` a byte at a time. Codes start 9 bits wide and grow
+/// with the table of the sequences seen, up to 12 bits and 4096 codes, at which point the table is
+/// cleared and started again.
+fn lzw_encode<I, P>(indices: I, push: P)
+where
+    I: IntoIterator<Item = u8>,
+    P: FnMut(u8),
+{
+    const CLEAR: u16 = 256;
+    const END: u16 = 257;
+    const FIRST_WIDTH: u32 = 9;
+    const MAX_WIDTH: u32 = 12;
+    const MAX_CODES: u16 = 1 << MAX_WIDTH;
+    // Prime number of slots, enough for the 3838 sequences the table holds at most.
+    const SLOTS: usize = 5003;
+    const EMPTY: u32 = u32::MAX;
+
+    let mut output = Codes {
+        buffer: 0,
+        bits: 0,
+        push,
+    };
+    // Sequences, keyed by the code of all but their last index and that index, and their codes.
+    let mut keys = vec![EMPTY; SLOTS];
+    let mut codes = vec![0u16; SLOTS];
+    let mut width = FIRST_WIDTH;
+    let mut next = END + 1;
+
+    output.emit(CLEAR, width);
+    let mut indices = indices.into_iter();
+    let mut prefix = match indices.next() {
+        Some(index) => u16::from(index),
+        None => {
+            output.emit(END, width);
+            output.finish();
+            return;
+        }
+    };
+    for index in indices {
+        let key = (u32::from(index) << MAX_WIDTH) | u32::from(prefix);
+        let mut slot = key.wrapping_mul(2_654_435_761) as usize % SLOTS;
+        while keys[slot] != EMPTY && keys[slot] != key {
+            slot = (slot + 1) % SLOTS;
+        }
+        if keys[slot] == key {
+            prefix = codes[slot];
+            continue;
+        }
+        output.emit(prefix, width);
+        if next < MAX_CODES {
+            keys[slot] = key;
+            codes[slot] = next;
+            next += 1;
+            // The decoder adds each sequence one code later, so widens its codes one code later.
+            if u32::from(next) > 1 << width && width < MAX_WIDTH {
+                width += 1;
+            }
+        } else {
+            output.emit(CLEAR, width);
+            keys.iter_mut().for_each(|key| *key = EMPTY);
+            width = FIRST_WIDTH;
+            next = END + 1;
+        }
+        prefix = u16::from(index);
+    }
+    output.emit(prefix, width);
+    if u32::from(next) >= 1 << width && width < MAX_WIDTH {
+        width += 1;
+    }
+    output.emit(END, width);
+    output.finish();
+}
+
+/// Codes of varying width packed into bytes, least significant bits first.
+struct Codes<P: FnMut(u8)> {
+    buffer: u32,
+    bits: u32,
+    push: P,
+}
+
+impl<P: FnMut(u8)> Codes<P> {
+    fn emit(&mut self, code: u16, width: u32) {
+        self.buffer |= u32::from(code) << self.bits;
+        self.bits += width;
+        while self.bits >= 8 {
+            (self.push)((self.buffer & 0xFF) as u8);
+            self.buffer >>= 8;
+            self.bits -= 8;
+        }
+    }
+
+    /// Pushes the bits left, padded to a byte.
+    fn finish(&mut self) {
+        if self.bits > 0 {
+            (self.push)((self.buffer & 0xFF) as u8);
+            self.buffer = 0;
+            self.bits = 0;
+        }
+    }
+}
+
+fn push_u16(output: &mut Vec<u8>, value: u16) {
+    output.extend_from_slice(&value.to_le_bytes());
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    /// Indices of the LZW stream `data`, decoded as a GIF viewer does.
+    fn lzw_decode(data: &[u8]) -> Vec<u8> {
+        let (mut position, mut width) = (0, 9);
+        let mut read = |width: usize| {
+            let mut code = 0;
+            for offset in 0..width {
+                let bit = position + offset;
+                if data[bit / 8] & (1 << (bit % 8)) != 0 {
+                    code |= 1 << offset;
+                }
+            }
+            position += width;
+            code
+        };
+        let mut table: Vec<Vec<u8>> = Vec::new();
+        let mut previous: Option<usize> = None;
+        let mut indices = Vec::new();
+        loop {
+            let code = read(width);
+            if code == 256 {
+                table = (0..=255u8).map(|index| vec![index]).collect();
+                table.extend(vec![Vec::new(), Vec::new()]);
+                width = 9;
+                previous = None;
+                continue;
+            }
+            if code == 257 {
+                return indices;
+            }
+            let sequence = match table.get(code) {
+                Some(sequence) => sequence.clone(),
+                None => {
+                    let mut sequence = table[previous.unwrap()].clone();
+                    sequence.push(sequence[0]);
+                    sequence
+                }
+            };
+            if let Some(previous) = previous {
+                if table.len() < 4096 {
+                    let mut added = table[previous].clone();
+                    added.push(sequence[0]);
+                    table.push(added);
+                    if table.len() == 1 << width && width < 12 {
+                        width += 1;
+                    }
+                }
+            }
+            indices.extend_from_slice(&sequence);
+            previous = Some(code);
+        }
+    }
+
+    #[test]
+    fn palette_round_trip_test() {
+        for index in 0..=255u8 {
+            assert_eq!(index, palette_index(palette_color(index)));
+        }
+        assert_eq!(Color::WHITE, palette_color(palette_index(Color::WHITE)));
+        assert_eq!(Color::BLACK, palette_color(palette_index(Color::BLACK)));
+    }
+
+    #[test]
+    fn lzw_encode_test() {
+        let mut noise = 12345u32;
+        let inputs: Vec<Vec<u8>> = vec![
+            Vec::new(),
+            vec![7],
+            vec![0; 100_000],
+            (0..300).map(|i| (i % 256) as u8).collect(),
+            // Enough distinct sequences to fill the table several times.
+            (0..200_000)
+                .map(|_| {
+                    noise = noise.wrapping_mul(1_103_515_245).wrapping_add(12345);
+                    (noise >> 24) as u8
+                })
+                .collect(),
+        ];
+        for indices in inputs {
+            let mut data = Vec::new();
+            lzw_encode(indices.iter().copied(), |byte| data.push(byte));
+            assert_eq!(indices, lzw_decode(&data), "{} indices", indices.len());
+        }
+        // Runs of one color, most of a line drawing, take a few bytes.
+        let mut data = Vec::new();
+        lzw_encode(vec![0; 100_000], |byte| data.push(byte));
+        assert!(data.len() < 1000, "{} bytes", data.len());
+    }
+
+    #[test]
+    fn frame_count_test() {
+        let animation = GifConfig {
+            fps: 30,
+            duration: 2.5,
+            ..GifConfig::default()
+        };
+        // Frames of 3/100 s, so 83 of them for 2.5 s.
+        assert_eq!(83, animation.frame_count());
+        assert_eq!(3, animation.frame_delay());
+        let animation = GifConfig {
+            fps: 25,
+            duration: 2.5,
+            ..GifConfig::default()
+        };
+        assert_eq!(63, animation.frame_count());
+        let animation = GifConfig {
+            fps: 200,
+            ..animation
+        };
+        assert_eq!(2, animation.frame_delay());
+    }
+
+    fn small_config() -> render::RenderConfig {
+        render::RenderConfig {
+            width: 16,
+            height: 16,
+            ..render::RenderConfig::default()
+        }
+    }
+
+    #[test]
+    fn encode_test() {
+        let config = render::RenderConfig {
+            width: 32,
+            height: 16,
+            ..render::RenderConfig::default()
+        };
+        let animation = GifConfig {
+            fps: 4,
+            duration: 1.0,
+            repeat: Repeat::Count(3),
+        };
+        let output = encode(&epicycle::unit_square(), 4, &config, &animation).unwrap();
+
+        assert_eq!(b"GIF89a", &output[0..6]);
+        assert_eq!(&[32, 0, 16, 0], &output[6..10]);
+        assert_eq!(b"NETSCAPE2.0", &output[784..795]);
+        assert_eq!(&[3, 0], &output[797..799]);
+        assert_eq!(Some(&0x3B), output.last());
+        let frames = output
+            .windows(4)
+            .filter(|w| w == &[0x21, 0xF9, 0x04, 0x00])
+            .count();
+        assert_eq!(4, frames);
+    }
+
+    #[test]
+    fn size_test() {
+        assert!(check_size(65535, 65535).is_ok());
+        assert!(check_size(65536, 2).is_err());
+        assert!(check_size(2, 70000).is_err());
+        let config = render::RenderConfig {
+            width: 65537,
+            height: 2,
+            ..render::RenderConfig::default()
+        };
+        let animation = GifConfig::default();
+        let mut output = Vec::new();
+        let result = encode_to(
+            &mut output,
+            &epicycle::unit_square(),
+            4,
+            &config,
+            &animation,
+            &mut progress::silent,
+        );
+        assert_eq!(io::ErrorKind::InvalidInput, result.unwrap_err().kind());
+        assert!(output.is_empty());
+        let canvas = Canvas::new(65537, 1, Color::WHITE);
+        assert!(encode_canvases(vec![canvas], &small_config(), &animation).is_err());
+    }
+
+    #[test]
+    fn encode_to_test() {
+        /// Records the size of every write.
+        struct Writes(Vec<u8>, Vec<usize>);
+        impl Write for Writes {
+            fn write(&mut self, data: &[u8]) -> io::Result<usize> {
+                self.0.extend_from_slice(data);
+                self.1.push(data.len());
+                Ok(data.len())
+            }
+            fn flush(&mut self) -> io::Result<()> {
+                Ok(())
+            }
+        }
+
+        let animation = GifConfig {
+            fps: 4,
+            duration: 1.0,
+            ..GifConfig::default()
+        };
+        let mut writes = Writes(Vec::new(), Vec::new());
+        encode_to(
+            &mut writes,
+            &epicycle::unit_square(),
+            4,
+            &small_config(),
+            &animation,
+            &mut progress::silent,
+        )
+        .unwrap();
+        assert_eq!(
+            encode(&epicycle::unit_square(), 4, &small_config(), &animation).unwrap(),
+            writes.0
+        );
+        // The header, each of the 4 frames and the trailer.
+        assert_eq!(6, writes.1.len());
+    }
+
+    #[test]
+    fn encode_variants_test() {
+        let animation = GifConfig {
+            fps: 2,
+            duration: 1.0,
+            ..GifConfig::default()
+        };
+        let plain = encode(&epicycle::unit_square(), 4, &small_config(), &animation).unwrap();
+        let variants = vec![
+            render::RenderConfig {
+                circles: true,
+                max_circles: Some(2),
+                ..small_config()
+            },
+            render::RenderConfig {
+                trail: render::Trail::Fade(0.25),
+                ..small_config()
+            },
+            render::RenderConfig {
+                background: Color::BLACK,
+                palette: render::Palette {
+                    trace: Color::WHITE,
+                    ..render::Palette::default()
+                },
+                ..small_config()
+            },
+            render::RenderConfig {
+                caption: Some("Hi".to_string()),
+                ..small_config()
+            },
+        ];
+        for config in variants {
+            let output = encode(&epicycle::unit_square(), 4, &config, &animation).unwrap();
+            assert_ne!(plain, output);
+        }
+    }
+}
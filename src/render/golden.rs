@@ -0,0 +1,209 @@
+//! Regression tests comparing renders of canonical shapes with reference images stored in
+//! `resources/test/golden`. Run the tests with `UPDATE_GOLDEN=1` to write the reference images
+//! again after an intended change of the output, and review them before committing.
+
+use crate::complex;
+use crate::epicycle;
+use crate::path;
+use crate::render;
+use crate::render::canvas::Canvas;
+use crate::render::renderer;
+use crate::render::{overlay, ppm, spectrum, tower};
+use crate::render::{Color, RenderConfig};
+use std::env;
+use std::f64::consts::PI;
+use std::path::PathBuf;
+
+/// Distance between two colors, in the range of one channel, above which pixels differ visibly.
+const PIXEL_TOLERANCE: f64 = 24.0;
+
+/// Fraction of the pixels allowed to differ visibly from the reference image.
+const MISMATCH_TOLERANCE: f64 = 0.002;
+
+/// Distance between two colors, channels weighted by their contribution to the luminance.
+fn distance(a: Color, b: Color) -> f64 {
+    let difference = |x: u8, y: u8| f64::from(x) - f64::from(y);
+    let (r, g, b) = (
+        difference(a.r, b.r),
+        difference(a.g, b.g),
+        difference(a.b, b.b),
+    );
+    (0.299 * r * r + 0.587 * g * g + 0.114 * b * b).sqrt()
+}
+
+/// Fraction of the pixels of `actual` that differ visibly from every pixel around the same
+/// position in `expected`, so that edges moved by at most one pixel are not counted.
+fn mismatch(actual: &Canvas, expected: &Canvas) -> f64 {
+    let (width, height) = (actual.width(), actual.height());
+    let mut mismatched = 0;
+    for y in 0..height {
+        for x in 0..width {
+            let color = actual.pixel(x, y);
+            let close = (y.saturating_sub(1)..(y + 2).min(height)).any(|ny| {
+                (x.saturating_sub(1)..(x + 2).min(width))
+                    .any(|nx| distance(color, expected.pixel(nx, ny)) <= PIXEL_TOLERANCE)
+            });
+            if !close {
+                mismatched += 1;
+            }
+        }
+    }
+    f64::from(mismatched) / f64::from((width * height).max(1))
+}
+
+/// Compares `actual` with the reference image `name`, or replaces the reference image when
+/// `UPDATE_GOLDEN` is set. On failure the render is written next to the reference image.
+fn check(name: &str, actual: &Canvas) {
+    let mut file = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    file.push("resources");
+    file.push("test");
+    file.push("golden");
+    file.push(format!("{}.ppm", name));
+    let filename = file.to_str().unwrap();
+    if env::var_os("UPDATE_GOLDEN").is_some() {
+        std::fs::create_dir_all(file.parent().unwrap()).unwrap();
+        ppm::write(actual, filename).unwrap();
+        return;
+    }
+
+    let expected = ppm::read(filename).unwrap_or_else(|error| {
+        panic!(
+            "Cannot read {}: {}, run with UPDATE_GOLDEN=1 to create it",
+            filename, error
+        )
+    });
+    assert_eq!(
+        (expected.width(), expected.height()),
+        (actual.width(), actual.height()),
+        "{} has a different size",
+        name
+    );
+    // Both ways, so that missing strokes count as much as extra ones.
+    let fraction = mismatch(actual, &expected).max(mismatch(&expected, actual));
+    if fraction > MISMATCH_TOLERANCE {
+        let rejected = filename.replace(".ppm", ".actual.ppm");
+        ppm::write(actual, &rejected).unwrap();
+        panic!(
+            "{:.2}% of the pixels of {} differ from the reference image, render written to {}",
+            fraction * 100.0,
+            name,
+            rejected
+        );
+    }
+}
+
+fn heart() -> epicycle::Epicycle {
+    epicycle::Epicycle::new(&path::Path {
+        data: (0..64)
+            .map(|k| {
+                let t = 2.0 * PI * f64::from(k) / 64.0;
+                complex::Complex::new(
+                    16.0 * t.sin().powi(3),
+                    13.0 * t.cos()
+                        - 5.0 * (2.0 * t).cos()
+                        - 2.0 * (3.0 * t).cos()
+                        - (4.0 * t).cos(),
+                )
+            })
+            .collect(),
+    })
+}
+
+fn config() -> RenderConfig {
+    RenderConfig {
+        width: 128,
+        height: 128,
+        ..RenderConfig::default()
+    }
+}
+
+#[test]
+fn square_trace_golden_test() {
+    let epicycle = epicycle::unit_square();
+    let canvas = render::rasterize(&config(), |canvas, config| {
+        renderer::draw_frame(canvas, &epicycle, 4, 2.0 * PI, config)
+    })
+    .unwrap();
+    check("square_trace", &canvas);
+}
+
+#[test]
+fn heart_circles_golden_test() {
+    let epicycle = heart();
+    let config = RenderConfig {
+        circles: true,
+        grid: true,
+        supersampling: 2,
+        ..config()
+    };
+    let canvas = render::rasterize(&config, |canvas, config| {
+        renderer::draw_frame(canvas, &epicycle, 16, 2.0, config)
+    })
+    .unwrap();
+    check("heart_circles", &canvas);
+}
+
+#[test]
+fn heart_spectrum_golden_test() {
+    let epicycle = heart();
+    let options = spectrum::SpectrumConfig {
+        phase: true,
+        max_frequency: Some(8),
+        ..spectrum::SpectrumConfig::default()
+    };
+    let canvas = render::rasterize(&config(), |canvas, config| {
+        spectrum::draw(canvas, &epicycle, config, &options)
+    })
+    .unwrap();
+    check("heart_spectrum", &canvas);
+}
+
+#[test]
+fn heart_overlay_golden_test() {
+    let epicycle = heart();
+    let config = RenderConfig {
+        width: 128,
+        height: 128,
+        ..RenderConfig::dark()
+    };
+    let canvas = render::rasterize(&config, |canvas, config| {
+        overlay::draw(canvas, &epicycle, &[2, 6, 32], config)
+    })
+    .unwrap();
+    check("heart_overlay", &canvas);
+}
+
+#[test]
+fn heart_tower_golden_test() {
+    let epicycle = heart();
+    let config = RenderConfig {
+        max_circles: Some(6),
+        ..config()
+    };
+    let canvas = render::rasterize(&config, |canvas, config| {
+        tower::draw(
+            canvas,
+            &epicycle,
+            24,
+            1.0,
+            config,
+            &tower::TowerConfig::default(),
+        )
+    })
+    .unwrap();
+    check("heart_tower", &canvas);
+}
+
+#[test]
+fn mismatch_test() {
+    let mut expected = Canvas::new(10, 10, Color::WHITE);
+    expected.set_pixel(5, 5, Color::BLACK);
+    let mut shifted = Canvas::new(10, 10, Color::WHITE);
+    shifted.set_pixel(6, 5, Color::BLACK);
+    assert_eq!(0.0, mismatch(&shifted, &expected));
+    let mut moved = Canvas::new(10, 10, Color::WHITE);
+    moved.set_pixel(8, 5, Color::BLACK);
+    assert_eq!(0.01, mismatch(&moved, &expected));
+    assert_eq!(0.0, distance(Color::WHITE, Color::WHITE));
+    assert!(distance(Color::WHITE, Color::new(250, 250, 250)) < PIXEL_TOLERANCE);
+}
@@ -0,0 +1,172 @@
+use crate::complex;
+use crate::epicycle;
+use crate::path;
+use crate::render;
+use crate::render::png;
+use crate::render::renderer::Renderer;
+use crate::render::svg::SvgRenderer;
+use crate::render::{Color, RenderConfig};
+use std::error::Error;
+use std::fs;
+
+/// Color of accurate parts of the trace.
+pub const ACCURATE: Color = Color::new(40, 80, 220);
+
+/// Color of the parts of the trace with the largest deviation.
+pub const OFF: Color = Color::new(220, 30, 30);
+
+/// Distance between the reconstruction using `precision` terms and `path` at `n_points` evenly
+/// spaced times. Between two of its points, the path is interpolated linearly.
+pub fn deviations(
+    path: &path::Path,
+    epicycle: &epicycle::Epicycle,
+    precision: usize,
+    n_points: usize,
+) -> Vec<f64> {
+    if path.data.is_empty() {
+        return vec![0.0; n_points];
+    }
+    let length = path.data.len();
+    epicycle
+        .sample(precision, n_points)
+        .iter()
+        .enumerate()
+        .map(|(index, estimated)| {
+            let position = (index * length) as f64 / n_points as f64;
+            let before = position.floor() as usize % length;
+            let after = (before + 1) % length;
+            let fraction = position - position.floor();
+            let (from, to) = (path.data[before], path.data[after]);
+            let original = complex::Complex::new(
+                from.re + (to.re - from.re) * fraction,
+                from.im + (to.im - from.im) * fraction,
+            );
+            complex::Complex::minus(&original, estimated).amplitude()
+        })
+        .collect()
+}
+
+/// Color of a deviation relative to the largest one, from `ACCURATE` at 0 to `OFF` at 1.
+pub fn color(relative: f64) -> Color {
+    ACCURATE.blend(OFF, relative)
+}
+
+/// Draws the reconstruction using `precision` terms colored by its deviation from `path`, with
+/// the largest deviation written at the bottom.
+pub fn draw(
+    renderer: &mut dyn Renderer,
+    path: &path::Path,
+    epicycle: &epicycle::Epicycle,
+    precision: usize,
+    config: &RenderConfig,
+) -> Result<(), Box<dyn Error>> {
+    let config = &config.with_caption_for(epicycle, precision);
+    let points = epicycle.sample(precision, render::TRACE_SAMPLES);
+    let errors = deviations(path, epicycle, precision, render::TRACE_SAMPLES);
+    let largest = errors.iter().cloned().fold(0.0, f64::max);
+    let projection = config.projection(&points);
+    let projected: Vec<_> = points.iter().map(|p| projection.project(p)).collect();
+
+    renderer.begin_frame(config.width, config.height, config.background);
+    for index in 0..projected.len() {
+        let next = (index + 1) % projected.len();
+        let error = (errors[index] + errors[next]) / 2.0;
+        let relative = if largest > 0.0 { error / largest } else { 0.0 };
+        let segment = [projected[index], projected[next]];
+        renderer.draw_polyline(&segment, false, color(relative), config.line_width);
+    }
+    render::draw_caption(renderer, config);
+    let legend = format!("max deviation {:.4}", largest);
+    render::draw_annotation(renderer, &legend, config);
+    renderer.end_frame()
+}
+
+pub fn svg(
+    path: &path::Path,
+    epicycle: &epicycle::Epicycle,
+    precision: usize,
+    config: &RenderConfig,
+) -> Result<String, Box<dyn Error>> {
+    let mut renderer = SvgRenderer::for_config(config);
+    draw(&mut renderer, path, epicycle, precision, config)?;
+    Ok(renderer.documents()[0].clone())
+}
+
+pub fn write_svg(
+    path: &path::Path,
+    epicycle: &epicycle::Epicycle,
+    precision: usize,
+    config: &RenderConfig,
+    filename: &str,
+) -> Result<(), Box<dyn Error>> {
+    fs::write(filename, svg(path, epicycle, precision, config)?)?;
+    Ok(())
+}
+
+pub fn write_png(
+    path: &path::Path,
+    epicycle: &epicycle::Epicycle,
+    precision: usize,
+    config: &RenderConfig,
+    filename: &str,
+) -> Result<(), Box<dyn Error>> {
+    let canvas = render::rasterize(config, |canvas, config| {
+        draw(canvas, path, epicycle, precision, config)
+    })?;
+    png::write_with(&canvas, config, filename)
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::render::canvas::Canvas;
+
+    #[test]
+    fn deviations_test() {
+        let path = path::unit_square();
+        let epicycle = epicycle::Epicycle::new(&path);
+        let exact = deviations(&path, &epicycle, 4, 4);
+        assert!(exact.iter().all(|error| *error < 1E-9));
+
+        let constant = deviations(&path, &epicycle, 1, 8);
+        assert_eq!(8, constant.len());
+        // Only the center of the square is left, corners are further than edge midpoints.
+        assert!((constant[0] - 0.5f64.sqrt()).abs() < 1E-9);
+        assert!((constant[1] - 0.5).abs() < 1E-9);
+    }
+
+    #[test]
+    fn color_test() {
+        assert_eq!(ACCURATE, color(0.0));
+        assert_eq!(OFF, color(1.0));
+    }
+
+    #[test]
+    fn draw_test() {
+        let path = path::unit_square();
+        let epicycle = epicycle::Epicycle::new(&path);
+        let config = RenderConfig {
+            width: 64,
+            height: 64,
+            ..RenderConfig::default()
+        };
+        let mut canvas = Canvas::new(1, 1, Color::WHITE);
+        draw(&mut canvas, &path, &epicycle, 2, &config).unwrap();
+        let reddish = canvas
+            .pixels()
+            .iter()
+            .filter(|p| p.r > 150 && p.b < 100)
+            .count();
+        let bluish = canvas
+            .pixels()
+            .iter()
+            .filter(|p| p.b > 150 && p.r < 100)
+            .count();
+        assert!(reddish > 0 && bluish > 0);
+
+        let document = svg(&path, &epicycle, 2, &config).unwrap();
+        assert_eq!(render::TRACE_SAMPLES, document.matches("<path").count());
+        assert!(document.contains("max deviation"));
+    }
+}
@@ -0,0 +1,1533 @@
+pub mod animator;
+
+pub mod canvas;
+
+pub mod dashboard;
+
+#[cfg(feature = "evcxr")]
+pub mod evcxr;
+
+pub mod font;
+
+pub mod gif;
+
+#[cfg(test)]
+mod golden;
+
+pub mod heatmap;
+
+pub mod morph;
+
+pub mod overlay;
+
+pub mod pipeline;
+
+pub mod png;
+
+pub mod ppm;
+
+pub mod progress;
+
+pub mod renderer;
+
+pub mod sheet;
+
+pub mod spectrum;
+
+pub mod svg;
+
+pub mod terminal;
+
+pub mod tower;
+
+use crate::complex;
+use crate::epicycle;
+pub use crate::path::Bounds;
+use crate::profile;
+use crate::render::animator::Animator;
+use crate::render::canvas::Canvas;
+use crate::render::progress::{Progress, Tracker};
+use crate::render::renderer::Renderer;
+use std::collections::hash_map::DefaultHasher;
+use std::error::Error;
+use std::fmt::Write;
+use std::fs;
+use std::hash::{Hash, Hasher};
+use std::io;
+use std::mem;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+/// Number of points sampled along one full turn when tracing a reconstruction.
+pub const TRACE_SAMPLES: usize = 2048;
+
+/// Number of segments used to approximate a circle.
+const CIRCLE_SEGMENTS: usize = 64;
+
+/// Approximate number of grid cells along the longest axis of the drawing.
+const GRID_DIVISIONS: f64 = 10.0;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Color {
+    pub r: u8,
+    pub g: u8,
+    pub b: u8,
+}
+
+impl Color {
+    pub const BLACK: Color = Color { r: 0, g: 0, b: 0 };
+    pub const WHITE: Color = Color {
+        r: 255,
+        g: 255,
+        b: 255,
+    };
+
+    pub const fn new(r: u8, g: u8, b: u8) -> Color {
+        Color { r, g, b }
+    }
+
+    /// Mix of this color with `other`, `alpha` being the weight of `other` between 0 and 1.
+    pub fn blend(&self, other: Color, alpha: f64) -> Color {
+        let alpha = alpha.clamp(0.0, 1.0);
+        let mix = |from: u8, to: u8| {
+            (f64::from(from) * (1.0 - alpha) + f64::from(to) * alpha).round() as u8
+        };
+        Color::new(
+            mix(self.r, other.r),
+            mix(self.g, other.g),
+            mix(self.b, other.b),
+        )
+    }
+
+    /// RGBA color which, drawn over `background`, gives this color, with the lowest possible
+    /// opacity. Shades blended into the background, such as anti-aliased edges, become
+    /// translucent versions of their original color.
+    pub fn to_alpha(&self, background: Color) -> [u8; 4] {
+        let channels = [
+            (self.r, background.r),
+            (self.g, background.g),
+            (self.b, background.b),
+        ];
+        let alpha = channels
+            .iter()
+            .map(|(value, background)| {
+                let (value, background) = (f64::from(*value), f64::from(*background));
+                if value > background {
+                    (value - background) / (255.0 - background)
+                } else if value < background {
+                    (background - value) / background
+                } else {
+                    0.0
+                }
+            })
+            .fold(0.0, f64::max);
+        if alpha <= 0.0 {
+            return [0, 0, 0, 0];
+        }
+        let original = |(value, background): (u8, u8)| {
+            let (value, background) = (f64::from(value), f64::from(background));
+            ((value - background) / alpha + background).round() as u8
+        };
+        [
+            original(channels[0]),
+            original(channels[1]),
+            original(channels[2]),
+            (alpha * 255.0).round() as u8,
+        ]
+    }
+
+    /// Hexadecimal notation used by SVG and CSS, such as `#ff8000`.
+    pub fn to_hex(&self) -> String {
+        format!("#{:02x}{:02x}{:02x}", self.r, self.g, self.b)
+    }
+
+    /// Color written as `#rrggbb`, the inverse of `to_hex`.
+    pub fn from_hex(text: &str) -> Option<Color> {
+        let digits = text.strip_prefix('#')?;
+        if digits.len() != 6 || !digits.is_ascii() {
+            return None;
+        }
+        let channel = |index: usize| u8::from_str_radix(&digits[index..index + 2], 16).ok();
+        Some(Color::new(channel(0)?, channel(2)?, channel(4)?))
+    }
+}
+
+/// Colors of the elements of a drawing.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Palette {
+    pub trace: Color,
+    /// Original path when compared with its reconstruction.
+    pub reference: Color,
+    pub circles: Color,
+    pub arms: Color,
+    pub pen: Color,
+    pub grid: Color,
+    pub text: Color,
+}
+
+impl Default for Palette {
+    fn default() -> Palette {
+        Palette {
+            trace: Color::BLACK,
+            reference: Color::new(240, 140, 30),
+            circles: Color::new(170, 170, 170),
+            arms: Color::new(40, 80, 200),
+            pen: Color::new(220, 0, 0),
+            grid: Color::new(225, 225, 225),
+            text: Color::BLACK,
+        }
+    }
+}
+
+impl Palette {
+    /// Light colors suited to a dark background.
+    pub fn dark() -> Palette {
+        Palette {
+            trace: Color::new(235, 235, 235),
+            reference: Color::new(255, 170, 60),
+            circles: Color::new(90, 90, 100),
+            arms: Color::new(110, 160, 255),
+            pen: Color::new(255, 80, 80),
+            grid: Color::new(45, 45, 55),
+            text: Color::new(235, 235, 235),
+        }
+    }
+}
+
+/// Appearance settings shared by every renderer.
+#[derive(Debug, Clone)]
+pub struct RenderConfig {
+    pub width: u32,
+    pub height: u32,
+    /// Free space left around the drawing on each side, as a fraction of the image size.
+    pub margin: f64,
+    pub background: Color,
+    /// Makes the background transparent in PNG and SVG outputs.
+    pub transparent: bool,
+    pub palette: Palette,
+    /// Width of the trace, in pixels.
+    pub line_width: f64,
+    /// Text written at the top of the image. The placeholders `{file}`, `{precision}`, `{terms}`
+    /// and `{error}` are replaced by the source file, the number of terms drawn, the total number
+    /// of terms and the reconstruction error.
+    pub caption: Option<String>,
+    /// Name of the file the drawing comes from, substituted for `{file}` in the caption.
+    pub source: Option<String>,
+    pub grid: bool,
+    /// Draws the rotating circles, their arms and the pen on animation frames.
+    pub circles: bool,
+    /// Only draws the given number of largest circles.
+    pub max_circles: Option<usize>,
+    /// When only some circles are drawn, joins the last one to the pen with a single arm showing
+    /// the displacement of all the remaining terms.
+    pub lump_remainder: bool,
+    pub trail: Trail,
+    pub timing: Timing,
+    /// Stamps animation frames with the number of terms, the time and the reconstruction error.
+    pub annotate: bool,
+    /// Resolution recorded in bitmap outputs, in dots per inch.
+    pub dpi: Option<u32>,
+    /// Bitmaps are drawn at this many times their size, then scaled down to smooth edges.
+    pub supersampling: u32,
+    /// Image drawn under bitmap renders.
+    pub underlay: Option<Underlay>,
+    /// Largest distance allowed between the traced curve and the segments drawing it in static
+    /// plots, in pixels. Sampling is denser where the curve bends.
+    pub max_chord_error: f64,
+    /// Bytes the buffers of a render may take beyond its image, none for no limit. Over it,
+    /// fewer frames of an animation are encoded at the same time and dense curves are traced with
+    /// fewer points.
+    pub memory_budget: Option<usize>,
+}
+
+impl Default for RenderConfig {
+    fn default() -> RenderConfig {
+        RenderConfig {
+            width: 640,
+            height: 640,
+            margin: 0.05,
+            background: Color::WHITE,
+            transparent: false,
+            palette: Palette::default(),
+            line_width: 1.0,
+            caption: None,
+            source: None,
+            grid: false,
+            circles: false,
+            max_circles: None,
+            lump_remainder: false,
+            trail: Trail::Full,
+            timing: Timing::default(),
+            annotate: false,
+            dpi: None,
+            supersampling: 1,
+            underlay: None,
+            max_chord_error: 0.25,
+            memory_budget: None,
+        }
+    }
+}
+
+impl RenderConfig {
+    /// Default configuration with a dark background and the dark palette.
+    pub fn dark() -> RenderConfig {
+        RenderConfig {
+            background: Color::new(18, 18, 24),
+            palette: Palette::dark(),
+            ..RenderConfig::default()
+        }
+    }
+
+    /// Configuration of an image printed at `width` by `height` inches with the given resolution.
+    pub fn print_size(width: f64, height: f64, dpi: u32) -> RenderConfig {
+        RenderConfig {
+            width: (width * f64::from(dpi)).round() as u32,
+            height: (height * f64::from(dpi)).round() as u32,
+            dpi: Some(dpi),
+            ..RenderConfig::default()
+        }
+    }
+
+    /// Configuration to draw with before scaling the bitmap down by `supersampling`.
+    pub fn supersampled(&self) -> RenderConfig {
+        let factor = self.supersampling.max(1);
+        RenderConfig {
+            width: self.width * factor,
+            height: self.height * factor,
+            line_width: self.line_width * f64::from(factor),
+            max_chord_error: self.max_chord_error * f64::from(factor),
+            supersampling: 1,
+            ..self.clone()
+        }
+    }
+
+    /// Projection fitting `points` in the image described by this configuration.
+    pub fn projection(&self, points: &[complex::Complex]) -> Projection {
+        Projection::fit(points, self.width, self.height, self.margin)
+    }
+
+    /// Projection fitting the trace of the reconstruction using `precision` terms, sampled
+    /// without keeping the points.
+    pub fn trace_projection(&self, epicycle: &epicycle::Epicycle, precision: usize) -> Projection {
+        let bounds = Bounds::of_iter(epicycle.samples(precision, TRACE_SAMPLES));
+        Projection::fit_bounds(&bounds, self.width, self.height, self.margin)
+    }
+
+    /// Points of the full reconstruction using `precision` terms, sampled so that the trace stays
+    /// within `max_chord_error` pixels of the curve once fitted in the image.
+    pub fn trace_points(
+        &self,
+        epicycle: &epicycle::Epicycle,
+        precision: usize,
+    ) -> Vec<complex::Complex> {
+        let coarse = self.trace_projection(epicycle, precision);
+        let tolerance = self.max_chord_error / coarse.scale(1.0);
+        if !(tolerance.is_finite() && tolerance > 0.0) {
+            return epicycle.sample(precision, TRACE_SAMPLES);
+        }
+        // Each point is projected once traced.
+        let point_bytes = mem::size_of::<complex::Complex>() + mem::size_of::<(f64, f64)>();
+        let max_points = self.memory_budget.map_or(usize::MAX, |budget| {
+            (budget / point_bytes).max(TRACE_SAMPLES)
+        });
+        epicycle.sample_adaptive_within(precision, tolerance, max_points)
+    }
+
+    /// Configuration with the placeholders of the caption resolved for the reconstruction of
+    /// `epicycle` using `precision` terms. Unknown placeholders are left as they are.
+    pub fn with_caption_for(
+        &self,
+        epicycle: &epicycle::Epicycle,
+        precision: usize,
+    ) -> RenderConfig {
+        let caption = self.caption.as_ref().map(|template| {
+            let mut caption = String::with_capacity(template.len());
+            let mut rest = template.as_str();
+            while let Some(start) = rest.find('{') {
+                caption.push_str(&rest[..start]);
+                rest = &rest[start..];
+                let end = match rest.find('}') {
+                    Some(end) => end,
+                    None => break,
+                };
+                let value = match &rest[1..end] {
+                    "file" => self.source.clone().unwrap_or_default(),
+                    "precision" => precision.min(epicycle.len()).to_string(),
+                    "terms" => epicycle.len().to_string(),
+                    "error" => format!("{:.4}", epicycle.truncation_error(precision)),
+                    _ => rest[..=end].to_string(),
+                };
+                caption.push_str(&value);
+                rest = &rest[end + 1..];
+            }
+            caption.push_str(rest);
+            caption
+        });
+        RenderConfig {
+            caption,
+            ..self.clone()
+        }
+    }
+
+    /// Scale of the caption font, growing with the image height.
+    pub fn text_scale(&self) -> u32 {
+        (self.height / 320).max(1)
+    }
+}
+
+/// Image drawn under bitmap renders, such as the picture a path was traced from.
+#[derive(Debug, Clone)]
+pub struct Underlay {
+    pub image: Arc<Canvas>,
+    /// Area of the drawing covered by the image, the top left corner of the image being at
+    /// (`min_x`, `max_y`).
+    pub bounds: Bounds,
+    /// Between 0 for an invisible image and 1 for an opaque one.
+    pub opacity: f64,
+}
+
+/// How the trace drawn so far is shown on animation frames.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Trail {
+    /// The whole trace stays visible.
+    Full,
+    /// Segments fade out linearly and disappear once older than the given fraction of a turn.
+    Fade(f64),
+}
+
+/// Variation of the drawing speed over an animation.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Easing {
+    Linear,
+    /// Starts slowly and accelerates.
+    EaseIn,
+    /// Starts quickly and decelerates.
+    EaseOut,
+    /// Starts and ends slowly.
+    EaseInOut,
+}
+
+impl Easing {
+    /// Eased progress for a linear progress `x`, both between 0 and 1.
+    pub fn apply(&self, x: f64) -> f64 {
+        let x = x.clamp(0.0, 1.0);
+        match self {
+            Easing::Linear => x,
+            Easing::EaseIn => x * x,
+            Easing::EaseOut => x * (2.0 - x),
+            Easing::EaseInOut => x * x * (3.0 - 2.0 * x),
+        }
+    }
+}
+
+/// Pacing of animations. The number of times an animation is played is set by the output
+/// format, such as `gif::Repeat`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Timing {
+    pub easing: Easing,
+    /// Draws the trace during the first half of the animation and erases it backwards during the
+    /// second half, so that looping plays smoothly.
+    pub ping_pong: bool,
+    /// Slows the pen down where the trace turns sharply, such as at corners.
+    pub slow_at_corners: bool,
+}
+
+impl Default for Timing {
+    fn default() -> Timing {
+        Timing {
+            easing: Easing::Linear,
+            ping_pong: false,
+            slow_at_corners: false,
+        }
+    }
+}
+
+impl Timing {
+    /// Fraction of the trace drawn at the given frame, between 0 and 1, before any slowing down
+    /// at corners. The last frame of an animation that is not ping-pong shows the whole trace.
+    pub fn progress(&self, frame: usize, frame_count: usize) -> f64 {
+        let linear = (frame + 1) as f64 / frame_count.max(1) as f64;
+        let linear = if self.ping_pong {
+            1.0 - (2.0 * linear - 1.0).abs()
+        } else {
+            linear
+        };
+        self.easing.apply(linear)
+    }
+}
+
+/// Maps path coordinates onto pixel coordinates. The y axis of the path points up while the
+/// pixel rows grow downwards.
+#[derive(Debug, Clone, Copy)]
+pub struct Projection {
+    min_x: f64,
+    min_y: f64,
+    scale: f64,
+    offset_x: f64,
+    offset_y: f64,
+    height: u32,
+}
+
+impl Projection {
+    /// Projection fitting the bounding box of `points` in a `width` by `height` image, leaving
+    /// `margin` (a fraction of the image size) free on each side. Both axes share the same scale
+    /// so that shapes are not distorted, and the drawing is centered.
+    pub fn fit(points: &[complex::Complex], width: u32, height: u32, margin: f64) -> Projection {
+        Projection::fit_bounds(&Bounds::of(points), width, height, margin)
+    }
+
+    /// Same as `fit` for the points within `bounds`.
+    pub fn fit_bounds(bounds: &Bounds, width: u32, height: u32, margin: f64) -> Projection {
+        let pixels_x = f64::from(width.max(1) - 1);
+        let pixels_y = f64::from(height.max(1) - 1);
+        let usable_x = pixels_x * (1.0 - 2.0 * margin);
+        let usable_y = pixels_y * (1.0 - 2.0 * margin);
+
+        let scale_x = usable_x / bounds.width();
+        let scale_y = usable_y / bounds.height();
+        let scale = match (scale_x.is_finite(), scale_y.is_finite()) {
+            (true, true) => scale_x.min(scale_y),
+            (true, false) => scale_x,
+            (false, true) => scale_y,
+            (false, false) => 1.0,
+        };
+
+        Projection {
+            min_x: bounds.min_x,
+            min_y: bounds.min_y,
+            scale,
+            offset_x: (pixels_x - bounds.width() * scale) / 2.0,
+            offset_y: (pixels_y - bounds.height() * scale) / 2.0,
+            height,
+        }
+    }
+
+    pub fn project(&self, point: &complex::Complex) -> (f64, f64) {
+        let x = self.offset_x + (point.re - self.min_x) * self.scale;
+        let y = self.offset_y + (point.im - self.min_y) * self.scale;
+        (x, f64::from(self.height.max(1) - 1) - y)
+    }
+
+    /// Path coordinates of a pixel position, the inverse of `project`.
+    pub fn unproject(&self, position: (f64, f64)) -> complex::Complex {
+        let y = f64::from(self.height.max(1) - 1) - position.1;
+        complex::Complex::new(
+            self.min_x + (position.0 - self.offset_x) / self.scale,
+            self.min_y + (y - self.offset_y) / self.scale,
+        )
+    }
+
+    /// Length in pixels of a path length.
+    pub fn scale(&self, length: f64) -> f64 {
+        length * self.scale
+    }
+}
+
+/// Pixel positions of the vertical and horizontal grid lines of an image of the given size. Lines
+/// are placed on round values of the path coordinates.
+pub fn grid_lines(projection: &Projection, width: u32, height: u32) -> (Vec<f64>, Vec<f64>) {
+    let bottom_left = projection.unproject((0.0, f64::from(height.max(1) - 1)));
+    let top_right = projection.unproject((f64::from(width.max(1) - 1), 0.0));
+    let range = (top_right.re - bottom_left.re).max(top_right.im - bottom_left.im);
+    let step = nice_step(range / GRID_DIVISIONS);
+    if !(step.is_finite() && step > 0.0) {
+        return (Vec::new(), Vec::new());
+    }
+
+    let multiples = |from: f64, to: f64| {
+        let first = (from / step).ceil() as i64;
+        let last = (to / step).floor() as i64;
+        (first..=last)
+            .map(|k| k as f64 * step)
+            .collect::<Vec<f64>>()
+    };
+    let vertical = multiples(bottom_left.re, top_right.re)
+        .into_iter()
+        .map(|x| projection.project(&complex::Complex::new(x, 0.0)).0)
+        .collect();
+    let horizontal = multiples(bottom_left.im, top_right.im)
+        .into_iter()
+        .map(|y| projection.project(&complex::Complex::new(0.0, y)).1)
+        .collect();
+    (vertical, horizontal)
+}
+
+/// Smallest value of the form 1, 2 or 5 times a power of ten which is at least `raw`.
+fn nice_step(raw: f64) -> f64 {
+    let magnitude = 10f64.powf(raw.log10().floor());
+    let normalized = raw / magnitude;
+    let factor = if normalized <= 1.0 {
+        1.0
+    } else if normalized <= 2.0 {
+        2.0
+    } else if normalized <= 5.0 {
+        5.0
+    } else {
+        10.0
+    };
+    factor * magnitude
+}
+
+/// Fills the canvas with the background and, when enabled, the grid.
+pub fn draw_background(canvas: &mut Canvas, projection: &Projection, config: &RenderConfig) {
+    canvas.fill(config.background);
+    if let Some(underlay) = &config.underlay {
+        draw_underlay(canvas, projection, underlay);
+    }
+    if !config.grid {
+        return;
+    }
+    let (width, height) = (canvas.width(), canvas.height());
+    let (vertical, horizontal) = grid_lines(projection, width, height);
+    let (right, bottom) = (f64::from(width), f64::from(height));
+    for x in vertical {
+        canvas.draw_line((x, 0.0), (x, bottom), config.palette.grid, 1.0);
+    }
+    for y in horizontal {
+        canvas.draw_line((0.0, y), (right, y), config.palette.grid, 1.0);
+    }
+}
+
+/// Blends the underlay image into the canvas, sampling the nearest image pixel.
+fn draw_underlay(canvas: &mut Canvas, projection: &Projection, underlay: &Underlay) {
+    let image = &underlay.image;
+    let bounds = &underlay.bounds;
+    if image.width() == 0 || image.height() == 0 || bounds.width() <= 0.0 || bounds.height() <= 0.0
+    {
+        return;
+    }
+    let scale_x = f64::from(image.width()) / bounds.width();
+    let scale_y = f64::from(image.height()) / bounds.height();
+    for y in 0..canvas.height() {
+        for x in 0..canvas.width() {
+            let point = projection.unproject((f64::from(x), f64::from(y)));
+            let column = ((point.re - bounds.min_x) * scale_x).floor();
+            let row = ((bounds.max_y - point.im) * scale_y).floor();
+            if column < 0.0
+                || row < 0.0
+                || column >= f64::from(image.width())
+                || row >= f64::from(image.height())
+            {
+                continue;
+            }
+            let color = image.pixel(column as u32, row as u32);
+            let blended = canvas.pixel(x, y).blend(color, underlay.opacity);
+            canvas.set_pixel(i64::from(x), i64::from(y), blended);
+        }
+    }
+}
+
+/// Writes the caption, if any, centered at the top of the canvas.
+pub fn draw_caption(renderer: &mut dyn Renderer, config: &RenderConfig) {
+    if let Some(caption) = &config.caption {
+        let scale = config.text_scale();
+        let width = i64::from(font::text_width(caption, scale));
+        let left = (i64::from(config.width) - width) / 2;
+        let top = i64::from(4 * scale);
+        renderer.draw_text(
+            (left as f64, top as f64),
+            caption,
+            config.palette.text,
+            scale,
+        );
+    }
+}
+
+/// Annotation of the frame at time `t` of the animation of the reconstruction using `precision`
+/// terms.
+pub fn annotation(epicycle: &epicycle::Epicycle, precision: usize, t: f64) -> String {
+    let mut text = String::new();
+    annotation_into(epicycle, precision, t, &mut text);
+    text
+}
+
+/// Replaces the content of `text` with the `annotation`, reusing its allocation.
+pub fn annotation_into(epicycle: &epicycle::Epicycle, precision: usize, t: f64, text: &mut String) {
+    text.clear();
+    // Writing to a string does not fail.
+    let _ = write!(
+        text,
+        "terms {}/{}  t {:.3}  RMS error {:.4}",
+        precision.min(epicycle.len()),
+        epicycle.len(),
+        t,
+        epicycle.truncation_error(precision)
+    );
+}
+
+/// Writes `text` centered at the bottom of the frame.
+pub fn draw_annotation(renderer: &mut dyn Renderer, text: &str, config: &RenderConfig) {
+    let scale = config.text_scale();
+    let width = i64::from(font::text_width(text, scale));
+    let left = (i64::from(config.width) - width) / 2;
+    let top = i64::from(config.height) - i64::from((4 + font::GLYPH_HEIGHT) * scale);
+    renderer.draw_text((left as f64, top as f64), text, config.palette.text, scale);
+}
+
+/// Draws a polyline whose segments fade into the background as they get older, the newest
+/// segment being at the end of `points`. Ages are measured in fractions of a turn of
+/// `samples_per_turn` points and segments older than `length` are not drawn.
+pub fn draw_fading_trace(
+    canvas: &mut Canvas,
+    points: &[(f64, f64)],
+    samples_per_turn: usize,
+    length: f64,
+    config: &RenderConfig,
+) {
+    let segments = points.len().saturating_sub(1);
+    for (index, pair) in points.windows(2).enumerate() {
+        let age = (segments - index - 1) as f64 / samples_per_turn as f64;
+        let alpha = 1.0 - age / length;
+        if alpha > 0.0 {
+            let color = config.background.blend(config.palette.trace, alpha);
+            canvas.draw_line(pair[0], pair[1], color, config.line_width);
+        }
+    }
+}
+
+/// Draws the first `max_circles` circles of the chain with their arms, and the pen at the end of
+/// the whole chain.
+pub fn draw_circles(
+    renderer: &mut dyn Renderer,
+    projection: &Projection,
+    circles: &[epicycle::Circle],
+    config: &RenderConfig,
+) {
+    let shown = config
+        .max_circles
+        .unwrap_or(circles.len())
+        .min(circles.len());
+    for circle in &circles[..shown] {
+        let center = projection.project(&circle.center);
+        let radius = projection.scale(circle.radius);
+        renderer.draw_circle(center, radius, config.palette.circles, false);
+    }
+    for circle in &circles[..shown] {
+        let arm = [
+            projection.project(&circle.center),
+            projection.project(&circle.end),
+        ];
+        renderer.draw_polyline(&arm, false, config.palette.arms, 1.0);
+    }
+    if config.lump_remainder && shown > 0 && shown < circles.len() {
+        let remainder = [
+            projection.project(&circles[shown - 1].end),
+            projection.project(&circles[circles.len() - 1].end),
+        ];
+        renderer.draw_polyline(&remainder, false, config.palette.pen, 1.0);
+    }
+    if let Some(last) = circles.last() {
+        renderer.draw_circle(projection.project(&last.end), 2.0, config.palette.pen, true);
+    }
+}
+
+/// Full reconstruction using `precision` terms as RGBA bytes, `config.width` pixels per row,
+/// drawn like the last frame of an animation.
+pub fn to_rgba(epicycle: &epicycle::Epicycle, precision: usize, config: &RenderConfig) -> Vec<u8> {
+    let mut animator = Animator::new(epicycle, precision, config, 1);
+    animator
+        .next_frame()
+        .map(|canvas| rgba(canvas, config))
+        .unwrap_or_default()
+}
+
+/// Pixels of `canvas` as RGBA bytes, with a transparent background when configured.
+fn rgba(canvas: &Canvas, config: &RenderConfig) -> Vec<u8> {
+    if !config.transparent {
+        return canvas.to_rgba();
+    }
+    canvas
+        .pixels()
+        .iter()
+        .flat_map(|pixel| pixel.to_alpha(config.background).to_vec())
+        .collect()
+}
+
+/// Iterator over the frames of an animation as RGBA bytes, for integrations compositing or
+/// streaming frames in memory.
+pub struct RgbaFrames {
+    animator: Animator,
+    config: RenderConfig,
+}
+
+impl Iterator for RgbaFrames {
+    type Item = Vec<u8>;
+
+    fn next(&mut self) -> Option<Vec<u8>> {
+        let config = &self.config;
+        self.animator
+            .next_frame()
+            .map(|canvas| rgba(canvas, config))
+    }
+}
+
+/// The `frame_count` frames of the animation of the reconstruction using `precision` terms, as
+/// RGBA bytes.
+pub fn rgba_frames(
+    epicycle: &epicycle::Epicycle,
+    precision: usize,
+    config: &RenderConfig,
+    frame_count: usize,
+) -> RgbaFrames {
+    RgbaFrames {
+        animator: Animator::new(epicycle, precision, config, frame_count),
+        config: config.clone(),
+    }
+}
+
+/// Bitmap of the configured size drawn by `draw`, supersampled when enabled.
+pub fn rasterize<F>(config: &RenderConfig, draw: F) -> Result<Canvas, Box<dyn Error>>
+where
+    F: FnOnce(&mut Canvas, &RenderConfig) -> Result<(), Box<dyn Error>>,
+{
+    let _stage = profile::stage("draw");
+    let large = config.supersampled();
+    let mut canvas = Canvas::new(large.width, large.height, large.background);
+    draw(&mut canvas, &large)?;
+    if config.supersampling <= 1 {
+        return Ok(canvas);
+    }
+    let mut output = Canvas::new(config.width, config.height, config.background);
+    canvas.downsample_into(config.supersampling, &mut output);
+    Ok(output)
+}
+
+/// Writes the `n_frames` frames of the animation as PNG files named `frame_0001.png`,
+/// `frame_0002.png`... in `output_dir`, which is created if needed. Numbers are zero padded to the
+/// width of the largest one, and at least 4 digits.
+pub fn frames(
+    epicycle: &epicycle::Epicycle,
+    precision: usize,
+    output_dir: &str,
+    n_frames: usize,
+    config: &RenderConfig,
+) -> Result<(), Box<dyn Error>> {
+    frames_with_progress(
+        epicycle,
+        precision,
+        output_dir,
+        n_frames,
+        config,
+        &mut progress::silent,
+    )
+}
+
+/// Same as `frames`, calling `progress` after every frame written.
+pub fn frames_with_progress(
+    epicycle: &epicycle::Epicycle,
+    precision: usize,
+    output_dir: &str,
+    n_frames: usize,
+    config: &RenderConfig,
+    progress: &mut dyn FnMut(Progress),
+) -> Result<(), Box<dyn Error>> {
+    let export = FrameExport {
+        frame_count: n_frames,
+        ..FrameExport::default()
+    };
+    export_frames(epicycle, precision, output_dir, &export, config, progress)
+}
+
+/// Options of the export of an animation as a sequence of PNG files.
+#[derive(Debug, Clone)]
+pub struct FrameExport {
+    pub frame_count: usize,
+    /// Writes next to each frame a JSON file with the same name holding the time, the pen
+    /// position and the circles drawn, in pixels, for tools syncing overlays with the frames.
+    pub sidecars: bool,
+    /// Keeps the frames an interrupted export of the same animation recorded as written in its
+    /// `CHECKPOINT` file, and only draws the following ones.
+    pub resume: bool,
+}
+
+impl Default for FrameExport {
+    fn default() -> FrameExport {
+        FrameExport {
+            frame_count: 100,
+            sidecars: false,
+            resume: false,
+        }
+    }
+}
+
+/// File of the output directory of an export recording the animation drawn and the number of
+/// frames already written, updated after every frame and removed once all are written.
+pub const CHECKPOINT: &str = ".fft-checkpoint";
+
+/// Writes the frames of the animation in `output_dir` as described by `export`, calling
+/// `progress` after every frame written.
+pub fn export_frames(
+    epicycle: &epicycle::Epicycle,
+    precision: usize,
+    output_dir: &str,
+    export: &FrameExport,
+    config: &RenderConfig,
+    progress: &mut dyn FnMut(Progress),
+) -> Result<(), Box<dyn Error>> {
+    fs::create_dir_all(output_dir)?;
+    let digits = export.frame_count.to_string().len().max(4);
+    let frame_name = |number: usize| {
+        let mut filename = PathBuf::from(output_dir);
+        filename.push(format!("frame_{:0width$}.png", number, width = digits));
+        filename
+    };
+    let checkpoint = PathBuf::from(output_dir).join(CHECKPOINT);
+    let fingerprint = export_fingerprint(epicycle, precision, export, config);
+    let written = if export.resume {
+        let written = read_checkpoint(&checkpoint, &fingerprint);
+        // Frames deleted since are drawn again.
+        (1..=written)
+            .find(|number| !frame_name(*number).is_file())
+            .map_or(written, |missing| missing - 1)
+    } else {
+        0
+    };
+
+    let projection = config.trace_projection(epicycle, precision);
+    let mut animator = Animator::new(epicycle, precision, config, export.frame_count);
+    animator.skip(written);
+    let mut tracker = Tracker::new(animator.frame_count() - written, progress);
+    let mut circles = Vec::new();
+    let mut number = written + 1;
+    let encode = |canvas: &Canvas, encoded: &mut Vec<u8>| {
+        png::encode_into_with(canvas, config, encoded);
+    };
+    pipeline::encode_frames(animator, config.memory_budget, encode, |time, encoded| {
+        let filename = frame_name(number);
+        fs::write(&filename, encoded)?;
+        if export.sidecars {
+            epicycle.circles_into(precision, time, &mut circles);
+            let state = frame_state(number, time, &circles, &projection, config);
+            fs::write(filename.with_extension("json"), state)?;
+        }
+        write_checkpoint(&checkpoint, &fingerprint, number)?;
+        tracker.frame_done();
+        number += 1;
+        Ok(())
+    })?;
+    // Only the frames are left once complete.
+    if checkpoint.is_file() {
+        fs::remove_file(&checkpoint)?;
+    }
+    Ok(())
+}
+
+/// Digest of everything the frames of an export depend on, so that a checkpoint is only used to
+/// resume the export of the same animation.
+fn export_fingerprint(
+    epicycle: &epicycle::Epicycle,
+    precision: usize,
+    export: &FrameExport,
+    config: &RenderConfig,
+) -> String {
+    let mut hasher = DefaultHasher::new();
+    for (coefficient, frequency) in epicycle.terms() {
+        coefficient.re.to_bits().hash(&mut hasher);
+        coefficient.im.to_bits().hash(&mut hasher);
+        frequency.hash(&mut hasher);
+    }
+    (precision, export.frame_count, export.sidecars).hash(&mut hasher);
+    format!("{:?}", config).hash(&mut hasher);
+    format!("{:016x}", hasher.finish())
+}
+
+/// Number of frames recorded as written in `checkpoint`, 0 when it is missing or was written for
+/// another animation.
+fn read_checkpoint(checkpoint: &std::path::Path, fingerprint: &str) -> usize {
+    let text = fs::read_to_string(checkpoint).unwrap_or_default();
+    let mut lines = text.lines();
+    match (
+        lines.next(),
+        lines.next().and_then(|line| line.parse().ok()),
+    ) {
+        (Some(recorded), Some(written)) if recorded == fingerprint => written,
+        _ => 0,
+    }
+}
+
+/// Records that the frames up to `written` are complete. The checkpoint is replaced through a
+/// rename so that an interruption never leaves it half written.
+fn write_checkpoint(
+    checkpoint: &std::path::Path,
+    fingerprint: &str,
+    written: usize,
+) -> io::Result<()> {
+    let temporary = checkpoint.with_extension("tmp");
+    fs::write(&temporary, format!("{}\n{}\n", fingerprint, written))?;
+    fs::rename(&temporary, checkpoint)
+}
+
+/// JSON description of the state of frame `number` at time `t`.
+fn frame_state(
+    number: usize,
+    t: f64,
+    circles: &[epicycle::Circle],
+    projection: &Projection,
+    config: &RenderConfig,
+) -> String {
+    let point = |point: &complex::Complex| {
+        let (x, y) = projection.project(point);
+        format!("{{\"x\": {:.3}, \"y\": {:.3}}}", x, y)
+    };
+    let pen = circles
+        .last()
+        .map(|circle| point(&circle.end))
+        .unwrap_or_else(|| "null".to_string());
+    let shown = config
+        .max_circles
+        .unwrap_or(circles.len())
+        .min(circles.len());
+    let circles: Vec<String> = circles[..shown]
+        .iter()
+        .map(|circle| {
+            format!(
+                "{{\"center\": {}, \"radius\": {:.3}, \"end\": {}}}",
+                point(&circle.center),
+                projection.scale(circle.radius),
+                point(&circle.end)
+            )
+        })
+        .collect();
+    format!(
+        "{{\"frame\": {}, \"t\": {:.6}, \"width\": {}, \"height\": {}, \"pen\": {}, \
+         \"circles\": [{}]}}\n",
+        number,
+        t,
+        config.width,
+        config.height,
+        pen,
+        circles.join(", ")
+    )
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn color_hex_test() {
+        let color = Color::new(18, 171, 255);
+        assert_eq!("#12abff", color.to_hex());
+        assert_eq!(Some(color), Color::from_hex("#12ABff"));
+        assert_eq!(None, Color::from_hex("12abff"));
+        assert_eq!(None, Color::from_hex("#12abf"));
+        assert_eq!(None, Color::from_hex("#12abfg"));
+    }
+
+    #[test]
+    fn color_blend_test() {
+        let gray = Color::WHITE.blend(Color::BLACK, 0.5);
+        assert_eq!(Color::new(128, 128, 128), gray);
+        assert_eq!(Color::BLACK, Color::WHITE.blend(Color::BLACK, 2.0));
+    }
+
+    #[test]
+    fn draw_fading_trace_test() {
+        let points: Vec<(f64, f64)> = (0..5).map(|x| (f64::from(x) * 2.0, 0.0)).collect();
+        let mut canvas = Canvas::new(9, 1, Color::WHITE);
+        draw_fading_trace(&mut canvas, &points, 4, 0.5, &RenderConfig::default());
+        assert_eq!(Color::BLACK, canvas.pixel(8, 0));
+        assert_eq!(Color::new(128, 128, 128), canvas.pixel(5, 0));
+        assert_eq!(Color::WHITE, canvas.pixel(1, 0));
+    }
+
+    #[test]
+    fn color_to_alpha_test() {
+        assert_eq!([0, 0, 0, 0], Color::WHITE.to_alpha(Color::WHITE));
+        assert_eq!([0, 0, 0, 255], Color::BLACK.to_alpha(Color::WHITE));
+        let gray = Color::new(128, 128, 128);
+        assert_eq!([0, 0, 0, 127], gray.to_alpha(Color::WHITE));
+        let dark = Color::new(18, 18, 24);
+        let faded = dark.blend(Color::new(235, 235, 235), 0.5);
+        let [r, g, b, a] = faded.to_alpha(dark);
+        let restored = dark.blend(Color::new(r, g, b), f64::from(a) / 255.0);
+        assert_eq!(faded, restored);
+    }
+
+    #[test]
+    fn color_to_hex_test() {
+        assert_eq!("#ff8000", Color::new(255, 128, 0).to_hex());
+    }
+
+    #[test]
+    fn trace_points_test() {
+        let square = epicycle::Epicycle::new(&crate::path::Path {
+            data: vec![
+                complex::Complex::new(0.0, 0.0),
+                complex::Complex::new(1.0, 0.0),
+                complex::Complex::new(1.0, 1.0),
+                complex::Complex::new(0.0, 1.0),
+            ],
+        });
+        let small = RenderConfig {
+            width: 64,
+            height: 64,
+            ..RenderConfig::default()
+        };
+        let large = RenderConfig {
+            width: 4096,
+            height: 4096,
+            ..RenderConfig::default()
+        };
+        assert!(small.trace_points(&square, 4).len() < large.trace_points(&square, 4).len());
+        let loose = RenderConfig {
+            max_chord_error: 4.0,
+            ..large.clone()
+        };
+        assert!(loose.trace_points(&square, 4).len() < large.trace_points(&square, 4).len());
+    }
+
+    #[test]
+    fn projection_fit_test() {
+        let points = vec![
+            complex::Complex::new(-1.0, 2.0),
+            complex::Complex::new(3.0, 4.0),
+        ];
+        let projection = Projection::fit(&points, 101, 51, 0.0);
+        assert_eq!((0.0, 50.0), projection.project(&points[0]));
+        assert_eq!((100.0, 0.0), projection.project(&points[1]));
+        assert_eq!(50.0, projection.scale(2.0));
+    }
+
+    #[test]
+    fn projection_preserves_aspect_ratio_test() {
+        let points = vec![
+            complex::Complex::new(-1.0, 2.0),
+            complex::Complex::new(3.0, 4.0),
+        ];
+        let projection = Projection::fit(&points, 101, 101, 0.1);
+        assert_eq!((10.0, 70.0), projection.project(&points[0]));
+        assert_eq!((90.0, 30.0), projection.project(&points[1]));
+    }
+
+    #[test]
+    fn bounds_test() {
+        let points = vec![
+            complex::Complex::new(-1.0, 2.0),
+            complex::Complex::new(3.0, -4.0),
+        ];
+        let bounds = Bounds::of(&points);
+        assert_eq!(-1.0, bounds.min_x);
+        assert_eq!(-4.0, bounds.min_y);
+        assert_eq!(4.0, bounds.width());
+        assert_eq!(6.0, bounds.height());
+        assert_eq!(0.0, Bounds::of(&[]).width());
+    }
+
+    #[test]
+    fn draw_circles_test() {
+        let circles = vec![
+            epicycle::Circle {
+                center: complex::Complex::new(0.0, 0.0),
+                radius: 1.0,
+                end: complex::Complex::new(1.0, 0.0),
+            },
+            epicycle::Circle {
+                center: complex::Complex::new(1.0, 0.0),
+                radius: 0.5,
+                end: complex::Complex::new(1.0, 0.5),
+            },
+        ];
+        let bounds = vec![
+            complex::Complex::new(-2.0, -2.0),
+            complex::Complex::new(2.0, 2.0),
+        ];
+        let projection = Projection::fit(&bounds, 41, 41, 0.0);
+
+        let mut config = RenderConfig {
+            max_circles: Some(1),
+            ..RenderConfig::default()
+        };
+        let palette = config.palette;
+
+        let mut canvas = Canvas::new(41, 41, Color::WHITE);
+        draw_circles(&mut canvas, &projection, &circles, &config);
+        assert_eq!(palette.circles, canvas.pixel(20, 10));
+        assert_eq!(palette.arms, canvas.pixel(25, 20));
+        assert_eq!(palette.pen, canvas.pixel(30, 15));
+        assert_eq!(Color::WHITE, canvas.pixel(35, 20));
+
+        config.lump_remainder = true;
+        draw_circles(&mut canvas, &projection, &circles, &config);
+        assert_eq!(palette.pen, canvas.pixel(30, 18));
+
+        config.max_circles = None;
+        draw_circles(&mut canvas, &projection, &circles, &config);
+        assert_eq!(palette.circles, canvas.pixel(35, 20));
+    }
+
+    #[test]
+    fn unproject_test() {
+        let points = vec![
+            complex::Complex::new(-1.0, 2.0),
+            complex::Complex::new(3.0, 4.0),
+        ];
+        let projection = Projection::fit(&points, 101, 101, 0.1);
+        assert_eq!(
+            points[1],
+            projection.unproject(projection.project(&points[1]))
+        );
+    }
+
+    #[test]
+    fn nice_step_test() {
+        assert_eq!(1.0, nice_step(1.0));
+        assert_eq!(2.0, nice_step(1.3));
+        assert_eq!(0.5, nice_step(0.31));
+        assert_eq!(100.0, nice_step(64.0));
+    }
+
+    #[test]
+    fn grid_lines_test() {
+        let points = vec![
+            complex::Complex::new(0.0, 0.0),
+            complex::Complex::new(10.0, 10.0),
+        ];
+        let projection = Projection::fit(&points, 101, 101, 0.0);
+        let (vertical, horizontal) = grid_lines(&projection, 101, 101);
+        assert_eq!(11, vertical.len());
+        assert_eq!(0.0, vertical[0]);
+        assert_eq!(10.0, vertical[1]);
+        assert_eq!(100.0, horizontal[0]);
+    }
+
+    #[test]
+    fn draw_background_test() {
+        let points = vec![
+            complex::Complex::new(0.0, 0.0),
+            complex::Complex::new(10.0, 10.0),
+        ];
+        let mut config = RenderConfig {
+            width: 101,
+            height: 101,
+            margin: 0.0,
+            background: Color::BLACK,
+            ..RenderConfig::default()
+        };
+        let projection = config.projection(&points);
+        let mut canvas = Canvas::new(101, 101, Color::WHITE);
+        draw_background(&mut canvas, &projection, &config);
+        assert!(canvas.pixels().iter().all(|p| *p == Color::BLACK));
+
+        config.grid = true;
+        draw_background(&mut canvas, &projection, &config);
+        assert_eq!(config.palette.grid, canvas.pixel(10, 55));
+        assert_eq!(Color::BLACK, canvas.pixel(15, 55));
+    }
+
+    #[test]
+    fn with_caption_for_test() {
+        let epicycle = epicycle::Epicycle::new(&crate::path::Path {
+            data: vec![
+                complex::Complex::new(0.0, 0.0),
+                complex::Complex::new(1.0, 0.0),
+                complex::Complex::new(1.0, 1.0),
+                complex::Complex::new(0.0, 1.0),
+            ],
+        });
+        let config = RenderConfig {
+            caption: Some("{file}: {precision}/{terms} terms, error {error} {other} {".to_string()),
+            source: Some("square.txt".to_string()),
+            ..RenderConfig::default()
+        };
+        assert_eq!(
+            Some("square.txt: 4/4 terms, error 0.0000 {other} {".to_string()),
+            config.with_caption_for(&epicycle, 10).caption
+        );
+        assert_eq!(
+            None,
+            RenderConfig::default()
+                .with_caption_for(&epicycle, 1)
+                .caption
+        );
+    }
+
+    #[test]
+    fn draw_caption_test() {
+        let config = RenderConfig {
+            width: 20,
+            height: 20,
+            caption: Some("I".to_string()),
+            ..RenderConfig::default()
+        };
+        let mut canvas = Canvas::new(20, 20, Color::WHITE);
+        draw_caption(&mut canvas, &config);
+        assert_eq!(config.palette.text, canvas.pixel(9, 4));
+        assert_eq!(Color::WHITE, canvas.pixel(9, 3));
+    }
+
+    #[test]
+    fn frames_test() {
+        let epicycle = epicycle::Epicycle::new(&crate::path::Path {
+            data: vec![
+                complex::Complex::new(0.0, 0.0),
+                complex::Complex::new(1.0, 0.0),
+                complex::Complex::new(0.0, 1.0),
+            ],
+        });
+        let config = RenderConfig {
+            width: 8,
+            height: 8,
+            ..RenderConfig::default()
+        };
+        let mut dir = std::env::temp_dir();
+        dir.push(format!("fft_frames_test_{}", std::process::id()));
+        let output_dir = dir.to_str().unwrap();
+
+        let mut completed = Vec::new();
+        frames_with_progress(&epicycle, 3, output_dir, 3, &config, &mut |progress| {
+            completed.push(progress.completed)
+        })
+        .expect("Problem writing frames");
+        assert_eq!(vec![1, 2, 3], completed);
+        let mut names: Vec<String> = fs::read_dir(&dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+            .collect();
+        names.sort();
+        fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(
+            vec!["frame_0001.png", "frame_0002.png", "frame_0003.png"],
+            names
+        );
+    }
+
+    #[test]
+    fn projection_degenerate_test() {
+        let points = vec![complex::Complex::new(2.0, 2.0)];
+        let projection = Projection::fit(&points, 10, 10, 0.1);
+        assert_eq!((4.5, 4.5), projection.project(&points[0]));
+
+        let line = vec![
+            complex::Complex::new(0.0, 1.0),
+            complex::Complex::new(8.0, 1.0),
+        ];
+        let projection = Projection::fit(&line, 9, 9, 0.0);
+        assert_eq!((0.0, 4.0), projection.project(&line[0]));
+        assert_eq!((8.0, 4.0), projection.project(&line[1]));
+    }
+
+    #[test]
+    fn draw_underlay_test() {
+        let mut image = Canvas::new(2, 2, Color::WHITE);
+        image.set_pixel(0, 0, Color::BLACK);
+        let bounds = Bounds {
+            min_x: 0.0,
+            min_y: 0.0,
+            max_x: 2.0,
+            max_y: 2.0,
+        };
+        let config = RenderConfig {
+            margin: 0.0,
+            underlay: Some(Underlay {
+                image: Arc::new(image),
+                bounds,
+                opacity: 0.5,
+            }),
+            ..RenderConfig::default()
+        };
+        let corners = [
+            complex::Complex::new(0.0, 0.0),
+            complex::Complex::new(4.0, 4.0),
+        ];
+        let projection = Projection::fit(&corners, 9, 9, 0.0);
+        let mut canvas = Canvas::new(9, 9, Color::WHITE);
+        draw_background(&mut canvas, &projection, &config);
+        // The image covers the bottom left quarter, its black pixel being its top left one.
+        assert_eq!(Color::new(128, 128, 128), canvas.pixel(1, 5));
+        assert_eq!(Color::WHITE, canvas.pixel(3, 7));
+        assert_eq!(Color::WHITE, canvas.pixel(1, 1));
+    }
+
+    #[test]
+    fn easing_test() {
+        for easing in &[
+            Easing::Linear,
+            Easing::EaseIn,
+            Easing::EaseOut,
+            Easing::EaseInOut,
+        ] {
+            assert_eq!(0.0, easing.apply(0.0));
+            assert_eq!(1.0, easing.apply(1.0));
+        }
+        assert_eq!(0.25, Easing::EaseIn.apply(0.5));
+        assert_eq!(0.75, Easing::EaseOut.apply(0.5));
+        assert_eq!(0.5, Easing::EaseInOut.apply(0.5));
+        assert!(Easing::EaseInOut.apply(0.1) < 0.1);
+    }
+
+    #[test]
+    fn timing_progress_test() {
+        let timing = Timing::default();
+        assert_eq!(0.25, timing.progress(0, 4));
+        assert_eq!(1.0, timing.progress(3, 4));
+
+        let ping_pong = Timing {
+            ping_pong: true,
+            ..Timing::default()
+        };
+        let progress: Vec<f64> = (0..4).map(|frame| ping_pong.progress(frame, 4)).collect();
+        assert_eq!(vec![0.5, 1.0, 0.5, 0.0], progress);
+    }
+
+    #[test]
+    fn rgba_test() {
+        let epicycle = epicycle::Epicycle::new(&crate::path::Path {
+            data: vec![
+                complex::Complex::new(0.0, 0.0),
+                complex::Complex::new(1.0, 0.0),
+                complex::Complex::new(0.0, 1.0),
+            ],
+        });
+        let config = RenderConfig {
+            width: 8,
+            height: 6,
+            ..RenderConfig::default()
+        };
+        let full = to_rgba(&epicycle, 3, &config);
+        assert_eq!(8 * 6 * 4, full.len());
+        assert!(full.chunks(4).any(|pixel| pixel == [0, 0, 0, 255]));
+
+        let frames: Vec<Vec<u8>> = rgba_frames(&epicycle, 3, &config, 3).collect();
+        assert_eq!(3, frames.len());
+        assert_eq!(full, frames[2]);
+    }
+
+    #[test]
+    fn tiny_rgba_test() {
+        let config = RenderConfig {
+            width: 9,
+            height: 9,
+            ..RenderConfig::default()
+        };
+        let background = [255, 255, 255, 255];
+        let empty = epicycle::Epicycle::new(&crate::path::Path { data: Vec::new() });
+        let image = to_rgba(&empty, 0, &config);
+        assert_eq!(9 * 9 * 4, image.len());
+        // The origin, where an empty reconstruction stays, is drawn in the middle.
+        let dot = epicycle::Epicycle::new(&crate::path::Path {
+            data: vec![complex::Complex::new(5.0, 5.0)],
+        });
+        for image in &[image, to_rgba(&dot, 1, &config)] {
+            let drawn: Vec<usize> = image
+                .chunks(4)
+                .enumerate()
+                .filter(|(_, pixel)| *pixel != background)
+                .map(|(index, _)| index)
+                .collect();
+            assert_eq!(vec![4 * 9 + 4], drawn);
+        }
+    }
+
+    #[test]
+    fn frame_state_test() {
+        let circles = vec![epicycle::Circle {
+            center: complex::Complex::new(0.0, 0.0),
+            radius: 1.0,
+            end: complex::Complex::new(1.0, 0.0),
+        }];
+        let bounds = vec![
+            complex::Complex::new(-1.0, -1.0),
+            complex::Complex::new(1.0, 1.0),
+        ];
+        let projection = Projection::fit(&bounds, 11, 11, 0.0);
+        let state = frame_state(3, 0.5, &circles, &projection, &RenderConfig::default());
+        assert_eq!(
+            "{\"frame\": 3, \"t\": 0.500000, \"width\": 640, \"height\": 640, \
+             \"pen\": {\"x\": 10.000, \"y\": 5.000}, \"circles\": [{\"center\": \
+             {\"x\": 5.000, \"y\": 5.000}, \"radius\": 5.000, \"end\": \
+             {\"x\": 10.000, \"y\": 5.000}}]}\n",
+            state
+        );
+    }
+
+    #[test]
+    fn export_frames_with_sidecars_test() {
+        let epicycle = epicycle::Epicycle::new(&crate::path::Path {
+            data: vec![
+                complex::Complex::new(0.0, 0.0),
+                complex::Complex::new(1.0, 0.0),
+                complex::Complex::new(0.0, 1.0),
+            ],
+        });
+        let config = RenderConfig {
+            width: 8,
+            height: 8,
+            ..RenderConfig::default()
+        };
+        let mut dir = std::env::temp_dir();
+        dir.push(format!("fft_sidecars_test_{}", std::process::id()));
+        let export = FrameExport {
+            frame_count: 2,
+            sidecars: true,
+            ..FrameExport::default()
+        };
+        let output_dir = dir.to_str().unwrap();
+        export_frames(
+            &epicycle,
+            3,
+            output_dir,
+            &export,
+            &config,
+            &mut progress::silent,
+        )
+        .expect("Problem writing frames");
+        let sidecar = fs::read_to_string(dir.join("frame_0002.json")).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+        assert!(sidecar.starts_with("{\"frame\": 2, \"t\": "));
+        assert_eq!(3, sidecar.matches("\"radius\"").count());
+    }
+
+    #[test]
+    fn export_frames_resume_test() {
+        let epicycle = epicycle::Epicycle::new(&crate::path::Path {
+            data: vec![
+                complex::Complex::new(0.0, 0.0),
+                complex::Complex::new(1.0, 0.0),
+                complex::Complex::new(0.0, 1.0),
+            ],
+        });
+        let config = RenderConfig {
+            width: 8,
+            height: 8,
+            ..RenderConfig::default()
+        };
+        let mut dir = std::env::temp_dir();
+        dir.push(format!("fft_resume_test_{}", std::process::id()));
+        let output_dir = dir.to_str().unwrap();
+        let mut export = FrameExport {
+            frame_count: 4,
+            ..FrameExport::default()
+        };
+        export_frames(
+            &epicycle,
+            3,
+            output_dir,
+            &export,
+            &config,
+            &mut progress::silent,
+        )
+        .unwrap();
+        let complete = fs::read(dir.join("frame_0004.png")).unwrap();
+
+        // As if interrupted after the third frame, whose file was then lost.
+        let fingerprint = export_fingerprint(&epicycle, 3, &export, &config);
+        write_checkpoint(&dir.join(CHECKPOINT), &fingerprint, 3).unwrap();
+        fs::remove_file(dir.join("frame_0003.png")).unwrap();
+        fs::remove_file(dir.join("frame_0004.png")).unwrap();
+        export.resume = true;
+        let mut drawn = 0;
+        export_frames(&epicycle, 3, output_dir, &export, &config, &mut |_| {
+            drawn += 1
+        })
+        .unwrap();
+        let resumed = fs::read(dir.join("frame_0004.png")).unwrap();
+        // A checkpoint of another animation is ignored.
+        let other = read_checkpoint(&dir.join(CHECKPOINT), "0000000000000000");
+        fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(2, drawn);
+        assert_eq!(complete, resumed);
+        assert_eq!(0, other);
+    }
+}
@@ -0,0 +1,142 @@
+use crate::epicycle;
+use crate::render;
+use crate::render::canvas::Canvas;
+use crate::render::gif::{self, GifConfig};
+use crate::render::renderer::Renderer;
+use crate::render::{Projection, RenderConfig};
+use std::error::Error;
+use std::fs;
+
+/// Animation of the reconstruction of one drawing continuously turning into another one.
+pub struct Morph {
+    from: epicycle::Epicycle,
+    to: epicycle::Epicycle,
+    precision: usize,
+}
+
+impl Morph {
+    pub fn new(from: &epicycle::Epicycle, to: &epicycle::Epicycle, precision: usize) -> Morph {
+        Morph {
+            from: from.clone(),
+            to: to.clone(),
+            precision,
+        }
+    }
+
+    /// Projection fitting both drawings in the image, and therefore every step in between: the
+    /// reconstructions are linear in their coefficients so intermediate points stay within the
+    /// bounds of the two ends.
+    pub fn projection(&self, config: &RenderConfig) -> Projection {
+        let mut points = self.from.sample(self.precision, render::TRACE_SAMPLES);
+        points.extend(self.to.sample(self.precision, render::TRACE_SAMPLES));
+        config.projection(&points)
+    }
+
+    /// Draws the full reconstruction of the drawing `alpha` of the way from the first drawing to
+    /// the second one.
+    pub fn draw(
+        &self,
+        renderer: &mut dyn Renderer,
+        alpha: f64,
+        projection: &Projection,
+        config: &RenderConfig,
+    ) -> Result<(), Box<dyn Error>> {
+        let blended = self.from.blend(&self.to, alpha);
+        let points: Vec<_> = blended
+            .sample(self.precision, render::TRACE_SAMPLES)
+            .iter()
+            .map(|p| projection.project(p))
+            .collect();
+        let config = &config.with_caption_for(&blended, self.precision);
+        renderer.begin_frame(config.width, config.height, config.background);
+        renderer.draw_polyline(&points, true, config.palette.trace, config.line_width);
+        render::draw_caption(renderer, config);
+        renderer.end_frame()
+    }
+
+    /// Frames of the morph, the first one showing the first drawing and the last one the second.
+    pub fn frames(
+        &self,
+        frame_count: usize,
+        config: &RenderConfig,
+    ) -> Result<Vec<Canvas>, Box<dyn Error>> {
+        let large = config.supersampled();
+        let projection = self.projection(&large);
+        (0..frame_count)
+            .map(|frame| {
+                let alpha = frame as f64 / (frame_count.max(2) - 1) as f64;
+                render::rasterize(config, |canvas, config| {
+                    self.draw(canvas, alpha, &projection, config)
+                })
+            })
+            .collect()
+    }
+
+    /// Animated GIF of the morph, lasting the duration of `animation`.
+    pub fn gif(
+        &self,
+        config: &RenderConfig,
+        animation: &GifConfig,
+    ) -> Result<Vec<u8>, Box<dyn Error>> {
+        let frames = self.frames(animation.frame_count(), config)?;
+        Ok(gif::encode_canvases(frames, config, animation)?)
+    }
+
+    pub fn write_gif(
+        &self,
+        config: &RenderConfig,
+        animation: &GifConfig,
+        filename: &str,
+    ) -> Result<(), Box<dyn Error>> {
+        fs::write(filename, self.gif(config, animation)?)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::complex;
+    use crate::path;
+    use crate::render::Color;
+
+    #[test]
+    fn frames_test() {
+        let square = epicycle::Epicycle::new(&path::Path {
+            data: vec![
+                complex::Complex::new(0.0, 0.0),
+                complex::Complex::new(1.0, 0.0),
+                complex::Complex::new(1.0, 1.0),
+                complex::Complex::new(0.0, 1.0),
+            ],
+        });
+        let triangle = epicycle::Epicycle::new(&path::Path {
+            data: vec![
+                complex::Complex::new(0.0, 0.0),
+                complex::Complex::new(2.0, 0.0),
+                complex::Complex::new(1.0, 2.0),
+            ],
+        });
+        let config = RenderConfig {
+            width: 32,
+            height: 32,
+            ..RenderConfig::default()
+        };
+        let morph = Morph::new(&square, &triangle, 4);
+        let frames = morph.frames(3, &config).unwrap();
+        assert_eq!(3, frames.len());
+        let first = frames[0].pixels();
+        assert_ne!(first, frames[1].pixels());
+        assert_ne!(frames[1].pixels(), frames[2].pixels());
+        assert!(first.contains(&Color::BLACK));
+
+        let animation = GifConfig {
+            fps: 10,
+            duration: 0.5,
+            ..GifConfig::default()
+        };
+        let output = morph.gif(&config, &animation).unwrap();
+        assert_eq!(5, output.windows(2).filter(|w| w == &[0x21, 0xF9]).count());
+    }
+}
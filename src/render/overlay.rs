@@ -0,0 +1,135 @@
+use crate::epicycle;
+use crate::render;
+use crate::render::font;
+use crate::render::png;
+use crate::render::renderer::Renderer;
+use crate::render::svg::SvgRenderer;
+use crate::render::{Color, RenderConfig};
+use std::error::Error;
+use std::fs;
+
+/// Colors given in turn to the reconstructions, starting again from the first one when there are
+/// more reconstructions than colors.
+pub const SERIES: [Color; 6] = [
+    Color::new(31, 119, 180),
+    Color::new(255, 127, 14),
+    Color::new(44, 160, 44),
+    Color::new(214, 39, 40),
+    Color::new(148, 103, 189),
+    Color::new(140, 86, 75),
+];
+
+/// Draws the reconstructions of `epicycle` using each of `precisions` terms on top of each other,
+/// each in its own color, with a legend in the top left corner. Caption placeholders describe the
+/// most precise reconstruction.
+pub fn draw(
+    renderer: &mut dyn Renderer,
+    epicycle: &epicycle::Epicycle,
+    precisions: &[usize],
+    config: &RenderConfig,
+) -> Result<(), Box<dyn Error>> {
+    let largest = precisions.iter().cloned().max().unwrap_or(0);
+    let config = &config.with_caption_for(epicycle, largest);
+    let reconstructions: Vec<_> = precisions
+        .iter()
+        .map(|precision| config.trace_points(epicycle, *precision))
+        .collect();
+    let all: Vec<_> = reconstructions.iter().flatten().cloned().collect();
+    let projection = config.projection(&all);
+
+    renderer.begin_frame(config.width, config.height, config.background);
+    for (index, points) in reconstructions.iter().enumerate() {
+        let projected: Vec<_> = points.iter().map(|p| projection.project(p)).collect();
+        let color = SERIES[index % SERIES.len()];
+        renderer.draw_polyline(&projected, true, color, config.line_width);
+    }
+
+    let scale = config.text_scale();
+    let row = f64::from((font::GLYPH_HEIGHT + 4) * scale);
+    let sample = f64::from(4 * font::GLYPH_WIDTH * scale);
+    let left = f64::from(4 * scale);
+    for (index, precision) in precisions.iter().enumerate() {
+        let top = left + index as f64 * row;
+        let middle = top + f64::from(font::GLYPH_HEIGHT * scale) / 2.0;
+        let color = SERIES[index % SERIES.len()];
+        renderer.draw_polyline(
+            &[(left, middle), (left + sample, middle)],
+            false,
+            color,
+            config.line_width.max(2.0),
+        );
+        let label = format!("{} terms", precision);
+        let position = (left + sample + f64::from(3 * scale), top);
+        renderer.draw_text(position, &label, config.palette.text, scale);
+    }
+    render::draw_caption(renderer, config);
+    renderer.end_frame()
+}
+
+pub fn svg(
+    epicycle: &epicycle::Epicycle,
+    precisions: &[usize],
+    config: &RenderConfig,
+) -> Result<String, Box<dyn Error>> {
+    let mut renderer = SvgRenderer::for_config(config);
+    draw(&mut renderer, epicycle, precisions, config)?;
+    Ok(renderer.documents()[0].clone())
+}
+
+pub fn write_svg(
+    epicycle: &epicycle::Epicycle,
+    precisions: &[usize],
+    config: &RenderConfig,
+    filename: &str,
+) -> Result<(), Box<dyn Error>> {
+    fs::write(filename, svg(epicycle, precisions, config)?)?;
+    Ok(())
+}
+
+pub fn write_png(
+    epicycle: &epicycle::Epicycle,
+    precisions: &[usize],
+    config: &RenderConfig,
+    filename: &str,
+) -> Result<(), Box<dyn Error>> {
+    let canvas = render::rasterize(config, |canvas, config| {
+        draw(canvas, epicycle, precisions, config)
+    })?;
+    png::write_with(&canvas, config, filename)
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::render::canvas::Canvas;
+
+    #[test]
+    fn svg_test() {
+        let document = svg(
+            &epicycle::unit_square(),
+            &[1, 2, 4],
+            &RenderConfig::default(),
+        )
+        .unwrap();
+        // One reconstruction and one legend sample per precision.
+        assert_eq!(6, document.matches("<path").count());
+        assert_eq!(2, document.matches(&SERIES[1].to_hex()).count());
+        assert!(document.contains(">4 terms</text>"));
+    }
+
+    #[test]
+    fn draw_test() {
+        let config = RenderConfig {
+            width: 64,
+            height: 64,
+            ..RenderConfig::default()
+        };
+        let mut canvas = Canvas::new(1, 1, Color::WHITE);
+        draw(&mut canvas, &epicycle::unit_square(), &[2, 4], &config).unwrap();
+        for color in &SERIES[..2] {
+            assert!(canvas.pixels().contains(color));
+        }
+        assert!(canvas.pixels().contains(&config.palette.text));
+    }
+}
@@ -0,0 +1,240 @@
+//! Frames of an animation drawn on one thread while other threads encode the previous ones.
+
+use crate::render::animator::Animator;
+use crate::render::canvas::Canvas;
+use crate::render::Color;
+use std::collections::BTreeMap;
+use std::io;
+use std::mem;
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+/// Frame drawn by the animator, numbered from 0, with the time of the pen and the buffer to
+/// encode it into.
+struct Drawn {
+    index: usize,
+    time: f64,
+    canvas: Canvas,
+    data: Vec<u8>,
+}
+
+/// Frame once encoded, still holding its canvas to give it back with its buffer for a later
+/// frame.
+struct Encoded {
+    index: usize,
+    time: f64,
+    data: Vec<u8>,
+    canvas: Canvas,
+}
+
+/// Draws the frames of `animator` while `encode` turns them into bytes on other threads, replacing
+/// the content of the buffer it is given, then calls `write` on the calling thread with the time
+/// of the pen and the bytes of each frame, in order. Stops at the first error of `write`.
+///
+/// Frames are drawn into a fixed number of canvases and encoded into as many buffers, given back
+/// once written, so that memory does not grow when encoding or writing falls behind drawing and
+/// frames are not allocated again once the first ones are done. With a `budget` in bytes, there
+/// are no more frames between drawing and writing than it holds, each taken as its canvas and
+/// encoded data as large. Frames are encoded one at a time on the calling thread when it holds
+/// fewer than two.
+pub fn encode_frames<E, W>(
+    mut animator: Animator,
+    budget: Option<usize>,
+    encode: E,
+    mut write: W,
+) -> io::Result<()>
+where
+    E: Fn(&Canvas, &mut Vec<u8>) + Sync,
+    W: FnMut(f64, &[u8]) -> io::Result<()>,
+{
+    let (width, height) = animator.frame_size();
+    let frame_bytes = 2 * width as usize * height as usize * mem::size_of::<Color>();
+    let in_flight = budget.map_or(usize::MAX, |budget| budget / frame_bytes.max(1));
+    if in_flight < 2 {
+        let mut data = Vec::new();
+        while let Some(canvas) = animator.next_frame() {
+            encode(canvas, &mut data);
+            write(animator.time(), &data)?;
+        }
+        return Ok(());
+    }
+    let encoders = thread::available_parallelism()
+        .map_or(1, |threads| threads.get())
+        .saturating_sub(1)
+        .max(1)
+        .min(in_flight - 1);
+    let canvases = (2 * encoders + 1).min(in_flight);
+    let (drawn_sender, drawn) = mpsc::sync_channel::<Drawn>(canvases);
+    let drawn = Arc::new(Mutex::new(drawn));
+    let (encoded_sender, encoded) = mpsc::channel::<Encoded>();
+    let (free, free_receiver) = mpsc::channel::<(Canvas, Vec<u8>)>();
+    let encode = &encode;
+
+    thread::scope(|scope| {
+        scope.spawn(move || draw(animator, canvases, drawn_sender, free_receiver));
+        for _ in 0..encoders {
+            let drawn = Arc::clone(&drawn);
+            let encoded_sender = encoded_sender.clone();
+            scope.spawn(move || loop {
+                // The lock is released before encoding.
+                let next = drawn.lock().unwrap().recv();
+                let mut frame = match next {
+                    Ok(frame) => frame,
+                    Err(_) => return,
+                };
+                encode(&frame.canvas, &mut frame.data);
+                let encoded = Encoded {
+                    index: frame.index,
+                    time: frame.time,
+                    data: frame.data,
+                    canvas: frame.canvas,
+                };
+                if encoded_sender.send(encoded).is_err() {
+                    return;
+                }
+            });
+        }
+        // Only the encoders keep the channels, so that they close once the encoders stop.
+        drop(drawn);
+        drop(encoded_sender);
+        write_in_order(encoded, free, &mut write)
+    })
+}
+
+/// Draws every frame of `animator` into one of `canvases` canvases, each with its buffer, waiting
+/// for one to be given back through `free` once all are in use.
+fn draw(
+    mut animator: Animator,
+    canvases: usize,
+    drawn: mpsc::SyncSender<Drawn>,
+    free: Receiver<(Canvas, Vec<u8>)>,
+) {
+    let mut created = 0;
+    let mut index = 0;
+    while let Some(frame) = animator.next_frame() {
+        let (canvas, data) = if created < canvases {
+            created += 1;
+            (frame.clone(), Vec::new())
+        } else {
+            match free.recv() {
+                Ok((mut canvas, data)) => {
+                    canvas.copy_from(frame);
+                    (canvas, data)
+                }
+                // Writing stopped.
+                Err(_) => return,
+            }
+        };
+        let frame = Drawn {
+            index,
+            time: animator.time(),
+            canvas,
+            data,
+        };
+        if drawn.send(frame).is_err() {
+            return;
+        }
+        index += 1;
+    }
+}
+
+/// Writes the frames received from `encoded` in order, giving their canvases and buffers back to
+/// `free`. Both channels are dropped on return, which stops the other threads on an error.
+fn write_in_order<W>(
+    encoded: Receiver<Encoded>,
+    free: Sender<(Canvas, Vec<u8>)>,
+    write: &mut W,
+) -> io::Result<()>
+where
+    W: FnMut(f64, &[u8]) -> io::Result<()>,
+{
+    let mut pending = BTreeMap::new();
+    let mut next = 0;
+    for frame in encoded {
+        pending.insert(frame.index, frame);
+        while let Some(frame) = pending.remove(&next) {
+            write(frame.time, &frame.data)?;
+            let _ = free.send((frame.canvas, frame.data));
+            next += 1;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::epicycle;
+    use crate::render::RenderConfig;
+
+    fn rgba(canvas: &Canvas, data: &mut Vec<u8>) {
+        *data = canvas.to_rgba();
+    }
+
+    fn animator(frame_count: usize) -> Animator {
+        let epicycle = epicycle::unit_square();
+        let config = RenderConfig {
+            width: 16,
+            height: 16,
+            ..RenderConfig::default()
+        };
+        Animator::new(&epicycle, 4, &config, frame_count)
+    }
+
+    #[test]
+    fn encode_frames_test() {
+        let mut expected = Vec::new();
+        let mut sequential = animator(40);
+        while let Some(canvas) = sequential.next_frame() {
+            let data = canvas.to_rgba();
+            expected.push((sequential.time(), data));
+        }
+
+        let mut written = Vec::new();
+        encode_frames(animator(40), None, rgba, |time, data| {
+            written.push((time, data.to_vec()));
+            Ok(())
+        })
+        .unwrap();
+        assert_eq!(expected, written);
+    }
+
+    #[test]
+    fn encode_frames_budget_test() {
+        let expected = {
+            let mut written = Vec::new();
+            encode_frames(animator(10), None, rgba, |time, data| {
+                written.push((time, data.to_vec()));
+                Ok(())
+            })
+            .unwrap();
+            written
+        };
+        // Budgets for no frame at all, one frame and three frames of 16 by 16 pixels.
+        for budget in [0, 2 * 16 * 16 * 3, 6 * 16 * 16 * 3] {
+            let mut written = Vec::new();
+            encode_frames(animator(10), Some(budget), rgba, |time, data| {
+                written.push((time, data.to_vec()));
+                Ok(())
+            })
+            .unwrap();
+            assert_eq!(expected, written);
+        }
+    }
+
+    #[test]
+    fn encode_frames_error_test() {
+        let mut written = 0;
+        let result = encode_frames(animator(40), None, rgba, |_, _| {
+            written += 1;
+            if written == 3 {
+                return Err(io::Error::other("full"));
+            }
+            Ok(())
+        });
+        assert_eq!("full", result.unwrap_err().to_string());
+        assert_eq!(3, written);
+    }
+}
@@ -0,0 +1,572 @@
+use crate::profile;
+use crate::render::canvas::Canvas;
+use crate::render::{Color, RenderConfig};
+use std::error::Error;
+use std::fs;
+
+const SIGNATURE: [u8; 8] = [0x89, b'P', b'N', b'G', 0x0D, 0x0A, 0x1A, 0x0A];
+
+/// Farthest back a deflate match can start.
+const WINDOW: usize = 1 << 15;
+
+/// Shortest and longest deflate matches.
+const MIN_MATCH: usize = 3;
+const MAX_MATCH: usize = 258;
+
+/// Number of earlier positions sharing their first bytes tried for each match.
+const MAX_CHAIN: usize = 32;
+
+const HASH_BITS: u32 = 15;
+
+/// Smallest lengths of the length codes 257 to 285, and their numbers of extra bits.
+const LENGTH_BASES: [u16; 29] = [
+    3, 4, 5, 6, 7, 8, 9, 10, 11, 13, 15, 17, 19, 23, 27, 31, 35, 43, 51, 59, 67, 83, 99, 115, 131,
+    163, 195, 227, 258,
+];
+const LENGTH_EXTRA_BITS: [u8; 29] = [
+    0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 3, 3, 3, 3, 4, 4, 4, 4, 5, 5, 5, 5, 0,
+];
+
+/// Smallest distances of the distance codes 0 to 29, and their numbers of extra bits.
+const DISTANCE_BASES: [u16; 30] = [
+    1, 2, 3, 4, 5, 7, 9, 13, 17, 25, 33, 49, 65, 97, 129, 193, 257, 385, 513, 769, 1025, 1537,
+    2049, 3073, 4097, 6145, 8193, 12289, 16385, 24577,
+];
+const DISTANCE_EXTRA_BITS: [u8; 30] = [
+    0, 0, 0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 5, 5, 6, 6, 7, 7, 8, 8, 9, 9, 10, 10, 11, 11, 12, 12, 13,
+    13,
+];
+
+pub fn encode(canvas: &Canvas) -> Vec<u8> {
+    let mut output = Vec::new();
+    encode_into(canvas, &mut output);
+    output
+}
+
+/// Encodes `canvas` as an 8 bits RGB PNG into `output`, replacing its content but reusing its
+/// allocation. Each row is filtered as predicted best, then the rows are compressed with deflate
+/// and its fixed Huffman codes.
+pub fn encode_into(canvas: &Canvas, output: &mut Vec<u8>) {
+    encode_image(canvas, None, None, output);
+}
+
+/// Same as `encode_into`, following the output settings of `config`: the resolution is recorded
+/// when `dpi` is set and the background is made transparent when `transparent` is set.
+pub fn encode_into_with(canvas: &Canvas, config: &RenderConfig, output: &mut Vec<u8>) {
+    let transparent = if config.transparent {
+        Some(config.background)
+    } else {
+        None
+    };
+    encode_image(canvas, config.dpi, transparent, output);
+}
+
+/// Encodes `canvas` as RGB, or as RGBA with `transparent` turned into alpha.
+fn encode_image(
+    canvas: &Canvas,
+    dpi: Option<u32>,
+    transparent: Option<Color>,
+    output: &mut Vec<u8>,
+) {
+    let _stage = profile::stage("encode");
+    output.clear();
+    output.extend_from_slice(&SIGNATURE);
+
+    let mut header = Vec::with_capacity(13);
+    header.extend_from_slice(&canvas.width().to_be_bytes());
+    header.extend_from_slice(&canvas.height().to_be_bytes());
+    // 8 bits per channel, RGB or RGBA, default compression, filtering and no interlacing.
+    let color_type = if transparent.is_some() { 6 } else { 2 };
+    header.extend_from_slice(&[8, color_type, 0, 0, 0]);
+    write_chunk(output, b"IHDR", &header);
+    if let Some(dpi) = dpi {
+        let per_meter = (f64::from(dpi) / 0.0254).round() as u32;
+        let mut physical = Vec::with_capacity(9);
+        physical.extend_from_slice(&per_meter.to_be_bytes());
+        physical.extend_from_slice(&per_meter.to_be_bytes());
+        // Unit is the meter.
+        physical.push(1);
+        write_chunk(output, b"pHYs", &physical);
+    }
+
+    let channels = if transparent.is_some() { 4 } else { 3 };
+    let mut raw = Vec::with_capacity(canvas.pixels().len() * channels);
+    for pixel in canvas.pixels() {
+        match transparent {
+            Some(background) => raw.extend_from_slice(&pixel.to_alpha(background)),
+            None => raw.extend_from_slice(&[pixel.r, pixel.g, pixel.b]),
+        }
+    }
+    let filtered = filter(&raw, canvas.width() as usize * channels, channels);
+    write_chunk(output, b"IDAT", &zlib(&filtered));
+    write_chunk(output, b"IEND", &[]);
+}
+
+pub fn write(canvas: &Canvas, filename: &str) -> Result<(), Box<dyn Error>> {
+    fs::write(filename, encode(canvas))?;
+    Ok(())
+}
+
+pub fn write_with(
+    canvas: &Canvas,
+    config: &RenderConfig,
+    filename: &str,
+) -> Result<(), Box<dyn Error>> {
+    let mut output = Vec::new();
+    encode_into_with(canvas, config, &mut output);
+    fs::write(filename, output)?;
+    Ok(())
+}
+
+fn write_chunk(output: &mut Vec<u8>, kind: &[u8; 4], data: &[u8]) {
+    output.extend_from_slice(&(data.len() as u32).to_be_bytes());
+    let start = output.len();
+    output.extend_from_slice(kind);
+    output.extend_from_slice(data);
+    let crc = crc32(&output[start..]);
+    output.extend_from_slice(&crc.to_be_bytes());
+}
+
+/// `rows` of `row_length` bytes, pixels of `channels` bytes, each preceded by the type of the
+/// filter applied to it. Each row takes the filter leaving the smallest differences, counted as
+/// signed bytes, which then compress best: runs of one color become runs of zeros.
+fn filter(rows: &[u8], row_length: usize, channels: usize) -> Vec<u8> {
+    let mut output = Vec::with_capacity(rows.len() + rows.len() / row_length.max(1));
+    if row_length == 0 {
+        return output;
+    }
+    let zeros = vec![0; row_length];
+    let mut candidate = vec![0; row_length];
+    let mut best = vec![0; row_length];
+    let mut above: &[u8] = &zeros;
+    for row in rows.chunks(row_length) {
+        let mut best_score = u64::MAX;
+        let mut best_kind = 0;
+        for kind in 0..5u8 {
+            for index in 0..row_length {
+                let left = if index >= channels {
+                    row[index - channels]
+                } else {
+                    0
+                };
+                let upper_left = if index >= channels {
+                    above[index - channels]
+                } else {
+                    0
+                };
+                let prediction = match kind {
+                    0 => 0,
+                    1 => left,
+                    2 => above[index],
+                    3 => ((u16::from(left) + u16::from(above[index])) / 2) as u8,
+                    _ => paeth(left, above[index], upper_left),
+                };
+                candidate[index] = row[index].wrapping_sub(prediction);
+            }
+            let score = candidate
+                .iter()
+                .map(|byte| u64::from((*byte as i8).unsigned_abs()))
+                .sum();
+            if score < best_score {
+                best_score = score;
+                best_kind = kind;
+                std::mem::swap(&mut best, &mut candidate);
+            }
+        }
+        output.push(best_kind);
+        output.extend_from_slice(&best);
+        above = row;
+    }
+    output
+}
+
+/// Of `left`, `above` and `upper_left`, the closest to `left + above - upper_left`.
+fn paeth(left: u8, above: u8, upper_left: u8) -> u8 {
+    let estimate = i16::from(left) + i16::from(above) - i16::from(upper_left);
+    let distance = |value: u8| (estimate - i16::from(value)).abs();
+    if distance(left) <= distance(above) && distance(left) <= distance(upper_left) {
+        left
+    } else if distance(above) <= distance(upper_left) {
+        above
+    } else {
+        upper_left
+    }
+}
+
+/// Zlib stream of `data`, compressed as a single deflate block with the fixed Huffman codes.
+/// Repeated bytes are found through chains of the earlier positions starting with the same
+/// three bytes.
+fn zlib(data: &[u8]) -> Vec<u8> {
+    let mut output = Vec::with_capacity(data.len() / 8 + 16);
+    output.extend_from_slice(&[0x78, 0x5E]);
+    let mut bits = Bits {
+        output: &mut output,
+        buffer: 0,
+        count: 0,
+    };
+    // Final block, with fixed codes.
+    bits.write(0b011, 3);
+
+    let mut chains = Chains {
+        data,
+        heads: vec![NONE; 1 << HASH_BITS],
+        previous: vec![NONE; WINDOW],
+    };
+    let mut position = 0;
+    while position < data.len() {
+        let longest = MAX_MATCH.min(data.len() - position);
+        let (mut length, mut distance) = (0, 0);
+        if longest >= MIN_MATCH {
+            let mut candidate = chains.heads[chains.hash(position)];
+            let mut tries = 0;
+            while candidate != NONE && tries < MAX_CHAIN && position - candidate as usize <= WINDOW
+            {
+                let start = candidate as usize;
+                let matched = data[start..start + longest]
+                    .iter()
+                    .zip(&data[position..position + longest])
+                    .take_while(|(first, second)| first == second)
+                    .count();
+                if matched > length {
+                    length = matched;
+                    distance = position - start;
+                    if length == longest {
+                        break;
+                    }
+                }
+                candidate = chains.previous[start % WINDOW];
+                tries += 1;
+            }
+        }
+        if length >= MIN_MATCH {
+            bits.write_length(length);
+            bits.write_distance(distance);
+            for skipped in position..position + length {
+                chains.insert(skipped);
+            }
+            position += length;
+        } else {
+            bits.write_symbol(u16::from(data[position]));
+            chains.insert(position);
+            position += 1;
+        }
+    }
+    bits.write_symbol(256);
+    bits.flush();
+    output.extend_from_slice(&adler32(data).to_be_bytes());
+    output
+}
+
+/// No earlier position in `Chains`.
+const NONE: u32 = u32::MAX;
+
+/// Earlier positions of `data` by the hash of their first three bytes, from the latest back, and
+/// within the window.
+struct Chains<'a> {
+    data: &'a [u8],
+    /// Latest position of each hash.
+    heads: Vec<u32>,
+    /// Position before each one of the window with the same hash.
+    previous: Vec<u32>,
+}
+
+impl Chains<'_> {
+    fn hash(&self, position: usize) -> usize {
+        let bytes = (u32::from(self.data[position]) << 16)
+            | (u32::from(self.data[position + 1]) << 8)
+            | u32::from(self.data[position + 2]);
+        (bytes.wrapping_mul(2_654_435_761) >> (32 - HASH_BITS)) as usize
+    }
+
+    fn insert(&mut self, position: usize) {
+        if position + MIN_MATCH <= self.data.len() {
+            let hash = self.hash(position);
+            self.previous[position % WINDOW] = self.heads[hash];
+            self.heads[hash] = position as u32;
+        }
+    }
+}
+
+/// Bits of a deflate stream, packed least significant first.
+struct Bits<'a> {
+    output: &'a mut Vec<u8>,
+    buffer: u64,
+    count: u32,
+}
+
+impl Bits<'_> {
+    fn write(&mut self, value: u32, count: u32) {
+        self.buffer |= u64::from(value) << self.count;
+        self.count += count;
+        while self.count >= 8 {
+            self.output.push(self.buffer as u8);
+            self.buffer >>= 8;
+            self.count -= 8;
+        }
+    }
+
+    /// Writes a Huffman code, whose most significant bit comes first.
+    fn write_code(&mut self, code: u32, length: u32) {
+        self.write(code.reverse_bits() >> (32 - length), length);
+    }
+
+    /// Writes the fixed code of the literal or length symbol `symbol`.
+    fn write_symbol(&mut self, symbol: u16) {
+        let symbol = u32::from(symbol);
+        match symbol {
+            0..=143 => self.write_code(0x30 + symbol, 8),
+            144..=255 => self.write_code(0x190 + symbol - 144, 9),
+            256..=279 => self.write_code(symbol - 256, 7),
+            _ => self.write_code(0xC0 + symbol - 280, 8),
+        }
+    }
+
+    fn write_length(&mut self, length: usize) {
+        let index = LENGTH_BASES.partition_point(|base| usize::from(*base) <= length) - 1;
+        self.write_symbol(257 + index as u16);
+        let extra = length - usize::from(LENGTH_BASES[index]);
+        self.write(extra as u32, u32::from(LENGTH_EXTRA_BITS[index]));
+    }
+
+    fn write_distance(&mut self, distance: usize) {
+        let index = DISTANCE_BASES.partition_point(|base| usize::from(*base) <= distance) - 1;
+        self.write_code(index as u32, 5);
+        let extra = distance - usize::from(DISTANCE_BASES[index]);
+        self.write(extra as u32, u32::from(DISTANCE_EXTRA_BITS[index]));
+    }
+
+    /// Writes the bits left, padded to a byte.
+    fn flush(&mut self) {
+        if self.count > 0 {
+            self.output.push(self.buffer as u8);
+            self.buffer = 0;
+            self.count = 0;
+        }
+    }
+}
+
+fn crc32(data: &[u8]) -> u32 {
+    let mut crc = 0xFFFF_FFFFu32;
+    for byte in data {
+        crc ^= u32::from(*byte);
+        for _ in 0..8 {
+            let mask = (crc & 1).wrapping_neg();
+            crc = (crc >> 1) ^ (0xEDB8_8320 & mask);
+        }
+    }
+    !crc
+}
+
+fn adler32(data: &[u8]) -> u32 {
+    const MODULO: u32 = 65521;
+    let (mut a, mut b) = (1u32, 0u32);
+    for chunk in data.chunks(5552) {
+        for byte in chunk {
+            a += u32::from(*byte);
+            b += a;
+        }
+        a %= MODULO;
+        b %= MODULO;
+    }
+    (b << 16) | a
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use std::convert::TryFrom;
+
+    #[test]
+    fn crc32_test() {
+        assert_eq!(0xAE42_6082, crc32(b"IEND"));
+        assert_eq!(0xCBF4_3926, crc32(b"123456789"));
+    }
+
+    #[test]
+    fn adler32_test() {
+        assert_eq!(0x11E6_0398, adler32(b"Wikipedia"));
+        assert_eq!(1, adler32(&[]));
+    }
+
+    /// Data of the zlib stream `stream` made of a single block with the fixed codes, as written
+    /// by `zlib`.
+    fn inflate(stream: &[u8]) -> Vec<u8> {
+        let mut position = 16;
+        let mut read = |count: usize| {
+            let mut value = 0;
+            for offset in 0..count {
+                let bit = position + offset;
+                value |= usize::from((stream[bit / 8] >> (bit % 8)) & 1) << offset;
+            }
+            position += count;
+            value
+        };
+        assert_eq!(0b011, read(3));
+        let mut output: Vec<u8> = Vec::new();
+        loop {
+            // Codes are read most significant bit first.
+            let mut code = 0;
+            let mut symbol = None;
+            for length in 1..=9 {
+                code = (code << 1) | read(1);
+                symbol = match (length, code) {
+                    (7, 0..=0x17) => Some(code + 256),
+                    (8, 0x30..=0xBF) => Some(code - 0x30),
+                    (8, 0xC0..=0xC7) => Some(code - 0xC0 + 280),
+                    (9, 0x190..=0x1FF) => Some(code - 0x190 + 144),
+                    _ => None,
+                };
+                if symbol.is_some() {
+                    break;
+                }
+            }
+            match symbol.unwrap() {
+                literal @ 0..=255 => output.push(literal as u8),
+                256 => break,
+                symbol => {
+                    let index = symbol - 257;
+                    let length = usize::from(LENGTH_BASES[index])
+                        + read(usize::from(LENGTH_EXTRA_BITS[index]));
+                    let index = (0..5).fold(0, |code, _| (code << 1) | read(1));
+                    let distance = usize::from(DISTANCE_BASES[index])
+                        + read(usize::from(DISTANCE_EXTRA_BITS[index]));
+                    for _ in 0..length {
+                        output.push(output[output.len() - distance]);
+                    }
+                }
+            }
+        }
+        let end = position.div_ceil(8);
+        assert_eq!(&adler32(&output).to_be_bytes(), &stream[end..]);
+        output
+    }
+
+    /// Rows of `filtered` with their filters undone.
+    fn unfilter(filtered: &[u8], row_length: usize, channels: usize) -> Vec<u8> {
+        let mut rows: Vec<u8> = Vec::new();
+        for (y, row) in filtered.chunks(row_length + 1).enumerate() {
+            for index in 0..row_length {
+                let above = if y > 0 {
+                    rows[rows.len() - row_length]
+                } else {
+                    0
+                };
+                let left = if index >= channels {
+                    rows[rows.len() - channels]
+                } else {
+                    0
+                };
+                let upper_left = if y > 0 && index >= channels {
+                    rows[rows.len() - row_length - channels]
+                } else {
+                    0
+                };
+                let prediction = match row[0] {
+                    0 => 0,
+                    1 => left,
+                    2 => above,
+                    3 => ((u16::from(left) + u16::from(above)) / 2) as u8,
+                    _ => paeth(left, above, upper_left),
+                };
+                rows.push(row[index + 1].wrapping_add(prediction));
+            }
+        }
+        rows
+    }
+
+    /// Pixels of the PNG `output`, from its single IDAT chunk.
+    fn pixels(output: &[u8], row_length: usize, channels: usize) -> Vec<u8> {
+        let start = output.windows(4).position(|kind| kind == b"IDAT").unwrap();
+        let length =
+            u32::from_be_bytes(<[u8; 4]>::try_from(&output[start - 4..start]).unwrap()) as usize;
+        let filtered = inflate(&output[start + 4..start + 4 + length]);
+        unfilter(&filtered, row_length, channels)
+    }
+
+    #[test]
+    fn zlib_test() {
+        let mut noise = 7u32;
+        let inputs: Vec<Vec<u8>> = vec![
+            Vec::new(),
+            vec![42],
+            vec![0; 100_000],
+            b"abcabcabcabd".repeat(50),
+            (0..70_000)
+                .map(|_| {
+                    noise = noise.wrapping_mul(1_103_515_245).wrapping_add(12345);
+                    (noise >> 28) as u8
+                })
+                .collect(),
+        ];
+        for data in inputs {
+            assert_eq!(data, inflate(&zlib(&data)), "{} bytes", data.len());
+        }
+        assert!(zlib(&vec![0; 100_000]).len() < 1000);
+    }
+
+    #[test]
+    fn filter_test() {
+        // A gradient, predicted exactly from the left, then a row like the one above.
+        let rows = [10, 20, 30, 40, 50, 60, 10, 20, 30, 40, 50, 60];
+        let filtered = filter(&rows, 6, 3);
+        assert_eq!(&[1, 10, 20, 30, 30, 30, 30], &filtered[0..7]);
+        assert_eq!(&[2, 0, 0, 0, 0, 0, 0], &filtered[7..14]);
+        assert_eq!(rows.to_vec(), unfilter(&filtered, 6, 3));
+        assert_eq!(4, paeth(4, 9, 9));
+        assert_eq!(9, paeth(4, 9, 4));
+        assert!(filter(&[], 0, 3).is_empty());
+    }
+
+    #[test]
+    fn encode_test() {
+        let mut canvas = Canvas::new(2, 1, Color::WHITE);
+        canvas.set_pixel(1, 0, Color::new(1, 2, 3));
+        let output = encode(&canvas);
+
+        assert_eq!(&SIGNATURE, &output[0..8]);
+        assert_eq!(b"IHDR", &output[12..16]);
+        assert_eq!(&[0, 0, 0, 2, 0, 0, 0, 1, 8, 2], &output[16..26]);
+        let idat = 8 + 25;
+        assert_eq!(b"IDAT", &output[idat + 4..idat + 8]);
+        assert_eq!(vec![255, 255, 255, 1, 2, 3], pixels(&output, 6, 3));
+        assert_eq!(b"IEND", &output[output.len() - 8..output.len() - 4]);
+
+        let mut reused = vec![1, 2, 3];
+        encode_into(&canvas, &mut reused);
+        assert_eq!(output, reused);
+    }
+
+    #[test]
+    fn encode_with_dpi_test() {
+        let canvas = Canvas::new(1, 1, Color::WHITE);
+        let config = RenderConfig {
+            dpi: Some(300),
+            ..RenderConfig::default()
+        };
+        let mut output = Vec::new();
+        encode_into_with(&canvas, &config, &mut output);
+        let physical = 8 + 25;
+        assert_eq!(b"pHYs", &output[physical + 4..physical + 8]);
+        assert_eq!(
+            &11811u32.to_be_bytes(),
+            &output[physical + 8..physical + 12]
+        );
+        assert_eq!(1, output[physical + 16]);
+        assert_eq!(encode(&canvas).len() + 21, output.len());
+    }
+
+    #[test]
+    fn encode_transparent_test() {
+        let mut canvas = Canvas::new(2, 1, Color::WHITE);
+        canvas.set_pixel(1, 0, Color::BLACK);
+        let config = RenderConfig {
+            transparent: true,
+            ..RenderConfig::default()
+        };
+        let mut output = Vec::new();
+        encode_into_with(&canvas, &config, &mut output);
+        assert_eq!(6, output[25]);
+        assert_eq!(vec![0, 0, 0, 0, 0, 0, 0, 255], pixels(&output, 8, 4));
+    }
+}
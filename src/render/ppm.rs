@@ -0,0 +1,136 @@
+use crate::profile;
+use crate::render::canvas::Canvas;
+use crate::render::Color;
+use std::error::Error;
+use std::fs;
+use std::io::BufRead;
+
+/// Decodes a binary PPM (`P6`) image with 8 bits per channel.
+pub fn decode(data: &[u8]) -> Result<Canvas, Box<dyn Error>> {
+    read_image(&mut &data[..])?.ok_or_else(|| "Truncated PPM header".into())
+}
+
+/// Reads the next binary PPM image from `reader`, which can hold several one after the other, as
+/// written by video decoders, or `None` at the end of the stream.
+pub fn read_image<R: BufRead>(reader: &mut R) -> Result<Option<Canvas>, Box<dyn Error>> {
+    let mut fields = Vec::with_capacity(4);
+    let mut field = Vec::new();
+    let mut comment = false;
+    let mut byte = [0];
+    // The whitespace character ending the last field is the only one before the pixels.
+    while fields.len() < 4 {
+        if reader.read(&mut byte)? == 0 {
+            if fields.is_empty() && field.is_empty() {
+                return Ok(None);
+            }
+            return Err("Truncated PPM header".into());
+        }
+        match byte[0] {
+            b'\n' if comment => comment = false,
+            _ if comment => {}
+            b'#' if field.is_empty() => comment = true,
+            value if value.is_ascii_whitespace() => {
+                if !field.is_empty() {
+                    fields.push(String::from_utf8_lossy(&field).into_owned());
+                    field.clear();
+                }
+            }
+            value => field.push(value),
+        }
+    }
+
+    if fields[0] != "P6" {
+        return Err(format!("Unsupported PPM format {}, expected P6", fields[0]).into());
+    }
+    let width: u32 = fields[1].parse()?;
+    let height: u32 = fields[2].parse()?;
+    let max: u32 = fields[3].parse()?;
+    if max == 0 || max > 255 {
+        return Err(format!("Unsupported PPM maximum value {}", max).into());
+    }
+    let mut data = vec![0; width as usize * height as usize * 3];
+    reader
+        .read_exact(&mut data)
+        .map_err(|_| "Truncated PPM pixel data")?;
+
+    let mut canvas = Canvas::new(width, height, Color::BLACK);
+    let scale = |value: u8| (u32::from(value) * 255 / max) as u8;
+    for (index, rgb) in data.chunks(3).enumerate() {
+        let (x, y) = (index as u32 % width, index as u32 / width);
+        let color = Color::new(scale(rgb[0]), scale(rgb[1]), scale(rgb[2]));
+        canvas.set_pixel(i64::from(x), i64::from(y), color);
+    }
+    Ok(Some(canvas))
+}
+
+pub fn read(filename: &str) -> Result<Canvas, Box<dyn Error>> {
+    decode(&fs::read(filename)?)
+}
+
+/// Encodes `canvas` as a binary PPM (`P6`) image.
+pub fn encode(canvas: &Canvas) -> Vec<u8> {
+    let _stage = profile::stage("encode");
+    let header = format!("P6\n{} {}\n255\n", canvas.width(), canvas.height());
+    let mut data = Vec::with_capacity(header.len() + canvas.pixels().len() * 3);
+    data.extend_from_slice(header.as_bytes());
+    for pixel in canvas.pixels() {
+        data.extend_from_slice(&[pixel.r, pixel.g, pixel.b]);
+    }
+    data
+}
+
+pub fn write(canvas: &Canvas, filename: &str) -> Result<(), Box<dyn Error>> {
+    fs::write(filename, encode(canvas))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn decode_test() {
+        let mut data = b"P6\n# comment\n2 1\n255\n".to_vec();
+        data.extend_from_slice(&[1, 2, 3, 255, 255, 255]);
+        let canvas = decode(&data).unwrap();
+        assert_eq!((2, 1), (canvas.width(), canvas.height()));
+        assert_eq!(Color::new(1, 2, 3), canvas.pixel(0, 0));
+        assert_eq!(Color::WHITE, canvas.pixel(1, 0));
+    }
+
+    #[test]
+    fn encode_test() {
+        let mut canvas = Canvas::new(2, 1, Color::WHITE);
+        canvas.set_pixel(0, 0, Color::new(1, 2, 3));
+        let data = encode(&canvas);
+        assert!(data.starts_with(b"P6\n2 1\n255\n"));
+        let decoded = decode(&data).unwrap();
+        assert_eq!(canvas.pixels(), decoded.pixels());
+    }
+
+    #[test]
+    fn decode_errors_test() {
+        assert!(decode(b"P3\n1 1\n255\n0 0 0").is_err());
+        assert!(decode(b"P6\n2 2\n255\n\x00\x00\x00").is_err());
+        assert!(decode(b"P6\n2").is_err());
+        assert!(decode(b"").is_err());
+    }
+
+    #[test]
+    fn read_image_test() {
+        let mut first = Canvas::new(2, 1, Color::WHITE);
+        first.set_pixel(1, 0, Color::new(1, 2, 3));
+        let second = Canvas::new(1, 2, Color::new(4, 5, 6));
+        let mut data = encode(&first);
+        data.extend_from_slice(&encode(&second));
+
+        let mut reader = &data[..];
+        let read = read_image(&mut reader).unwrap().unwrap();
+        assert_eq!(first.pixels(), read.pixels());
+        let read = read_image(&mut reader).unwrap().unwrap();
+        assert_eq!((1, 2), (read.width(), read.height()));
+        assert_eq!(second.pixels(), read.pixels());
+        assert!(read_image(&mut reader).unwrap().is_none());
+    }
+}
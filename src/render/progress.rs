@@ -0,0 +1,138 @@
+use std::io::Write;
+use std::time::{Duration, Instant};
+
+/// State of a render producing several frames, passed to progress callbacks after every frame.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Progress {
+    pub completed: usize,
+    pub total: usize,
+    pub elapsed: Duration,
+}
+
+impl Progress {
+    /// Share of the frames completed, between 0 and 1.
+    pub fn fraction(&self) -> f64 {
+        if self.total == 0 {
+            return 1.0;
+        }
+        self.completed as f64 / self.total as f64
+    }
+
+    /// Estimated time left, assuming the remaining frames take as long as the completed ones.
+    /// `None` until the first frame is completed.
+    pub fn eta(&self) -> Option<Duration> {
+        if self.completed == 0 {
+            return None;
+        }
+        let remaining = self.total.saturating_sub(self.completed) as f64;
+        let per_frame = self.elapsed.as_secs_f64() / self.completed as f64;
+        Some(Duration::from_secs_f64(per_frame * remaining))
+    }
+}
+
+/// Measures the time elapsed since the start of a render and notifies `callback` of each frame.
+pub(crate) struct Tracker<'a> {
+    callback: &'a mut dyn FnMut(Progress),
+    start: Instant,
+    total: usize,
+    completed: usize,
+}
+
+impl<'a> Tracker<'a> {
+    pub(crate) fn new(total: usize, callback: &'a mut dyn FnMut(Progress)) -> Tracker<'a> {
+        Tracker {
+            callback,
+            start: Instant::now(),
+            total,
+            completed: 0,
+        }
+    }
+
+    pub(crate) fn frame_done(&mut self) {
+        self.completed += 1;
+        (self.callback)(Progress {
+            completed: self.completed,
+            total: self.total,
+            elapsed: self.start.elapsed(),
+        });
+    }
+}
+
+/// Progress callback printing a single updating line on the standard error, such as
+/// `frame 12/100 (12%), 00:41 left`.
+pub fn print(progress: Progress) {
+    let eta = match progress.eta() {
+        Some(eta) => format!(", {} left", format_duration(eta)),
+        None => String::new(),
+    };
+    let end = if progress.completed >= progress.total {
+        "\n"
+    } else {
+        ""
+    };
+    let mut stderr = std::io::stderr();
+    let _ = write!(
+        stderr,
+        "\rframe {}/{} ({:.0}%){}{}",
+        progress.completed,
+        progress.total,
+        progress.fraction() * 100.0,
+        eta,
+        end
+    );
+    let _ = stderr.flush();
+}
+
+/// Progress callback ignoring every update.
+pub fn silent(_: Progress) {}
+
+fn format_duration(duration: Duration) -> String {
+    let seconds = duration.as_secs_f64().round() as u64;
+    if seconds >= 3600 {
+        format!(
+            "{}:{:02}:{:02}",
+            seconds / 3600,
+            seconds / 60 % 60,
+            seconds % 60
+        )
+    } else {
+        format!("{:02}:{:02}", seconds / 60, seconds % 60)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn eta_test() {
+        let mut progress = Progress {
+            completed: 0,
+            total: 4,
+            elapsed: Duration::from_secs(0),
+        };
+        assert_eq!(None, progress.eta());
+
+        progress.completed = 1;
+        progress.elapsed = Duration::from_secs(3);
+        assert_eq!(Some(Duration::from_secs(9)), progress.eta());
+        assert_eq!(0.25, progress.fraction());
+    }
+
+    #[test]
+    fn tracker_test() {
+        let mut reported = Vec::new();
+        let mut callback = |progress: Progress| reported.push((progress.completed, progress.total));
+        let mut tracker = Tracker::new(2, &mut callback);
+        tracker.frame_done();
+        tracker.frame_done();
+        assert_eq!(vec![(1, 2), (2, 2)], reported);
+    }
+
+    #[test]
+    fn format_duration_test() {
+        assert_eq!("01:05", format_duration(Duration::from_secs(65)));
+        assert_eq!("2:00:03", format_duration(Duration::from_secs(7203)));
+    }
+}
@@ -0,0 +1,309 @@
+use crate::epicycle;
+use crate::render;
+use crate::render::canvas::Canvas;
+use crate::render::{Color, RenderConfig};
+use std::error::Error;
+use std::f64::consts::PI;
+
+/// Drawing backend. Coordinates are in pixels, with the origin at the top left corner of the
+/// frame.
+pub trait Renderer {
+    /// Starts a new frame of the given size, cleared with `background`.
+    fn begin_frame(&mut self, width: u32, height: u32, background: Color);
+
+    fn draw_polyline(&mut self, points: &[(f64, f64)], closed: bool, color: Color, width: f64);
+
+    /// Draws the outline of a circle, or a disc when `filled` is set.
+    fn draw_circle(&mut self, center: (f64, f64), radius: f64, color: Color, filled: bool);
+
+    /// Draws `text` in the 5x7 bitmap font with its top left corner at `position`, each font
+    /// pixel being a `scale` by `scale` square.
+    fn draw_text(&mut self, position: (f64, f64), text: &str, color: Color, scale: u32);
+
+    /// Fills the rectangle of `size` with its top left corner at `position`, by default with one
+    /// line per row of pixels.
+    fn fill_rect(&mut self, position: (f64, f64), size: (f64, f64), color: Color) {
+        let mut y = position.1 + 0.5;
+        while y < position.1 + size.1 {
+            let row = [(position.0, y), (position.0 + size.0, y)];
+            self.draw_polyline(&row, false, color, 1.0);
+            y += 1.0;
+        }
+    }
+
+    /// Completes the current frame.
+    fn end_frame(&mut self) -> Result<(), Box<dyn Error>>;
+}
+
+/// Part of a larger drawing that frames are drawn into, such as one plot among others in a
+/// figure. Frames start at `position` in the drawing of the wrapped renderer, whose own frames
+/// are begun and ended by the caller around those of the area. Beginning a frame fills the
+/// area with its background, and what is drawn is not clipped to it.
+pub struct Area<'a> {
+    renderer: &'a mut dyn Renderer,
+    position: (f64, f64),
+}
+
+impl<'a> Area<'a> {
+    pub fn new(renderer: &'a mut dyn Renderer, position: (f64, f64)) -> Area<'a> {
+        Area { renderer, position }
+    }
+
+    fn moved(&self, point: (f64, f64)) -> (f64, f64) {
+        (point.0 + self.position.0, point.1 + self.position.1)
+    }
+}
+
+impl Renderer for Area<'_> {
+    fn begin_frame(&mut self, width: u32, height: u32, background: Color) {
+        let size = (f64::from(width), f64::from(height));
+        self.renderer.fill_rect(self.position, size, background);
+    }
+
+    fn draw_polyline(&mut self, points: &[(f64, f64)], closed: bool, color: Color, width: f64) {
+        let moved: Vec<(f64, f64)> = points.iter().map(|point| self.moved(*point)).collect();
+        self.renderer.draw_polyline(&moved, closed, color, width);
+    }
+
+    fn draw_circle(&mut self, center: (f64, f64), radius: f64, color: Color, filled: bool) {
+        let center = self.moved(center);
+        self.renderer.draw_circle(center, radius, color, filled);
+    }
+
+    fn draw_text(&mut self, position: (f64, f64), text: &str, color: Color, scale: u32) {
+        let position = self.moved(position);
+        self.renderer.draw_text(position, text, color, scale);
+    }
+
+    fn fill_rect(&mut self, position: (f64, f64), size: (f64, f64), color: Color) {
+        let position = self.moved(position);
+        self.renderer.fill_rect(position, size, color);
+    }
+
+    /// Leaves the frame of the whole drawing to the caller.
+    fn end_frame(&mut self) -> Result<(), Box<dyn Error>> {
+        Ok(())
+    }
+}
+
+impl Renderer for Canvas {
+    fn begin_frame(&mut self, width: u32, height: u32, background: Color) {
+        if width == self.width() && height == self.height() {
+            self.fill(background);
+        } else {
+            *self = Canvas::new(width, height, background);
+        }
+    }
+
+    fn draw_polyline(&mut self, points: &[(f64, f64)], closed: bool, color: Color, width: f64) {
+        for pair in points.windows(2) {
+            self.draw_line(pair[0], pair[1], color, width);
+        }
+        if closed && points.len() > 2 {
+            self.draw_line(points[points.len() - 1], points[0], color, width);
+        }
+    }
+
+    fn draw_circle(&mut self, center: (f64, f64), radius: f64, color: Color, filled: bool) {
+        if filled {
+            self.fill_disc(center, radius, color);
+            return;
+        }
+        // Drawn as the closed polyline of its segments, one segment at a time rather than
+        // collecting the points, since animations draw many circles on every frame.
+        let point = |segment: usize| {
+            let angle = 2.0 * PI * segment as f64 / render::CIRCLE_SEGMENTS as f64;
+            (
+                center.0 + radius * angle.cos(),
+                center.1 + radius * angle.sin(),
+            )
+        };
+        let first = point(0);
+        let mut previous = first;
+        for segment in 1..render::CIRCLE_SEGMENTS {
+            let next = point(segment);
+            self.draw_line(previous, next, color, 1.0);
+            previous = next;
+        }
+        if render::CIRCLE_SEGMENTS > 2 {
+            self.draw_line(previous, first, color, 1.0);
+        }
+    }
+
+    fn draw_text(&mut self, position: (f64, f64), text: &str, color: Color, scale: u32) {
+        let position = (position.0.round() as i64, position.1.round() as i64);
+        Canvas::draw_text(self, position, text, color, scale);
+    }
+
+    fn fill_rect(&mut self, position: (f64, f64), size: (f64, f64), color: Color) {
+        let left = position.0.round() as i64;
+        let top = position.1.round() as i64;
+        let right = (position.0 + size.0).round() as i64;
+        let bottom = (position.1 + size.1).round() as i64;
+        for y in top..bottom {
+            for x in left..right {
+                self.set_pixel(x, y, color);
+            }
+        }
+    }
+
+    fn end_frame(&mut self) -> Result<(), Box<dyn Error>> {
+        Ok(())
+    }
+}
+
+/// Draws one frame of the animation at time `t` with any backend: the grid, the trace drawn so
+/// far, the circles leading to the pen when enabled, the caption and the annotation.
+pub fn draw_frame(
+    renderer: &mut dyn Renderer,
+    epicycle: &epicycle::Epicycle,
+    precision: usize,
+    t: f64,
+    config: &RenderConfig,
+) -> Result<(), Box<dyn Error>> {
+    let config = &config.with_caption_for(epicycle, precision);
+    let projection = config.trace_projection(epicycle, precision);
+    let drawn = ((t / (2.0 * PI)) * render::TRACE_SAMPLES as f64).floor() as usize;
+    let mut partial: Vec<(f64, f64)> = epicycle
+        .samples(precision, render::TRACE_SAMPLES)
+        .take(drawn + 1)
+        .map(|p| projection.project(&p))
+        .collect();
+    partial.push(projection.project(&epicycle.get_coordinate_for(precision, t)));
+
+    renderer.begin_frame(config.width, config.height, config.background);
+    if config.grid {
+        let (vertical, horizontal) = render::grid_lines(&projection, config.width, config.height);
+        let (width, height) = (f64::from(config.width), f64::from(config.height));
+        for x in vertical {
+            renderer.draw_polyline(&[(x, 0.0), (x, height)], false, config.palette.grid, 1.0);
+        }
+        for y in horizontal {
+            renderer.draw_polyline(&[(0.0, y), (width, y)], false, config.palette.grid, 1.0);
+        }
+    }
+    match config.trail {
+        render::Trail::Full => {
+            renderer.draw_polyline(&partial, false, config.palette.trace, config.line_width)
+        }
+        render::Trail::Fade(length) => {
+            let segments = partial.len() - 1;
+            for (index, pair) in partial.windows(2).enumerate() {
+                let age = (segments - index - 1) as f64 / render::TRACE_SAMPLES as f64;
+                let alpha = 1.0 - age / length;
+                if alpha > 0.0 {
+                    let color = config.background.blend(config.palette.trace, alpha);
+                    renderer.draw_polyline(pair, false, color, config.line_width);
+                }
+            }
+        }
+    }
+    if config.circles {
+        let circles = epicycle.circles_at(precision, t);
+        render::draw_circles(renderer, &projection, &circles, config);
+    }
+    render::draw_caption(renderer, config);
+    if config.annotate {
+        let annotation = render::annotation(epicycle, precision, t);
+        render::draw_annotation(renderer, &annotation, config);
+    }
+    renderer.end_frame()
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::complex;
+    use crate::path;
+
+    #[test]
+    fn canvas_renderer_test() {
+        let mut canvas = Canvas::new(1, 1, Color::BLACK);
+        canvas.begin_frame(9, 9, Color::WHITE);
+        assert_eq!(9, canvas.width());
+        assert_eq!(Color::WHITE, canvas.pixel(0, 0));
+
+        canvas.draw_polyline(
+            &[(1.0, 1.0), (7.0, 1.0), (7.0, 7.0)],
+            true,
+            Color::BLACK,
+            1.0,
+        );
+        assert_eq!(Color::BLACK, canvas.pixel(4, 1));
+        assert_eq!(Color::BLACK, canvas.pixel(4, 4));
+
+        canvas.draw_circle((4.0, 4.0), 1.0, Color::new(1, 2, 3), true);
+        assert_eq!(Color::new(1, 2, 3), canvas.pixel(4, 5));
+        canvas.end_frame().unwrap();
+    }
+
+    #[test]
+    fn area_test() {
+        let epicycle = epicycle::Epicycle::new(&path::Path {
+            data: vec![
+                complex::Complex::new(0.0, 0.0),
+                complex::Complex::new(1.0, 0.0),
+                complex::Complex::new(1.0, 1.0),
+                complex::Complex::new(0.0, 1.0),
+            ],
+        });
+        let config = RenderConfig {
+            width: 24,
+            height: 20,
+            circles: true,
+            ..RenderConfig::default()
+        };
+        let mut alone = Canvas::new(1, 1, Color::WHITE);
+        draw_frame(&mut alone, &epicycle, 4, PI, &config).unwrap();
+
+        let mut figure = Canvas::new(1, 1, Color::WHITE);
+        figure.begin_frame(40, 30, Color::new(1, 2, 3));
+        draw_frame(
+            &mut Area::new(&mut figure, (10.0, 5.0)),
+            &epicycle,
+            4,
+            PI,
+            &config,
+        )
+        .unwrap();
+        assert_eq!((40, 30), (figure.width(), figure.height()));
+        for y in 0..20 {
+            for x in 0..24 {
+                assert_eq!(
+                    alone.pixel(x, y),
+                    figure.pixel(x + 10, y + 5),
+                    "{} {}",
+                    x,
+                    y
+                );
+            }
+        }
+        assert_eq!(Color::new(1, 2, 3), figure.pixel(5, 5));
+        assert_eq!(Color::new(1, 2, 3), figure.pixel(10, 26));
+    }
+
+    #[test]
+    fn draw_frame_test() {
+        let epicycle = epicycle::Epicycle::new(&path::Path {
+            data: vec![
+                complex::Complex::new(0.0, 0.0),
+                complex::Complex::new(1.0, 0.0),
+                complex::Complex::new(1.0, 1.0),
+                complex::Complex::new(0.0, 1.0),
+            ],
+        });
+        let config = RenderConfig {
+            width: 32,
+            height: 32,
+            circles: true,
+            ..RenderConfig::default()
+        };
+        let mut canvas = Canvas::new(1, 1, Color::WHITE);
+        draw_frame(&mut canvas, &epicycle, 4, PI, &config).unwrap();
+        assert_eq!(32, canvas.height());
+        let count = |color: Color| canvas.pixels().iter().filter(|p| **p == color).count();
+        assert!(count(config.palette.trace) > 0);
+        assert!(count(config.palette.pen) > 0);
+    }
+}
@@ -0,0 +1,98 @@
+use crate::epicycle;
+use crate::render;
+use crate::render::canvas::Canvas;
+use crate::render::renderer;
+use crate::render::RenderConfig;
+use std::error::Error;
+use std::f64::consts::PI;
+
+/// Caption of each cell when the configuration has none.
+pub const DEFAULT_CAPTION: &str = "{precision} terms";
+
+/// Number of columns and rows of a sheet of `count` cells, as square as possible.
+pub fn layout(count: usize) -> (u32, u32) {
+    let columns = (count as f64).sqrt().ceil().max(1.0) as u32;
+    let rows = (count as u32).div_ceil(columns);
+    (columns, rows.max(1))
+}
+
+/// Contact sheet of the configured size showing the reconstruction with each of `precisions`,
+/// left to right then top to bottom, each cell captioned with its number of terms.
+pub fn draw(
+    epicycle: &epicycle::Epicycle,
+    precisions: &[usize],
+    config: &RenderConfig,
+) -> Result<Canvas, Box<dyn Error>> {
+    let (columns, rows) = layout(precisions.len());
+    let cell = RenderConfig {
+        width: config.width / columns,
+        height: config.height / rows,
+        caption: config
+            .caption
+            .clone()
+            .or_else(|| Some(DEFAULT_CAPTION.to_string())),
+        ..config.clone()
+    };
+    if cell.width == 0 || cell.height == 0 {
+        return Err(format!(
+            "A {}x{} image is too small for {} drawings",
+            config.width,
+            config.height,
+            precisions.len()
+        )
+        .into());
+    }
+    let mut sheet = Canvas::new(config.width, config.height, config.background);
+    for (index, precision) in precisions.iter().enumerate() {
+        let image = render::rasterize(&cell, |canvas, config| {
+            renderer::draw_frame(canvas, epicycle, *precision, 2.0 * PI, config)
+        })?;
+        let (column, row) = (index as u32 % columns, index as u32 / columns);
+        sheet.draw_canvas(&image, column * cell.width, row * cell.height);
+    }
+    Ok(sheet)
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::complex;
+    use crate::path;
+
+    #[test]
+    fn layout_test() {
+        assert_eq!((1, 1), layout(0));
+        assert_eq!((1, 1), layout(1));
+        assert_eq!((2, 2), layout(4));
+        assert_eq!((3, 2), layout(5));
+        assert_eq!((3, 3), layout(7));
+    }
+
+    #[test]
+    fn draw_test() {
+        let epicycle = epicycle::Epicycle::new(&path::Path {
+            data: vec![
+                complex::Complex::new(0.0, 0.0),
+                complex::Complex::new(1.0, 0.0),
+                complex::Complex::new(1.0, 1.0),
+                complex::Complex::new(0.0, 1.0),
+            ],
+        });
+        let config = RenderConfig {
+            width: 64,
+            height: 40,
+            ..RenderConfig::default()
+        };
+        let sheet = draw(&epicycle, &[1, 2, 4], &config).unwrap();
+        assert_eq!((64, 40), (sheet.width(), sheet.height()));
+        // Every cell of the top row holds a drawing.
+        for column in 0..2 {
+            let drawn = (0..20)
+                .flat_map(|y| (0..32).map(move |x| (column * 32 + x, y)))
+                .any(|(x, y)| sheet.pixel(x, y) != config.background);
+            assert!(drawn);
+        }
+        assert!(draw(&epicycle, &[1; 5000], &config).is_err());
+    }
+}
@@ -0,0 +1,272 @@
+use crate::epicycle;
+use crate::render;
+use crate::render::png;
+use crate::render::renderer::Renderer;
+use crate::render::svg::SvgRenderer;
+use crate::render::RenderConfig;
+use std::error::Error;
+use std::f64::consts::PI;
+use std::fs;
+
+/// Options of the spectrum plot.
+#[derive(Debug, Clone, Default)]
+pub struct SpectrumConfig {
+    /// Adds a second panel showing the phase of each term, between -π and π.
+    pub phase: bool,
+    /// Plots the phase unwrapped along increasing frequencies as a line instead of bars.
+    pub unwrap_phase: bool,
+    /// Only shows frequencies from `-max_frequency` to `max_frequency`, all of them by default.
+    pub max_frequency: Option<u64>,
+}
+
+/// Terms with an amplitude below this fraction of the largest one are left out of unwrapped
+/// phases, their phase being mostly rounding noise.
+const SIGNIFICANT_AMPLITUDE: f64 = 1E-9;
+
+/// Phase of each significant term in radians, sorted by increasing frequency and unwrapped: a
+/// multiple of 2π is added to each phase so that it differs from the previous one by at most π.
+pub fn unwrapped_phases(epicycle: &epicycle::Epicycle) -> Vec<(i64, f64)> {
+    let largest = epicycle
+        .terms()
+        .iter()
+        .map(|(coefficient, _)| coefficient.amplitude())
+        .fold(0.0, f64::max);
+    let mut terms: Vec<_> = epicycle
+        .terms()
+        .iter()
+        .filter(|(coefficient, _)| {
+            largest > 0.0 && coefficient.amplitude() >= SIGNIFICANT_AMPLITUDE * largest
+        })
+        .collect();
+    terms.sort_by_key(|(_, frequency)| *frequency);
+
+    let mut phases = Vec::with_capacity(terms.len());
+    let mut previous: Option<f64> = None;
+    for (coefficient, frequency) in terms {
+        let mut phase = coefficient.phase().in_radians();
+        if let Some(previous) = previous {
+            phase += 2.0 * PI * ((previous - phase) / (2.0 * PI)).round();
+        }
+        previous = Some(phase);
+        phases.push((*frequency, phase));
+    }
+    phases
+}
+
+/// Draws the amplitude of every term of `epicycle` as a bar at its signed frequency, with the
+/// constant term in the middle. Colors come from the palette: bars use `trace`, phases
+/// `reference` and axes `grid`.
+pub fn draw(
+    renderer: &mut dyn Renderer,
+    epicycle: &epicycle::Epicycle,
+    config: &RenderConfig,
+    spectrum: &SpectrumConfig,
+) -> Result<(), Box<dyn Error>> {
+    let highest = epicycle
+        .terms()
+        .iter()
+        .map(|(_, frequency)| frequency.unsigned_abs())
+        .max()
+        .unwrap_or(0);
+    let limit = spectrum.max_frequency.unwrap_or(highest).min(highest) as i64;
+    let shown: Vec<_> = epicycle
+        .terms()
+        .iter()
+        .filter(|(_, frequency)| frequency.abs() <= limit)
+        .collect();
+
+    let (width, height) = (f64::from(config.width), f64::from(config.height));
+    let margin = config.margin * width.min(height);
+    let (left, right) = (margin, width - margin);
+    let slot = (right - left) / (2 * limit + 1) as f64;
+    let bar_width = (slot * 0.8).max(1.0);
+    let x = |frequency: i64| left + (frequency + limit) as f64 * slot + slot / 2.0;
+
+    let (amplitude_top, amplitude_bottom, phase_panel) = if spectrum.phase {
+        let middle = height / 2.0;
+        (
+            margin,
+            middle - margin / 2.0,
+            Some((middle + margin / 2.0, height - margin)),
+        )
+    } else {
+        (margin, height - margin, None)
+    };
+
+    renderer.begin_frame(config.width, config.height, config.background);
+    let axis = config.palette.grid;
+    renderer.draw_polyline(
+        &[(left, amplitude_bottom), (right, amplitude_bottom)],
+        false,
+        axis,
+        1.0,
+    );
+    let largest = shown
+        .iter()
+        .map(|(coefficient, _)| coefficient.amplitude())
+        .fold(0.0, f64::max);
+    if largest > 0.0 {
+        let scale = (amplitude_bottom - amplitude_top) / largest;
+        for (coefficient, frequency) in &shown {
+            let top = amplitude_bottom - coefficient.amplitude() * scale;
+            let bar = [(x(*frequency), amplitude_bottom), (x(*frequency), top)];
+            renderer.draw_polyline(&bar, false, config.palette.trace, bar_width);
+        }
+    }
+
+    if let Some((top, bottom)) = phase_panel {
+        if spectrum.unwrap_phase {
+            let phases: Vec<_> = unwrapped_phases(epicycle)
+                .into_iter()
+                .filter(|(frequency, _)| frequency.abs() <= limit)
+                .collect();
+            let lowest = phases.iter().map(|p| p.1).fold(f64::INFINITY, f64::min);
+            let highest = phases.iter().map(|p| p.1).fold(f64::NEG_INFINITY, f64::max);
+            let range = (highest - lowest).max(2.0 * PI);
+            let middle = (lowest + highest) / 2.0;
+            let y = |phase: f64| (top + bottom) / 2.0 - (phase - middle) / range * (bottom - top);
+            renderer.draw_polyline(&[(left, bottom), (right, bottom)], false, axis, 1.0);
+            let points: Vec<_> = phases.iter().map(|(f, phase)| (x(*f), y(*phase))).collect();
+            let color = config.palette.reference;
+            renderer.draw_polyline(&points, false, color, config.line_width);
+            for point in points {
+                renderer.draw_circle(point, (bar_width / 4.0).clamp(1.0, 3.0), color, true);
+            }
+            return renderer.end_frame();
+        }
+        let zero = (top + bottom) / 2.0;
+        renderer.draw_polyline(&[(left, zero), (right, zero)], false, axis, 1.0);
+        let scale = (bottom - top) / (2.0 * PI);
+        for (coefficient, frequency) in &shown {
+            let mut phase = coefficient.phase().in_radians();
+            if phase > PI {
+                phase -= 2.0 * PI;
+            }
+            let end = zero - phase * scale;
+            let bar = [(x(*frequency), zero), (x(*frequency), end)];
+            renderer.draw_polyline(&bar, false, config.palette.reference, bar_width);
+        }
+    }
+    renderer.end_frame()
+}
+
+/// SVG document of the spectrum of `epicycle`.
+pub fn svg(
+    epicycle: &epicycle::Epicycle,
+    config: &RenderConfig,
+    spectrum: &SpectrumConfig,
+) -> Result<String, Box<dyn Error>> {
+    let mut renderer = SvgRenderer::for_config(config);
+    draw(&mut renderer, epicycle, config, spectrum)?;
+    Ok(renderer.documents()[0].clone())
+}
+
+pub fn write_svg(
+    epicycle: &epicycle::Epicycle,
+    config: &RenderConfig,
+    spectrum: &SpectrumConfig,
+    filename: &str,
+) -> Result<(), Box<dyn Error>> {
+    fs::write(filename, svg(epicycle, config, spectrum)?)?;
+    Ok(())
+}
+
+pub fn write_png(
+    epicycle: &epicycle::Epicycle,
+    config: &RenderConfig,
+    spectrum: &SpectrumConfig,
+    filename: &str,
+) -> Result<(), Box<dyn Error>> {
+    let canvas = render::rasterize(config, |canvas, config| {
+        draw(canvas, epicycle, config, spectrum)
+    })?;
+    png::write_with(&canvas, config, filename)
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::complex;
+    use crate::path;
+    use crate::render::canvas::Canvas;
+    use crate::render::Color;
+
+    /// Path made of the constant term 1 and the frequency -1 with amplitude 1/2.
+    fn two_terms() -> epicycle::Epicycle {
+        epicycle::Epicycle::new(&path::Path {
+            data: (0..4)
+                .map(|k| {
+                    let angle = -2.0 * PI * f64::from(k) / 4.0;
+                    complex::Complex::new(1.0 + 0.5 * angle.cos(), 0.5 * angle.sin())
+                })
+                .collect(),
+        })
+    }
+
+    #[test]
+    fn draw_test() {
+        let config = RenderConfig {
+            width: 50,
+            height: 40,
+            margin: 0.0,
+            ..RenderConfig::default()
+        };
+        let mut canvas = Canvas::new(1, 1, Color::WHITE);
+        draw(
+            &mut canvas,
+            &two_terms(),
+            &config,
+            &SpectrumConfig::default(),
+        )
+        .unwrap();
+
+        let trace = config.palette.trace;
+        // Slots of 10 pixels from frequency -2 to 2, bars at their centers.
+        assert_eq!(trace, canvas.pixel(25, 2));
+        assert_eq!(trace, canvas.pixel(15, 25));
+        assert_eq!(Color::WHITE, canvas.pixel(15, 15));
+        assert_eq!(Color::WHITE, canvas.pixel(35, 25));
+    }
+
+    #[test]
+    fn svg_test() {
+        let spectrum = SpectrumConfig {
+            phase: true,
+            max_frequency: Some(0),
+            ..SpectrumConfig::default()
+        };
+        let document = svg(&two_terms(), &RenderConfig::default(), &spectrum).unwrap();
+        assert!(document.starts_with("<svg"));
+        // Two axes, one amplitude bar and one phase bar.
+        assert_eq!(4, document.matches("<path").count());
+    }
+
+    #[test]
+    fn unwrapped_phases_test() {
+        // A delayed impulse has a phase decreasing linearly with the frequency, which wraps
+        // around several times over the spectrum.
+        let mut data = vec![complex::Complex::new(0.0, 0.0); 16];
+        data[3] = complex::Complex::new(1.0, 0.0);
+        let epicycle = epicycle::Epicycle::new(&path::Path { data });
+        let phases = unwrapped_phases(&epicycle);
+        assert_eq!(16, phases.len());
+        assert_eq!(-7, phases[0].0);
+        for pair in phases.windows(2) {
+            let step = pair[1].1 - pair[0].1;
+            assert!((step + 2.0 * PI * 3.0 / 16.0).abs() < 1E-9);
+        }
+        assert!(unwrapped_phases(&two_terms()).len() == 2);
+    }
+
+    #[test]
+    fn unwrapped_phase_plot_test() {
+        let spectrum = SpectrumConfig {
+            phase: true,
+            unwrap_phase: true,
+            ..SpectrumConfig::default()
+        };
+        let document = svg(&two_terms(), &RenderConfig::default(), &spectrum).unwrap();
+        assert_eq!(2, document.matches("<circle").count());
+    }
+}
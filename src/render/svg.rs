@@ -0,0 +1,694 @@
+use crate::complex;
+use crate::epicycle;
+use crate::path;
+use crate::profile;
+use crate::render;
+use crate::render::renderer::Renderer;
+use crate::render::{Color, Projection, RenderConfig};
+use std::error::Error;
+use std::f64::consts::PI;
+use std::fmt::Write;
+use std::fs;
+
+/// Number of separate strokes a fading trail is split into.
+const FADE_STEPS: usize = 32;
+
+/// Arrangement of the original path and its reconstruction in a comparison.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Layout {
+    /// Both drawn on top of each other.
+    Overlay,
+    /// Original on the left half, reconstruction on the right half.
+    SideBySide,
+}
+
+/// SVG document of the full reconstruction using `precision` terms.
+pub fn trace(epicycle: &epicycle::Epicycle, precision: usize, config: &RenderConfig) -> String {
+    let _stage = profile::stage("draw");
+    let config = &config.with_caption_for(epicycle, precision);
+    let points = config.trace_points(epicycle, precision);
+    let projection = config.projection(&points);
+
+    let mut document = begin(config);
+    write_grid(&mut document, &projection, config);
+    let color = config.palette.trace;
+    write_polyline(
+        &mut document,
+        &projection,
+        &points,
+        true,
+        color,
+        1.0,
+        config,
+    );
+    finish(&mut document, config);
+    document
+}
+
+/// SVG document of the full reconstruction drawn by the same calls as raster images, with the
+/// circles and annotation they show, so that it matches them.
+pub fn matched(
+    epicycle: &epicycle::Epicycle,
+    precision: usize,
+    config: &RenderConfig,
+) -> Result<String, Box<dyn Error>> {
+    let _stage = profile::stage("draw");
+    let mut renderer = SvgRenderer::for_config(config);
+    render::renderer::draw_frame(&mut renderer, epicycle, precision, 2.0 * PI, config)?;
+    Ok(renderer.documents()[0].clone())
+}
+
+/// SVG document comparing `path` with its reconstruction using `precision` terms, annotated with
+/// the root mean square error of the reconstruction.
+pub fn comparison(
+    path: &path::Path,
+    epicycle: &epicycle::Epicycle,
+    precision: usize,
+    layout: Layout,
+    config: &RenderConfig,
+) -> String {
+    let config = &config.with_caption_for(epicycle, precision);
+    let reconstruction = config.trace_points(epicycle, precision);
+    let mut all = path.data.clone();
+    all.extend_from_slice(&reconstruction);
+    let panel = match layout {
+        Layout::Overlay => config.clone(),
+        Layout::SideBySide => RenderConfig {
+            width: config.width / 2,
+            ..config.clone()
+        },
+    };
+    let projection = panel.projection(&all);
+    let (original, estimated) = (config.palette.reference, config.palette.trace);
+
+    let mut document = begin(config);
+    match layout {
+        Layout::Overlay => {
+            write_grid(&mut document, &projection, &panel);
+            write_polyline(
+                &mut document,
+                &projection,
+                &path.data,
+                true,
+                original,
+                1.0,
+                &panel,
+            );
+            write_polyline(
+                &mut document,
+                &projection,
+                &reconstruction,
+                true,
+                estimated,
+                1.0,
+                &panel,
+            );
+        }
+        Layout::SideBySide => {
+            write_grid(&mut document, &projection, &panel);
+            write_polyline(
+                &mut document,
+                &projection,
+                &path.data,
+                true,
+                original,
+                1.0,
+                &panel,
+            );
+            writeln!(document, "<g transform=\"translate({} 0)\">", panel.width).unwrap();
+            write_grid(&mut document, &projection, &panel);
+            write_polyline(
+                &mut document,
+                &projection,
+                &reconstruction,
+                true,
+                estimated,
+                1.0,
+                &panel,
+            );
+            document.push_str("</g>\n");
+        }
+    }
+    let annotation = format!(
+        "precision {}: RMS error {:.4}",
+        precision,
+        epicycle.rms_error(path, precision)
+    );
+    let bottom = config.height - 4 * config.text_scale();
+    write_text(&mut document, &annotation, bottom, config);
+    finish(&mut document, config);
+    document
+}
+
+/// SVG snapshot of the animation at time `t`: the trace drawn so far and, when enabled, the
+/// circles leading to the pen.
+pub fn frame(
+    epicycle: &epicycle::Epicycle,
+    precision: usize,
+    t: f64,
+    config: &RenderConfig,
+) -> String {
+    let config = &config.with_caption_for(epicycle, precision);
+    let points = epicycle.sample(precision, render::TRACE_SAMPLES);
+    let projection = config.projection(&points);
+    let drawn = ((t / (2.0 * PI)) * points.len() as f64).floor() as usize;
+    let mut partial: Vec<complex::Complex> = points.into_iter().take(drawn + 1).collect();
+    partial.push(epicycle.get_coordinate_for(precision, t));
+
+    let mut document = begin(config);
+    write_grid(&mut document, &projection, config);
+    let color = config.palette.trace;
+    match config.trail {
+        render::Trail::Full => write_polyline(
+            &mut document,
+            &projection,
+            &partial,
+            false,
+            color,
+            1.0,
+            config,
+        ),
+        render::Trail::Fade(length) => {
+            let segments = partial.len() - 1;
+            let step = (segments / FADE_STEPS).max(1);
+            for start in (0..segments).step_by(step) {
+                let end = (start + step).min(segments);
+                let age = (segments - end) as f64 / render::TRACE_SAMPLES as f64;
+                let opacity = 1.0 - age / length;
+                if opacity > 0.0 {
+                    let stroke = &partial[start..=end];
+                    write_polyline(
+                        &mut document,
+                        &projection,
+                        stroke,
+                        false,
+                        color,
+                        opacity,
+                        config,
+                    );
+                }
+            }
+        }
+    }
+    if config.circles {
+        let circles = epicycle.circles_at(precision, t);
+        write_circles(&mut document, &projection, &circles, config);
+    }
+    if config.annotate {
+        let annotation = render::annotation(epicycle, precision, t);
+        let bottom = config.height - 4 * config.text_scale();
+        write_text(&mut document, &annotation, bottom, config);
+    }
+    finish(&mut document, config);
+    document
+}
+
+pub fn write_trace(
+    epicycle: &epicycle::Epicycle,
+    precision: usize,
+    config: &RenderConfig,
+    filename: &str,
+) -> Result<(), Box<dyn Error>> {
+    fs::write(filename, trace(epicycle, precision, config))?;
+    Ok(())
+}
+
+pub fn write_comparison(
+    path: &path::Path,
+    epicycle: &epicycle::Epicycle,
+    precision: usize,
+    layout: Layout,
+    config: &RenderConfig,
+    filename: &str,
+) -> Result<(), Box<dyn Error>> {
+    fs::write(
+        filename,
+        comparison(path, epicycle, precision, layout, config),
+    )?;
+    Ok(())
+}
+
+pub fn write_frame(
+    epicycle: &epicycle::Epicycle,
+    precision: usize,
+    t: f64,
+    config: &RenderConfig,
+    filename: &str,
+) -> Result<(), Box<dyn Error>> {
+    fs::write(filename, frame(epicycle, precision, t, config))?;
+    Ok(())
+}
+
+/// `Renderer` producing one SVG document per frame.
+#[derive(Debug, Default)]
+pub struct SvgRenderer {
+    document: String,
+    documents: Vec<String>,
+    transparent: bool,
+}
+
+impl SvgRenderer {
+    pub fn new() -> SvgRenderer {
+        SvgRenderer::default()
+    }
+
+    /// Renderer following the output settings of `config`, such as a transparent background.
+    pub fn for_config(config: &RenderConfig) -> SvgRenderer {
+        SvgRenderer {
+            transparent: config.transparent,
+            ..SvgRenderer::default()
+        }
+    }
+
+    /// Documents of the frames completed so far.
+    pub fn documents(&self) -> &[String] {
+        &self.documents
+    }
+}
+
+impl Renderer for SvgRenderer {
+    fn begin_frame(&mut self, width: u32, height: u32, background: Color) {
+        self.document = begin(&RenderConfig {
+            width,
+            height,
+            background,
+            transparent: self.transparent,
+            ..RenderConfig::default()
+        });
+    }
+
+    fn draw_polyline(&mut self, points: &[(f64, f64)], closed: bool, color: Color, width: f64) {
+        if points.is_empty() {
+            return;
+        }
+        self.document.push_str("<path d=\"");
+        for (index, (x, y)) in points.iter().enumerate() {
+            let command = if index == 0 { "M" } else { " L" };
+            write!(self.document, "{}{:.2} {:.2}", command, x, y).unwrap();
+        }
+        if closed {
+            self.document.push_str(" Z");
+        }
+        writeln!(
+            self.document,
+            "\" fill=\"none\" stroke=\"{}\" stroke-width=\"{}\"/>",
+            color.to_hex(),
+            width
+        )
+        .unwrap();
+    }
+
+    fn draw_circle(&mut self, center: (f64, f64), radius: f64, color: Color, filled: bool) {
+        let paint = if filled {
+            format!("fill=\"{}\"", color.to_hex())
+        } else {
+            format!("fill=\"none\" stroke=\"{}\"", color.to_hex())
+        };
+        writeln!(
+            self.document,
+            "<circle cx=\"{:.2}\" cy=\"{:.2}\" r=\"{:.2}\" {}/>",
+            center.0, center.1, radius, paint
+        )
+        .unwrap();
+    }
+
+    fn draw_text(&mut self, position: (f64, f64), text: &str, color: Color, scale: u32) {
+        writeln!(
+            self.document,
+            "<text x=\"{:.2}\" y=\"{:.2}\" font-family=\"monospace\" font-size=\"{}\" \
+             fill=\"{}\">{}</text>",
+            position.0,
+            position.1 + f64::from(7 * scale),
+            8 * scale,
+            color.to_hex(),
+            escape(text)
+        )
+        .unwrap();
+    }
+
+    fn fill_rect(&mut self, position: (f64, f64), size: (f64, f64), color: Color) {
+        writeln!(
+            self.document,
+            "<rect x=\"{:.2}\" y=\"{:.2}\" width=\"{:.2}\" height=\"{:.2}\" fill=\"{}\"/>",
+            position.0,
+            position.1,
+            size.0,
+            size.1,
+            color.to_hex()
+        )
+        .unwrap();
+    }
+
+    fn end_frame(&mut self) -> Result<(), Box<dyn Error>> {
+        self.document.push_str("</svg>\n");
+        self.documents.push(std::mem::take(&mut self.document));
+        Ok(())
+    }
+}
+
+fn begin(config: &RenderConfig) -> String {
+    let mut document = format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{w}\" height=\"{h}\" \
+         viewBox=\"0 0 {w} {h}\">\n",
+        w = config.width,
+        h = config.height
+    );
+    if !config.transparent {
+        writeln!(
+            document,
+            "<rect width=\"100%\" height=\"100%\" fill=\"{}\"/>",
+            config.background.to_hex()
+        )
+        .unwrap();
+    }
+    document
+}
+
+fn write_grid(document: &mut String, projection: &Projection, config: &RenderConfig) {
+    if !config.grid {
+        return;
+    }
+    let (vertical, horizontal) = render::grid_lines(projection, config.width, config.height);
+    let grid = config.palette.grid.to_hex();
+    for x in vertical {
+        writeln!(
+            document,
+            "<line x1=\"{x:.2}\" y1=\"0\" x2=\"{x:.2}\" y2=\"{}\" stroke=\"{}\"/>",
+            config.height,
+            grid,
+            x = x
+        )
+        .unwrap();
+    }
+    for y in horizontal {
+        writeln!(
+            document,
+            "<line x1=\"0\" y1=\"{y:.2}\" x2=\"{}\" y2=\"{y:.2}\" stroke=\"{}\"/>",
+            config.width,
+            grid,
+            y = y
+        )
+        .unwrap();
+    }
+}
+
+/// Writes `text` centered horizontally, with its baseline at `baseline`.
+fn write_text(document: &mut String, text: &str, baseline: u32, config: &RenderConfig) {
+    writeln!(
+        document,
+        "<text x=\"50%\" y=\"{}\" text-anchor=\"middle\" font-family=\"monospace\" \
+         font-size=\"{}\" fill=\"{}\">{}</text>",
+        baseline,
+        8 * config.text_scale(),
+        config.palette.text.to_hex(),
+        escape(text)
+    )
+    .unwrap();
+}
+
+fn finish(document: &mut String, config: &RenderConfig) {
+    if let Some(caption) = &config.caption {
+        write_text(document, caption, 12 * config.text_scale(), config);
+    }
+    document.push_str("</svg>\n");
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn write_polyline(
+    document: &mut String,
+    projection: &Projection,
+    points: &[complex::Complex],
+    closed: bool,
+    color: Color,
+    opacity: f64,
+    config: &RenderConfig,
+) {
+    if points.is_empty() {
+        return;
+    }
+    document.push_str("<path d=\"");
+    for (index, point) in points.iter().enumerate() {
+        let (x, y) = projection.project(point);
+        let command = if index == 0 { "M" } else { " L" };
+        write!(document, "{}{:.2} {:.2}", command, x, y).unwrap();
+    }
+    if closed {
+        document.push_str(" Z");
+    }
+    write!(
+        document,
+        "\" fill=\"none\" stroke=\"{}\" stroke-width=\"{}\"",
+        color.to_hex(),
+        config.line_width
+    )
+    .unwrap();
+    if opacity < 1.0 {
+        write!(document, " stroke-opacity=\"{:.3}\"", opacity).unwrap();
+    }
+    document.push_str("/>\n");
+}
+
+fn write_circles(
+    document: &mut String,
+    projection: &Projection,
+    circles: &[epicycle::Circle],
+    config: &RenderConfig,
+) {
+    let shown = config
+        .max_circles
+        .unwrap_or(circles.len())
+        .min(circles.len());
+    for circle in &circles[..shown] {
+        let (x, y) = projection.project(&circle.center);
+        writeln!(
+            document,
+            "<circle cx=\"{:.2}\" cy=\"{:.2}\" r=\"{:.2}\" fill=\"none\" stroke=\"{}\"/>",
+            x,
+            y,
+            projection.scale(circle.radius),
+            config.palette.circles.to_hex()
+        )
+        .unwrap();
+    }
+    for circle in &circles[..shown] {
+        let (x1, y1) = projection.project(&circle.center);
+        let (x2, y2) = projection.project(&circle.end);
+        writeln!(
+            document,
+            "<line x1=\"{:.2}\" y1=\"{:.2}\" x2=\"{:.2}\" y2=\"{:.2}\" stroke=\"{}\"/>",
+            x1,
+            y1,
+            x2,
+            y2,
+            config.palette.arms.to_hex()
+        )
+        .unwrap();
+    }
+    if config.lump_remainder && shown > 0 && shown < circles.len() {
+        let (x1, y1) = projection.project(&circles[shown - 1].end);
+        let (x2, y2) = projection.project(&circles[circles.len() - 1].end);
+        writeln!(
+            document,
+            "<line x1=\"{:.2}\" y1=\"{:.2}\" x2=\"{:.2}\" y2=\"{:.2}\" stroke=\"{}\" \
+             stroke-dasharray=\"4 3\"/>",
+            x1,
+            y1,
+            x2,
+            y2,
+            config.palette.pen.to_hex()
+        )
+        .unwrap();
+    }
+    if let Some(last) = circles.last() {
+        let (x, y) = projection.project(&last.end);
+        writeln!(
+            document,
+            "<circle cx=\"{:.2}\" cy=\"{:.2}\" r=\"2\" fill=\"{}\"/>",
+            x,
+            y,
+            config.palette.pen.to_hex()
+        )
+        .unwrap();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::path;
+    use crate::render::renderer;
+
+    #[test]
+    fn trace_test() {
+        let document = trace(&epicycle::unit_square(), 4, &RenderConfig::default());
+        assert!(document.starts_with("<svg"));
+        assert!(document.contains("width=\"640\" height=\"640\""));
+        assert!(document.contains("<path d=\"M"));
+        let points = RenderConfig::default().trace_points(&epicycle::unit_square(), 4);
+        assert_eq!(points.len() - 1, document.matches(" L").count());
+        assert!(document.contains(" Z\""));
+        assert!(!document.contains("<circle"));
+        assert!(document.ends_with("</svg>\n"));
+    }
+
+    #[test]
+    fn frame_test() {
+        let config = RenderConfig {
+            circles: true,
+            max_circles: Some(2),
+            ..RenderConfig::default()
+        };
+        let document = frame(&epicycle::unit_square(), 4, PI, &config);
+        assert_eq!(
+            render::TRACE_SAMPLES / 2 + 1,
+            document.matches(" L").count()
+        );
+        assert!(!document.contains(" Z\""));
+        assert_eq!(
+            2,
+            document.matches("fill=\"none\" stroke=\"#aaaaaa\"").count()
+        );
+        assert_eq!(2, document.matches("<line").count());
+        assert_eq!(3, document.matches("<circle").count());
+
+        let lumped = RenderConfig {
+            lump_remainder: true,
+            ..config
+        };
+        let document = frame(&epicycle::unit_square(), 4, PI, &lumped);
+        assert_eq!(3, document.matches("<line").count());
+        assert_eq!(1, document.matches("stroke-dasharray").count());
+    }
+
+    #[test]
+    fn frame_with_fading_trail_test() {
+        let config = RenderConfig {
+            trail: render::Trail::Fade(0.25),
+            ..RenderConfig::default()
+        };
+        let document = frame(&epicycle::unit_square(), 4, PI, &config);
+        let strokes = document.matches("<path").count();
+        assert!(strokes > 1 && strokes <= FADE_STEPS / 2 + 1);
+        assert!(document.contains("stroke-opacity"));
+    }
+
+    #[test]
+    fn grid_and_caption_test() {
+        let config = RenderConfig {
+            grid: true,
+            caption: Some("a < b".to_string()),
+            background: render::Color::BLACK,
+            line_width: 2.5,
+            ..RenderConfig::default()
+        };
+        let document = trace(&epicycle::unit_square(), 4, &config);
+        assert!(document.contains("<rect width=\"100%\" height=\"100%\" fill=\"#000000\"/>"));
+        assert!(document.matches("<line").count() > 4);
+        assert!(document.contains(">a &lt; b</text>"));
+        assert!(document.contains("stroke-width=\"2.5\""));
+
+        let transparent = RenderConfig {
+            transparent: true,
+            ..RenderConfig::dark()
+        };
+        let document = trace(&epicycle::unit_square(), 4, &transparent);
+        assert!(!document.contains("<rect"));
+        assert!(document.contains(&transparent.palette.trace.to_hex()));
+    }
+
+    #[test]
+    fn comparison_test() {
+        let path = path::Path {
+            data: vec![
+                complex::Complex::new(0.0, 0.0),
+                complex::Complex::new(1.0, 0.0),
+                complex::Complex::new(1.0, 1.0),
+                complex::Complex::new(0.0, 1.0),
+            ],
+        };
+        let epicycle = epicycle::Epicycle::new(&path);
+        let config = RenderConfig::default();
+        let reference = config.palette.reference.to_hex();
+
+        let overlay = comparison(&path, &epicycle, 2, Layout::Overlay, &config);
+        assert_eq!(2, overlay.matches("<path").count());
+        assert!(overlay.contains(&format!("stroke=\"{}\"", reference)));
+        assert!(!overlay.contains("<g "));
+        let annotation = format!("RMS error {:.4}", epicycle.rms_error(&path, 2));
+        assert!(overlay.contains(&annotation));
+
+        let panels = comparison(&path, &epicycle, 2, Layout::SideBySide, &config);
+        assert_eq!(2, panels.matches("<path").count());
+        assert!(panels.contains("<g transform=\"translate(320 0)\">"));
+    }
+
+    #[test]
+    fn annotated_frame_test() {
+        let config = RenderConfig {
+            annotate: true,
+            ..RenderConfig::default()
+        };
+        let document = frame(&epicycle::unit_square(), 2, PI, &config);
+        let error = epicycle::unit_square().truncation_error(2);
+        let expected = format!(">terms 2/4  t 3.142  RMS error {:.4}</text>", error);
+        assert!(document.contains(&expected));
+        assert!(
+            !frame(&epicycle::unit_square(), 2, PI, &RenderConfig::default()).contains("<text")
+        );
+    }
+
+    #[test]
+    fn frame_without_circles_test() {
+        let config = RenderConfig::default();
+        let document = frame(&epicycle::unit_square(), 4, 0.0, &config);
+        assert_eq!(1, document.matches(" L").count());
+        assert!(!document.contains("<circle"));
+    }
+
+    #[test]
+    fn svg_renderer_test() {
+        let config = RenderConfig {
+            circles: true,
+            max_circles: Some(1),
+            ..RenderConfig::default()
+        };
+        let mut renderer = SvgRenderer::new();
+        renderer::draw_frame(&mut renderer, &epicycle::unit_square(), 4, PI, &config).unwrap();
+        renderer::draw_frame(
+            &mut renderer,
+            &epicycle::unit_square(),
+            4,
+            2.0 * PI,
+            &config,
+        )
+        .unwrap();
+
+        let documents = renderer.documents();
+        assert_eq!(2, documents.len());
+        assert!(documents[0].starts_with("<svg"));
+        assert!(documents[0].ends_with("</svg>\n"));
+        assert_eq!(2, documents[0].matches("<circle").count());
+        assert!(documents[1].matches(" L").count() > documents[0].matches(" L").count());
+    }
+
+    #[test]
+    fn matched_test() {
+        let config = RenderConfig {
+            circles: true,
+            annotate: true,
+            ..RenderConfig::default()
+        };
+        let document = matched(&epicycle::unit_square(), 4, &config).unwrap();
+        assert!(document.starts_with("<svg"));
+        assert!(document.contains("<circle"));
+        assert!(document.contains("<text"));
+        assert!(!trace(&epicycle::unit_square(), 4, &config).contains("<circle"));
+    }
+}
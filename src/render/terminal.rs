@@ -0,0 +1,237 @@
+use crate::epicycle;
+use crate::render::renderer::{self, Renderer};
+use crate::render::{Color, RenderConfig};
+use std::error::Error;
+use std::f64::consts::PI;
+
+/// Bit of each dot of a braille character, indexed by row then column.
+const DOTS: [[u32; 2]; 4] = [[0x01, 0x08], [0x02, 0x10], [0x04, 0x20], [0x40, 0x80]];
+
+/// `Renderer` drawing with Unicode braille characters, each character cell holding 2 by 4 dots.
+/// Colors are ignored and frames are kept as text, one line per row of characters.
+#[derive(Debug)]
+pub struct BrailleRenderer {
+    columns: usize,
+    rows: usize,
+    scale: f64,
+    cells: Vec<u32>,
+    text: Vec<Option<char>>,
+    frames: Vec<String>,
+}
+
+impl BrailleRenderer {
+    pub fn new(columns: usize, rows: usize) -> BrailleRenderer {
+        BrailleRenderer {
+            columns,
+            rows,
+            scale: 1.0,
+            cells: vec![0; columns * rows],
+            text: vec![None; columns * rows],
+            frames: Vec::new(),
+        }
+    }
+
+    /// Frames completed so far.
+    pub fn frames(&self) -> &[String] {
+        &self.frames
+    }
+
+    fn set_dot(&mut self, x: i64, y: i64) {
+        if x < 0 || y < 0 || x >= 2 * self.columns as i64 || y >= 4 * self.rows as i64 {
+            return;
+        }
+        let cell = (y / 4) as usize * self.columns + (x / 2) as usize;
+        self.cells[cell] |= DOTS[(y % 4) as usize][(x % 2) as usize];
+    }
+
+    fn to_dots(&self, point: (f64, f64)) -> (i64, i64) {
+        (
+            (point.0 * self.scale).round() as i64,
+            (point.1 * self.scale).round() as i64,
+        )
+    }
+
+    fn draw_line(&mut self, from: (f64, f64), to: (f64, f64)) {
+        let (mut x, mut y) = self.to_dots(from);
+        let (end_x, end_y) = self.to_dots(to);
+        let dx = (end_x - x).abs();
+        let dy = -(end_y - y).abs();
+        let step_x = if x < end_x { 1 } else { -1 };
+        let step_y = if y < end_y { 1 } else { -1 };
+        let mut error = dx + dy;
+        loop {
+            self.set_dot(x, y);
+            if x == end_x && y == end_y {
+                break;
+            }
+            let doubled = 2 * error;
+            if doubled >= dy {
+                error += dy;
+                x += step_x;
+            }
+            if doubled <= dx {
+                error += dx;
+                y += step_y;
+            }
+        }
+    }
+}
+
+impl Renderer for BrailleRenderer {
+    /// Clears the frame and fits the `width` by `height` pixels drawing into the dots available,
+    /// keeping its aspect ratio.
+    fn begin_frame(&mut self, width: u32, height: u32, _: Color) {
+        let horizontal = (2 * self.columns) as f64 / f64::from(width.max(1));
+        let vertical = (4 * self.rows) as f64 / f64::from(height.max(1));
+        self.scale = horizontal.min(vertical);
+        self.cells.iter_mut().for_each(|cell| *cell = 0);
+        self.text.iter_mut().for_each(|cell| *cell = None);
+    }
+
+    fn draw_polyline(&mut self, points: &[(f64, f64)], closed: bool, _: Color, _: f64) {
+        for pair in points.windows(2) {
+            self.draw_line(pair[0], pair[1]);
+        }
+        if closed && points.len() > 2 {
+            self.draw_line(points[points.len() - 1], points[0]);
+        }
+        if points.len() == 1 {
+            let (x, y) = self.to_dots(points[0]);
+            self.set_dot(x, y);
+        }
+    }
+
+    fn draw_circle(&mut self, center: (f64, f64), radius: f64, _: Color, filled: bool) {
+        let (center_x, center_y) = self.to_dots(center);
+        let radius = radius * self.scale;
+        if filled {
+            let extent = radius.ceil() as i64;
+            for dy in -extent..=extent {
+                for dx in -extent..=extent {
+                    if ((dx * dx + dy * dy) as f64) <= radius * radius {
+                        self.set_dot(center_x + dx, center_y + dy);
+                    }
+                }
+            }
+            return;
+        }
+        let segments = ((2.0 * PI * radius).ceil() as usize).clamp(8, 256);
+        for segment in 0..segments {
+            let angle = 2.0 * PI * segment as f64 / segments as f64;
+            let x = center_x as f64 + radius * angle.cos();
+            let y = center_y as f64 + radius * angle.sin();
+            self.set_dot(x.round() as i64, y.round() as i64);
+        }
+    }
+
+    /// Clears the dots and characters of the rectangle: without colors, only backgrounds are
+    /// filled, to clear parts of the frame.
+    fn fill_rect(&mut self, position: (f64, f64), size: (f64, f64), _: Color) {
+        let (left, top) = self.to_dots(position);
+        let (right, bottom) = self.to_dots((position.0 + size.0, position.1 + size.1));
+        for y in top.max(0)..bottom.min(4 * self.rows as i64) {
+            for x in left.max(0)..right.min(2 * self.columns as i64) {
+                let cell = (y / 4) as usize * self.columns + (x / 2) as usize;
+                self.cells[cell] &= !DOTS[(y % 4) as usize][(x % 2) as usize];
+                self.text[cell] = None;
+            }
+        }
+    }
+
+    /// Writes `text` as plain characters over the dots, one character per cell.
+    fn draw_text(&mut self, position: (f64, f64), text: &str, _: Color, _: u32) {
+        let (x, y) = self.to_dots(position);
+        let (column, row) = (x / 2, y / 4);
+        if row < 0 || row >= self.rows as i64 {
+            return;
+        }
+        for (offset, character) in text.chars().enumerate() {
+            let column = column + offset as i64;
+            if column >= 0 && column < self.columns as i64 {
+                self.text[row as usize * self.columns + column as usize] = Some(character);
+            }
+        }
+    }
+
+    fn end_frame(&mut self) -> Result<(), Box<dyn Error>> {
+        let mut frame = String::with_capacity(self.rows * (self.columns * 3 + 1));
+        for row in 0..self.rows {
+            for column in 0..self.columns {
+                let index = row * self.columns + column;
+                let character = match self.text[index] {
+                    Some(character) => character,
+                    None => std::char::from_u32(0x2800 + self.cells[index]).unwrap(),
+                };
+                frame.push(character);
+            }
+            frame.push('\n');
+        }
+        self.frames.push(frame);
+        Ok(())
+    }
+}
+
+/// Full reconstruction using `precision` terms drawn with braille characters on `columns` by
+/// `rows` character cells.
+pub fn preview(
+    epicycle: &epicycle::Epicycle,
+    precision: usize,
+    columns: usize,
+    rows: usize,
+    config: &RenderConfig,
+) -> String {
+    preview_at(epicycle, precision, 2.0 * PI, columns, rows, config)
+}
+
+/// Frame at time `t` of the reconstruction being traced, drawn like `preview`.
+pub fn preview_at(
+    epicycle: &epicycle::Epicycle,
+    precision: usize,
+    t: f64,
+    columns: usize,
+    rows: usize,
+    config: &RenderConfig,
+) -> String {
+    let mut renderer = BrailleRenderer::new(columns, rows);
+    renderer::draw_frame(&mut renderer, epicycle, precision, t, config).unwrap();
+    renderer.frames.pop().unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::complex;
+    use crate::path;
+
+    #[test]
+    fn braille_renderer_test() {
+        let mut renderer = BrailleRenderer::new(2, 1);
+        renderer.begin_frame(4, 4, Color::WHITE);
+        renderer.draw_polyline(&[(0.0, 0.0), (0.0, 3.0)], false, Color::BLACK, 1.0);
+        renderer.draw_polyline(&[(3.0, 3.0)], false, Color::BLACK, 1.0);
+        renderer.end_frame().unwrap();
+        assert_eq!("\u{2847}\u{2880}\n", renderer.frames()[0]);
+
+        renderer.begin_frame(4, 4, Color::WHITE);
+        renderer.draw_text((2.0, 0.0), "ab", Color::BLACK, 1);
+        renderer.end_frame().unwrap();
+        assert_eq!("\u{2800}a\n", renderer.frames()[1]);
+    }
+
+    #[test]
+    fn preview_test() {
+        let epicycle = epicycle::Epicycle::new(&path::Path {
+            data: vec![
+                complex::Complex::new(0.0, 0.0),
+                complex::Complex::new(1.0, 0.0),
+                complex::Complex::new(1.0, 1.0),
+                complex::Complex::new(0.0, 1.0),
+            ],
+        });
+        let preview = preview(&epicycle, 4, 20, 10, &RenderConfig::default());
+        assert_eq!(10, preview.lines().count());
+        assert!(preview.lines().all(|line| line.chars().count() == 20));
+        assert!(preview.chars().any(|c| c != '\u{2800}' && c != '\n'));
+    }
+}
@@ -0,0 +1,224 @@
+use crate::complex;
+use crate::epicycle;
+use crate::render;
+use crate::render::png;
+use crate::render::renderer::Renderer;
+use crate::render::svg::SvgRenderer;
+use crate::render::{Bounds, RenderConfig};
+use std::error::Error;
+use std::f64::consts::PI;
+use std::fs;
+
+/// Point of view of the tower.
+#[derive(Debug, Clone, Copy)]
+pub struct TowerConfig {
+    /// Rotation of the drawing plane around the vertical axis, in radians.
+    pub azimuth: f64,
+    /// Angle between the line of sight and the drawing plane, in radians: 0 looks at the tower
+    /// from the side and π/2 from above.
+    pub elevation: f64,
+    /// Height of the tower as a fraction of the largest dimension of the drawing.
+    pub height: f64,
+}
+
+impl Default for TowerConfig {
+    fn default() -> TowerConfig {
+        TowerConfig {
+            azimuth: PI / 6.0,
+            elevation: PI / 6.0,
+            height: 1.0,
+        }
+    }
+}
+
+impl TowerConfig {
+    /// Position in the view plane of `point` raised to the altitude `z`, in path units.
+    fn view(&self, point: &complex::Complex, z: f64) -> complex::Complex {
+        let (sin, cos) = self.azimuth.sin_cos();
+        let x = point.re * cos - point.im * sin;
+        let y = point.re * sin + point.im * cos;
+        complex::Complex::new(x, y * self.elevation.sin() + z * self.elevation.cos())
+    }
+}
+
+/// Draws the chain of circles of the reconstruction using `precision` terms at time `t` with each
+/// circle one level above the previous one, largest at the bottom. Each arm is joined to the
+/// center of the next circle by a vertical line, and the full reconstruction is drawn on top of
+/// the tower with the pen at its current position. When only the largest circles are drawn, the
+/// last arm is joined to the pen on the top level.
+pub fn draw(
+    renderer: &mut dyn Renderer,
+    epicycle: &epicycle::Epicycle,
+    precision: usize,
+    t: f64,
+    config: &RenderConfig,
+    tower: &TowerConfig,
+) -> Result<(), Box<dyn Error>> {
+    let config = &config.with_caption_for(epicycle, precision);
+    let circles = epicycle.circles_at(precision, t);
+    let shown = config
+        .max_circles
+        .unwrap_or(circles.len())
+        .min(circles.len());
+    let trace = epicycle.sample(precision, render::TRACE_SAMPLES);
+    let bounds = Bounds::of(&trace);
+    let step = tower.height * bounds.width().max(bounds.height()) / shown.max(1) as f64;
+    let level = |index: usize| index as f64 * step;
+
+    let rings: Vec<Vec<complex::Complex>> = circles[..shown]
+        .iter()
+        .enumerate()
+        .map(|(index, circle)| {
+            (0..render::CIRCLE_SEGMENTS)
+                .map(|segment| {
+                    let angle = 2.0 * PI * segment as f64 / render::CIRCLE_SEGMENTS as f64;
+                    let point = complex::Complex::new(
+                        circle.center.re + circle.radius * angle.cos(),
+                        circle.center.im + circle.radius * angle.sin(),
+                    );
+                    tower.view(&point, level(index))
+                })
+                .collect()
+        })
+        .collect();
+    let top: Vec<_> = trace.iter().map(|p| tower.view(p, level(shown))).collect();
+    let mut all: Vec<_> = rings.iter().flatten().cloned().collect();
+    all.extend_from_slice(&top);
+    let projection = config.projection(&all);
+    let project = |point: &complex::Complex, z: f64| projection.project(&tower.view(point, z));
+
+    renderer.begin_frame(config.width, config.height, config.background);
+    for (index, circle) in circles[..shown].iter().enumerate() {
+        let riser = [
+            project(&circle.end, level(index)),
+            project(&circle.end, level(index + 1)),
+        ];
+        renderer.draw_polyline(&riser, false, config.palette.grid, 1.0);
+    }
+    for ring in &rings {
+        let projected: Vec<_> = ring.iter().map(|p| projection.project(p)).collect();
+        renderer.draw_polyline(&projected, true, config.palette.circles, 1.0);
+    }
+    for (index, circle) in circles[..shown].iter().enumerate() {
+        let arm = [
+            project(&circle.center, level(index)),
+            project(&circle.end, level(index)),
+        ];
+        renderer.draw_polyline(&arm, false, config.palette.arms, 1.0);
+    }
+    let projected: Vec<_> = top.iter().map(|p| projection.project(p)).collect();
+    renderer.draw_polyline(&projected, true, config.palette.trace, config.line_width);
+    if let Some(last) = circles.last() {
+        let pen = project(&last.end, level(shown));
+        if shown > 0 && shown < circles.len() {
+            let remainder = [project(&circles[shown - 1].end, level(shown)), pen];
+            renderer.draw_polyline(&remainder, false, config.palette.pen, 1.0);
+        }
+        renderer.draw_circle(pen, 2.0, config.palette.pen, true);
+    }
+    render::draw_caption(renderer, config);
+    renderer.end_frame()
+}
+
+pub fn svg(
+    epicycle: &epicycle::Epicycle,
+    precision: usize,
+    t: f64,
+    config: &RenderConfig,
+    tower: &TowerConfig,
+) -> Result<String, Box<dyn Error>> {
+    let mut renderer = SvgRenderer::for_config(config);
+    draw(&mut renderer, epicycle, precision, t, config, tower)?;
+    Ok(renderer.documents()[0].clone())
+}
+
+pub fn write_svg(
+    epicycle: &epicycle::Epicycle,
+    precision: usize,
+    t: f64,
+    config: &RenderConfig,
+    tower: &TowerConfig,
+    filename: &str,
+) -> Result<(), Box<dyn Error>> {
+    fs::write(filename, svg(epicycle, precision, t, config, tower)?)?;
+    Ok(())
+}
+
+pub fn write_png(
+    epicycle: &epicycle::Epicycle,
+    precision: usize,
+    t: f64,
+    config: &RenderConfig,
+    tower: &TowerConfig,
+    filename: &str,
+) -> Result<(), Box<dyn Error>> {
+    let canvas = render::rasterize(config, |canvas, config| {
+        draw(canvas, epicycle, precision, t, config, tower)
+    })?;
+    png::write_with(&canvas, config, filename)
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::render::canvas::Canvas;
+    use crate::render::Color;
+
+    #[test]
+    fn view_test() {
+        let side = TowerConfig {
+            azimuth: 0.0,
+            elevation: 0.0,
+            height: 1.0,
+        };
+        let point = complex::Complex::new(2.0, 3.0);
+        assert_eq!(complex::Complex::new(2.0, 5.0), side.view(&point, 5.0));
+        let above = TowerConfig {
+            elevation: PI / 2.0,
+            ..side
+        };
+        let viewed = above.view(&point, 5.0);
+        assert!((viewed.re - 2.0).abs() < 1E-9 && (viewed.im - 3.0).abs() < 1E-9);
+    }
+
+    #[test]
+    fn svg_test() {
+        let config = RenderConfig {
+            max_circles: Some(2),
+            ..RenderConfig::default()
+        };
+        let document = svg(
+            &epicycle::unit_square(),
+            4,
+            1.0,
+            &config,
+            &TowerConfig::default(),
+        )
+        .unwrap();
+        // Two risers, two rings, two arms, the trace and the remainder.
+        assert_eq!(8, document.matches("<path").count());
+        assert_eq!(1, document.matches("<circle").count());
+    }
+
+    #[test]
+    fn draw_test() {
+        let config = RenderConfig {
+            width: 64,
+            height: 64,
+            ..RenderConfig::default()
+        };
+        let mut canvas = Canvas::new(1, 1, Color::WHITE);
+        draw(
+            &mut canvas,
+            &epicycle::unit_square(),
+            4,
+            0.0,
+            &config,
+            &TowerConfig::default(),
+        )
+        .unwrap();
+        assert!(canvas.pixels().contains(&config.palette.trace));
+        assert!(canvas.pixels().contains(&config.palette.pen));
+    }
+}
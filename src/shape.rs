@@ -0,0 +1,139 @@
+//! Procedural closed paths, to try the drawing without a path file at hand.
+
+use crate::complex;
+use crate::path;
+use std::error::Error;
+use std::f64::consts::PI;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Shape {
+    Circle,
+    Square,
+    Star,
+    Heart,
+    Flower,
+}
+
+impl Shape {
+    pub const ALL: [Shape; 5] = [
+        Shape::Circle,
+        Shape::Square,
+        Shape::Star,
+        Shape::Heart,
+        Shape::Flower,
+    ];
+
+    pub fn parse(name: &str) -> Result<Shape, Box<dyn Error>> {
+        Shape::ALL
+            .iter()
+            .copied()
+            .find(|shape| shape.name().eq_ignore_ascii_case(name))
+            .ok_or_else(|| {
+                let names: Vec<&str> = Shape::ALL.iter().map(|shape| shape.name()).collect();
+                format!("Unknown shape {}, expected {}", name, names.join(", ")).into()
+            })
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Shape::Circle => "circle",
+            Shape::Square => "square",
+            Shape::Star => "star",
+            Shape::Heart => "heart",
+            Shape::Flower => "flower",
+        }
+    }
+
+    /// Outline of the shape as `n_points` points evenly spaced along it, counterclockwise and
+    /// within the square from -1 to 1.
+    pub fn path(self, n_points: usize) -> path::Path {
+        match self {
+            Shape::Circle => curve(n_points, |t| complex::Complex::new(t.cos(), t.sin())),
+            Shape::Square => {
+                polygon(&[(-1.0, -1.0), (1.0, -1.0), (1.0, 1.0), (-1.0, 1.0)]).resample(n_points)
+            }
+            Shape::Star => {
+                // Five outer tips alternating with inner corners, the first tip pointing up.
+                let corners: Vec<(f64, f64)> = (0..10)
+                    .map(|k| {
+                        let radius = if k % 2 == 0 { 1.0 } else { 0.4 };
+                        let angle = PI / 2.0 + PI * f64::from(k) / 5.0;
+                        (radius * angle.cos(), radius * angle.sin())
+                    })
+                    .collect();
+                polygon(&corners).resample(n_points)
+            }
+            Shape::Heart => {
+                // The classic heart curve, 32 units wide and reaching 17 units down.
+                let outline = curve(4 * n_points.max(64), |t| {
+                    complex::Complex::new(
+                        16.0 * t.sin().powi(3) / 17.0,
+                        (13.0 * t.cos()
+                            - 5.0 * (2.0 * t).cos()
+                            - 2.0 * (3.0 * t).cos()
+                            - (4.0 * t).cos())
+                            / 17.0,
+                    )
+                });
+                outline.resample(n_points)
+            }
+            Shape::Flower => {
+                let outline = curve(4 * n_points.max(64), |t| {
+                    let radius = 0.7 + 0.3 * (6.0 * t).cos();
+                    complex::Complex::new(radius * t.cos(), radius * t.sin())
+                });
+                outline.resample(n_points)
+            }
+        }
+    }
+}
+
+/// `n_points` points of the closed curve `point(t)` for `t` evenly spaced over a turn.
+fn curve<F: Fn(f64) -> complex::Complex>(n_points: usize, point: F) -> path::Path {
+    path::Path {
+        data: (0..n_points)
+            .map(|k| point(2.0 * PI * k as f64 / n_points as f64))
+            .collect(),
+    }
+}
+
+fn polygon(corners: &[(f64, f64)]) -> path::Path {
+    path::Path {
+        data: corners
+            .iter()
+            .map(|&(x, y)| complex::Complex::new(x, y))
+            .collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn parse_test() {
+        assert_eq!(Shape::Star, Shape::parse("Star").unwrap());
+        for shape in &Shape::ALL {
+            assert_eq!(*shape, Shape::parse(shape.name()).unwrap());
+        }
+        assert!(Shape::parse("blob").is_err());
+    }
+
+    #[test]
+    fn path_test() {
+        for shape in &Shape::ALL {
+            let path = shape.path(100);
+            assert_eq!(100, path.data.len());
+            assert!(path
+                .data
+                .iter()
+                .all(|point| point.re.abs() <= 1.0 + 1e-9 && point.im.abs() <= 1.0 + 1e-9));
+        }
+        let star = Shape::Star.path(10);
+        assert!((star.data[0].im - 1.0).abs() < 1e-12);
+        let square = Shape::Square.path(8);
+        assert!((square.data[1].re - 0.0).abs() < 1e-12);
+        assert!((square.data[1].im + 1.0).abs() < 1e-12);
+    }
+}
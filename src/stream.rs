@@ -0,0 +1,284 @@
+//! Decompositions streamed to audiovisual software, such as sound synthesis or lighting in
+//! installations, as OSC messages over UDP or as MIDI control changes.
+//!
+//! OSC receivers get one `/fft/term` message per term, holding its index, frequency, amplitude
+//! and phase, and `/fft/pen` messages holding the position of the pen and its time. MIDI
+//! receivers get the amplitudes of the largest terms on controllers 20 to 31 and the position of
+//! the pen on controllers 102 and 103, all of which are left undefined by the MIDI standard.
+
+use crate::complex;
+use crate::epicycle;
+use crate::path::Bounds;
+use crate::render::{self, Timing};
+use std::f64::consts::PI;
+use std::io::{self, Write};
+use std::net::{ToSocketAddrs, UdpSocket};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Controller of the amplitude of the largest term, the next ones following.
+pub const TERM_CONTROLLER: u8 = 20;
+
+/// Number of terms sent as MIDI control changes.
+pub const TERM_CONTROLLERS: usize = 12;
+
+/// Controllers of the horizontal and vertical positions of the pen.
+pub const PEN_CONTROLLERS: (u8, u8) = (102, 103);
+
+/// Argument of an OSC message.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Argument {
+    Int(i32),
+    Float(f32),
+}
+
+/// OSC message sent to `address` with `arguments`, as carried by one UDP packet.
+pub fn osc_message(address: &str, arguments: &[Argument]) -> Vec<u8> {
+    let mut tags = String::from(",");
+    for argument in arguments {
+        tags.push(match argument {
+            Argument::Int(_) => 'i',
+            Argument::Float(_) => 'f',
+        });
+    }
+    let mut message = Vec::with_capacity(address.len() + tags.len() + 4 * arguments.len() + 8);
+    for text in &[address, &tags] {
+        message.extend_from_slice(text.as_bytes());
+        // Strings end with at least one null byte, padded to a multiple of 4 bytes.
+        message.resize((message.len() / 4 + 1) * 4, 0);
+    }
+    for argument in arguments {
+        match argument {
+            Argument::Int(value) => message.extend_from_slice(&value.to_be_bytes()),
+            Argument::Float(value) => message.extend_from_slice(&value.to_be_bytes()),
+        }
+    }
+    message
+}
+
+/// MIDI control change setting `controller` to `value` on `channel`, between 0 and 15. Larger
+/// controllers and values are clamped to 127.
+pub fn midi_control(channel: u8, controller: u8, value: u8) -> [u8; 3] {
+    [0xB0 | channel.min(15), controller.min(127), value.min(127)]
+}
+
+/// Receiver of the terms of a decomposition and of the moves of its pen.
+pub trait Sink {
+    /// Sends the `precision` largest terms of `epicycle`.
+    fn send_terms(&mut self, epicycle: &epicycle::Epicycle, precision: usize) -> io::Result<()>;
+
+    /// Sends the position of the pen at time `t`, between 0 and 2π.
+    fn send_pen(&mut self, position: complex::Complex, t: f64) -> io::Result<()>;
+}
+
+/// Sink sending OSC messages to one receiver.
+#[derive(Debug)]
+pub struct Osc {
+    socket: UdpSocket,
+}
+
+impl Osc {
+    /// Sink sending to `receiver`, such as `"127.0.0.1:57120"`, from any local port.
+    pub fn connect<A: ToSocketAddrs>(receiver: A) -> io::Result<Osc> {
+        let socket = UdpSocket::bind(("0.0.0.0", 0))?;
+        socket.connect(receiver)?;
+        Ok(Osc { socket })
+    }
+}
+
+impl Sink for Osc {
+    fn send_terms(&mut self, epicycle: &epicycle::Epicycle, precision: usize) -> io::Result<()> {
+        for (index, (coefficient, frequency)) in epicycle.terms().iter().take(precision).enumerate()
+        {
+            let message = osc_message(
+                "/fft/term",
+                &[
+                    Argument::Int(index as i32),
+                    Argument::Int(*frequency as i32),
+                    Argument::Float(coefficient.amplitude() as f32),
+                    Argument::Float(coefficient.phase().in_radians() as f32),
+                ],
+            );
+            self.socket.send(&message)?;
+        }
+        Ok(())
+    }
+
+    fn send_pen(&mut self, position: complex::Complex, t: f64) -> io::Result<()> {
+        let message = osc_message(
+            "/fft/pen",
+            &[
+                Argument::Float(position.re as f32),
+                Argument::Float(position.im as f32),
+                Argument::Float(t as f32),
+            ],
+        );
+        self.socket.send(&message).map(|_| ())
+    }
+}
+
+/// Sink writing MIDI control changes to `output`, such as a raw MIDI device like
+/// `/dev/snd/midiC1D0`. Amplitudes are scaled to the largest one and positions to `bounds`.
+#[derive(Debug)]
+pub struct Midi<W: Write> {
+    output: W,
+    channel: u8,
+    bounds: Bounds,
+}
+
+impl<W: Write> Midi<W> {
+    pub fn new(output: W, channel: u8, bounds: Bounds) -> Midi<W> {
+        Midi {
+            output,
+            channel,
+            bounds,
+        }
+    }
+
+    /// Sink whose pen positions are scaled to the bounds of the reconstruction of `epicycle`
+    /// using `precision` terms.
+    pub fn for_epicycle(
+        output: W,
+        channel: u8,
+        epicycle: &epicycle::Epicycle,
+        precision: usize,
+    ) -> Midi<W> {
+        let bounds = Bounds::of(&epicycle.sample(precision, render::TRACE_SAMPLES));
+        Midi::new(output, channel, bounds)
+    }
+
+    pub fn into_inner(self) -> W {
+        self.output
+    }
+}
+
+/// `value` between `low` and `high` as a controller value, the middle one when they are equal.
+fn control_value(value: f64, low: f64, high: f64) -> u8 {
+    if high <= low {
+        return 64;
+    }
+    ((value - low) / (high - low) * 127.0)
+        .round()
+        .clamp(0.0, 127.0) as u8
+}
+
+impl<W: Write> Sink for Midi<W> {
+    fn send_terms(&mut self, epicycle: &epicycle::Epicycle, precision: usize) -> io::Result<()> {
+        let terms = &epicycle.terms()[..precision.min(epicycle.len())];
+        let largest = terms.first().map_or(0.0, |term| term.0.amplitude());
+        for (index, term) in terms.iter().take(TERM_CONTROLLERS).enumerate() {
+            let value = control_value(term.0.amplitude(), 0.0, largest);
+            let controller = TERM_CONTROLLER + index as u8;
+            self.output
+                .write_all(&midi_control(self.channel, controller, value))?;
+        }
+        self.output.flush()
+    }
+
+    fn send_pen(&mut self, position: complex::Complex, _: f64) -> io::Result<()> {
+        let bounds = &self.bounds;
+        let x = control_value(position.re, bounds.min_x, bounds.max_x);
+        let y = control_value(position.im, bounds.min_y, bounds.max_y);
+        self.output
+            .write_all(&midi_control(self.channel, PEN_CONTROLLERS.0, x))?;
+        self.output
+            .write_all(&midi_control(self.channel, PEN_CONTROLLERS.1, y))?;
+        self.output.flush()
+    }
+}
+
+/// Sends the terms of the reconstruction using `precision` terms, then the position of its pen
+/// `fps` times per second while it traces the path in `duration` seconds, paced as animations
+/// are by `timing` apart from slowing down at corners, which depends on the drawing.
+pub fn play(
+    epicycle: &epicycle::Epicycle,
+    precision: usize,
+    fps: u32,
+    duration: f64,
+    timing: &Timing,
+    sink: &mut dyn Sink,
+) -> io::Result<()> {
+    sink.send_terms(epicycle, precision)?;
+    let frame_count = ((duration * f64::from(fps)).round() as usize).max(1);
+    let start = Instant::now();
+    for frame in 0..frame_count {
+        let due = Duration::from_secs_f64(duration * frame as f64 / frame_count as f64);
+        if let Some(wait) = due.checked_sub(start.elapsed()) {
+            thread::sleep(wait);
+        }
+        let t = 2.0 * PI * timing.progress(frame, frame_count);
+        sink.send_pen(epicycle.get_coordinate_for(precision, t), t)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn osc_message_test() {
+        assert_eq!(
+            b"/pen\0\0\0\0,if\0\0\0\0\x07\x3f\x80\0\0".to_vec(),
+            osc_message("/pen", &[Argument::Int(7), Argument::Float(1.0)])
+        );
+        assert_eq!(b"/fft\0\0\0\0,\0\0\0".to_vec(), osc_message("/fft", &[]));
+    }
+
+    #[test]
+    fn osc_test() {
+        let receiver = UdpSocket::bind("127.0.0.1:0").unwrap();
+        receiver
+            .set_read_timeout(Some(Duration::from_secs(5)))
+            .unwrap();
+        let mut osc = Osc::connect(receiver.local_addr().unwrap()).unwrap();
+        let epicycle = epicycle::unit_square();
+        play(&epicycle, 2, 100, 0.02, &Timing::default(), &mut osc).unwrap();
+
+        let mut packet = [0; 64];
+        let mut received = Vec::new();
+        for _ in 0..4 {
+            let size = receiver.recv(&mut packet).unwrap();
+            received.push(packet[..size].to_vec());
+        }
+        let (coefficient, frequency) = epicycle.terms()[1];
+        let expected = osc_message(
+            "/fft/term",
+            &[
+                Argument::Int(1),
+                Argument::Int(frequency as i32),
+                Argument::Float(coefficient.amplitude() as f32),
+                Argument::Float(coefficient.phase().in_radians() as f32),
+            ],
+        );
+        assert_eq!(expected, received[1]);
+        assert!(received[2].starts_with(b"/fft/pen\0\0\0\0,fff\0\0\0\0"));
+        assert!(received[3].starts_with(b"/fft/pen\0"));
+    }
+
+    #[test]
+    fn midi_test() {
+        let epicycle = epicycle::unit_square();
+        let bounds = Bounds {
+            min_x: 0.0,
+            min_y: 0.0,
+            max_x: 1.0,
+            max_y: 1.0,
+        };
+        let mut midi = Midi::new(Vec::new(), 3, bounds);
+        midi.send_terms(&epicycle, 2).unwrap();
+        midi.send_pen(epicycle.get_coordinate_for(4, 2.0 * PI), 2.0 * PI)
+            .unwrap();
+        let second = control_value(
+            epicycle.terms()[1].0.amplitude(),
+            0.0,
+            epicycle.terms()[0].0.amplitude(),
+        );
+        assert_eq!(
+            vec![0xB3, 20, 127, 0xB3, 21, second, 0xB3, 102, 0, 0xB3, 103, 0],
+            midi.into_inner()
+        );
+        assert_eq!([0xBF, 0x7F, 0x7F], midi_control(16, 255, 200));
+    }
+}
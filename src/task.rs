@@ -0,0 +1,252 @@
+//! Decompositions and renders run on a thread of their own, to be awaited from async code or
+//! waited for from any other thread.
+//!
+//! Tasks implement `Future` without depending on a runtime: the work never blocks the executor
+//! polling them, which is woken once it is done, the way `spawn_blocking` behaves in the usual
+//! runtimes.
+
+use crate::epicycle;
+use crate::path;
+#[cfg(feature = "render")]
+use crate::render::{self, canvas::Canvas, gif, renderer};
+#[cfg(feature = "render")]
+use std::error::Error;
+#[cfg(feature = "render")]
+use std::f64::consts::PI;
+use std::future::Future;
+#[cfg(feature = "render")]
+use std::io;
+use std::panic::{self, AssertUnwindSafe};
+use std::pin::Pin;
+use std::sync::{Arc, Condvar, Mutex};
+use std::task::{Context, Poll, Waker};
+use std::thread;
+
+/// Work running on its own thread, giving its result once done. Panics of the work are resumed
+/// in the thread taking the result.
+#[derive(Debug)]
+pub struct Task<T> {
+    shared: Arc<Shared<T>>,
+}
+
+#[derive(Debug)]
+struct Shared<T> {
+    state: Mutex<State<T>>,
+    done: Condvar,
+}
+
+#[derive(Debug)]
+struct State<T> {
+    result: Option<thread::Result<T>>,
+    waker: Option<Waker>,
+}
+
+/// Runs `work` on a new thread.
+pub fn spawn<T, F>(work: F) -> Task<T>
+where
+    T: Send + 'static,
+    F: FnOnce() -> T + Send + 'static,
+{
+    let shared = Arc::new(Shared {
+        state: Mutex::new(State {
+            result: None,
+            waker: None,
+        }),
+        done: Condvar::new(),
+    });
+    let theirs = Arc::clone(&shared);
+    thread::spawn(move || {
+        let result = panic::catch_unwind(AssertUnwindSafe(work));
+        let waker = {
+            let mut state = theirs.state.lock().unwrap();
+            state.result = Some(result);
+            state.waker.take()
+        };
+        theirs.done.notify_all();
+        if let Some(waker) = waker {
+            waker.wake();
+        }
+    });
+    Task { shared }
+}
+
+impl<T> Task<T> {
+    /// Whether the work is done, so that taking its result does not wait.
+    pub fn is_finished(&self) -> bool {
+        self.shared.state.lock().unwrap().result.is_some()
+    }
+
+    /// Result of the work, blocking the calling thread until it is done.
+    pub fn wait(self) -> T {
+        let mut state = self.shared.state.lock().unwrap();
+        loop {
+            match state.result.take() {
+                Some(result) => return unwind(result),
+                None => state = self.shared.done.wait(state).unwrap(),
+            }
+        }
+    }
+}
+
+impl<T> Future for Task<T> {
+    type Output = T;
+
+    /// Ready once the work is done. Polling again after that never completes.
+    fn poll(self: Pin<&mut Self>, context: &mut Context<'_>) -> Poll<T> {
+        let mut state = self.shared.state.lock().unwrap();
+        match state.result.take() {
+            Some(result) => Poll::Ready(unwind(result)),
+            None => {
+                state.waker = Some(context.waker().clone());
+                Poll::Pending
+            }
+        }
+    }
+}
+
+fn unwind<T>(result: thread::Result<T>) -> T {
+    match result {
+        Ok(value) => value,
+        Err(payload) => panic::resume_unwind(payload),
+    }
+}
+
+/// Decomposition of `path`.
+pub fn decompose(path: path::Path) -> Task<epicycle::Epicycle> {
+    spawn(move || epicycle::Epicycle::new(&path))
+}
+
+/// Image of the reconstruction using `precision` terms, as drawn by the render command. Errors
+/// are turned into messages to be sent between threads.
+#[cfg(feature = "render")]
+pub fn image(
+    epicycle: epicycle::Epicycle,
+    precision: usize,
+    config: render::RenderConfig,
+) -> Task<Result<Canvas, Box<dyn Error + Send + Sync>>> {
+    spawn(move || {
+        render::rasterize(&config, |canvas, config| {
+            renderer::draw_frame(canvas, &epicycle, precision, 2.0 * PI, config)
+        })
+        .map_err(|error| error.to_string().into())
+    })
+}
+
+/// Animated GIF of the reconstruction using `precision` terms, as encoded by `gif::encode`.
+#[cfg(feature = "render")]
+pub fn animation(
+    epicycle: epicycle::Epicycle,
+    precision: usize,
+    config: render::RenderConfig,
+    animation: gif::GifConfig,
+) -> Task<io::Result<Vec<u8>>> {
+    spawn(move || gif::encode(&epicycle, precision, &config, &animation))
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::task::Wake;
+
+    /// Waker unparking the thread blocked on a future, counting its wakes.
+    struct Unpark {
+        thread: thread::Thread,
+        wakes: AtomicUsize,
+    }
+
+    impl Wake for Unpark {
+        fn wake(self: Arc<Self>) {
+            self.wakes.fetch_add(1, Ordering::SeqCst);
+            self.thread.unpark();
+        }
+    }
+
+    fn unpark() -> Arc<Unpark> {
+        Arc::new(Unpark {
+            thread: thread::current(),
+            wakes: AtomicUsize::new(0),
+        })
+    }
+
+    /// Output of `future`, parking the current thread while it is pending.
+    fn block_on<F: Future>(future: F) -> F::Output {
+        let waker = Waker::from(unpark());
+        let mut context = Context::from_waker(&waker);
+        let mut future = Box::pin(future);
+        loop {
+            if let Poll::Ready(output) = future.as_mut().poll(&mut context) {
+                return output;
+            }
+            thread::park();
+        }
+    }
+
+    #[test]
+    fn spawn_test() {
+        let (release, released) = std::sync::mpsc::channel::<()>();
+        let mut task = spawn(move || {
+            released.recv().unwrap();
+            42
+        });
+        let unpark = unpark();
+        let waker = Waker::from(Arc::clone(&unpark));
+        let mut context = Context::from_waker(&waker);
+        assert!(!task.is_finished());
+        assert_eq!(Poll::Pending, Pin::new(&mut task).poll(&mut context));
+
+        release.send(()).unwrap();
+        let value = loop {
+            if let Poll::Ready(value) = Pin::new(&mut task).poll(&mut context) {
+                break value;
+            }
+            thread::park();
+        };
+        assert_eq!(42, value);
+        assert_eq!(1, unpark.wakes.load(Ordering::SeqCst));
+
+        assert_eq!("done", spawn(|| "done").wait());
+    }
+
+    #[test]
+    #[should_panic(expected = "broken")]
+    fn spawn_panic_test() {
+        spawn(|| panic!("broken")).wait();
+    }
+
+    #[test]
+    fn decompose_test() {
+        let expected = epicycle::Epicycle::new(&path::unit_square());
+        let decomposed = block_on(decompose(path::unit_square()));
+        assert_eq!(
+            expected.get_coordinate_for(4, 1.0),
+            decomposed.get_coordinate_for(4, 1.0)
+        );
+    }
+
+    #[cfg(feature = "render")]
+    #[test]
+    fn render_test() {
+        let epicycle = epicycle::Epicycle::new(&path::unit_square());
+        let config = render::RenderConfig {
+            width: 16,
+            height: 16,
+            ..render::RenderConfig::default()
+        };
+        let settings = gif::GifConfig::default();
+
+        let expected = render::rasterize(&config, |canvas, config| {
+            renderer::draw_frame(canvas, &epicycle, 4, 2.0 * PI, config)
+        })
+        .unwrap();
+        let canvas = image(epicycle.clone(), 4, config.clone()).wait().unwrap();
+        assert_eq!(expected.to_rgba(), canvas.to_rgba());
+
+        let expected = gif::encode(&epicycle, 4, &config, &settings).unwrap();
+        assert_eq!(
+            expected,
+            block_on(animation(epicycle, 4, config, settings)).unwrap()
+        );
+    }
+}
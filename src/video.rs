@@ -0,0 +1,289 @@
+//! Paths traced from the frames of a video, for rotoscoped animations: each frame becomes the
+//! outline of its dominant shape, ready to be decomposed like any other path.
+//!
+//! The standard library cannot decode videos, so frames are decoded by ffmpeg, which must be
+//! installed, and streamed from it as PPM images.
+
+use crate::complex;
+use crate::path;
+use crate::render::canvas::Canvas;
+use crate::render::ppm;
+use std::error::Error;
+use std::io::{self, BufReader};
+use std::process::{Child, ChildStdout, Command, Stdio};
+
+/// Neighbors of a pixel clockwise from the left one, the y axis pointing down.
+const NEIGHBORS: [(i64, i64); 8] = [
+    (-1, 0),
+    (-1, -1),
+    (0, -1),
+    (1, -1),
+    (1, 0),
+    (1, 1),
+    (0, 1),
+    (-1, 1),
+];
+
+/// Frames of a video being decoded by ffmpeg.
+#[derive(Debug)]
+pub struct Frames {
+    decoder: Child,
+    images: BufReader<ChildStdout>,
+}
+
+/// Frames of the video file `video`, all of them, or `fps` per second when given.
+pub fn frames(video: &str, fps: Option<f64>) -> io::Result<Frames> {
+    let mut command = Command::new("ffmpeg");
+    command.args(["-v", "error", "-nostdin", "-i", video]);
+    if let Some(fps) = fps {
+        command.arg("-vf").arg(format!("fps={}", fps));
+    }
+    command.args(["-f", "image2pipe", "-vcodec", "ppm", "-"]);
+    let mut decoder = command
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .spawn()
+        .map_err(|error| match error.kind() {
+            io::ErrorKind::NotFound => {
+                io::Error::new(error.kind(), "ffmpeg is needed to decode videos")
+            }
+            _ => error,
+        })?;
+    let images = BufReader::new(decoder.stdout.take().unwrap());
+    Ok(Frames { decoder, images })
+}
+
+impl Iterator for Frames {
+    type Item = Result<Canvas, Box<dyn Error>>;
+
+    /// Next frame, or the failure of ffmpeg once it has written every frame it could.
+    fn next(&mut self) -> Option<Self::Item> {
+        match ppm::read_image(&mut self.images) {
+            Ok(Some(canvas)) => Some(Ok(canvas)),
+            Ok(None) => match self.decoder.wait() {
+                Ok(status) if status.success() => None,
+                Ok(status) => Some(Err(format!("ffmpeg failed with {}", status).into())),
+                Err(error) => Some(Err(error.into())),
+            },
+            Err(error) => Some(Err(error)),
+        }
+    }
+}
+
+impl Drop for Frames {
+    fn drop(&mut self) {
+        let _ = self.decoder.kill();
+        let _ = self.decoder.wait();
+    }
+}
+
+/// Outline of the largest shape of `canvas`, from its top left pixel and clockwise on screen, in
+/// pixels with the y axis pointing up as in path files. Shapes are the connected regions on the
+/// other side of the brightness threshold from most of the border, which is taken to be the
+/// background. `None` when there is no shape.
+pub fn dominant_contour(canvas: &Canvas) -> Option<path::Path> {
+    let (width, height) = (canvas.width() as i64, canvas.height() as i64);
+    let luminance: Vec<u8> = canvas
+        .pixels()
+        .iter()
+        .map(|pixel| {
+            ((299 * u32::from(pixel.r) + 587 * u32::from(pixel.g) + 114 * u32::from(pixel.b))
+                / 1000) as u8
+        })
+        .collect();
+    let threshold = otsu_threshold(&luminance);
+    let dark: Vec<bool> = luminance.iter().map(|value| *value <= threshold).collect();
+    let border: Vec<usize> = (0..width as usize)
+        .flat_map(|x| vec![x, (height as usize - 1) * width as usize + x])
+        .chain(
+            (0..height as usize)
+                .flat_map(|y| vec![y * width as usize, y * width as usize + width as usize - 1]),
+        )
+        .collect();
+    let dark_border = border.iter().filter(|index| dark[**index]).count();
+    let foreground = dark_border * 2 <= border.len();
+    let inside: Vec<bool> = dark.iter().map(|dark| *dark == foreground).collect();
+
+    let (labels, largest) = largest_region(&inside, width, height)?;
+    let start = labels.iter().position(|label| *label == largest)? as i64;
+    let member = |x: i64, y: i64| {
+        x >= 0 && y >= 0 && x < width && y < height && labels[(y * width + x) as usize] == largest
+    };
+    let outline = trace(member, (start % width, start / width));
+    Some(path::Path {
+        data: outline
+            .into_iter()
+            .map(|(x, y)| complex::Complex::new(x as f64, (height - 1 - y) as f64))
+            .collect(),
+    })
+}
+
+/// Threshold separating `values` into two classes with the largest variance between them.
+fn otsu_threshold(values: &[u8]) -> u8 {
+    let mut histogram = [0usize; 256];
+    for value in values {
+        histogram[*value as usize] += 1;
+    }
+    let total = values.len() as f64;
+    let sum: f64 = (0..256)
+        .map(|value| value as f64 * histogram[value] as f64)
+        .sum();
+    let (mut below, mut below_sum) = (0.0, 0.0);
+    let (mut best, mut best_variance) = (0, -1.0);
+    for (value, count) in histogram.iter().enumerate() {
+        below += *count as f64;
+        below_sum += value as f64 * *count as f64;
+        let above = total - below;
+        if below == 0.0 || above == 0.0 {
+            continue;
+        }
+        let difference = below_sum / below - (sum - below_sum) / above;
+        let variance = below * above * difference * difference;
+        if variance > best_variance {
+            best = value;
+            best_variance = variance;
+        }
+    }
+    best as u8
+}
+
+/// Labels of the 8-connected regions of the pixels `inside`, 0 for the others, with the label of
+/// the largest region.
+fn largest_region(inside: &[bool], width: i64, height: i64) -> Option<(Vec<usize>, usize)> {
+    let mut labels = vec![0; inside.len()];
+    let (mut largest, mut largest_size) = (0, 0);
+    let mut pending = Vec::new();
+    let mut label = 0;
+    for seed in 0..inside.len() {
+        if !inside[seed] || labels[seed] != 0 {
+            continue;
+        }
+        label += 1;
+        labels[seed] = label;
+        pending.push(seed);
+        let mut size = 0;
+        while let Some(index) = pending.pop() {
+            size += 1;
+            let (x, y) = (index as i64 % width, index as i64 / width);
+            for (dx, dy) in &NEIGHBORS {
+                let (x, y) = (x + dx, y + dy);
+                if x < 0 || y < 0 || x >= width || y >= height {
+                    continue;
+                }
+                let neighbor = (y * width + x) as usize;
+                if inside[neighbor] && labels[neighbor] == 0 {
+                    labels[neighbor] = label;
+                    pending.push(neighbor);
+                }
+            }
+        }
+        if size > largest_size {
+            largest = label;
+            largest_size = size;
+        }
+    }
+    if largest == 0 {
+        return None;
+    }
+    Some((labels, largest))
+}
+
+/// Boundary pixels of the region of the pixels `inside`, by Moore neighbor tracing from `start`,
+/// its first pixel in raster order.
+fn trace<F: Fn(i64, i64) -> bool>(inside: F, start: (i64, i64)) -> Vec<(i64, i64)> {
+    let mut outline = vec![start];
+    let mut current = start;
+    // Neighbor of `current` last seen outside, where the search for the next pixel starts.
+    let mut backtrack = 0;
+    loop {
+        let found = (1..=8)
+            .map(|turn| (backtrack + turn) % 8)
+            .find(|direction| {
+                let (dx, dy) = NEIGHBORS[*direction];
+                inside(current.0 + dx, current.1 + dy)
+            });
+        let direction = match found {
+            Some(direction) => direction,
+            None => return outline,
+        };
+        let (dx, dy) = NEIGHBORS[direction];
+        let next = (current.0 + dx, current.1 + dy);
+        if current == start && outline.len() > 1 && next == outline[1] {
+            outline.pop();
+            return outline;
+        }
+        let (bx, by) = NEIGHBORS[(direction + 7) % 8];
+        let seen = (current.0 + bx - next.0, current.1 + by - next.1);
+        backtrack = NEIGHBORS.iter().position(|offset| *offset == seen).unwrap();
+        outline.push(next);
+        current = next;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::render::Color;
+
+    #[test]
+    fn dominant_contour_test() {
+        let mut canvas = Canvas::new(8, 6, Color::WHITE);
+        // A 3x2 rectangle and a smaller square elsewhere.
+        for (x, y) in &[(1, 1), (2, 1), (3, 1), (1, 2), (2, 2), (3, 2), (6, 4)] {
+            canvas.set_pixel(*x, *y, Color::BLACK);
+        }
+        let path = dominant_contour(&canvas).unwrap();
+        let points: Vec<(f64, f64)> = path.data.iter().map(|point| (point.re, point.im)).collect();
+        assert_eq!(
+            vec![
+                (1.0, 4.0),
+                (2.0, 4.0),
+                (3.0, 4.0),
+                (3.0, 3.0),
+                (2.0, 3.0),
+                (1.0, 3.0)
+            ],
+            points
+        );
+
+        // Light shapes on a dark background are traced alike.
+        let mut inverted = Canvas::new(8, 6, Color::BLACK);
+        for (x, y) in &[(1, 1), (2, 1), (3, 1), (1, 2), (2, 2), (3, 2)] {
+            inverted.set_pixel(*x, *y, Color::WHITE);
+        }
+        assert_eq!(path.data, dominant_contour(&inverted).unwrap().data);
+        assert!(dominant_contour(&Canvas::new(4, 4, Color::WHITE)).is_none());
+    }
+
+    #[test]
+    fn trace_test() {
+        // A ring, whose hole is not part of the outline.
+        let ring = |x: i64, y: i64| (0..3).contains(&x) && (0..3).contains(&y) && (x, y) != (1, 1);
+        assert_eq!(
+            vec![
+                (0, 0),
+                (1, 0),
+                (2, 0),
+                (2, 1),
+                (2, 2),
+                (1, 2),
+                (0, 2),
+                (0, 1)
+            ],
+            trace(ring, (0, 0))
+        );
+        assert_eq!(vec![(5, 5)], trace(|x, y| (x, y) == (5, 5), (5, 5)));
+        // A diagonal line is walked there and back.
+        let line = |x: i64, y: i64| x == y && (0..3).contains(&x);
+        assert_eq!(vec![(0, 0), (1, 1), (2, 2), (1, 1)], trace(line, (0, 0)));
+    }
+
+    #[test]
+    fn otsu_threshold_test() {
+        let values = [10, 12, 11, 200, 210, 205];
+        let threshold = otsu_threshold(&values);
+        assert!((12..200).contains(&threshold));
+        assert_eq!(0, otsu_threshold(&[7; 4]));
+    }
+}
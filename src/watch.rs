@@ -0,0 +1,109 @@
+use crate::path;
+use std::error::Error;
+use std::fs;
+use std::thread;
+use std::time::{Duration, SystemTime};
+
+/// Time between two checks of the watched file by default.
+pub const DEFAULT_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Notices changes to a file by polling its modification time and size, which works the same on
+/// every platform and with editors that replace the file instead of writing into it.
+#[derive(Debug)]
+pub struct Watcher {
+    filename: String,
+    stamp: Option<(SystemTime, u64)>,
+}
+
+impl Watcher {
+    /// Watcher of `filename`, taking its current state as the starting point.
+    pub fn new(filename: &str) -> Watcher {
+        Watcher {
+            filename: filename.to_string(),
+            stamp: stamp(filename),
+        }
+    }
+
+    /// Whether the file was modified, created or removed since the previous call.
+    pub fn changed(&mut self) -> bool {
+        let current = stamp(&self.filename);
+        if current == self.stamp {
+            return false;
+        }
+        self.stamp = current;
+        true
+    }
+}
+
+fn stamp(filename: &str) -> Option<(SystemTime, u64)> {
+    let metadata = fs::metadata(filename).ok()?;
+    Some((metadata.modified().ok()?, metadata.len()))
+}
+
+/// Reads the path in `filename` and passes it to `render`, then again every time the file
+/// changes, checking every `interval`. Reading errors are passed to `render` as well so that a
+/// file saved half-way does not end the loop. Stops when `render` returns `false`.
+pub fn watch<F>(filename: &str, interval: Duration, mut render: F)
+where
+    F: FnMut(Result<path::Path, Box<dyn Error>>) -> bool,
+{
+    let mut watcher = Watcher::new(filename);
+    if !render(path::Path::new(filename)) {
+        return;
+    }
+    loop {
+        thread::sleep(interval);
+        if watcher.changed() && !render(path::Path::new(filename)) {
+            return;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use std::env;
+
+    #[test]
+    fn watcher_test() {
+        let mut file = env::temp_dir();
+        file.push(format!("fft_watcher_test_{}.txt", std::process::id()));
+        let filename = file.to_str().unwrap();
+        let _ = fs::remove_file(filename);
+
+        let mut watcher = Watcher::new(filename);
+        assert!(!watcher.changed());
+        fs::write(filename, "0,0\n").unwrap();
+        assert!(watcher.changed());
+        assert!(!watcher.changed());
+        fs::write(filename, "0,0\n1,1\n").unwrap();
+        assert!(watcher.changed());
+        fs::remove_file(filename).unwrap();
+        assert!(watcher.changed());
+    }
+
+    #[test]
+    fn watch_test() {
+        let mut file = env::temp_dir();
+        file.push(format!("fft_watch_test_{}.txt", std::process::id()));
+        let filename = file.to_str().unwrap().to_string();
+        fs::write(&filename, "0,0\n1,0\n").unwrap();
+
+        let writer = {
+            let filename = filename.clone();
+            thread::spawn(move || {
+                thread::sleep(Duration::from_millis(50));
+                fs::write(filename, "0,0\n1,0\n1,1\n").unwrap();
+            })
+        };
+        let mut lengths = Vec::new();
+        watch(&filename, Duration::from_millis(10), |path| {
+            lengths.push(path.unwrap().data.len());
+            lengths.len() < 2
+        });
+        writer.join().unwrap();
+        fs::remove_file(&filename).unwrap();
+        assert_eq!(vec![2, 3], lengths);
+    }
+}